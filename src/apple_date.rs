@@ -0,0 +1,61 @@
+//! The plist dates, which are seconds relative to the Apple epoch (2001-01-01) rather than the UNIX one.
+
+use plist_plus::Plist;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The seconds between the UNIX epoch and the Apple epoch
+const APPLE_EPOCH_OFFSET: u64 = 978_307_200;
+
+/// Converts a time to a plist date, the times before the Apple epoch become the epoch itself.
+#[cfg(feature = "backup")]
+pub(crate) fn to_plist(time: SystemTime) -> Plist {
+    Plist::new_date(to_apple_seconds(time), 0)
+}
+
+/// Reads the date of the given key, `None` if it's missing, not a date or before the UNIX epoch.
+pub(crate) fn get_date(plist: &Plist, key: &str) -> Option<SystemTime> {
+    let (seconds, _) = plist.dict_get_item(key).ok()?.get_date_val().ok()?;
+    from_apple_seconds(seconds)
+}
+
+#[cfg(feature = "backup")]
+fn to_apple_seconds(time: SystemTime) -> i32 {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+        .saturating_sub(APPLE_EPOCH_OFFSET);
+    i32::try_from(seconds).unwrap_or(i32::MAX)
+}
+
+fn from_apple_seconds(seconds: i32) -> Option<SystemTime> {
+    let seconds = u64::try_from(i64::from(seconds) + APPLE_EPOCH_OFFSET as i64).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_from_the_apple_epoch() {
+        assert_eq!(
+            from_apple_seconds(0),
+            Some(UNIX_EPOCH + Duration::from_secs(APPLE_EPOCH_OFFSET))
+        );
+        assert_eq!(
+            from_apple_seconds(-(APPLE_EPOCH_OFFSET as i32)),
+            Some(UNIX_EPOCH)
+        );
+        assert_eq!(from_apple_seconds(-(APPLE_EPOCH_OFFSET as i32) - 1), None);
+    }
+
+    #[cfg(feature = "backup")]
+    #[test]
+    fn converts_to_the_apple_epoch() {
+        let time = UNIX_EPOCH + Duration::from_secs(APPLE_EPOCH_OFFSET + 60);
+        assert_eq!(to_apple_seconds(time), 60);
+        assert_eq!(from_apple_seconds(to_apple_seconds(time)), Some(time));
+        assert_eq!(to_apple_seconds(UNIX_EPOCH), 0);
+    }
+}
@@ -0,0 +1,178 @@
+//! Decryption of the files of an encrypted backup (AES-256-CBC, with a zero IV).
+
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use aes::Aes256;
+use cbc::cipher::{generic_array::GenericArray, BlockDecryptMut, KeyIvInit};
+
+use crate::errors::BackupError;
+
+type Decryptor = cbc::Decryptor<Aes256>;
+
+const BLOCK_SIZE: usize = 16;
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Decrypts data held in memory, such as the Manifest.db, the padding is kept.
+pub(crate) fn decrypt_data(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, BackupError> {
+    if !data.len().is_multiple_of(BLOCK_SIZE) {
+        return Err(BackupError::DecryptionError(
+            "the data is not a multiple of the block size".into(),
+        ));
+    }
+
+    let mut decryptor = Decryptor::new(key.into(), &[0; BLOCK_SIZE].into());
+    let mut data = data.to_vec();
+    decrypt_blocks(&mut decryptor, &mut data);
+
+    Ok(data)
+}
+
+/// Decrypts a file chunk by chunk, removing the padding.
+///
+/// # Parameters
+/// - `size`: The size of the decrypted file, if known, otherwise the PKCS#7 padding is used.
+pub(crate) fn decrypt_file(
+    key: &[u8; 32],
+    source: &Path,
+    destination: &Path,
+    size: Option<u64>,
+) -> Result<(), BackupError> {
+    let mut source = File::open(source)?;
+    let mut destination = File::create(destination)?;
+    let mut decryptor = Decryptor::new(key.into(), &[0; BLOCK_SIZE].into());
+
+    let mut chunk = vec![0; CHUNK_SIZE];
+    // The last chunk is held back, as its padding can only be removed once the end is reached
+    let mut pending: Vec<u8> = Vec::new();
+    let mut written = 0;
+
+    loop {
+        let count = read_full(&mut source, &mut chunk)?;
+        if count == 0 {
+            break;
+        }
+        if !count.is_multiple_of(BLOCK_SIZE) {
+            return Err(BackupError::DecryptionError(
+                "the file is not a multiple of the block size".into(),
+            ));
+        }
+
+        destination.write_all(&pending)?;
+        written += pending.len() as u64;
+
+        pending = chunk[..count].to_vec();
+        decrypt_blocks(&mut decryptor, &mut pending);
+    }
+
+    let end = match size {
+        Some(size) => size.saturating_sub(written).min(pending.len() as u64) as usize,
+        None => {
+            let padding = pending.last().copied().unwrap_or(0) as usize;
+            if padding == 0 || padding > BLOCK_SIZE || padding > pending.len() {
+                return Err(BackupError::DecryptionError("invalid padding".into()));
+            }
+            pending.len() - padding
+        }
+    };
+    destination.write_all(&pending[..end])?;
+
+    Ok(())
+}
+
+fn decrypt_blocks(decryptor: &mut Decryptor, data: &mut [u8]) {
+    for block in data.chunks_exact_mut(BLOCK_SIZE) {
+        decryptor.decrypt_block_mut(GenericArray::from_mut_slice(block));
+    }
+}
+
+/// Fills the buffer as much as possible, returns less only at the end of the file.
+fn read_full(file: &mut File, buffer: &mut [u8]) -> Result<usize, BackupError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let count = file.read(&mut buffer[filled..])?;
+        if count == 0 {
+            break;
+        }
+        filled += count;
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+    use std::fs;
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&text[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// "backup content, padded by PKCS7" encrypted with the key 80 81 ... 9f
+    const FILE_KEY_START: u8 = 0x80;
+    const PLAINTEXT: &[u8] = b"backup content, padded by PKCS7";
+    const CIPHERTEXT: &str = "f9e257e3fc85a102784e48650a395a149c20ffcf08633757e9af54d9c5d2913a";
+
+    fn decrypt_to_file(data: &[u8], size: Option<u64>) -> Result<Vec<u8>, BackupError> {
+        let dir = TempDir::create("crypto").unwrap();
+        let source = dir.path().join("encrypted");
+        let destination = dir.path().join("decrypted");
+        fs::write(&source, data).unwrap();
+
+        let key = std::array::from_fn(|index| FILE_KEY_START + index as u8);
+        decrypt_file(&key, &source, &destination, size)?;
+        Ok(fs::read(&destination).unwrap())
+    }
+
+    #[test]
+    fn decrypts_the_nist_cbc_vector() {
+        // SP 800-38A, F.2.6 (CBC-AES256.Decrypt), the IV is zero here
+        // so the first block is the NIST one XORed with 000102...0f
+        let key = hex("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4");
+        let ciphertext = hex("f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d");
+
+        assert_eq!(
+            decrypt_data(&key.try_into().unwrap(), &ciphertext).unwrap(),
+            hex("6bc0bce12a459991e134741a7f9e1925ae2d8a571e03ac9c9eb76fac45af8e51")
+        );
+        assert!(matches!(
+            decrypt_data(&[0; 32], &ciphertext[..20]),
+            Err(BackupError::DecryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn removes_the_padding_of_a_file() {
+        assert_eq!(decrypt_to_file(&hex(CIPHERTEXT), None).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn cuts_a_file_at_its_size() {
+        assert_eq!(
+            decrypt_to_file(&hex(CIPHERTEXT), Some(6)).unwrap(),
+            &PLAINTEXT[..6]
+        );
+    }
+
+    #[test]
+    fn rejects_a_malformed_file() {
+        let ciphertext = hex(CIPHERTEXT);
+
+        assert!(matches!(
+            decrypt_to_file(&ciphertext[..20], None),
+            Err(BackupError::DecryptionError(_))
+        ));
+        // Without the last block, the file ends with a space (0x20), which isn't a valid padding
+        assert!(matches!(
+            decrypt_to_file(&ciphertext[..16], None),
+            Err(BackupError::DecryptionError(_))
+        ));
+    }
+}
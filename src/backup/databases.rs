@@ -0,0 +1,49 @@
+/// Enum representing the well-known databases kept in the backups.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WellKnownDatabase {
+    /// The messages (SMS and iMessage)
+    Sms,
+    /// The contacts
+    AddressBook,
+    /// The pictures of the contacts
+    AddressBookImages,
+    /// The call history
+    CallHistory,
+    /// The calendar events
+    Calendar,
+    /// The notes
+    Notes,
+    /// The Safari history
+    SafariHistory,
+    /// The visual voicemails
+    Voicemail,
+}
+
+impl WellKnownDatabase {
+    /// The domains and paths the database can be found at, the current iOS versions first.
+    pub fn locations(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            WellKnownDatabase::Sms => &[("HomeDomain", "Library/SMS/sms.db")],
+            WellKnownDatabase::AddressBook => {
+                &[("HomeDomain", "Library/AddressBook/AddressBook.sqlitedb")]
+            }
+            WellKnownDatabase::AddressBookImages => &[(
+                "HomeDomain",
+                "Library/AddressBook/AddressBookImages.sqlitedb",
+            )],
+            WellKnownDatabase::CallHistory => &[
+                ("HomeDomain", "Library/CallHistoryDB/CallHistory.storedata"),
+                // Before iOS 8
+                ("WirelessDomain", "Library/CallHistory/call_history.db"),
+            ],
+            WellKnownDatabase::Calendar => &[("HomeDomain", "Library/Calendar/Calendar.sqlitedb")],
+            WellKnownDatabase::Notes => &[
+                ("AppDomainGroup-group.com.apple.notes", "NoteStore.sqlite"),
+                // Before iOS 9
+                ("HomeDomain", "Library/Notes/notes.sqlite"),
+            ],
+            WellKnownDatabase::SafariHistory => &[("HomeDomain", "Library/Safari/History.db")],
+            WellKnownDatabase::Voicemail => &[("HomeDomain", "Library/Voicemail/voicemail.db")],
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use plist_plus::error::PlistError;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Manifest database error: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+
+    #[error("`{0}` is not a backup directory (no Manifest.db found)")]
+    NotABackup(PathBuf),
+
+    #[error("The backup is encrypted, it must be opened with its password")]
+    PasswordRequired,
+
+    #[error("The `{key}` key is missing from the {file}")]
+    MissingInfo { file: String, key: String },
+
+    #[error("Wrong backup password")]
+    WrongPassword,
+
+    #[error("Invalid backup keybag: {0}")]
+    InvalidKeybag(String),
+
+    #[error("Couldn't decrypt the backup: {0}")]
+    DecryptionError(String),
+
+    #[error("The file `{domain}/{relative_path}` is not in the backup")]
+    FileNotFound {
+        domain: String,
+        relative_path: String,
+    },
+
+    #[error("The file `{domain}/{relative_path}` would be extracted outside of the destination directory")]
+    UnsafePath {
+        domain: String,
+        relative_path: String,
+    },
+
+    #[error("Invalid file identifier in the Manifest.db: `{0}`")]
+    InvalidFileId(String),
+}
@@ -0,0 +1,337 @@
+//! The backup keybag, holding the keys of the protection classes of an encrypted backup.
+
+use std::collections::HashMap;
+
+use aes_kw::KekAes256;
+use pbkdf2::pbkdf2_hmac;
+use sha1::Sha1;
+use sha2::Sha256;
+
+use crate::errors::BackupError;
+
+/// The class key is wrapped with the key derived from the backup password
+const WRAP_PASSCODE: u32 = 2;
+
+#[derive(Debug, Default)]
+struct ClassKey {
+    wrap: u32,
+    wrapped_key: Vec<u8>,
+    key: Option<[u8; 32]>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Keybag {
+    salt: Vec<u8>,
+    iterations: u32,
+    double_protection_salt: Vec<u8>,
+    double_protection_iterations: u32,
+    class_keys: HashMap<u32, ClassKey>,
+}
+
+impl Keybag {
+    /// Parses the keybag, a list of `tag (4 bytes), length (4 bytes, big endian), value` items,
+    /// where each `UUID` after the first one starts a new class key.
+    pub(crate) fn parse(data: &[u8]) -> Result<Keybag, BackupError> {
+        let mut keybag = Keybag::default();
+        let mut current: Option<(u32, ClassKey)> = None;
+        let mut seen_uuid = false;
+        let mut offset = 0;
+
+        while offset + 8 <= data.len() {
+            let tag = &data[offset..offset + 4];
+            let length =
+                u32::from_be_bytes(data[offset + 4..offset + 8].try_into().unwrap_or_default())
+                    as usize;
+            let value = data
+                .get(offset + 8..offset + 8 + length)
+                .ok_or_else(|| BackupError::InvalidKeybag("truncated item".into()))?;
+            offset += 8 + length;
+
+            let number = || {
+                value
+                    .try_into()
+                    .map(u32::from_be_bytes)
+                    .map_err(|_| BackupError::InvalidKeybag("invalid number".into()))
+            };
+
+            match tag {
+                b"UUID" if !seen_uuid => seen_uuid = true,
+                b"UUID" => {
+                    if let Some((class, class_key)) = current.take() {
+                        keybag.class_keys.insert(class, class_key);
+                    }
+                    current = Some((0, ClassKey::default()));
+                }
+                b"CLAS" => {
+                    if let Some((class, _)) = &mut current {
+                        *class = number()?;
+                    }
+                }
+                b"WRAP" => {
+                    if let Some((_, class_key)) = &mut current {
+                        class_key.wrap = number()?;
+                    }
+                }
+                b"WPKY" => {
+                    if let Some((_, class_key)) = &mut current {
+                        class_key.wrapped_key = value.to_vec();
+                    }
+                }
+                b"SALT" => keybag.salt = value.to_vec(),
+                b"ITER" => keybag.iterations = number()?,
+                b"DPSL" => keybag.double_protection_salt = value.to_vec(),
+                b"DPIC" => keybag.double_protection_iterations = number()?,
+                _ => {}
+            }
+        }
+
+        if let Some((class, class_key)) = current.take() {
+            keybag.class_keys.insert(class, class_key);
+        }
+
+        if keybag.class_keys.is_empty() {
+            return Err(BackupError::InvalidKeybag("no class keys".into()));
+        }
+
+        Ok(keybag)
+    }
+
+    /// Unwraps the class keys with the backup password.
+    pub(crate) fn unlock(&mut self, password: &str) -> Result<(), BackupError> {
+        let kek = KekAes256::new(&self.passcode_key(password).into());
+
+        for class_key in self.class_keys.values_mut() {
+            if class_key.wrap & WRAP_PASSCODE == 0 {
+                continue;
+            }
+
+            let mut key = [0; 32];
+            kek.unwrap(&class_key.wrapped_key, &mut key)
+                .map_err(|_| BackupError::WrongPassword)?;
+            class_key.key = Some(key);
+        }
+
+        Ok(())
+    }
+
+    /// Derives the key wrapping the class keys from the backup password.
+    fn passcode_key(&self, password: &str) -> [u8; 32] {
+        let mut passcode_key = [0; 32];
+
+        // Since iOS 10.2, the password goes through a round of PBKDF2-SHA256 first
+        if self.double_protection_salt.is_empty() {
+            pbkdf2_hmac::<Sha1>(
+                password.as_bytes(),
+                &self.salt,
+                self.iterations,
+                &mut passcode_key,
+            );
+        } else {
+            let mut intermediate = [0; 32];
+            pbkdf2_hmac::<Sha256>(
+                password.as_bytes(),
+                &self.double_protection_salt,
+                self.double_protection_iterations,
+                &mut intermediate,
+            );
+            pbkdf2_hmac::<Sha1>(
+                &intermediate,
+                &self.salt,
+                self.iterations,
+                &mut passcode_key,
+            );
+        }
+
+        passcode_key
+    }
+
+    /// Unwraps a file (or manifest) key with the key of its protection class.
+    pub(crate) fn unwrap_key(
+        &self,
+        protection_class: u32,
+        wrapped_key: &[u8],
+    ) -> Result<[u8; 32], BackupError> {
+        let class_key = self
+            .class_keys
+            .get(&protection_class)
+            .and_then(|class_key| class_key.key)
+            .ok_or_else(|| {
+                BackupError::DecryptionError(format!(
+                    "no key for the protection class {}",
+                    protection_class
+                ))
+            })?;
+
+        let mut key = [0; 32];
+        KekAes256::new(&class_key.into())
+            .unwrap(wrapped_key, &mut key)
+            .map_err(|_| BackupError::DecryptionError("couldn't unwrap the key".into()))?;
+
+        Ok(key)
+    }
+
+    /// Unwraps a key stored as `protection class (4 bytes, little endian) + wrapped key`.
+    pub(crate) fn unwrap_class_prefixed_key(&self, data: &[u8]) -> Result<[u8; 32], BackupError> {
+        if data.len() < 4 {
+            return Err(BackupError::DecryptionError("the key is too short".into()));
+        }
+        let protection_class = u32::from_le_bytes(data[..4].try_into().unwrap_or_default());
+        self.unwrap_key(protection_class, &data[4..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&text[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn item(tag: &[u8; 4], value: &[u8]) -> Vec<u8> {
+        let mut item = tag.to_vec();
+        item.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        item.extend_from_slice(value);
+        item
+    }
+
+    /// A keybag with the class 3 wrapped with the password "password" (salt "salt", 2 iterations),
+    /// and the class 1 left to the device
+    fn keybag_data() -> Vec<u8> {
+        [
+            item(b"VERS", &3u32.to_be_bytes()),
+            item(b"UUID", &[0xAA; 16]),
+            item(b"SALT", b"salt"),
+            item(b"ITER", &2u32.to_be_bytes()),
+            item(b"UUID", &[0x01; 16]),
+            item(b"CLAS", &3u32.to_be_bytes()),
+            item(b"WRAP", &3u32.to_be_bytes()),
+            item(
+                b"WPKY",
+                &hex("248158d44de7f5d48d85acb3f5c3599874731a115fae351cdab8c25882714c2e99e4e7509236c867"),
+            ),
+            item(b"UUID", &[0x02; 16]),
+            item(b"CLAS", &1u32.to_be_bytes()),
+            item(b"WRAP", &1u32.to_be_bytes()),
+            item(b"WPKY", &[0x00; 40]),
+        ]
+        .concat()
+    }
+
+    fn class_key(key: [u8; 32]) -> Keybag {
+        let mut keybag = Keybag::default();
+        keybag.class_keys.insert(
+            3,
+            ClassKey {
+                key: Some(key),
+                ..ClassKey::default()
+            },
+        );
+        keybag
+    }
+
+    #[test]
+    fn parses_the_keybag_items() {
+        let keybag = Keybag::parse(&keybag_data()).unwrap();
+
+        assert_eq!(keybag.salt, b"salt");
+        assert_eq!(keybag.iterations, 2);
+        assert!(keybag.double_protection_salt.is_empty());
+        assert_eq!(keybag.class_keys.len(), 2);
+        assert_eq!(keybag.class_keys[&3].wrap, 3);
+        assert_eq!(keybag.class_keys[&3].wrapped_key.len(), 40);
+        assert_eq!(keybag.class_keys[&1].wrap, 1);
+    }
+
+    #[test]
+    fn rejects_a_truncated_or_empty_keybag() {
+        let data = keybag_data();
+
+        assert!(matches!(
+            Keybag::parse(&data[..data.len() - 1]),
+            Err(BackupError::InvalidKeybag(_))
+        ));
+        assert!(matches!(
+            Keybag::parse(&item(b"SALT", b"salt")),
+            Err(BackupError::InvalidKeybag(_))
+        ));
+    }
+
+    #[test]
+    fn derives_the_passcode_key() {
+        // RFC 6070, the first 20 bytes are the PBKDF2-HMAC-SHA1 vector
+        let keybag = Keybag {
+            salt: b"salt".to_vec(),
+            iterations: 2,
+            ..Keybag::default()
+        };
+        assert_eq!(
+            keybag.passcode_key("password").to_vec(),
+            hex("ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957cae93136266537a8d7bf4b76")
+        );
+
+        // The PBKDF2-HMAC-SHA256 round of RFC 7914 (55ac046e...), then the PBKDF2-HMAC-SHA1 one
+        let keybag = Keybag {
+            salt: b"salt".to_vec(),
+            iterations: 2,
+            double_protection_salt: b"salt".to_vec(),
+            double_protection_iterations: 1,
+            ..Keybag::default()
+        };
+        assert_eq!(
+            keybag.passcode_key("passwd").to_vec(),
+            hex("21ee4d47e186b654d0e9284226c734c943bee1828b8c06c46682827911027e3e")
+        );
+    }
+
+    #[test]
+    fn unwraps_the_rfc_3394_key() {
+        // RFC 3394, 4.6: 256 bits of key data with a 256-bit KEK
+        let keybag = class_key(std::array::from_fn(|index| index as u8));
+        let wrapped =
+            hex("28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21");
+        let expected = hex("00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f");
+
+        assert_eq!(keybag.unwrap_key(3, &wrapped).unwrap().to_vec(), expected);
+
+        let prefixed = [3u32.to_le_bytes().to_vec(), wrapped.clone()].concat();
+        assert_eq!(
+            keybag
+                .unwrap_class_prefixed_key(&prefixed)
+                .unwrap()
+                .to_vec(),
+            expected
+        );
+
+        let mut corrupted = wrapped;
+        corrupted[0] ^= 1;
+        assert!(matches!(
+            keybag.unwrap_key(3, &corrupted),
+            Err(BackupError::DecryptionError(_))
+        ));
+        assert!(matches!(
+            keybag.unwrap_key(1, &expected),
+            Err(BackupError::DecryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn unlocks_the_class_keys_with_the_password() {
+        let mut keybag = Keybag::parse(&keybag_data()).unwrap();
+        keybag.unlock("password").unwrap();
+
+        let expected: [u8; 32] = std::array::from_fn(|index| 0x40 + index as u8);
+        assert_eq!(keybag.class_keys[&3].key, Some(expected));
+        // Not wrapped with the password
+        assert_eq!(keybag.class_keys[&1].key, None);
+
+        let mut keybag = Keybag::parse(&keybag_data()).unwrap();
+        assert!(matches!(
+            keybag.unlock("wrong"),
+            Err(BackupError::WrongPassword)
+        ));
+    }
+}
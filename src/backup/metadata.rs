@@ -0,0 +1,222 @@
+//! Typed metadata of a backup, from its Info.plist, Manifest.plist and Manifest.db.
+
+use crate::apple_date;
+use plist_plus::Plist;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The device a backup was made from and when, as kept in its Info.plist.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupInfo {
+    /// The name of the device (e.g., "John's iPhone")
+    pub device_name: Option<String>,
+
+    /// The UDID of the device
+    pub udid: Option<String>,
+
+    /// The product type (e.g., "iPhone14,2")
+    pub product_type: Option<String>,
+
+    /// The iOS version (e.g., "17.4.1")
+    pub product_version: Option<String>,
+
+    /// The iOS build (e.g., "21E236")
+    pub build_version: Option<String>,
+
+    pub serial_number: Option<String>,
+
+    pub phone_number: Option<String>,
+
+    pub imei: Option<String>,
+
+    /// When the backup was last updated
+    pub last_backup_date: Option<SystemTime>,
+
+    /// The bundle identifiers of the apps installed when the backup was made
+    pub installed_applications: Vec<String>,
+}
+
+impl BackupInfo {
+    pub(crate) fn from_plist(info: &Plist) -> BackupInfo {
+        BackupInfo {
+            device_name: get_string(info, "Device Name"),
+            udid: get_string(info, "Unique Identifier")
+                .or_else(|| get_string(info, "Target Identifier")),
+            product_type: get_string(info, "Product Type"),
+            product_version: get_string(info, "Product Version"),
+            build_version: get_string(info, "Build Version"),
+            serial_number: get_string(info, "Serial Number"),
+            phone_number: get_string(info, "Phone Number"),
+            imei: get_string(info, "IMEI"),
+            last_backup_date: apple_date::get_date(info, "Last Backup Date"),
+            installed_applications: info
+                .dict_get_item("Installed Applications")
+                .map(|apps| get_strings(&apps))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// An app kept in a backup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupApp {
+    /// The bundle identifier of the app, its data is in the `AppDomain-<bundle_id>` domain
+    pub bundle_id: String,
+
+    /// The build version of the app
+    pub version: Option<String>,
+
+    /// The path of the app bundle on the device
+    pub path: Option<String>,
+}
+
+/// The backup itself, as described by its Manifest.plist.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupManifest {
+    /// Whether the backup is encrypted
+    pub is_encrypted: bool,
+
+    /// Whether the device had a passcode when the backup was made
+    pub was_passcode_set: bool,
+
+    /// The version of the backup format (e.g., "10.0")
+    pub version: Option<String>,
+
+    /// When the backup was made
+    pub date: Option<SystemTime>,
+
+    /// The apps kept in the backup
+    pub apps: Vec<BackupApp>,
+}
+
+impl BackupManifest {
+    pub(crate) fn from_plist(manifest: &Plist) -> BackupManifest {
+        let apps = manifest
+            .dict_get_item("Applications")
+            .map(|apps| {
+                apps.clone()
+                    .into_iter()
+                    .map(|app| BackupApp {
+                        version: get_string(&app.plist, "CFBundleVersion"),
+                        path: get_string(&app.plist, "Path"),
+                        bundle_id: app.key.unwrap_or_default(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        BackupManifest {
+            is_encrypted: get_bool(manifest, "IsEncrypted"),
+            was_passcode_set: get_bool(manifest, "WasPasscodeSet"),
+            version: get_string(manifest, "Version"),
+            date: apple_date::get_date(manifest, "Date"),
+            apps,
+        }
+    }
+}
+
+/// Enum representing the type of a file kept in a backup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileRecordKind {
+    File,
+    Directory,
+    Symlink,
+    /// A type not known by this library, with its raw `flags` value
+    Other(i64),
+}
+
+impl From<i64> for FileRecordKind {
+    fn from(flags: i64) -> Self {
+        match flags {
+            1 => FileRecordKind::File,
+            2 => FileRecordKind::Directory,
+            4 => FileRecordKind::Symlink,
+            other => FileRecordKind::Other(other),
+        }
+    }
+}
+
+/// A file of the backup, as indexed in its Manifest.db.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRecord {
+    /// The identifier the file is stored under
+    pub file_id: String,
+
+    /// The domain of the file (e.g., `HomeDomain`)
+    pub domain: String,
+
+    /// The path of the file in its domain
+    pub relative_path: String,
+
+    pub kind: FileRecordKind,
+
+    /// The size of the file, in bytes
+    pub size: Option<u64>,
+
+    /// The last modification time of the file
+    pub mtime: Option<SystemTime>,
+}
+
+impl FileRecord {
+    /// Builds the record from a Manifest.db row, the size and time come from its archived `file` metadata.
+    pub(crate) fn new(
+        file_id: String,
+        domain: String,
+        relative_path: String,
+        flags: i64,
+        file: Option<&Plist>,
+    ) -> FileRecord {
+        let (size, mtime) = file.map_or((None, None), |file| {
+            let size = find_archived_uint(file, "Size");
+            let mtime = find_archived_uint(file, "LastModified")
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+            (size, mtime)
+        });
+
+        FileRecord {
+            file_id,
+            domain,
+            relative_path,
+            kind: FileRecordKind::from(flags),
+            size,
+            mtime,
+        }
+    }
+}
+
+/// Looks for an integer in the objects of an `NSKeyedArchiver` archive.
+pub(crate) fn find_archived_uint(archive: &Plist, key: &str) -> Option<u64> {
+    let objects = archive.dict_get_item("$objects").ok()?;
+
+    (0..objects.array_get_size().unwrap_or(0)).find_map(|index| {
+        objects
+            .array_get_item(index)
+            .and_then(|object| object.dict_get_item(key))
+            .and_then(|value| value.get_uint_val())
+            .ok()
+    })
+}
+
+fn get_string(plist: &Plist, key: &str) -> Option<String> {
+    plist
+        .dict_get_item(key)
+        .and_then(|value| value.get_string_val())
+        .ok()
+}
+
+fn get_strings(array: &Plist) -> Vec<String> {
+    (0..array.array_get_size().unwrap_or(0))
+        .filter_map(|index| {
+            array
+                .array_get_item(index)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        })
+        .collect()
+}
+
+fn get_bool(plist: &Plist, key: &str) -> bool {
+    plist
+        .dict_get_item(key)
+        .and_then(|value| value.get_bool_val())
+        .unwrap_or(false)
+}
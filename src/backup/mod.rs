@@ -0,0 +1,562 @@
+//! Provides access to the content of the backups made by iTunes, Finder or libimobiledevice
+//!
+//! A backup directory keeps every file under its SHA-1 based identifier, the `Manifest.db` index maps
+//! them back to their domain (e.g., `HomeDomain`) and path, so single files can be read
+//! without restoring the whole backup.
+//!
+//! ## Features
+//! - Locating the files of a backup by domain and path
+//! - Locating the well-known databases, such as the messages, the contacts and the call history
+//! - Extracting single files, or the ones matching a glob pattern, from plain and encrypted backups
+//! - Reading the backup metadata (device, date, encryption, apps) and its file index
+//!
+
+use std::{
+    fs,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use plist_plus::Plist;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+
+mod crypto;
+pub mod databases;
+pub(crate) mod errors;
+mod keybag;
+pub mod metadata;
+
+pub use databases::WellKnownDatabase;
+pub use metadata::{BackupApp, BackupInfo, BackupManifest, FileRecord, FileRecordKind};
+
+use crate::{errors::BackupError, temp::TempFile};
+use keybag::Keybag;
+
+const MANIFEST_DB: &str = "Manifest.db";
+const MANIFEST_PLIST: &str = "Manifest.plist";
+const INFO_PLIST: &str = "Info.plist";
+
+/// The `flags` of the regular files in Manifest.db
+const FILE_FLAG: i64 = 1;
+
+/// Length of a wrapped file key, prefixed with its protection class
+const FILE_KEY_LENGTH: usize = 4 + 40;
+
+/// Struct for reading a backup directory.
+#[derive(Debug)]
+pub struct Backup {
+    dir: PathBuf,
+    info: Option<BackupInfo>,
+    manifest: BackupManifest,
+    // Declared before the decrypted copy, so it's closed before the copy is removed
+    manifest_db: Connection,
+    keybag: Option<Keybag>,
+    _decrypted_manifest: Option<TempFile>,
+}
+
+impl Backup {
+    /// Opens the backup in the given directory (the one named after the device UDID).
+    ///
+    /// Encrypted backups must be opened with `open_encrypted`.
+    pub fn open<S>(dir: &S) -> Result<Backup, BackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest_db_path = manifest_db_path(&dir)?;
+        let manifest = read_plist(&dir, MANIFEST_PLIST)?
+            .map(|manifest| BackupManifest::from_plist(&manifest))
+            .unwrap_or_default();
+
+        if manifest.is_encrypted {
+            return Err(BackupError::PasswordRequired);
+        }
+
+        Ok(Backup {
+            manifest_db: open_manifest_db(&manifest_db_path)?,
+            info: read_info(&dir)?,
+            manifest,
+            dir,
+            keybag: None,
+            _decrypted_manifest: None,
+        })
+    }
+
+    /// Opens an encrypted backup with its password.
+    ///
+    /// The Manifest.db is decrypted to a temporary file only readable by the current user,
+    /// which is removed once the backup is dropped.
+    pub fn open_encrypted<S>(dir: &S, password: &str) -> Result<Backup, BackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest_db_path = manifest_db_path(&dir)?;
+        let manifest =
+            read_plist(&dir, MANIFEST_PLIST)?.ok_or_else(|| BackupError::MissingInfo {
+                file: MANIFEST_PLIST.into(),
+                key: "BackupKeyBag".into(),
+            })?;
+
+        let keybag_data =
+            get_data(&manifest, "BackupKeyBag").ok_or_else(|| BackupError::MissingInfo {
+                file: MANIFEST_PLIST.into(),
+                key: "BackupKeyBag".into(),
+            })?;
+        let mut keybag = Keybag::parse(&keybag_data)?;
+        keybag.unlock(password)?;
+
+        // Before iOS 10.2, the Manifest.db wasn't encrypted
+        let (manifest_db, decrypted_manifest) = match get_data(&manifest, "ManifestKey") {
+            Some(manifest_key) => {
+                let key = keybag.unwrap_class_prefixed_key(&manifest_key)?;
+                let decrypted = crypto::decrypt_data(&key, &fs::read(&manifest_db_path)?)?;
+
+                let (temp_file, mut file) = TempFile::create("manifest.db")?;
+                file.write_all(&decrypted)?;
+                drop(file);
+                (open_manifest_db(temp_file.path())?, Some(temp_file))
+            }
+            None => (open_manifest_db(&manifest_db_path)?, None),
+        };
+
+        Ok(Backup {
+            info: read_info(&dir)?,
+            manifest: BackupManifest::from_plist(&manifest),
+            dir,
+            manifest_db,
+            keybag: Some(keybag),
+            _decrypted_manifest: decrypted_manifest,
+        })
+    }
+
+    /// The directory of the backup
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Checks whether the backup is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.keybag.is_some()
+    }
+
+    /// The device the backup was made from, `None` if the backup has no Info.plist
+    pub fn info(&self) -> Option<&BackupInfo> {
+        self.info.as_ref()
+    }
+
+    /// The backup details, such as its date and the apps it keeps
+    pub fn manifest(&self) -> &BackupManifest {
+        &self.manifest
+    }
+
+    /// Lists the domains of the backup (e.g., `HomeDomain`, `AppDomain-com.apple.Pages`).
+    pub fn domains(&self) -> Result<Vec<String>, BackupError> {
+        let mut statement = self
+            .manifest_db
+            .prepare("SELECT DISTINCT domain FROM Files ORDER BY domain")?;
+        let domains = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(domains)
+    }
+
+    /// Lists every file, directory and symlink of the backup.
+    pub fn files(&self) -> Result<Vec<FileRecord>, BackupError> {
+        self.query_files(
+            "SELECT fileID, domain, relativePath, flags, file FROM Files",
+            [],
+        )
+    }
+
+    /// Lists the files, directories and symlinks of a domain.
+    ///
+    /// # Parameters
+    /// - `domain`: The domain (e.g., `HomeDomain`).
+    pub fn files_in_domain(&self, domain: &str) -> Result<Vec<FileRecord>, BackupError> {
+        self.query_files(
+            "SELECT fileID, domain, relativePath, flags, file FROM Files WHERE domain = ?1",
+            [domain],
+        )
+    }
+
+    /// Looks up the identifier a file is stored under, `None` if it's not in the backup.
+    ///
+    /// # Parameters
+    /// - `domain`: The domain of the file (e.g., `HomeDomain`, `AppDomain-com.apple.Pages`).
+    /// - `relative_path`: The path of the file in its domain (e.g., `Library/SMS/sms.db`).
+    pub fn file_id(
+        &self,
+        domain: &str,
+        relative_path: &str,
+    ) -> Result<Option<String>, BackupError> {
+        Ok(self
+            .manifest_db
+            .query_row(
+                "SELECT fileID FROM Files WHERE domain = ?1 AND relativePath = ?2",
+                [domain, relative_path],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Returns the path of a file in the backup directory, ready to be read or copied.
+    ///
+    /// The files of an encrypted backup are stored encrypted, use `extract` to read them.
+    ///
+    /// # Parameters
+    /// - `domain`: The domain of the file (e.g., `HomeDomain`).
+    /// - `relative_path`: The path of the file in its domain.
+    pub fn file_path(&self, domain: &str, relative_path: &str) -> Result<PathBuf, BackupError> {
+        let file_id =
+            self.file_id(domain, relative_path)?
+                .ok_or_else(|| BackupError::FileNotFound {
+                    domain: domain.to_string(),
+                    relative_path: relative_path.to_string(),
+                })?;
+
+        stored_path(&self.dir, &file_id)
+    }
+
+    /// Returns the path of a well-known database in the backup directory.
+    ///
+    /// Note that the databases may have `-wal` files next to them in the backup,
+    /// which can be located with `file_path` and the same path plus `-wal`.
+    pub fn database(&self, database: WellKnownDatabase) -> Result<PathBuf, BackupError> {
+        let (domain, relative_path) = self.database_location(database)?;
+        self.file_path(domain, relative_path)
+    }
+
+    /// Extracts a well-known database, decrypting it if needed.
+    ///
+    /// # Parameters
+    /// - `database`: The database to extract.
+    /// - `destination`: The path of the extracted file.
+    pub fn extract_database<S>(
+        &self,
+        database: WellKnownDatabase,
+        destination: &S,
+    ) -> Result<(), BackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let (domain, relative_path) = self.database_location(database)?;
+        self.extract(domain, relative_path, destination)
+    }
+
+    /// Extracts a single file from the backup, decrypting it if needed.
+    ///
+    /// # Parameters
+    /// - `domain`: The domain of the file (e.g., `HomeDomain`).
+    /// - `relative_path`: The path of the file in its domain.
+    /// - `destination`: The path of the extracted file, its parent directories are created if needed.
+    pub fn extract<S>(
+        &self,
+        domain: &str,
+        relative_path: &str,
+        destination: &S,
+    ) -> Result<(), BackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let record = self
+            .manifest_db
+            .query_row(
+                "SELECT fileID, file FROM Files WHERE domain = ?1 AND relativePath = ?2 AND flags = ?3",
+                (domain, relative_path, FILE_FLAG),
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| BackupError::FileNotFound {
+                domain: domain.to_string(),
+                relative_path: relative_path.to_string(),
+            })?;
+
+        self.extract_record(&record.0, &record.1, destination.as_ref())
+    }
+
+    /// Extracts the files matching glob patterns, keeping their domain and path.
+    ///
+    /// The patterns are the SQLite `GLOB` ones, which are case sensitive, and where `*` matches `/` as well,
+    /// e.g., `extract_glob("AppDomain-*", "Documents/*", dir)` extracts the documents of every app.
+    ///
+    /// # Parameters
+    /// - `domain_pattern`: The pattern of the domains.
+    /// - `path_pattern`: The pattern of the paths in the domains.
+    /// - `destination_dir`: The directory the files are extracted to, as `<domain>/<path>`.
+    ///
+    /// # Returns
+    /// The paths of the extracted files.
+    ///
+    /// # Errors
+    /// Returns `BackupError::UnsafePath` if a domain or path of the Manifest.db would go out of `destination_dir`
+    /// (e.g., with `..`), the files extracted before it are kept.
+    pub fn extract_glob<S>(
+        &self,
+        domain_pattern: &str,
+        path_pattern: &str,
+        destination_dir: &S,
+    ) -> Result<Vec<PathBuf>, BackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let mut statement = self.manifest_db.prepare(
+            "SELECT fileID, file, domain, relativePath FROM Files \
+             WHERE domain GLOB ?1 AND relativePath GLOB ?2 AND flags = ?3",
+        )?;
+        let records = statement
+            .query_map((domain_pattern, path_pattern, FILE_FLAG), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Vec<u8>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut extracted = Vec::with_capacity(records.len());
+        for (file_id, file, domain, relative_path) in records {
+            let destination = extraction_path(destination_dir.as_ref(), &domain, &relative_path)?;
+            self.extract_record(&file_id, &file, &destination)?;
+            extracted.push(destination);
+        }
+
+        Ok(extracted)
+    }
+
+    fn extract_record(
+        &self,
+        file_id: &str,
+        file: &[u8],
+        destination: &Path,
+    ) -> Result<(), BackupError> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let stored_path = stored_path(&self.dir, file_id)?;
+
+        let Some(keybag) = &self.keybag else {
+            fs::copy(stored_path, destination)?;
+            return Ok(());
+        };
+
+        let record = Plist::from_bin(file.to_vec())?;
+        let (wrapped_key, size) = file_key_and_size(&record);
+
+        match wrapped_key {
+            Some(wrapped_key) => {
+                let key = keybag.unwrap_class_prefixed_key(&wrapped_key)?;
+                crypto::decrypt_file(&key, &stored_path, destination, size)
+            }
+            // Empty files have no key
+            None => {
+                fs::copy(stored_path, destination)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn query_files<P: rusqlite::Params>(
+        &self,
+        query: &str,
+        params: P,
+    ) -> Result<Vec<FileRecord>, BackupError> {
+        let mut statement = self.manifest_db.prepare(query)?;
+        let records = statement
+            .query_map(params, |row| {
+                let file = row
+                    .get::<_, Option<Vec<u8>>>(4)?
+                    .and_then(|file| Plist::from_bin(file).ok());
+
+                Ok(FileRecord::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    file.as_ref(),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    fn database_location(
+        &self,
+        database: WellKnownDatabase,
+    ) -> Result<(&'static str, &'static str), BackupError> {
+        let locations = database.locations();
+
+        for (domain, relative_path) in locations {
+            if self.file_id(domain, relative_path)?.is_some() {
+                return Ok((domain, relative_path));
+            }
+        }
+
+        let (domain, relative_path) = locations[0];
+        Err(BackupError::FileNotFound {
+            domain: domain.to_string(),
+            relative_path: relative_path.to_string(),
+        })
+    }
+}
+
+/// The files are stored in a subdirectory named after the first two characters of their identifier.
+///
+/// The identifiers are hex digests, anything else in the Manifest.db is refused rather than joined to the directory.
+fn stored_path(dir: &Path, file_id: &str) -> Result<PathBuf, BackupError> {
+    let subdirectory = file_id
+        .get(..2)
+        .filter(|_| file_id.bytes().all(|byte| byte.is_ascii_alphanumeric()))
+        .ok_or_else(|| BackupError::InvalidFileId(file_id.to_string()))?;
+
+    Ok(dir.join(subdirectory).join(file_id))
+}
+
+/// Joins the domain and the path of a file to the destination directory.
+///
+/// They come from the Manifest.db, so anything but plain names (e.g., `..` or an absolute domain) is refused,
+/// a crafted backup would write outside of the directory otherwise.
+fn extraction_path(
+    destination_dir: &Path,
+    domain: &str,
+    relative_path: &str,
+) -> Result<PathBuf, BackupError> {
+    let domain_path = Path::new(domain);
+    let relative = Path::new(relative_path.trim_start_matches('/'));
+
+    let is_single_name = matches!(
+        domain_path.components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(_)]
+    );
+    let is_plain = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if !is_single_name || !is_plain {
+        return Err(BackupError::UnsafePath {
+            domain: domain.to_string(),
+            relative_path: relative_path.to_string(),
+        });
+    }
+
+    Ok(destination_dir.join(domain_path).join(relative))
+}
+
+fn manifest_db_path(dir: &Path) -> Result<PathBuf, BackupError> {
+    let manifest_db_path = dir.join(MANIFEST_DB);
+
+    if !manifest_db_path.is_file() {
+        return Err(BackupError::NotABackup(dir.to_path_buf()));
+    }
+
+    Ok(manifest_db_path)
+}
+
+fn open_manifest_db(path: &Path) -> Result<Connection, BackupError> {
+    Ok(Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?)
+}
+
+fn read_info(dir: &Path) -> Result<Option<BackupInfo>, BackupError> {
+    Ok(read_plist(dir, INFO_PLIST)?.map(|info| BackupInfo::from_plist(&info)))
+}
+
+/// Reads a plist of the backup directory, `None` if the backup doesn't have it.
+fn read_plist(dir: &Path, name: &str) -> Result<Option<Plist>, BackupError> {
+    match fs::read(dir.join(name)) {
+        Ok(data) => Ok(Some(Plist::from_memory(data)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn get_data(plist: &Plist, key: &str) -> Option<Vec<u8>> {
+    plist
+        .dict_get_item(key)
+        .and_then(|data| data.get_data_val())
+        .ok()
+        .map(|data| data.into_iter().map(|byte| byte as u8).collect())
+}
+
+/// Finds the wrapped key and the size of a file in its Manifest.db record.
+///
+/// The record is an `NSKeyedArchiver` archive, the key is kept in one of the archived objects
+/// as `NS.data`, so the objects are searched instead of following the archive references.
+fn file_key_and_size(record: &Plist) -> (Option<Vec<u8>>, Option<u64>) {
+    let Ok(objects) = record.dict_get_item("$objects") else {
+        return (None, None);
+    };
+
+    let wrapped_key = (0..objects.array_get_size().unwrap_or(0)).find_map(|index| {
+        let object = objects.array_get_item(index).ok()?;
+        get_data(&object, "NS.data").filter(|data| data.len() == FILE_KEY_LENGTH)
+    });
+
+    (wrapped_key, metadata::find_archived_uint(record, "Size"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_path_uses_the_identifier_prefix() {
+        assert_eq!(
+            stored_path(
+                Path::new("backup"),
+                "3d0d7e5fb2ce288813306e4d4636395e047a3d28"
+            )
+            .unwrap(),
+            Path::new("backup/3d/3d0d7e5fb2ce288813306e4d4636395e047a3d28")
+        );
+    }
+
+    #[test]
+    fn stored_path_refuses_the_invalid_identifiers() {
+        for file_id in ["", "a", "é1234", "../secret", "ab/cd"] {
+            assert!(
+                matches!(
+                    stored_path(Path::new("backup"), file_id),
+                    Err(BackupError::InvalidFileId(_))
+                ),
+                "{file_id:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn extraction_path_keeps_the_domain_and_path() {
+        assert_eq!(
+            extraction_path(Path::new("out"), "HomeDomain", "/Library/SMS/sms.db").unwrap(),
+            Path::new("out/HomeDomain/Library/SMS/sms.db")
+        );
+    }
+
+    #[test]
+    fn extraction_path_refuses_the_escaping_paths() {
+        let refused = [
+            ("HomeDomain", "../../etc/passwd"),
+            ("HomeDomain", "Library/./../../x"),
+            ("..", "x"),
+            ("/tmp", "x"),
+            ("Home/Domain", "x"),
+            ("", "x"),
+        ];
+
+        for (domain, relative_path) in refused {
+            assert!(
+                matches!(
+                    extraction_path(Path::new("out"), domain, relative_path),
+                    Err(BackupError::UnsafePath { .. })
+                ),
+                "{domain}/{relative_path} was accepted"
+            );
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! The configuration shared by every client of the crate.
+//!
+//! It's set once, at startup, with `RsMobileDeviceConfig::set_global`, then the connections made afterwards
+//! follow it: the label the services see, where usbmuxd listens, and the pacing of the requests.
+//! Some settings only apply to parts of the crate, their setters list which ones: how long to wait for
+//! the devices to answer, and where the pair records and the developer disk images are kept.
+//!
+//! ## Features
+//! - Setting the label prefix of the connections (e.g., to tell the tools apart in the device logs)
+//! - Setting the default timeout of the requests
+//! - Pointing to another usbmuxd (e.g., a forwarded one), and the pair record files to another directory
+//! - Pacing the requests to each device, and capping the operations running at once on a group
+//!
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::{Arc, Once, RwLock},
+    time::Duration,
+};
+
+const DEFAULT_LABEL_PREFIX: &str = "rsmobiledevice";
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The variable libusbmuxd reads the address of usbmuxd from
+const USBMUXD_ADDRESS_VARIABLE: &str = "USBMUXD_SOCKET_ADDRESS";
+
+static GLOBAL_CONFIG: RwLock<Option<Arc<RsMobileDeviceConfig>>> = RwLock::new(None);
+static USBMUXD_ADDRESS_EXPORTED: Once = Once::new();
+
+/// The configuration of the clients, see `RsMobileDeviceConfig::set_global`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RsMobileDeviceConfig {
+    label_prefix: String,
+    request_timeout: Duration,
+    usbmuxd_address: Option<String>,
+    pair_record_dir: Option<PathBuf>,
+    ddi_cache_dir: Option<PathBuf>,
+    request_pacing: Option<Duration>,
+    max_in_flight: Option<usize>,
+}
+
+impl Default for RsMobileDeviceConfig {
+    fn default() -> Self {
+        RsMobileDeviceConfig {
+            label_prefix: DEFAULT_LABEL_PREFIX.to_string(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            usbmuxd_address: None,
+            pair_record_dir: None,
+            ddi_cache_dir: None,
+            request_pacing: None,
+            max_in_flight: None,
+        }
+    }
+}
+
+impl RsMobileDeviceConfig {
+    pub fn new() -> RsMobileDeviceConfig {
+        RsMobileDeviceConfig::default()
+    }
+
+    /// Sets the prefix of the labels the connections are made with ("rsmobiledevice" by default),
+    /// each one is followed by the part of the crate making it (e.g., "rsmobiledevice-heartbeat").
+    pub fn with_label_prefix(mut self, prefix: &str) -> RsMobileDeviceConfig {
+        self.label_prefix = prefix.to_string();
+        self
+    }
+
+    /// Sets how long to wait for a device to answer a request (5 seconds by default).
+    ///
+    /// Only the connections the crate opens itself follow it: the prelogin values and probes of `DeviceInfo`
+    /// (e.g., `DeviceInfo::get_prelogin_values`), `DeviceClient::pair`, and the replies of `DeviceInstruments`.
+    /// The services reached through libimobiledevice keep their own timeouts.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> RsMobileDeviceConfig {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Sets the address of usbmuxd (e.g., "127.0.0.1:27015"), instead of its local socket.
+    ///
+    /// libimobiledevice only follows the address of the first configuration set, see `set_global`.
+    pub fn with_usbmuxd_address(mut self, address: &str) -> RsMobileDeviceConfig {
+        self.usbmuxd_address = Some(address.to_string());
+        self
+    }
+
+    /// Sets the directory `PairRecordFiles::new` works in, instead of the system lockdown directory.
+    ///
+    /// Only `PairRecordFiles::new` follows it (and so `DeviceClient::get_pairing_identity`), it doesn't isolate
+    /// the pairings: the connections through libimobiledevice always use the records of usbmuxd.
+    pub fn with_pair_record_dir<S>(mut self, dir: &S) -> RsMobileDeviceConfig
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        self.pair_record_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the directory the developer disk images are kept in, see `DeviceImageMounter::mount_developer_image`.
+    pub fn with_ddi_cache_dir<S>(mut self, dir: &S) -> RsMobileDeviceConfig
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        self.ddi_cache_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the least time between two requests to the same device, the requests coming sooner wait
+    /// for their turn. No pacing by default.
+    pub fn with_request_pacing(mut self, pacing: Duration) -> RsMobileDeviceConfig {
+        self.request_pacing = Some(pacing);
+        self
+    }
+
+    /// Caps the operations of `FleetExecutor` (and the group APIs built on it) running at once across
+    /// all the devices, the others wait for a place instead of failing. No cap by default.
+    ///
+    /// An operation past the `FleetExecutor` timeout gives its place back, even though it may still be
+    /// running, so the hanging devices can't block the later runs.
+    ///
+    /// At least one operation runs at once, 0 is taken as 1.
+    pub fn with_max_in_flight(mut self, max: usize) -> RsMobileDeviceConfig {
+        self.max_in_flight = Some(max.max(1));
+        self
+    }
+
+    pub fn label_prefix(&self) -> &str {
+        &self.label_prefix
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        self.request_timeout
+    }
+
+    /// The address of usbmuxd, `None` for its local socket
+    pub fn usbmuxd_address(&self) -> Option<&str> {
+        self.usbmuxd_address.as_deref()
+    }
+
+    /// The directory of the pair records, `None` for the system lockdown directory
+    pub fn pair_record_dir(&self) -> Option<&Path> {
+        self.pair_record_dir.as_deref()
+    }
+
+    /// The directory of the developer disk images, `None` if it isn't set
+    pub fn ddi_cache_dir(&self) -> Option<&Path> {
+        self.ddi_cache_dir.as_deref()
+    }
+
+    /// The least time between two requests to the same device, `None` for no pacing
+    pub fn request_pacing(&self) -> Option<Duration> {
+        self.request_pacing
+    }
+
+    /// The most operations running at once across the devices, `None` for no cap
+    pub fn max_in_flight(&self) -> Option<usize> {
+        self.max_in_flight
+    }
+
+    /// Makes this the configuration of every client, replacing the previous one.
+    ///
+    /// The connections already made keep their settings.
+    ///
+    /// libimobiledevice reads the address of usbmuxd from the `USBMUXD_SOCKET_ADDRESS` environment variable,
+    /// which is only set by the first call (if it has an address): changing the environment while other threads
+    /// read it (the workers of the crate, and libimobiledevice itself) isn't sound. So the first configuration
+    /// should be set at startup, before any other thread is started, and the later ones only change the address
+    /// of the connections the crate opens itself (the prelogin values and the pairing).
+    pub fn set_global(self) {
+        USBMUXD_ADDRESS_EXPORTED.call_once(|| {
+            if let Some(address) = &self.usbmuxd_address {
+                env::set_var(USBMUXD_ADDRESS_VARIABLE, address);
+            }
+        });
+
+        if let Ok(mut global) = GLOBAL_CONFIG.write() {
+            *global = Some(Arc::new(self));
+        }
+    }
+
+    /// The configuration of the clients, the default one if none was set.
+    pub fn global() -> Arc<RsMobileDeviceConfig> {
+        GLOBAL_CONFIG
+            .read()
+            .ok()
+            .and_then(|global| global.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// The label of a connection made by the given part of the crate (e.g., "heartbeat").
+pub(crate) fn label(name: &str) -> String {
+    format!("{}-{}", RsMobileDeviceConfig::global().label_prefix, name)
+}
@@ -6,30 +6,80 @@
 //!
 //! You must create one to get anything else
 
+use plist_plus::Plist;
 use rusty_libimobiledevice::{
+    error::LockdowndError,
     idevice,
+    service::ServiceClient,
     services::{afc::AfcClient, lockdownd::LockdowndClient},
 };
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex, PoisonError},
+};
 
+#[cfg(feature = "afc")]
+use crate::device_afc::DeviceAfc;
+#[cfg(feature = "backup")]
+use crate::device_backup::DeviceBackup;
+#[cfg(feature = "debugserver")]
+use crate::device_debugserver::DeviceDebugServer;
+#[cfg(feature = "diagnostic")]
+use crate::device_diagnostic::DeviceDiagnostic;
+#[cfg(feature = "image_mounter")]
+use crate::device_image_mounter::DeviceImageMounter;
+#[cfg(feature = "info")]
+use crate::device_info::DeviceInfo;
+#[cfg(feature = "installer")]
+use crate::device_installer::DeviceInstaller;
+#[cfg(feature = "instruments")]
+use crate::device_instruments::DeviceInstruments;
+#[cfg(feature = "notification")]
+use crate::device_notification::DeviceNotification;
+#[cfg(feature = "preboard")]
+use crate::device_preboard::DevicePreboard;
+#[cfg(feature = "screenshot")]
+use crate::device_screenshot::DeviceScreenshot;
+#[cfg(feature = "springboard")]
+use crate::device_springboard::DeviceSpringboard;
+#[cfg(feature = "syslog")]
+use crate::device_syslog::DeviceSysLog;
 use crate::{
-    device_diagnostic::DeviceDiagnostic,
-    device_info::DeviceInfo,
-    device_installer::DeviceInstaller,
-    device_syslog::DeviceSysLog,
+    config::RsMobileDeviceConfig,
+    device_info::{domains::DeviceDomains, keys::DeviceKeys, kind::DeviceKind, version::OsVersion},
     devices_collection::{DeviceGroup, Devices, SingleDevice},
     errors::{
         AFCClientErrorTrait, DeviceClientError, DeviceNotFoundErrorTrait, LockdowndErrorTrait,
     },
+    heartbeat::HeartbeatHandle,
+    instrument::OperationSpan,
+    inventory::{self, DeviceInventory},
+    pair_record::{pairing, PairRecordFiles, PairingIdentity, PairingOptions},
+    power_assertion::PowerAssertion,
+    rate_limit,
+    service_availability::{self, ServiceAvailability},
+    service_manager::DeviceServiceManager,
+    service_options::ServiceStartOptions,
 };
 
+/// The longest device name accepted, in bytes
+pub const MAX_DEVICE_NAME_LENGTH: usize = 255;
+
 /// A high-level abstraction for managing iOS devices, generic over `T`.
 ///
 /// - `T = SingleDevice`: For single-device operations.
 /// - `T = DeviceGroup`: For operations involving multiple devices.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// The client is `Send` and cheap to clone, so each thread can own a copy of it.
+/// To share a single one between threads (e.g., a UI thread and a worker), use `SharedDeviceClient`.
+///
+/// A single device client of a device connected over the network keeps its heartbeat running
+/// in the background for as long as it or one of its clones is alive, see `with_auto_heartbeat`.
+#[derive(Debug, Clone)]
 pub struct DeviceClient<T = DeviceGroup> {
     device: Devices,
+    auto_heartbeat: bool,
+    heartbeat: Option<Arc<HeartbeatHandle>>,
     _p: PhantomData<T>,
 }
 
@@ -38,28 +88,112 @@ impl DeviceClient {
         let device = idevice::get_devices()?;
         Ok(DeviceClient {
             device: Devices::Multiple(device),
+            auto_heartbeat: true,
+            heartbeat: None,
             _p: PhantomData::<DeviceGroup>,
         })
     }
 }
 
+impl<T> DeviceClient<T> {
+    /// Enables or disables the automatic heartbeat of the devices connected over the network (enabled by default).
+    ///
+    /// Without it, the services of such a device are dropped about 15 seconds after connecting,
+    /// unless the heartbeat is answered elsewhere (e.g., with a `HeartbeatHandle`).
+    /// On a group, it applies to the single device clients created from it.
+    pub fn with_auto_heartbeat(mut self, enabled: bool) -> Self {
+        self.auto_heartbeat = enabled;
+        self.heartbeat = match (&self.device, enabled) {
+            (Devices::Single(device), true) => self.heartbeat.or_else(|| start_heartbeat(device)),
+            _ => None,
+        };
+        self
+    }
+
+    /// Checks whether a heartbeat is running for the device, only the case over the network.
+    pub fn is_heartbeat_running(&self) -> bool {
+        self.heartbeat
+            .as_ref()
+            .is_some_and(|heartbeat| heartbeat.is_running())
+    }
+
+    /// Wraps the client in a handle that can be shared between threads.
+    pub fn into_shared(self) -> SharedDeviceClient<T> {
+        SharedDeviceClient::new(self)
+    }
+}
+
 impl DeviceClient<SingleDevice> {
+    #[cfg(feature = "afc")]
+    pub fn get_device_afc(&self) -> DeviceAfc<'_, SingleDevice> {
+        DeviceAfc::new(self)
+    }
+
+    #[cfg(feature = "backup")]
+    pub fn get_device_backup(&self) -> DeviceBackup<'_, SingleDevice> {
+        DeviceBackup::new(self)
+    }
+
+    #[cfg(feature = "info")]
     pub fn get_device_info(&self) -> DeviceInfo<'_, SingleDevice> {
         DeviceInfo::new(self)
     }
 
+    #[cfg(feature = "debugserver")]
+    pub fn get_device_debugserver(&self) -> DeviceDebugServer<'_, SingleDevice> {
+        DeviceDebugServer::new(self)
+    }
+
+    #[cfg(feature = "diagnostic")]
     pub fn get_device_diagnostic(&self) -> DeviceDiagnostic<'_, SingleDevice> {
         DeviceDiagnostic::new(self)
     }
 
+    #[cfg(feature = "syslog")]
     pub fn get_device_syslog(self) -> DeviceSysLog<SingleDevice> {
         DeviceSysLog::new(self)
     }
 
+    #[cfg(feature = "image_mounter")]
+    pub fn get_device_image_mounter(&self) -> DeviceImageMounter<'_, SingleDevice> {
+        DeviceImageMounter::new(self)
+    }
+
+    #[cfg(feature = "installer")]
     pub fn get_device_installer(&self) -> DeviceInstaller<'_, SingleDevice> {
         DeviceInstaller::new(self)
     }
 
+    #[cfg(feature = "instruments")]
+    pub fn get_device_instruments(&self) -> DeviceInstruments<'_, SingleDevice> {
+        DeviceInstruments::new(self)
+    }
+
+    #[cfg(feature = "notification")]
+    pub fn get_device_notification(&self) -> DeviceNotification<'_, SingleDevice> {
+        DeviceNotification::new(self)
+    }
+
+    #[cfg(feature = "preboard")]
+    pub fn get_device_preboard(&self) -> DevicePreboard<'_, SingleDevice> {
+        DevicePreboard::new(self)
+    }
+
+    #[cfg(feature = "screenshot")]
+    pub fn get_device_screenshot(&self) -> DeviceScreenshot<'_, SingleDevice> {
+        DeviceScreenshot::new(self)
+    }
+
+    #[cfg(feature = "springboard")]
+    pub fn get_device_springboard(&self) -> DeviceSpringboard<'_, SingleDevice> {
+        DeviceSpringboard::new(self)
+    }
+
+    /// Creates a `DeviceServiceManager` sharing the lockdownd, AFC and service connections to the device.
+    pub fn get_service_manager(&self) -> DeviceServiceManager<'_> {
+        DeviceServiceManager::new(self)
+    }
+
     /// Retrieves the underlying `idevice::Device` instance.
     ///
     /// # Panics
@@ -79,7 +213,10 @@ impl DeviceClient<SingleDevice> {
     ) -> Result<AfcClient, E> {
         self.check_connected()?;
         let device = self.get_device();
-        AfcClient::start_service(device, "rsmobiledevice-afc_client").map_err(E::afcclient_error)
+        let _span = OperationSpan::service(&device.get_udid(), "com.apple.afc");
+        rate_limit::pace(&device.get_udid());
+        AfcClient::start_service(device, crate::config::label("afc_client"))
+            .map_err(E::afcclient_error)
     }
 
     /// Creates a `LockdowndClient` for interacting with device services.
@@ -91,10 +228,236 @@ impl DeviceClient<SingleDevice> {
     ) -> Result<LockdowndClient, E> {
         self.check_connected()?;
         let device = self.get_device();
-        LockdowndClient::new(device, "rsmobiledevice-lockdownd-client")
+        let _span = OperationSpan::lockdownd(&device.get_udid());
+        rate_limit::pace(&device.get_udid());
+        LockdowndClient::new(device, crate::config::label("lockdownd-client"))
             .map_err(|err| E::lockdownd_error(err))
     }
 
+    /// Starts any lockdown service by its name, for the services this crate doesn't wrap yet.
+    ///
+    /// The returned client sends and receives raw bytes, the framing is up to the service
+    /// (most of them use a 4 bytes big-endian length followed by a plist).
+    ///
+    /// # Parameters
+    /// - `name`: The name of the service (e.g., "com.apple.mobile.heartbeat").
+    /// - `use_ssl`: Whether the connection must be wrapped in SSL, as the service expects.
+    ///
+    /// # Errors
+    /// Returns an error if the device is not connected, or if lockdownd refuses to start the service.
+    pub fn start_custom_service(
+        &self,
+        name: &str,
+        use_ssl: bool,
+    ) -> Result<ServiceClient<'_>, DeviceClientError> {
+        self.start_custom_service_with_options(name, ServiceStartOptions::new().with_ssl(use_ssl))
+    }
+
+    /// Starts any lockdown service by its name, with the given options.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the service (e.g., "com.apple.mobile.heartbeat").
+    /// - `options`: The SSL and escrow bag options, see `ServiceStartOptions`.
+    ///
+    /// # Errors
+    /// Returns `DeviceClientError::DeviceLocked` if the device is passcode locked and the escrow bag
+    /// wasn't sent or couldn't be used (e.g., the device wasn't unlocked since it booted).
+    pub fn start_custom_service_with_options(
+        &self,
+        name: &str,
+        options: ServiceStartOptions,
+    ) -> Result<ServiceClient<'_>, DeviceClientError> {
+        let mut lockdownd = self.get_lockdownd_client::<DeviceClientError>()?;
+        self.start_service_with(&mut lockdownd, name, &options)
+    }
+
+    /// Starts a service through an existing lockdownd client, see `start_custom_service_with_options`.
+    pub(crate) fn start_service_with(
+        &self,
+        lockdownd: &mut LockdowndClient,
+        name: &str,
+        options: &ServiceStartOptions,
+    ) -> Result<ServiceClient<'_>, DeviceClientError> {
+        let _span = OperationSpan::service(&self.get_device().get_udid(), name);
+        let mut service = lockdownd
+            .start_service(name, options.use_escrow_bag)
+            .map_err(|error| match error {
+                LockdowndError::PasswordProtected | LockdowndError::EscrowLocked => {
+                    DeviceClientError::DeviceLocked
+                }
+                error => DeviceClientError::LockdowndError(error),
+            })?;
+
+        if let Some(use_ssl) = options.use_ssl {
+            service.ssl_enabled = use_ssl;
+        }
+
+        Ok(ServiceClient::new(self.get_device(), service)?)
+    }
+
+    /// Renames the device, then reads the name back to make sure it was applied.
+    ///
+    /// # Parameters
+    /// - `name`: The new name, leading and trailing whitespace is removed.
+    ///
+    /// # Errors
+    /// Returns `DeviceClientError::InvalidDeviceName` if the name is empty, longer than
+    /// `MAX_DEVICE_NAME_LENGTH` bytes or has control characters, and `DeviceClientError::DeviceNameNotApplied`
+    /// if the device kept another name.
+    pub fn set_device_name(&self, name: &str) -> Result<(), DeviceClientError> {
+        let name = name.trim();
+        validate_device_name(name)?;
+
+        let lockdownd = self.get_lockdownd_client::<DeviceClientError>()?;
+        lockdownd.set_value("DeviceName", "", name.into())?;
+
+        let applied = lockdownd
+            .get_value("DeviceName", "")?
+            .get_string_val()
+            .map_err(|_| LockdowndError::InvalidValue)?;
+
+        if applied != name {
+            return Err(DeviceClientError::DeviceNameNotApplied(applied));
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the device from idling until the returned guard is dropped, e.g., for a backup
+    /// or a long AFC transfer, as the device going to sleep drops the connection.
+    ///
+    /// # Errors
+    /// Returns an error if the device is not connected, or if it refused the power assertion.
+    pub fn keep_awake(&self) -> Result<PowerAssertion, DeviceClientError> {
+        self.check_connected::<DeviceClientError>()?;
+        PowerAssertion::start(
+            self.get_device().clone(),
+            RsMobileDeviceConfig::global().label_prefix(),
+        )
+    }
+
+    /// Reads the identity of the pairing of the device with this host from its pair record:
+    /// the host ID, the system BUID, the fingerprint of the device certificate and the Wi-Fi MAC address.
+    ///
+    /// The record is read from the directory of the configuration, or the system one, see `PairRecordFiles::new`.
+    ///
+    /// # Errors
+    /// Returns `DeviceClientError::PairRecordError` if the device isn't paired with this host.
+    pub fn get_pairing_identity(&self) -> Result<PairingIdentity, DeviceClientError> {
+        Ok(PairRecordFiles::new().pairing_identity(&self.get_device().get_udid())?)
+    }
+
+    /// Pairs the device with the host record of the options, and saves the record with the escrow bag
+    /// the device gave back, see `PairingOptions`.
+    ///
+    /// A supervised device asks for its challenge to be signed with the supervision identity it was
+    /// prepared with, so it's paired without the trust prompt, see `PairingOptions::with_supervision`.
+    ///
+    /// # Errors
+    /// Returns `DeviceClientError::PairRecordError`, with `PairRecordError::SupervisionRequired` if the device
+    /// is supervised and the options have no supervision identity, and `PairRecordError::PairingRefused`
+    /// if the device refused the pairing (e.g., "PasswordProtected" or "UserDeniedPairing").
+    pub fn pair(&self, options: &PairingOptions) -> Result<(), DeviceClientError> {
+        self.check_connected::<DeviceClientError>()?;
+        Ok(pairing::pair(self.get_device(), options)?)
+    }
+
+    /// Sends a request lockdownd isn't modeled for by the crate, and returns its response as is.
+    ///
+    /// The request goes through a paired session, so its SSL is handled here, the `Label` is filled in if missing.
+    /// Use `DeviceServiceManager::send_raw_request` to send several of them over the same session.
+    ///
+    /// # Parameters
+    /// - `request`: The request dictionary, with at least its `Request` key (e.g., "GetValue").
+    ///
+    /// # Errors
+    /// Returns `DeviceClientError::InvalidLockdowndRequest` if the request has no `Request` key,
+    /// and `DeviceClientError::LockdowndRequestFailed` if lockdownd answered with an `Error`.
+    pub fn send_raw_lockdownd_request(&self, request: Plist) -> Result<Plist, DeviceClientError> {
+        let lockdownd = self.get_lockdownd_client::<DeviceClientError>()?;
+        send_raw_request(&lockdownd, request)
+    }
+
+    /// Checks whether a lockdown service can be started, and why not, so the callers can degrade gracefully.
+    ///
+    /// The service is actually started then closed right away.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the service (e.g., "com.apple.mobile.screenshotr").
+    ///
+    /// # Errors
+    /// Returns an error if the device is not connected, or if lockdownd itself can't be reached.
+    pub fn is_service_available(
+        &self,
+        name: &str,
+    ) -> Result<ServiceAvailability, DeviceClientError> {
+        let mut lockdownd = self.get_lockdownd_client::<DeviceClientError>()?;
+
+        let error = match lockdownd.start_service(name, false) {
+            Ok(_) => return Ok(ServiceAvailability::Available),
+            Err(error) => error,
+        };
+
+        // Only asked when it matters, older iOS versions don't have the developer mode
+        let developer_mode = match service_availability::is_developer_service(name) {
+            true => lockdownd
+                .get_value("DeveloperModeStatus", DeviceDomains::Amfi.as_string())
+                .and_then(|status| {
+                    status
+                        .get_bool_val()
+                        .map_err(|_| LockdowndError::InvalidValue)
+                })
+                .ok(),
+            false => None,
+        };
+
+        Ok(ServiceAvailability::from_error(name, error, developer_mode))
+    }
+
+    /// Retrieves the family of the device (e.g., iPhone, Apple TV), from its `DeviceClass` or `ProductType`.
+    ///
+    /// # Errors
+    /// Returns an error if the device is not connected or if lockdownd fails.
+    pub fn get_device_kind<E: LockdowndErrorTrait + DeviceNotFoundErrorTrait>(
+        &self,
+    ) -> Result<DeviceKind, E> {
+        let lockdownd = self.get_lockdownd_client::<E>()?;
+        let get_string = |key: DeviceKeys| {
+            lockdownd
+                .get_value(key.to_string(), "")
+                .ok()
+                .and_then(|value| value.get_string_val().ok())
+        };
+
+        Ok(DeviceKind::detect(
+            get_string(DeviceKeys::DeviceClass).as_deref(),
+            get_string(DeviceKeys::ProductType).as_deref(),
+        ))
+    }
+
+    /// Fails with `unsupported_device` if the family of the device doesn't have a feature.
+    ///
+    /// # Parameters
+    /// - `supported`: Whether a family has the feature (e.g., `DeviceKind::has_battery`).
+    #[cfg(any(
+        feature = "diagnostic",
+        feature = "screenshot",
+        feature = "springboard"
+    ))]
+    pub(crate) fn check_supported<E>(&self, supported: fn(&DeviceKind) -> bool) -> Result<(), E>
+    where
+        E: crate::errors::UnsupportedDeviceErrorTrait
+            + LockdowndErrorTrait
+            + DeviceNotFoundErrorTrait,
+    {
+        let kind = self.get_device_kind::<E>()?;
+        if supported(&kind) {
+            Ok(())
+        } else {
+            Err(E::unsupported_device(kind))
+        }
+    }
+
     /// Verifies that the device is currently connected, if it was not found, that means
     /// that the device that was connected when the client was created is no longer connected.
     ///
@@ -128,10 +491,57 @@ impl DeviceClient<SingleDevice> {
 impl DeviceClient<DeviceGroup> {
     /// Retrieves the first available device in the group, if any.
     pub fn get_first_device(self) -> Option<DeviceClient<SingleDevice>> {
-        self.get_devices().first().map(|first_device| DeviceClient {
-            device: Devices::Single(first_device.to_owned()),
-            _p: PhantomData::<SingleDevice>,
-        })
+        self.get_devices()
+            .first()
+            .map(|first_device| single_client(first_device.to_owned(), self.auto_heartbeat))
+    }
+
+    /// Narrows the group down to the devices matching a predicate, e.g., to run the next operations
+    /// only on the iPads running iOS 17 or later.
+    ///
+    /// The devices that can't be queried are still given to the predicate, with the missing values as `None`.
+    ///
+    /// # Parameters
+    /// - `predicate`: Whether to keep a device, given its `DeviceSummary`.
+    pub fn filter<F>(&self, mut predicate: F) -> DeviceClient<DeviceGroup>
+    where
+        F: FnMut(&DeviceSummary) -> bool,
+    {
+        let devices = self
+            .get_devices()
+            .iter()
+            .filter(|device| predicate(&DeviceSummary::read(device)))
+            .cloned()
+            .collect();
+
+        DeviceClient {
+            device: Devices::Multiple(devices),
+            auto_heartbeat: self.auto_heartbeat,
+            heartbeat: None,
+            _p: PhantomData::<DeviceGroup>,
+        }
+    }
+
+    /// Creates a `DeviceSysLog` logging all the devices of the group at once.
+    #[cfg(feature = "syslog")]
+    pub fn get_device_syslog(self) -> DeviceSysLog<DeviceGroup> {
+        DeviceSysLog::new(self)
+    }
+
+    /// Reads the record of every device of the group (UDID, serial number, model, version, battery,
+    /// free storage, activation and supervision state) in parallel, sorted by UDID.
+    ///
+    /// The devices that can't be read are still listed, with the values they didn't give out as `None`.
+    pub fn inventory(&self) -> Vec<DeviceInventory> {
+        inventory::collect(self)
+    }
+
+    /// Creates a client for each device of the group.
+    pub fn get_device_clients(&self) -> Vec<DeviceClient<SingleDevice>> {
+        self.get_devices()
+            .iter()
+            .map(|device| single_client(device.to_owned(), self.auto_heartbeat))
+            .collect()
     }
 
     /// Retrieves a list of all the devices that were connected when the client was created
@@ -152,7 +562,8 @@ impl DeviceClient<DeviceGroup> {
         self.get_devices()
             .iter()
             .map(|device| {
-                AfcClient::start_service(device, "rsmobiledevice-afc_clients")
+                rate_limit::pace(&device.get_udid());
+                AfcClient::start_service(device, crate::config::label("afc_clients"))
                     .map_err(E::afcclient_error)
             })
             .collect()
@@ -169,7 +580,9 @@ impl DeviceClient<DeviceGroup> {
         self.get_devices()
             .iter()
             .map(|device| {
-                LockdowndClient::new(device, "rsmobiledevice-lockdownd-clients")
+                let _span = OperationSpan::lockdownd(&device.get_udid());
+                rate_limit::pace(&device.get_udid());
+                LockdowndClient::new(device, crate::config::label("lockdownd-clients"))
                     .map_err(E::lockdownd_error)
             })
             .collect()
@@ -203,6 +616,120 @@ impl DeviceClient<DeviceGroup> {
     }
 }
 
+/// The values of a device a group is filtered on, see `DeviceClient::filter`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSummary {
+    pub udid: String,
+
+    /// Whether the device is connected over the network
+    pub is_network: bool,
+
+    /// The name of the device (e.g., "John's iPhone")
+    pub name: Option<String>,
+
+    /// The product type (e.g., "iPhone14,2")
+    pub product_type: Option<String>,
+
+    /// The operating system version, with its build
+    pub version: Option<OsVersion>,
+
+    pub kind: DeviceKind,
+}
+
+impl DeviceSummary {
+    fn read(device: &idevice::Device) -> DeviceSummary {
+        let _span = OperationSpan::lockdownd(&device.get_udid());
+        rate_limit::pace(&device.get_udid());
+        let lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client")).ok();
+        let get_string = |key: DeviceKeys| {
+            lockdownd
+                .as_ref()?
+                .get_value(key.to_string(), "")
+                .ok()?
+                .get_string_val()
+                .ok()
+        };
+
+        let product_type = get_string(DeviceKeys::ProductType);
+        let version = get_string(DeviceKeys::ProductVersion)
+            .and_then(|version| version.parse::<OsVersion>().ok())
+            .map(|version| match get_string(DeviceKeys::BuildVersion) {
+                Some(build) => version.with_build(build),
+                None => version,
+            });
+
+        DeviceSummary {
+            udid: device.get_udid(),
+            is_network: device.get_network(),
+            name: get_string(DeviceKeys::DeviceName),
+            kind: DeviceKind::detect(
+                get_string(DeviceKeys::DeviceClass).as_deref(),
+                product_type.as_deref(),
+            ),
+            product_type,
+            version,
+        }
+    }
+}
+
+/// A thread-safe handle to a `DeviceClient`, `Send + Sync` and cheap to clone.
+///
+/// The services borrow the client they're created from, so each thread takes its own copy
+/// of the client with `client`, instead of sharing the services themselves.
+/// Replacing the client (e.g., after the device reconnected) is seen by every holder of the handle.
+#[derive(Debug)]
+pub struct SharedDeviceClient<T = DeviceGroup> {
+    client: Arc<Mutex<DeviceClient<T>>>,
+}
+
+impl<T> SharedDeviceClient<T> {
+    pub fn new(client: DeviceClient<T>) -> SharedDeviceClient<T> {
+        SharedDeviceClient {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    /// Returns a copy of the current client, owned by the calling thread.
+    pub fn client(&self) -> DeviceClient<T>
+    where
+        T: Clone,
+    {
+        // The client is never left half updated, so a poisoned lock is still usable
+        self.client
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the client for every holder of the handle, returning the previous one.
+    pub fn replace(&self, client: DeviceClient<T>) -> DeviceClient<T> {
+        let mut current = self.client.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::replace(&mut *current, client)
+    }
+}
+
+impl<T> Clone for SharedDeviceClient<T> {
+    fn clone(&self) -> Self {
+        SharedDeviceClient {
+            client: Arc::clone(&self.client),
+        }
+    }
+}
+
+// The clients are moved to the worker threads (syslog, backups, fleets), this keeps them movable
+const _: () = {
+    fn assert_send<T: Send>() {}
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[allow(dead_code)]
+    fn assert_thread_safety() {
+        assert_send::<DeviceClient<SingleDevice>>();
+        assert_send::<DeviceClient<DeviceGroup>>();
+        assert_send_sync::<SharedDeviceClient<SingleDevice>>();
+        assert_send_sync::<SharedDeviceClient<DeviceGroup>>();
+    }
+};
+
 impl TryFrom<String> for DeviceClient {
     type Error = DeviceClientError;
 
@@ -213,8 +740,86 @@ impl TryFrom<String> for DeviceClient {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let device = idevice::get_device(value)?;
         Ok(Self {
+            heartbeat: start_heartbeat(&device),
             device: Devices::Single(device),
+            auto_heartbeat: true,
             _p: PhantomData,
         })
     }
 }
+
+impl<T> PartialEq for DeviceClient<T> {
+    /// Two clients are equal when they're for the same devices, whatever their heartbeats.
+    fn eq(&self, other: &Self) -> bool {
+        self.device == other.device
+    }
+}
+
+fn single_client(device: idevice::Device, auto_heartbeat: bool) -> DeviceClient<SingleDevice> {
+    let heartbeat = if auto_heartbeat {
+        start_heartbeat(&device)
+    } else {
+        None
+    };
+
+    DeviceClient {
+        device: Devices::Single(device),
+        auto_heartbeat,
+        heartbeat,
+        _p: PhantomData::<SingleDevice>,
+    }
+}
+
+/// Starts the heartbeat of a device connected over the network, the USB ones don't need it.
+fn start_heartbeat(device: &idevice::Device) -> Option<Arc<HeartbeatHandle>> {
+    device
+        .get_network()
+        .then(|| Arc::new(HeartbeatHandle::start(device.clone())))
+}
+
+/// Sends a raw request through a lockdownd session, see `DeviceClient::send_raw_lockdownd_request`.
+pub(crate) fn send_raw_request(
+    lockdownd: &LockdowndClient,
+    mut request: Plist,
+) -> Result<Plist, DeviceClientError> {
+    let name = request
+        .dict_get_item("Request")
+        .and_then(|name| name.get_string_val())
+        .map_err(|_| DeviceClientError::InvalidLockdowndRequest)?;
+    if request.dict_get_item("Label").is_err() {
+        request.dict_set_item("Label", crate::config::label("lockdownd-client").into())?;
+    }
+
+    lockdownd.send(request)?;
+    let response = lockdownd.receive()?;
+
+    if let Ok(error) = response
+        .dict_get_item("Error")
+        .and_then(|error| error.get_string_val())
+    {
+        return Err(DeviceClientError::LockdowndRequestFailed(name, error));
+    }
+
+    Ok(response)
+}
+
+fn validate_device_name(name: &str) -> Result<(), DeviceClientError> {
+    if name.is_empty() {
+        return Err(DeviceClientError::InvalidDeviceName(
+            "the name is empty".into(),
+        ));
+    }
+    if name.len() > MAX_DEVICE_NAME_LENGTH {
+        return Err(DeviceClientError::InvalidDeviceName(format!(
+            "the name is longer than {} bytes",
+            MAX_DEVICE_NAME_LENGTH
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(DeviceClientError::InvalidDeviceName(
+            "the name has control characters".into(),
+        ));
+    }
+
+    Ok(())
+}
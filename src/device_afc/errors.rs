@@ -0,0 +1,46 @@
+use crate::errors::{AFCClientErrorTrait, DeviceNotFoundErrorTrait};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{AfcError, HouseArrestError, InstProxyError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceAfcError {
+    #[error("AFC Client Error: {0}")]
+    AfcClientError(#[from] AfcError),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("The device didn't report the `{0}` information")]
+    MissingInfo(String),
+
+    #[error("The time is before the UNIX epoch")]
+    InvalidTime,
+
+    #[error("Installation Proxy Error: {0}")]
+    InstallationProxyError(#[from] InstProxyError),
+
+    #[error("House Arrest Error: {0}")]
+    HouseArrestError(#[from] HouseArrestError),
+
+    #[error("The container of `{bundle_id}` can't be accessed: {reason}")]
+    ContainerUnavailable { bundle_id: String, reason: String },
+
+    #[error("Plist Error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl AFCClientErrorTrait for DeviceAfcError {
+    fn afcclient_error(error: AfcError) -> Self {
+        Self::AfcClientError(error)
+    }
+}
+
+impl DeviceNotFoundErrorTrait for DeviceAfcError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
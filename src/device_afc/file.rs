@@ -0,0 +1,93 @@
+//! AFC files exposed through the standard `Read`, `Write` and `Seek` traits.
+
+use rusty_libimobiledevice::{error::AfcError, services::afc::AfcClient};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// The largest chunk read or written in a single AFC request
+const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+const SEEK_SET: i32 = 0;
+const SEEK_CUR: i32 = 1;
+const SEEK_END: i32 = 2;
+
+/// An open file on the device.
+///
+/// It implements `std::io::Read`, `Write` and `Seek`, so it can be used with any code working
+/// on files (e.g., `io::copy`, the archive or image crates) without loading it in memory first.
+/// It has its own AFC connection, and the file is closed when it's dropped.
+pub struct AfcFile<'a> {
+    client: AfcClient<'a>,
+    handle: u64,
+}
+
+impl<'a> AfcFile<'a> {
+    pub(crate) fn new(client: AfcClient<'a>, handle: u64) -> AfcFile<'a> {
+        AfcFile { client, handle }
+    }
+}
+
+impl Read for AfcFile<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = buf.len().min(MAX_CHUNK_SIZE);
+        if length == 0 {
+            return Ok(0);
+        }
+
+        let data = self
+            .client
+            .file_read(self.handle, length as u32)
+            .map_err(to_io_error)?;
+
+        // The device shouldn't send more than asked, but it's not worth panicking over
+        let count = data.len().min(length);
+        buf[..count].copy_from_slice(&data[..count]);
+        Ok(count)
+    }
+}
+
+impl Write for AfcFile<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let length = buf.len().min(MAX_CHUNK_SIZE);
+        self.client
+            .file_write(self.handle, buf[..length].to_vec())
+            .map_err(to_io_error)?;
+        Ok(length)
+    }
+
+    /// The writes are sent right away, there's nothing to flush
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for AfcFile<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, SEEK_SET),
+            SeekFrom::Current(offset) => (offset, SEEK_CUR),
+            SeekFrom::End(offset) => (offset, SEEK_END),
+        };
+
+        self.client
+            .file_seek(self.handle, offset, whence)
+            .map_err(to_io_error)?;
+        self.client.file_tell(self.handle).map_err(to_io_error)
+    }
+}
+
+impl Drop for AfcFile<'_> {
+    fn drop(&mut self) {
+        let _ = self.client.file_close(self.handle);
+    }
+}
+
+fn to_io_error(error: AfcError) -> io::Error {
+    let kind = match error {
+        AfcError::ObjectNotFound => io::ErrorKind::NotFound,
+        AfcError::PermDenied => io::ErrorKind::PermissionDenied,
+        AfcError::OpTimeout => io::ErrorKind::TimedOut,
+        AfcError::OpInterrupted => io::ErrorKind::Interrupted,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, error)
+}
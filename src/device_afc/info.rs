@@ -0,0 +1,108 @@
+//! Typed metadata of the files on the device.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Enum representing the type of a file on the device.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AfcFileKind {
+    File,
+    Directory,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+    /// A type not known by this library, with its raw value
+    Unknown(String),
+}
+
+impl From<&str> for AfcFileKind {
+    fn from(value: &str) -> Self {
+        match value {
+            "S_IFREG" => AfcFileKind::File,
+            "S_IFDIR" => AfcFileKind::Directory,
+            "S_IFLNK" => AfcFileKind::Symlink,
+            "S_IFCHR" => AfcFileKind::CharDevice,
+            "S_IFBLK" => AfcFileKind::BlockDevice,
+            "S_IFIFO" => AfcFileKind::Fifo,
+            "S_IFSOCK" => AfcFileKind::Socket,
+            other => AfcFileKind::Unknown(other.to_string()),
+        }
+    }
+}
+
+/// The metadata of a file on the device, as returned by `DeviceAfc::stat`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AfcFileInfo {
+    /// The type of the file
+    pub kind: AfcFileKind,
+
+    /// The size, in bytes
+    pub size: u64,
+
+    /// The number of allocated blocks, in 512 bytes units
+    pub blocks: u64,
+
+    /// The number of hard links
+    pub nlink: u64,
+
+    /// The last modification time
+    pub mtime: Option<SystemTime>,
+
+    /// The creation time
+    pub birthtime: Option<SystemTime>,
+
+    /// The target of the link, for the symlinks
+    pub link_target: Option<String>,
+}
+
+impl AfcFileInfo {
+    pub fn is_dir(&self) -> bool {
+        self.kind == AfcFileKind::Directory
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.kind == AfcFileKind::File
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.kind == AfcFileKind::Symlink
+    }
+
+    /// The space actually taken on the disk, in bytes
+    pub fn allocated(&self) -> u64 {
+        self.blocks * 512
+    }
+}
+
+impl From<&HashMap<String, String>> for AfcFileInfo {
+    /// Parses the raw AFC file info, the missing or invalid values are set to 0 or `None`
+    fn from(info: &HashMap<String, String>) -> Self {
+        let number = |key: &str| info.get(key).and_then(|value| value.parse::<u64>().ok());
+        // The times are in nanoseconds since the UNIX epoch
+        let time = |key: &str| number(key).map(|nanos| UNIX_EPOCH + Duration::from_nanos(nanos));
+
+        AfcFileInfo {
+            kind: info
+                .get("st_ifmt")
+                .map_or(AfcFileKind::Unknown(String::new()), |kind| {
+                    AfcFileKind::from(kind.as_str())
+                }),
+            size: number("st_size").unwrap_or(0),
+            blocks: number("st_blocks").unwrap_or(0),
+            nlink: number("st_nlink").unwrap_or(0),
+            mtime: time("st_mtime"),
+            birthtime: time("st_birthtime"),
+            link_target: info.get("LinkTarget").cloned(),
+        }
+    }
+}
+
+/// Converts a time to the nanoseconds since the UNIX epoch used by AFC
+pub(crate) fn to_afc_time(time: SystemTime) -> Option<u64> {
+    let duration = time.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(duration.as_nanos()).ok()
+}
@@ -0,0 +1,324 @@
+//! Provides access to the device media filesystem through AFC (Apple File Conduit)
+//!
+//! It gives access to the `/var/mobile/Media` partition, where the photos, the downloads and
+//! the packages staging folders are.
+//!
+//! ## Features
+//! - Opening files as standard `Read`, `Write` and `Seek` types
+//! - Uploading and downloading whole directories with progress
+//! - Pulling the photos and videos of the camera roll
+//! - Reporting the storage usage
+//! - Renaming, linking, truncating files and reading their metadata
+//! - Listing the apps with file sharing enabled
+//!
+
+use std::{marker::PhantomData, path::Path, time::SystemTime};
+
+pub(crate) mod errors;
+pub mod file;
+pub mod info;
+pub mod photos;
+pub mod sharing;
+pub mod transfer;
+pub mod usage;
+
+pub use file::AfcFile;
+pub use info::{AfcFileInfo, AfcFileKind};
+pub use photos::{MediaType, PhotoPullOptions};
+pub use rusty_libimobiledevice::services::afc::{AfcFileMode, AfcLinkType};
+pub use sharing::FileSharingApp;
+pub use transfer::{TransferOptions, TransferProgress};
+pub use usage::{AfcDeviceInfo, DiskUsage};
+
+use crate::{
+    device::DeviceClient, devices_collection::SingleDevice, errors::DeviceAfcError,
+    instrument::OperationSpan,
+};
+use info::to_afc_time;
+
+/// Struct for managing the files of a device through AFC
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceAfc<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceAfc<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceAfc<'a, T> {
+        DeviceAfc {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl<'a> DeviceAfc<'a, SingleDevice> {
+    /// Opens a file on the device with the given mode.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the file, relative to the media partition.
+    /// - `mode`: How to open the file, `AfcFileMode::WriteOnly` creates or truncates it.
+    pub fn open(&self, path: &str, mode: AfcFileMode) -> Result<AfcFile<'a>, DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        let handle = afc_client.file_open(path, mode)?;
+
+        Ok(AfcFile::new(afc_client, handle))
+    }
+
+    /// Opens a file on the device for reading.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the file, relative to the media partition.
+    pub fn open_read(&self, path: &str) -> Result<AfcFile<'a>, DeviceAfcError> {
+        self.open(path, AfcFileMode::ReadOnly)
+    }
+
+    /// Creates a file on the device for writing, it's truncated if it exists.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the file, relative to the media partition.
+    pub fn create(&self, path: &str) -> Result<AfcFile<'a>, DeviceAfcError> {
+        self.open(path, AfcFileMode::WriteOnly)
+    }
+
+    /// Opens a file on the device for appending, it's created if it doesn't exist.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the file, relative to the media partition.
+    pub fn append(&self, path: &str) -> Result<AfcFile<'a>, DeviceAfcError> {
+        self.open(path, AfcFileMode::Append)
+    }
+
+    /// Uploads a local directory to the device, keeping its structure and the files modification times.
+    ///
+    /// # Parameters
+    /// - `local_path`: The directory to upload.
+    /// - `remote_path`: The destination directory on the device, it's created if it doesn't exist.
+    /// - `on_progress`: A function called with the progress after each chunk and each file.
+    pub fn upload_dir<S, F>(
+        &self,
+        local_path: &S,
+        remote_path: &str,
+        on_progress: F,
+    ) -> Result<(), DeviceAfcError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&TransferProgress),
+    {
+        self.upload_dir_with_options(
+            local_path,
+            remote_path,
+            TransferOptions::default(),
+            on_progress,
+        )
+    }
+
+    /// Uploads a local directory to the device with the given options, see `upload_dir`.
+    pub fn upload_dir_with_options<S, F>(
+        &self,
+        local_path: &S,
+        remote_path: &str,
+        options: TransferOptions,
+        on_progress: F,
+    ) -> Result<(), DeviceAfcError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&TransferProgress),
+    {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        let span = OperationSpan::transfer(&self.device.get_device().get_udid(), "afc_upload");
+        traced_transfer(&span, on_progress, |on_progress| {
+            transfer::upload_dir(
+                &afc_client,
+                local_path.as_ref(),
+                remote_path,
+                options,
+                on_progress,
+            )
+        })
+    }
+
+    /// Downloads a directory of the device, keeping its structure and the files modification times.
+    ///
+    /// # Parameters
+    /// - `remote_path`: The directory to download.
+    /// - `local_path`: The destination directory, it's created if it doesn't exist.
+    /// - `on_progress`: A function called with the progress after each chunk and each file.
+    pub fn download_dir<S, F>(
+        &self,
+        remote_path: &str,
+        local_path: &S,
+        on_progress: F,
+    ) -> Result<(), DeviceAfcError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&TransferProgress),
+    {
+        self.download_dir_with_options(
+            remote_path,
+            local_path,
+            TransferOptions::default(),
+            on_progress,
+        )
+    }
+
+    /// Downloads a directory of the device with the given options, see `download_dir`.
+    pub fn download_dir_with_options<S, F>(
+        &self,
+        remote_path: &str,
+        local_path: &S,
+        options: TransferOptions,
+        on_progress: F,
+    ) -> Result<(), DeviceAfcError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&TransferProgress),
+    {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        let span = OperationSpan::transfer(&self.device.get_device().get_udid(), "afc_download");
+        traced_transfer(&span, on_progress, |on_progress| {
+            transfer::download_dir(
+                &afc_client,
+                remote_path,
+                local_path.as_ref(),
+                options,
+                on_progress,
+            )
+        })
+    }
+
+    /// Pulls the photos and videos of the camera roll (`/DCIM`), keeping the `100APPLE`-like folders.
+    ///
+    /// The pulled files are listed in a manifest in the destination directory (see `photos::MANIFEST_FILE_NAME`),
+    /// so the next pulls only copy the new ones, even if the previous files were moved away since.
+    ///
+    /// # Parameters
+    /// - `local_path`: The destination directory, it's created if it doesn't exist.
+    /// - `options`: Which files to pull, see `PhotoPullOptions`.
+    /// - `on_progress`: A function called with the progress after each chunk and each file.
+    ///
+    /// # Returns
+    /// The number of pulled files.
+    pub fn pull_photos<S, F>(
+        &self,
+        local_path: &S,
+        options: PhotoPullOptions,
+        on_progress: F,
+    ) -> Result<usize, DeviceAfcError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&TransferProgress),
+    {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        let span = OperationSpan::transfer(&self.device.get_device().get_udid(), "afc_pull_photos");
+        traced_transfer(&span, on_progress, |on_progress| {
+            photos::pull_photos(&afc_client, local_path.as_ref(), &options, on_progress)
+        })
+    }
+
+    /// Retrieves the filesystem information of the media partition (total and free space, block size).
+    pub fn get_device_info(&self) -> Result<AfcDeviceInfo, DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        AfcDeviceInfo::from_info(&afc_client.get_device_info()?)
+    }
+
+    /// Computes the usage of a directory and each of its subdirectories, like `du`.
+    ///
+    /// This walks the whole directory, so it can take a while on large ones (e.g., `DCIM`).
+    ///
+    /// # Parameters
+    /// - `path`: The directory, relative to the media partition (`/` for the whole partition).
+    pub fn du(&self, path: &str) -> Result<DiskUsage, DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        usage::disk_usage(&afc_client, path)
+    }
+
+    /// Retrieves the metadata of a file or directory.
+    ///
+    /// # Parameters
+    /// - `path`: The path, relative to the media partition.
+    pub fn stat(&self, path: &str) -> Result<AfcFileInfo, DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        Ok(AfcFileInfo::from(&afc_client.get_file_info(path)?))
+    }
+
+    /// Renames (moves) a file or directory.
+    ///
+    /// # Parameters
+    /// - `from`: The current path.
+    /// - `to`: The new path.
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        Ok(afc_client.rename_path(from, to)?)
+    }
+
+    /// Creates a link to a file or directory.
+    ///
+    /// # Parameters
+    /// - `link_type`: Whether to create a symbolic or a hard link.
+    /// - `target`: The path the link points to.
+    /// - `link_path`: The path of the link itself.
+    pub fn make_link(
+        &self,
+        link_type: AfcLinkType,
+        target: &str,
+        link_path: &str,
+    ) -> Result<(), DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        Ok(afc_client.make_link(link_type, target, link_path)?)
+    }
+
+    /// Truncates (or extends) a file to the given size.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the file.
+    /// - `size`: The new size, in bytes.
+    pub fn truncate(&self, path: &str, size: u64) -> Result<(), DeviceAfcError> {
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        Ok(afc_client.truncate(path, size)?)
+    }
+
+    /// Sets the modification time of a file or directory.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the file.
+    /// - `mtime`: The new modification time, it must be after the UNIX epoch.
+    pub fn set_file_time(&self, path: &str, mtime: SystemTime) -> Result<(), DeviceAfcError> {
+        let mtime = to_afc_time(mtime).ok_or(DeviceAfcError::InvalidTime)?;
+
+        let afc_client = self.device.get_afc_client::<DeviceAfcError>()?;
+        Ok(afc_client.set_file_time(path, mtime)?)
+    }
+
+    /// Lists the apps whose Documents folder can be accessed (i.e., with file sharing enabled).
+    ///
+    /// The apps declaring `UIFileSharingEnabled` are checked against house_arrest,
+    /// so only the ones the device actually gives access to are returned, sorted by bundle identifier.
+    pub fn file_sharing_apps(&self) -> Result<Vec<FileSharingApp>, DeviceAfcError> {
+        self.device.check_connected::<DeviceAfcError>()?;
+        sharing::file_sharing_apps(self.device.get_device())
+    }
+}
+
+/// Runs a transfer, recording the transferred bytes in its span.
+fn traced_transfer<F, R>(
+    span: &OperationSpan,
+    mut on_progress: F,
+    transfer: impl FnOnce(&mut dyn FnMut(&TransferProgress)) -> Result<R, DeviceAfcError>,
+) -> Result<R, DeviceAfcError>
+where
+    F: FnMut(&TransferProgress),
+{
+    let mut transferred_bytes = 0;
+    let result = transfer(&mut |progress: &TransferProgress<'_>| {
+        transferred_bytes = progress.transferred_bytes;
+        on_progress(progress);
+    });
+    span.record_bytes(transferred_bytes);
+
+    result
+}
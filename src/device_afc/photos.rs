@@ -0,0 +1,196 @@
+//! Pulling the photos and videos of the camera roll (`/DCIM`).
+
+use crate::{
+    device_afc::transfer::{self, join_remote, Entry, TransferProgress},
+    errors::DeviceAfcError,
+};
+use rusty_libimobiledevice::services::afc::AfcClient;
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::SystemTime,
+};
+
+const DCIM_DIR: &str = "/DCIM";
+
+/// The file, in the destination directory, keeping track of the pulled files
+pub const MANIFEST_FILE_NAME: &str = ".dcim_manifest";
+
+const PHOTO_EXTENSIONS: [&str; 9] = [
+    "jpg", "jpeg", "heic", "heif", "png", "gif", "dng", "tif", "tiff",
+];
+const VIDEO_EXTENSIONS: [&str; 5] = ["mov", "mp4", "m4v", "hevc", "avi"];
+
+/// Enum representing the kind of media to pull.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MediaType {
+    Photos,
+    Videos,
+    /// Photos, videos and the rest of the files (e.g., the `.AAE` edit sidecars)
+    #[default]
+    All,
+}
+
+impl MediaType {
+    fn matches(&self, path: &str) -> bool {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match self {
+            MediaType::Photos => PHOTO_EXTENSIONS.contains(&extension.as_str()),
+            MediaType::Videos => VIDEO_EXTENSIONS.contains(&extension.as_str()),
+            MediaType::All => true,
+        }
+    }
+}
+
+/// Options for `DeviceAfc::pull_photos`.
+///
+/// By default, everything in `/DCIM` is pulled, and the files already pulled are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhotoPullOptions {
+    media_type: MediaType,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+    use_manifest: bool,
+}
+
+impl Default for PhotoPullOptions {
+    fn default() -> Self {
+        PhotoPullOptions {
+            media_type: MediaType::All,
+            since: None,
+            until: None,
+            use_manifest: true,
+        }
+    }
+}
+
+impl PhotoPullOptions {
+    pub fn new() -> PhotoPullOptions {
+        PhotoPullOptions::default()
+    }
+
+    /// Pulls only the given kind of media
+    pub fn with_media_type(mut self, media_type: MediaType) -> PhotoPullOptions {
+        self.media_type = media_type;
+        self
+    }
+
+    /// Pulls only the files modified in the given range, either bound can be left open
+    pub fn with_date_range(
+        mut self,
+        since: Option<SystemTime>,
+        until: Option<SystemTime>,
+    ) -> PhotoPullOptions {
+        self.since = since;
+        self.until = until;
+        self
+    }
+
+    /// Whether to skip the files listed in the manifest of the destination directory.
+    ///
+    /// The manifest is still updated when it's disabled, so the next pulls can use it.
+    pub fn with_manifest(mut self, use_manifest: bool) -> PhotoPullOptions {
+        self.use_manifest = use_manifest;
+        self
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        if entry.is_dir || !self.media_type.matches(&entry.relative_path) {
+            return false;
+        }
+
+        let in_range = |check: fn(&SystemTime, &SystemTime) -> bool, bound: Option<SystemTime>| {
+            match (bound, entry.mtime) {
+                (Some(bound), Some(mtime)) => check(&mtime, &bound),
+                // The files without a date can't be placed in the range
+                (Some(_), None) => false,
+                (None, _) => true,
+            }
+        };
+
+        in_range(SystemTime::ge, self.since) && in_range(SystemTime::le, self.until)
+    }
+}
+
+/// Pulls the camera roll to the host, see `DeviceAfc::pull_photos`.
+///
+/// Returns the number of pulled files.
+pub(crate) fn pull_photos<F>(
+    afc_client: &AfcClient,
+    local_path: &Path,
+    options: &PhotoPullOptions,
+    mut on_progress: F,
+) -> Result<usize, DeviceAfcError>
+where
+    F: FnMut(&TransferProgress),
+{
+    fs::create_dir_all(local_path)?;
+
+    let manifest_path = local_path.join(MANIFEST_FILE_NAME);
+    let manifest = match options.use_manifest {
+        true => read_manifest(&manifest_path)?,
+        false => HashMap::new(),
+    };
+
+    let mut entries = Vec::new();
+    transfer::walk_remote(afc_client, DCIM_DIR, "", &mut entries)?;
+
+    let entries = entries
+        .into_iter()
+        .filter(|entry| options.matches(entry))
+        .filter(|entry| manifest.get(&entry.relative_path) != Some(&entry.size))
+        .collect::<Vec<_>>();
+
+    let mut progress = transfer::new_progress(&entries, &mut on_progress);
+    let mut manifest_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&manifest_path)?;
+
+    for entry in &entries {
+        let local_file_path = local_path.join(&entry.relative_path);
+        if let Some(parent) = local_file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        transfer::download_file(
+            afc_client,
+            &join_remote(DCIM_DIR, &entry.relative_path),
+            &local_file_path,
+            entry,
+            true,
+            &mut progress,
+        )?;
+
+        // Written after each file, so an interrupted pull doesn't start over
+        writeln!(manifest_file, "{}\t{}", entry.size, entry.relative_path)?;
+
+        progress.files_done += 1;
+        progress.report(&entry.relative_path, entry.size, entry.size);
+    }
+
+    Ok(entries.len())
+}
+
+/// Reads the manifest, mapping the pulled files to their size.
+fn read_manifest(manifest_path: &Path) -> Result<HashMap<String, u64>, DeviceAfcError> {
+    let manifest = match fs::read_to_string(manifest_path) {
+        Ok(manifest) => manifest,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(manifest
+        .lines()
+        .filter_map(|line| {
+            let (size, path) = line.split_once('\t')?;
+            Some((path.to_string(), size.parse().ok()?))
+        })
+        .collect())
+}
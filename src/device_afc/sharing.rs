@@ -0,0 +1,123 @@
+//! Apps exposing their Documents folder through file sharing (the "Files" section in Finder/iTunes).
+
+use crate::errors::DeviceAfcError;
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    idevice::Device,
+    services::{house_arrest::HouseArrest, instproxy::InstProxyClient},
+};
+
+#[cfg(feature = "backup")]
+use rusty_libimobiledevice::services::afc::AfcClient;
+
+const HOUSE_ARREST_LABEL: &str = "deviceafc";
+
+/// An app whose Documents folder can be accessed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSharingApp {
+    /// The bundle identifier (e.g., "com.apple.Pages")
+    pub bundle_id: String,
+
+    /// The name shown under the app icon, if any
+    pub name: Option<String>,
+
+    /// The version of the app, if any
+    pub version: Option<String>,
+}
+
+/// Lists the user apps with `UIFileSharingEnabled`, keeping only the ones house_arrest gives access to.
+pub(crate) fn file_sharing_apps(device: &Device) -> Result<Vec<FileSharingApp>, DeviceAfcError> {
+    let instproxy_client = device.new_instproxy_client(crate::config::label(HOUSE_ARREST_LABEL))?;
+
+    let mut attributes = Plist::new_array();
+    for attribute in [
+        "CFBundleIdentifier",
+        "CFBundleDisplayName",
+        "CFBundleName",
+        "CFBundleShortVersionString",
+        "UIFileSharingEnabled",
+    ] {
+        attributes.array_append_item(Plist::new_string(attribute))?;
+    }
+
+    let mut options = InstProxyClient::client_options_new();
+    options.dict_set_item("ApplicationType", Plist::new_string("User"))?;
+    options.dict_set_item("ReturnAttributes", attributes)?;
+
+    let apps = instproxy_client.browse(Some(options))?;
+
+    let mut sharing_apps = Vec::new();
+
+    for index in 0..apps.array_get_size()? {
+        let app = apps.array_get_item(index)?;
+
+        let file_sharing = app
+            .dict_get_item("UIFileSharingEnabled")
+            .and_then(|value| value.get_bool_val())
+            .unwrap_or(false);
+        if !file_sharing {
+            continue;
+        }
+
+        let string = |key: &str| {
+            app.dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+
+        let Some(bundle_id) = string("CFBundleIdentifier") else {
+            continue;
+        };
+
+        // Some apps declare the key but are refused anyway (e.g., restricted by a profile)
+        if !can_vend_documents(device, &bundle_id)? {
+            continue;
+        }
+
+        sharing_apps.push(FileSharingApp {
+            name: string("CFBundleDisplayName").or_else(|| string("CFBundleName")),
+            version: string("CFBundleShortVersionString"),
+            bundle_id,
+        });
+    }
+
+    sharing_apps.sort_by(|a, b| a.bundle_id.cmp(&b.bundle_id));
+
+    Ok(sharing_apps)
+}
+
+/// Asks house_arrest for the Documents folder of the app, it answers with an `Error` key when it's refused.
+///
+/// A house_arrest connection can only vend a single app, hence a new connection each time.
+fn can_vend_documents(device: &Device, bundle_id: &str) -> Result<bool, DeviceAfcError> {
+    let house_arrest =
+        HouseArrest::start_service(device, crate::config::label(HOUSE_ARREST_LABEL))?;
+    house_arrest.send_command("VendDocuments", bundle_id)?;
+
+    let result = house_arrest.get_result()?;
+
+    Ok(result.dict_get_item("Error").is_err())
+}
+
+/// Opens the whole container of an app (`Documents`, `Library` and `tmp`) through house_arrest.
+///
+/// The device only vends the container of the apps installed with a development profile.
+#[cfg(feature = "backup")]
+pub(crate) fn app_container_client<'a>(
+    device: &'a Device,
+    bundle_id: &str,
+) -> Result<AfcClient<'a>, DeviceAfcError> {
+    let house_arrest =
+        HouseArrest::start_service(device, crate::config::label(HOUSE_ARREST_LABEL))?;
+    house_arrest.send_command("VendContainer", bundle_id)?;
+
+    let result = house_arrest.get_result()?;
+    if let Ok(error) = result.dict_get_item("Error") {
+        return Err(DeviceAfcError::ContainerUnavailable {
+            bundle_id: bundle_id.to_string(),
+            reason: error.get_string_val().unwrap_or_default(),
+        });
+    }
+
+    Ok(AfcClient::new_from_house_arrest(house_arrest)?)
+}
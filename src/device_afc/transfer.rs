@@ -0,0 +1,387 @@
+//! Recursive transfers of directories between the host and the device.
+
+use crate::{
+    device_afc::info::{to_afc_time, AfcFileInfo},
+    errors::DeviceAfcError,
+    progress::{AsProgressUpdate, ProgressObserver, ProgressUpdate},
+};
+use rusty_libimobiledevice::services::afc::{AfcClient, AfcFileMode};
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Options of the directory transfers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferOptions {
+    /// Skips the files that have the same size and modification time on both sides
+    pub skip_unchanged: bool,
+
+    /// Sets the modification time of the copied files to the one of their source
+    pub preserve_mtime: bool,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        TransferOptions {
+            skip_unchanged: false,
+            preserve_mtime: true,
+        }
+    }
+}
+
+/// The progress of a directory transfer, reported after each chunk and each skipped file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferProgress<'p> {
+    /// The path of the current file, relative to the transferred directory
+    pub path: &'p str,
+
+    /// The bytes of the current file transferred so far
+    pub file_bytes: u64,
+
+    /// The size of the current file
+    pub file_size: u64,
+
+    /// The bytes of all the files transferred so far, including the skipped ones
+    pub transferred_bytes: u64,
+
+    /// The size of all the files
+    pub total_bytes: u64,
+
+    /// The number of files done, including the skipped ones
+    pub files_done: usize,
+
+    /// The number of files
+    pub total_files: usize,
+}
+
+impl AsProgressUpdate for TransferProgress<'_> {
+    /// The updates are in bytes, with the current file as the message.
+    fn as_progress_update(&self) -> ProgressUpdate<'_> {
+        ProgressUpdate {
+            phase: "Transferring",
+            current: self.transferred_bytes,
+            total: Some(self.total_bytes),
+            message: Some(self.path),
+        }
+    }
+}
+
+/// Adapts a `ProgressObserver` to the transfer callbacks, such as `DeviceAfc::upload_dir`.
+pub fn observe_transfer<O>(observer: &mut O) -> impl FnMut(&TransferProgress) + '_
+where
+    O: ProgressObserver,
+{
+    move |progress: &TransferProgress<'_>| observer.on_progress(&progress.as_progress_update())
+}
+
+/// A file or directory to transfer, with its path relative to the transferred directory.
+pub(crate) struct Entry {
+    pub(crate) relative_path: String,
+    pub(crate) is_dir: bool,
+    pub(crate) size: u64,
+    pub(crate) mtime: Option<SystemTime>,
+}
+
+/// Keeps the totals of a transfer and reports them.
+pub(crate) struct Progress<'f, F: FnMut(&TransferProgress)> {
+    total_bytes: u64,
+    total_files: usize,
+    transferred_bytes: u64,
+    pub(crate) files_done: usize,
+    on_progress: &'f mut F,
+}
+
+impl<F: FnMut(&TransferProgress)> Progress<'_, F> {
+    pub(crate) fn report(&mut self, path: &str, file_bytes: u64, file_size: u64) {
+        (self.on_progress)(&TransferProgress {
+            path,
+            file_bytes,
+            file_size,
+            transferred_bytes: self.transferred_bytes,
+            total_bytes: self.total_bytes,
+            files_done: self.files_done,
+            total_files: self.total_files,
+        });
+    }
+}
+
+/// Uploads a local directory to the device, see `DeviceAfc::upload_dir`.
+pub(crate) fn upload_dir<F>(
+    afc_client: &AfcClient,
+    local_path: &Path,
+    remote_path: &str,
+    options: TransferOptions,
+    mut on_progress: F,
+) -> Result<(), DeviceAfcError>
+where
+    F: FnMut(&TransferProgress),
+{
+    let mut entries = Vec::new();
+    walk_local(local_path, "", &mut entries)?;
+
+    let mut progress = new_progress(&entries, &mut on_progress);
+
+    make_remote_dir(afc_client, remote_path)?;
+
+    for entry in &entries {
+        let remote_file = join_remote(remote_path, &entry.relative_path);
+
+        if entry.is_dir {
+            make_remote_dir(afc_client, &remote_file)?;
+            continue;
+        }
+
+        let unchanged = options.skip_unchanged
+            && remote_size_and_mtime(afc_client, &remote_file)
+                .is_some_and(|remote| same_file(remote, (entry.size, entry.mtime)));
+
+        if !unchanged {
+            let mut local_file = File::open(local_path.join(&entry.relative_path))?;
+            let handle = afc_client.file_open(remote_file.as_str(), AfcFileMode::WriteOnly)?;
+
+            let result = copy_chunks(&mut progress, &entry.relative_path, entry.size, |buffer| {
+                let count = local_file.read(buffer)?;
+                if count > 0 {
+                    afc_client.file_write(handle, buffer[..count].to_vec())?;
+                }
+                Ok(count)
+            });
+            afc_client.file_close(handle)?;
+            result?;
+
+            if options.preserve_mtime {
+                if let Some(mtime) = entry.mtime.and_then(to_afc_time) {
+                    afc_client.set_file_time(remote_file.as_str(), mtime)?;
+                }
+            }
+        }
+
+        progress.files_done += 1;
+        if unchanged {
+            progress.transferred_bytes += entry.size;
+        }
+        progress.report(&entry.relative_path, entry.size, entry.size);
+    }
+
+    Ok(())
+}
+
+/// Downloads a directory of the device to the host, see `DeviceAfc::download_dir`.
+pub(crate) fn download_dir<F>(
+    afc_client: &AfcClient,
+    remote_path: &str,
+    local_path: &Path,
+    options: TransferOptions,
+    mut on_progress: F,
+) -> Result<(), DeviceAfcError>
+where
+    F: FnMut(&TransferProgress),
+{
+    let mut entries = Vec::new();
+    walk_remote(afc_client, remote_path, "", &mut entries)?;
+
+    let mut progress = new_progress(&entries, &mut on_progress);
+
+    fs::create_dir_all(local_path)?;
+
+    for entry in &entries {
+        let local_file_path = local_path.join(&entry.relative_path);
+
+        if entry.is_dir {
+            fs::create_dir_all(&local_file_path)?;
+            continue;
+        }
+
+        let unchanged = options.skip_unchanged
+            && fs::metadata(&local_file_path).is_ok_and(|metadata| {
+                let local = (metadata.len(), metadata.modified().ok());
+                same_file((entry.size, entry.mtime), local)
+            });
+
+        if !unchanged {
+            let remote_file = join_remote(remote_path, &entry.relative_path);
+            download_file(
+                afc_client,
+                &remote_file,
+                &local_file_path,
+                entry,
+                options.preserve_mtime,
+                &mut progress,
+            )?;
+        }
+
+        progress.files_done += 1;
+        if unchanged {
+            progress.transferred_bytes += entry.size;
+        }
+        progress.report(&entry.relative_path, entry.size, entry.size);
+    }
+
+    Ok(())
+}
+
+/// Downloads a single file of a transfer, reporting its progress.
+pub(crate) fn download_file<F>(
+    afc_client: &AfcClient,
+    remote_file: &str,
+    local_file_path: &Path,
+    entry: &Entry,
+    preserve_mtime: bool,
+    progress: &mut Progress<'_, F>,
+) -> Result<(), DeviceAfcError>
+where
+    F: FnMut(&TransferProgress),
+{
+    let mut local_file = File::create(local_file_path)?;
+    let handle = afc_client.file_open(remote_file, AfcFileMode::ReadOnly)?;
+
+    let result = copy_chunks(progress, &entry.relative_path, entry.size, |buffer| {
+        let data = afc_client.file_read(handle, buffer.len() as u32)?;
+        local_file.write_all(&data)?;
+        Ok(data.len())
+    });
+    afc_client.file_close(handle)?;
+    result?;
+
+    if preserve_mtime {
+        if let Some(mtime) = entry.mtime {
+            local_file.set_modified(mtime)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn new_progress<'f, F: FnMut(&TransferProgress)>(
+    entries: &[Entry],
+    on_progress: &'f mut F,
+) -> Progress<'f, F> {
+    let files = entries.iter().filter(|entry| !entry.is_dir);
+
+    Progress {
+        total_bytes: files.clone().map(|entry| entry.size).sum(),
+        total_files: files.count(),
+        transferred_bytes: 0,
+        files_done: 0,
+        on_progress,
+    }
+}
+
+/// Moves the data of a file chunk by chunk with `transfer`, which returns 0 once there's nothing left.
+fn copy_chunks<F, T>(
+    progress: &mut Progress<'_, F>,
+    relative_path: &str,
+    file_size: u64,
+    mut transfer: T,
+) -> Result<(), DeviceAfcError>
+where
+    F: FnMut(&TransferProgress),
+    T: FnMut(&mut [u8]) -> Result<usize, DeviceAfcError>,
+{
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut file_bytes = 0;
+
+    loop {
+        let count = transfer(&mut buffer)?;
+        if count == 0 {
+            return Ok(());
+        }
+
+        file_bytes += count as u64;
+        progress.transferred_bytes += count as u64;
+        progress.report(relative_path, file_bytes, file_size);
+    }
+}
+
+fn walk_local(
+    dir: &Path,
+    relative_dir: &str,
+    entries: &mut Vec<Entry>,
+) -> Result<(), DeviceAfcError> {
+    for dir_entry in fs::read_dir(dir)? {
+        let dir_entry = dir_entry?;
+        let metadata = dir_entry.metadata()?;
+        let relative_path = join_remote(relative_dir, &dir_entry.file_name().to_string_lossy());
+
+        entries.push(Entry {
+            relative_path: relative_path.clone(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            mtime: metadata.modified().ok(),
+        });
+
+        if metadata.is_dir() {
+            walk_local(&dir_entry.path(), &relative_path, entries)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn walk_remote(
+    afc_client: &AfcClient,
+    root: &str,
+    relative_dir: &str,
+    entries: &mut Vec<Entry>,
+) -> Result<(), DeviceAfcError> {
+    let dir = join_remote(root, relative_dir);
+
+    for name in afc_client.read_directory(dir.as_str())? {
+        if name == "." || name == ".." {
+            continue;
+        }
+
+        let relative_path = join_remote(relative_dir, &name);
+        let info = AfcFileInfo::from(&afc_client.get_file_info(join_remote(root, &relative_path))?);
+
+        entries.push(Entry {
+            relative_path: relative_path.clone(),
+            is_dir: info.is_dir(),
+            size: info.size,
+            mtime: info.mtime,
+        });
+
+        if info.is_dir() {
+            walk_remote(afc_client, root, &relative_path, entries)?;
+        }
+    }
+    Ok(())
+}
+
+fn make_remote_dir(afc_client: &AfcClient, remote_path: &str) -> Result<(), DeviceAfcError> {
+    if afc_client.get_file_info(remote_path).is_err() {
+        afc_client.make_directory(remote_path)?;
+    }
+    Ok(())
+}
+
+fn remote_size_and_mtime(
+    afc_client: &AfcClient,
+    remote_path: &str,
+) -> Option<(u64, Option<SystemTime>)> {
+    let info = AfcFileInfo::from(&afc_client.get_file_info(remote_path).ok()?);
+    Some((info.size, info.mtime))
+}
+
+/// Compares the size and the modification time (to the second, as the precision differs between the filesystems)
+fn same_file(a: (u64, Option<SystemTime>), b: (u64, Option<SystemTime>)) -> bool {
+    let seconds = |time: Option<SystemTime>| {
+        time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+    };
+
+    a.0 == b.0 && seconds(a.1).is_some() && seconds(a.1) == seconds(b.1)
+}
+
+/// Joins two parts of a device path, the device always uses `/`
+pub(crate) fn join_remote(base: &str, name: &str) -> String {
+    match (base.trim_end_matches('/'), name.trim_start_matches('/')) {
+        ("", name) if base.is_empty() => name.to_string(),
+        (base, "") => base.to_string(),
+        (base, name) => format!("{}/{}", base, name),
+    }
+}
@@ -0,0 +1,103 @@
+//! Storage usage of the device media partition.
+
+use crate::{
+    device_afc::{info::AfcFileInfo, transfer::join_remote},
+    errors::DeviceAfcError,
+};
+use rusty_libimobiledevice::services::afc::AfcClient;
+use std::{cmp::Reverse, collections::HashMap};
+
+/// The filesystem information reported by AFC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AfcDeviceInfo {
+    /// The device model (e.g., "iPhone14,2"), if reported
+    pub model: Option<String>,
+
+    /// The size of the filesystem, in bytes
+    pub total_bytes: u64,
+
+    /// The available space, in bytes
+    pub free_bytes: u64,
+
+    /// The block size of the filesystem, in bytes
+    pub block_size: u64,
+}
+
+impl AfcDeviceInfo {
+    /// The used space, in bytes
+    pub fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.free_bytes)
+    }
+
+    pub(crate) fn from_info(
+        info: &HashMap<String, String>,
+    ) -> Result<AfcDeviceInfo, DeviceAfcError> {
+        let number = |key: &str| {
+            info.get(key)
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| DeviceAfcError::MissingInfo(key.to_string()))
+        };
+
+        Ok(AfcDeviceInfo {
+            model: info.get("Model").cloned(),
+            total_bytes: number("FSTotalBytes")?,
+            free_bytes: number("FSFreeBytes")?,
+            block_size: number("FSBlockSize")?,
+        })
+    }
+}
+
+/// The usage of a directory, as computed by `DeviceAfc::du`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiskUsage {
+    /// The path of the directory
+    pub path: String,
+
+    /// The total size of the files in the directory and its subdirectories, in bytes
+    pub size: u64,
+
+    /// The space actually taken on the disk, in bytes (the allocated blocks)
+    pub allocated: u64,
+
+    /// The number of files in the directory and its subdirectories
+    pub files: u64,
+
+    /// The usage of each subdirectory, the largest first
+    pub children: Vec<DiskUsage>,
+}
+
+/// Computes the usage of a directory, recursively.
+pub(crate) fn disk_usage(afc_client: &AfcClient, path: &str) -> Result<DiskUsage, DeviceAfcError> {
+    let mut usage = DiskUsage {
+        path: path.to_string(),
+        ..Default::default()
+    };
+
+    for name in afc_client.read_directory(path)? {
+        if name == "." || name == ".." {
+            continue;
+        }
+
+        let entry_path = join_remote(path, &name);
+        // The entries can disappear while walking, they're just not counted
+        let Ok(info) = afc_client.get_file_info(entry_path.as_str()) else {
+            continue;
+        };
+        let info = AfcFileInfo::from(&info);
+
+        if info.is_dir() {
+            let child = disk_usage(afc_client, &entry_path)?;
+            usage.size += child.size;
+            usage.allocated += child.allocated;
+            usage.files += child.files;
+            usage.children.push(child);
+        } else {
+            usage.size += info.size;
+            usage.allocated += info.allocated();
+            usage.files += 1;
+        }
+    }
+
+    usage.children.sort_by_key(|child| Reverse(child.size));
+    Ok(usage)
+}
@@ -0,0 +1,61 @@
+use crate::errors::{
+    BackupError, DeviceAfcError, DeviceNotFoundErrorTrait, LockdowndErrorTrait,
+    UnexpectedResponseErrorTrait,
+};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceBackupError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Unexpected response from the backup service: {0}")]
+    UnexpectedResponse(String),
+
+    #[error("The backup service failed with the code {code}: {description}")]
+    BackupServiceError { code: u64, description: String },
+
+    #[error("Backup Error: {0}")]
+    BackupError(#[from] BackupError),
+
+    #[error("AFC Error: {0}")]
+    AfcError(#[from] DeviceAfcError),
+
+    #[error("The backup has no data for `{0}`")]
+    AppNotInBackup(String),
+
+    #[error("The backup thread panicked")]
+    BackupPanicked,
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceBackupError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceBackupError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
+
+impl UnexpectedResponseErrorTrait for DeviceBackupError {
+    fn unexpected_response(description: String) -> Self {
+        Self::UnexpectedResponse(description)
+    }
+}
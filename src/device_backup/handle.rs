@@ -0,0 +1,68 @@
+use crate::{device_backup::progress::BackupSummary, errors::DeviceBackupError};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+/// Handle to a backup running in the background, returned by `DeviceBackup::start_backup`.
+///
+/// Dropping the handle cancels the backup and waits for the session to end.
+#[derive(Debug)]
+pub struct BackupHandle {
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Result<BackupSummary, DeviceBackupError>>>,
+}
+
+impl BackupHandle {
+    pub(crate) fn new(
+        cancel: Arc<AtomicBool>,
+        handle: JoinHandle<Result<BackupSummary, DeviceBackupError>>,
+    ) -> BackupHandle {
+        BackupHandle {
+            cancel,
+            handle: Some(handle),
+        }
+    }
+
+    /// Checks whether the backup has finished, by itself, cancelled or because of an error.
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    /// Cancels the backup and waits for the session to end cleanly.
+    ///
+    /// The summary tells whether it was cancelled, the device may have finished in the meantime.
+    pub fn cancel(mut self) -> Result<BackupSummary, DeviceBackupError> {
+        self.cancel.store(true, Ordering::SeqCst);
+        self.join_thread()
+    }
+
+    /// Waits for the backup to finish.
+    pub fn join(mut self) -> Result<BackupSummary, DeviceBackupError> {
+        self.join_thread()
+    }
+
+    fn join_thread(&mut self) -> Result<BackupSummary, DeviceBackupError> {
+        match self.handle.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| DeviceBackupError::BackupPanicked)?,
+            None => Err(DeviceBackupError::BackupPanicked),
+        }
+    }
+}
+
+impl Drop for BackupHandle {
+    fn drop(&mut self) {
+        if self.handle.is_some() {
+            self.cancel.store(true, Ordering::SeqCst);
+            let _ = self.join_thread();
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Client of the `com.apple.mobilebackup2` service.
+//!
+//! Like screenshotr, it speaks the DeviceLink protocol (see `plist_service::DeviceLinkClient`),
+//! the backup requests themselves are sent as `DLMessageProcessMessage`.
+
+use crate::{
+    errors::DeviceBackupError,
+    plist_service::{self, DeviceLinkClient},
+};
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    idevice::Device, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
+
+const MOBILEBACKUP2_SERVICE: &str = "com.apple.mobilebackup2";
+
+/// DeviceLink doesn't allow empty strings, this is sent instead
+const EMPTY_PARAMETER: &str = "___EmptyParameterString___";
+
+const SUPPORTED_PROTOCOL_VERSIONS: [f64; 2] = [2.0, 2.1];
+
+/// A connection to the backup service, after the DeviceLink version exchange and the protocol hello.
+pub(crate) struct MobileBackup2Client<'a> {
+    client: DeviceLinkClient<'a>,
+}
+
+impl<'a> MobileBackup2Client<'a> {
+    pub(crate) fn connect(
+        device: &'a Device,
+    ) -> Result<MobileBackup2Client<'a>, DeviceBackupError> {
+        let mut lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client"))?;
+        // The escrow bag lets the service read the protected files while the device is locked
+        let lockdownd_service = lockdownd.start_service(MOBILEBACKUP2_SERVICE, true)?;
+        let client = MobileBackup2Client {
+            client: DeviceLinkClient::connect::<DeviceBackupError>(ServiceClient::new(
+                device,
+                lockdownd_service,
+            )?)?,
+        };
+
+        let mut versions = Plist::new_array();
+        for version in SUPPORTED_PROTOCOL_VERSIONS {
+            versions.array_append_item(Plist::new_real(version))?;
+        }
+        let mut hello = Plist::new_dict();
+        hello.dict_set_item("SupportedProtocolVersions", versions)?;
+        client.send_request("Hello", hello)?;
+
+        let reply = client.expect_message("DLMessageProcessMessage")?;
+        check_error_code(&reply.array_get_item(1)?)?;
+
+        Ok(client)
+    }
+
+    /// Sends a request (e.g., `Backup`, `ChangePassword`) with its parameters.
+    pub(crate) fn send_request(
+        &self,
+        name: &str,
+        mut parameters: Plist,
+    ) -> Result<(), DeviceBackupError> {
+        parameters.dict_set_item("MessageName", name.into())?;
+        self.client.send_process_message(parameters)
+    }
+
+    /// Receives a DeviceLink message, returning its type along with the whole message.
+    pub(crate) fn receive_message(&self) -> Result<(String, Plist), DeviceBackupError> {
+        self.client.receive_message()
+    }
+
+    /// Answers a file operation requested by the device.
+    ///
+    /// # Parameters
+    /// - `code`: 0 on success, a negative errno-like code otherwise.
+    /// - `description`: The error description, if any.
+    /// - `content`: The result of the operation (e.g., the free space), an empty dictionary if it has none.
+    pub(crate) fn send_status_response(
+        &self,
+        code: i64,
+        description: Option<&str>,
+        content: Option<Plist>,
+    ) -> Result<(), DeviceBackupError> {
+        let mut message = Plist::new_array();
+        message.array_append_item("DLMessageStatusResponse".into())?;
+        // The negative codes are sent as their two's complement
+        message.array_append_item(Plist::new_uint(code as u64))?;
+        message.array_append_item(description.unwrap_or(EMPTY_PARAMETER).into())?;
+        message.array_append_item(content.unwrap_or_else(Plist::new_dict))?;
+        Ok(self.client.send_message(&message)?)
+    }
+
+    /// Ends the session, the device drops what it was doing.
+    pub(crate) fn disconnect(&self) -> Result<(), DeviceBackupError> {
+        let mut message = Plist::new_array();
+        message.array_append_item("DLMessageDisconnect".into())?;
+        message.array_append_item(EMPTY_PARAMETER.into())?;
+        Ok(self.client.send_message(&message)?)
+    }
+
+    /// Sends raw bytes, used by the file transfers.
+    pub(crate) fn send_raw(&self, data: &[u8]) -> Result<(), DeviceBackupError> {
+        Ok(plist_service::send_all(self.client.service(), data)?)
+    }
+
+    /// Receives exactly `size` raw bytes, used by the file transfers.
+    pub(crate) fn receive_raw(&self, size: usize) -> Result<Vec<u8>, DeviceBackupError> {
+        Ok(plist_service::receive_exact(self.client.service(), size)?)
+    }
+
+    /// Receives a big-endian 32 bits number, used by the file transfers.
+    pub(crate) fn receive_u32(&self) -> Result<u32, DeviceBackupError> {
+        let data = self.receive_raw(4)?;
+        Ok(u32::from_be_bytes([data[0], data[1], data[2], data[3]]))
+    }
+
+    fn expect_message(&self, message_type: &str) -> Result<Plist, DeviceBackupError> {
+        self.client.expect_message(message_type)
+    }
+}
+
+/// Fails if a `DLMessageProcessMessage` reply carries a non zero `ErrorCode`.
+pub(crate) fn check_error_code(reply: &Plist) -> Result<(), DeviceBackupError> {
+    let code = reply
+        .dict_get_item("ErrorCode")
+        .and_then(|code| code.get_uint_val())
+        .unwrap_or(0);
+
+    if code == 0 {
+        return Ok(());
+    }
+
+    Err(DeviceBackupError::BackupServiceError {
+        code,
+        description: reply
+            .dict_get_item("ErrorDescription")
+            .and_then(|description| description.get_string_val())
+            .unwrap_or_default(),
+    })
+}
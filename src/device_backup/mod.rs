@@ -0,0 +1,370 @@
+//! Provides access to the backup service (mobilebackup2) of iOS devices
+//!
+//! ## Features
+//! - Turning the backup encryption on and off, and changing its password
+//! - Backing up a device, with progress reporting and clean cancellation
+//! - Keeping a device backed up on an interval while it's connected
+//! - Restoring the data of a single app from a backup
+//!
+
+use std::{
+    fs,
+    marker::PhantomData,
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+    thread,
+    time::{Instant, SystemTime},
+};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    error::LockdowndError, idevice::Device, services::lockdownd::LockdowndClient,
+};
+
+pub(crate) mod errors;
+pub mod handle;
+mod mobilebackup2;
+pub mod progress;
+pub mod restore;
+pub mod scheduler;
+mod session;
+
+pub use handle::BackupHandle;
+pub use progress::{BackupPhase, BackupProgress, BackupSummary};
+pub use restore::AppRestoreOptions;
+pub use scheduler::{BackupEvent, BackupScheduler, SchedulerHandle};
+
+use crate::{
+    apple_date, backup::Backup, device::DeviceClient, device_afc::TransferProgress,
+    device_info::domains::DeviceDomains, devices_collection::SingleDevice,
+    errors::DeviceBackupError, instrument::OperationSpan,
+};
+use mobilebackup2::MobileBackup2Client;
+use session::Session;
+
+/// The lockdown values copied to the Info.plist of the backup, with the key they're kept under
+const INFO_KEYS: [(&str, &str); 9] = [
+    ("BuildVersion", "Build Version"),
+    ("DeviceName", "Device Name"),
+    ("DeviceName", "Display Name"),
+    ("IntegratedCircuitCardIdentity", "ICCID"),
+    ("InternationalMobileEquipmentIdentity", "IMEI"),
+    ("PhoneNumber", "Phone Number"),
+    ("ProductType", "Product Type"),
+    ("ProductVersion", "Product Version"),
+    ("SerialNumber", "Serial Number"),
+];
+
+/// Struct for configuring a backup.
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    full_backup: bool,
+}
+
+impl BackupOptions {
+    /// Creates the default options, an incremental backup when the directory has a previous one.
+    pub fn new() -> BackupOptions {
+        BackupOptions::default()
+    }
+
+    /// Asks the device for a full backup, even if the directory has a previous one.
+    pub fn with_full_backup(mut self, full_backup: bool) -> BackupOptions {
+        self.full_backup = full_backup;
+        self
+    }
+}
+
+/// Struct for managing the backups of a device
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceBackup<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceBackup<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceBackup<'a, T> {
+        DeviceBackup {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DeviceBackup<'_, SingleDevice> {
+    /// Checks whether the backups of the device are encrypted.
+    pub fn is_backup_encryption_enabled(&self) -> Result<bool, DeviceBackupError> {
+        self.device.check_connected::<DeviceBackupError>()?;
+
+        let lockdownd = self.device.get_lockdownd_client::<DeviceBackupError>()?;
+        let will_encrypt =
+            match lockdownd.get_value("WillEncrypt", DeviceDomains::MobileBackup.as_string()) {
+                Ok(value) => value
+                    .get_bool_val()
+                    .map_err(|_| LockdowndError::InvalidValue)?,
+                // The key is missing until the encryption is turned on once
+                Err(LockdowndError::MissingKey) => false,
+                Err(err) => return Err(err.into()),
+            };
+
+        Ok(will_encrypt)
+    }
+
+    /// Turns the backup encryption on or off.
+    ///
+    /// The setting is kept on the device, so every later backup (from any host) follows it.
+    /// Recent iOS versions ask for the device passcode on the screen before applying it.
+    ///
+    /// # Parameters
+    /// - `enabled`: Whether the backups should be encrypted.
+    /// - `password`: The new backup password when turning it on, the current one when turning it off.
+    pub fn set_backup_encryption(
+        &self,
+        enabled: bool,
+        password: &str,
+    ) -> Result<(), DeviceBackupError> {
+        let mut request = Plist::new_dict();
+        match enabled {
+            true => request.dict_set_item("NewPassword", password.into())?,
+            false => request.dict_set_item("OldPassword", password.into())?,
+        }
+
+        self.change_password(request)
+    }
+
+    /// Changes the password of the encrypted backups.
+    ///
+    /// # Parameters
+    /// - `old_password`: The current backup password.
+    /// - `new_password`: The new backup password.
+    pub fn change_backup_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), DeviceBackupError> {
+        let mut request = Plist::new_dict();
+        request.dict_set_item("OldPassword", old_password.into())?;
+        request.dict_set_item("NewPassword", new_password.into())?;
+
+        self.change_password(request)
+    }
+
+    /// Backs up the device, blocking until the backup is done.
+    ///
+    /// The backup is kept in `<backup_dir>/<udid>`, in the same layout as iTunes and Finder,
+    /// so it can be read with `crate::backup::Backup`.
+    ///
+    /// An interrupted or cancelled backup is resumed by backing up to the same directory again:
+    /// the device then makes an incremental backup against the last completed one,
+    /// the files already received by the interrupted backup are sent again.
+    ///
+    /// # Parameters
+    /// - `backup_dir`: The directory holding the backups.
+    /// - `options`: The backup options.
+    /// - `on_progress`: Called with the progress after each file operation.
+    pub fn backup<S, F>(
+        &self,
+        backup_dir: &S,
+        options: BackupOptions,
+        on_progress: F,
+    ) -> Result<BackupSummary, DeviceBackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&BackupProgress),
+    {
+        self.device.check_connected::<DeviceBackupError>()?;
+
+        run_backup(
+            self.device.get_device(),
+            backup_dir.as_ref(),
+            &options,
+            &AtomicBool::new(false),
+            on_progress,
+        )
+    }
+
+    /// Backs up the device in the background.
+    ///
+    /// This is a non blocking function, cancelling the returned handle ends the session cleanly,
+    /// see `backup` for the layout of the backup and how to resume it.
+    ///
+    /// # Parameters
+    /// - `backup_dir`: The directory holding the backups.
+    /// - `options`: The backup options.
+    /// - `on_progress`: Called with the progress after each file operation.
+    pub fn start_backup<S, F>(
+        &self,
+        backup_dir: &S,
+        options: BackupOptions,
+        on_progress: F,
+    ) -> Result<BackupHandle, DeviceBackupError>
+    where
+        S: AsRef<Path> + ?Sized,
+        F: FnMut(&BackupProgress) + Send + 'static,
+    {
+        self.device.check_connected::<DeviceBackupError>()?;
+
+        let device = self.device.get_device().clone();
+        let backup_dir = backup_dir.as_ref().to_path_buf();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let thread_cancel = Arc::clone(&cancel);
+
+        let handle = thread::spawn(move || {
+            run_backup(&device, &backup_dir, &options, &thread_cancel, on_progress)
+        });
+
+        Ok(BackupHandle::new(cancel, handle))
+    }
+
+    /// Keeps the device backed up on an interval, see `BackupScheduler`.
+    ///
+    /// This is a non blocking function, the scheduler runs until the returned handle is stopped or dropped,
+    /// even while the device is disconnected.
+    ///
+    /// # Parameters
+    /// - `scheduler`: The backup interval and options.
+    /// - `on_event`: The callback receiving the start, the progress and the result of every backup.
+    pub fn start_scheduler<F>(
+        &self,
+        scheduler: BackupScheduler,
+        on_event: F,
+    ) -> Result<SchedulerHandle, DeviceBackupError>
+    where
+        F: FnMut(BackupEvent) + Send + 'static,
+    {
+        self.device.check_connected::<DeviceBackupError>()?;
+
+        Ok(scheduler::spawn_scheduler(
+            self.device.get_device().get_udid(),
+            scheduler,
+            on_event,
+        ))
+    }
+
+    /// Restores the data of a single app from a backup, leaving the rest of the device untouched.
+    ///
+    /// The backup protocol only restores whole devices, so the files of the `AppDomain-<bundle_id>` domain
+    /// are copied into the app container through house_arrest instead. The device only gives access
+    /// to the container of the apps installed with a development profile, and the app should be closed
+    /// while its data is replaced.
+    ///
+    /// # Parameters
+    /// - `backup`: The backup to restore from, opened with its password if it's encrypted.
+    /// - `bundle_id`: The bundle identifier of the app (e.g., "com.example.app").
+    /// - `options`: Whether to remove the current data first.
+    /// - `on_progress`: Called with the progress of the copy.
+    ///
+    /// # Returns
+    /// The number of restored files.
+    pub fn restore_app_data<F>(
+        &self,
+        backup: &Backup,
+        bundle_id: &str,
+        options: AppRestoreOptions,
+        on_progress: F,
+    ) -> Result<usize, DeviceBackupError>
+    where
+        F: FnMut(&TransferProgress),
+    {
+        self.device.check_connected::<DeviceBackupError>()?;
+
+        restore::restore_app_data(
+            self.device.get_device(),
+            backup,
+            bundle_id,
+            &options,
+            on_progress,
+        )
+    }
+
+    fn change_password(&self, mut request: Plist) -> Result<(), DeviceBackupError> {
+        self.device.check_connected::<DeviceBackupError>()?;
+
+        let device = self.device.get_device();
+        let client = MobileBackup2Client::connect(device)?;
+
+        request.dict_set_item("TargetIdentifier", device.get_udid().into())?;
+        client.send_request("ChangePassword", request)?;
+
+        let (message_type, message) = client.receive_message()?;
+        match message_type.as_str() {
+            "DLMessageProcessMessage" => {
+                mobilebackup2::check_error_code(&message.array_get_item(1)?)
+            }
+            "DLMessageDisconnect" => Ok(()),
+            other => Err(DeviceBackupError::UnexpectedResponse(format!(
+                "unexpected {} while changing the password",
+                other
+            ))),
+        }
+    }
+}
+
+fn run_backup<F>(
+    device: &Device,
+    backup_dir: &Path,
+    options: &BackupOptions,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<BackupSummary, DeviceBackupError>
+where
+    F: FnMut(&BackupProgress),
+{
+    let started = Instant::now();
+    let udid = device.get_udid();
+    let span = OperationSpan::transfer(&udid, "backup");
+
+    let client = MobileBackup2Client::connect(device)?;
+    let mut session = Session::new(&client, backup_dir.to_path_buf(), cancel, on_progress);
+    session.report(BackupPhase::Preparing);
+
+    let device_dir = backup_dir.join(&udid);
+    fs::create_dir_all(&device_dir)?;
+    write_info_plist(device, &udid, &device_dir)?;
+
+    let mut backup_options = Plist::new_dict();
+    backup_options.dict_set_item("ForceFullBackup", Plist::new_bool(options.full_backup))?;
+
+    let mut request = Plist::new_dict();
+    request.dict_set_item("TargetIdentifier", udid.into())?;
+    request.dict_set_item("Options", backup_options)?;
+    client.send_request("Backup", request)?;
+
+    let cancelled = session.run()?;
+    let progress = session.progress();
+    span.record_bytes(progress.bytes_received);
+
+    Ok(BackupSummary {
+        cancelled,
+        files_received: progress.files_received,
+        bytes_received: progress.bytes_received,
+        duration: started.elapsed(),
+    })
+}
+
+/// Writes the Info.plist describing the device, which the backup service doesn't send.
+fn write_info_plist(
+    device: &Device,
+    udid: &str,
+    device_dir: &Path,
+) -> Result<(), DeviceBackupError> {
+    let lockdownd = LockdowndClient::new(device, crate::config::label("backup"))?;
+    let values = lockdownd.get_value("", "")?;
+
+    let mut info = Plist::new_dict();
+    for (lockdown_key, info_key) in INFO_KEYS {
+        if let Ok(value) = values.dict_get_item(lockdown_key) {
+            info.dict_set_item(info_key, value.clone())?;
+        }
+    }
+    info.dict_set_item("Last Backup Date", apple_date::to_plist(SystemTime::now()))?;
+    info.dict_set_item("Target Identifier", udid.into())?;
+    info.dict_set_item("Target Type", "Device".into())?;
+    info.dict_set_item("Unique Identifier", udid.to_uppercase().into())?;
+
+    fs::write(device_dir.join("Info.plist"), info.to_xml())?;
+    Ok(())
+}
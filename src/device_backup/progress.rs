@@ -0,0 +1,100 @@
+//! Progress reporting of the backups.
+
+use crate::progress::{AsProgressUpdate, ProgressUpdate};
+use std::time::Duration;
+
+/// Enum representing the stage a backup is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackupPhase {
+    /// Connecting to the backup service and writing the Info.plist
+    Preparing,
+    /// The device sends the files, and asks the host to move and remove the ones of the previous snapshot
+    Transferring,
+    /// The device reported the end of the backup
+    Finished,
+    /// The backup was cancelled, the session was ended cleanly
+    Cancelled,
+}
+
+impl BackupPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupPhase::Preparing => "Preparing",
+            BackupPhase::Transferring => "Transferring",
+            BackupPhase::Finished => "Finished",
+            BackupPhase::Cancelled => "Cancelled",
+        }
+    }
+}
+
+/// The progress of a backup, reported after each file operation asked by the device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupProgress {
+    pub phase: BackupPhase,
+
+    /// The overall progress reported by the device, from 0 to 100, if it sent one yet
+    pub percent: Option<f64>,
+
+    /// The number of files received so far.
+    ///
+    /// The device doesn't announce how many files it will send, see `percent` for the overall progress.
+    pub files_received: u64,
+
+    /// The number of bytes received so far
+    pub bytes_received: u64,
+
+    /// The size of the current batch of files, as announced by the device
+    pub batch_total_bytes: Option<u64>,
+
+    /// The time since the backup started
+    pub elapsed: Duration,
+}
+
+impl BackupProgress {
+    pub(crate) fn new() -> BackupProgress {
+        BackupProgress {
+            phase: BackupPhase::Preparing,
+            percent: None,
+            files_received: 0,
+            bytes_received: 0,
+            batch_total_bytes: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// The average transfer speed since the backup started, in bytes per second
+    pub fn bytes_per_second(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => self.bytes_received as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+}
+
+impl AsProgressUpdate for BackupProgress {
+    /// The updates are in percent, as the device reports it, the total is unknown until it sends one.
+    fn as_progress_update(&self) -> ProgressUpdate<'_> {
+        ProgressUpdate {
+            phase: self.phase.as_str(),
+            current: self.percent.map_or(0, |percent| percent.round() as u64),
+            total: self.percent.map(|_| 100),
+            message: None,
+        }
+    }
+}
+
+/// The outcome of a backup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupSummary {
+    /// Whether the backup was cancelled before the device finished it
+    pub cancelled: bool,
+
+    /// The number of files received
+    pub files_received: u64,
+
+    /// The number of bytes received
+    pub bytes_received: u64,
+
+    /// How long the backup took
+    pub duration: Duration,
+}
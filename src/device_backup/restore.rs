@@ -0,0 +1,92 @@
+//! Restoring the data of a single app from a backup, through its house_arrest container.
+//!
+//! mobilebackup2 only restores whole backups, so the files of the app domain are extracted
+//! from the backup and copied into the container of the app instead.
+
+use crate::{
+    backup::Backup,
+    device_afc::{
+        sharing,
+        transfer::{self, join_remote, TransferOptions, TransferProgress},
+    },
+    errors::{DeviceAfcError, DeviceBackupError},
+    temp::TempDir,
+};
+use rusty_libimobiledevice::{idevice::Device, services::afc::AfcClient};
+
+/// The folders of an app container
+const CONTAINER_DIRS: [&str; 3] = ["Documents", "Library", "tmp"];
+
+/// Struct for configuring the restore of an app data.
+#[derive(Debug, Clone, Default)]
+pub struct AppRestoreOptions {
+    remove_existing: bool,
+}
+
+impl AppRestoreOptions {
+    /// Creates the default options, the backed up files are copied over the current ones.
+    pub fn new() -> AppRestoreOptions {
+        AppRestoreOptions::default()
+    }
+
+    /// Removes the current content of the container first, so the app is in the exact state of the backup.
+    pub fn with_remove_existing(mut self, remove_existing: bool) -> AppRestoreOptions {
+        self.remove_existing = remove_existing;
+        self
+    }
+}
+
+/// Restores the app domain of the backup, see `DeviceBackup::restore_app_data`.
+pub(crate) fn restore_app_data<F>(
+    device: &Device,
+    backup: &Backup,
+    bundle_id: &str,
+    options: &AppRestoreOptions,
+    on_progress: F,
+) -> Result<usize, DeviceBackupError>
+where
+    F: FnMut(&TransferProgress),
+{
+    let domain = format!("AppDomain-{}", bundle_id);
+    if !backup.domains()?.contains(&domain) {
+        return Err(DeviceBackupError::AppNotInBackup(bundle_id.to_string()));
+    }
+
+    // Checking the container is reachable before extracting anything
+    let afc_client = sharing::app_container_client(device, bundle_id)?;
+
+    let staging = TempDir::create(bundle_id)?;
+    let extracted = backup.extract_glob(&domain, "*", staging.path())?;
+
+    if options.remove_existing {
+        clear_container(&afc_client)?;
+    }
+
+    transfer::upload_dir(
+        &afc_client,
+        &staging.path().join(&domain),
+        "/",
+        TransferOptions::default(),
+        on_progress,
+    )?;
+
+    Ok(extracted.len())
+}
+
+fn clear_container(afc_client: &AfcClient) -> Result<(), DeviceBackupError> {
+    for dir in CONTAINER_DIRS {
+        // Some containers don't have all the folders
+        let Ok(names) = afc_client.read_directory(join_remote("/", dir)) else {
+            continue;
+        };
+
+        for name in names.iter().filter(|name| *name != "." && *name != "..") {
+            let path = join_remote(&join_remote("/", dir), name);
+            afc_client
+                .remove_path_and_contents(path)
+                .map_err(DeviceAfcError::from)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Keeping a device backed up on an interval while it's connected.
+
+use crate::{
+    device_backup::{
+        progress::{BackupProgress, BackupSummary},
+        BackupOptions,
+    },
+    errors::DeviceBackupError,
+};
+use rusty_libimobiledevice::idevice::{self, Device};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Enum representing what the scheduler reports while keeping a device backed up.
+#[derive(Debug)]
+pub enum BackupEvent {
+    /// A backup started
+    Started,
+    /// The progress of the running backup
+    Progress(BackupProgress),
+    /// The backup finished, the next one is due after the interval
+    Completed(BackupSummary),
+    /// The backup failed, it's tried again after the retry interval
+    Failed(DeviceBackupError),
+}
+
+/// Struct for configuring a scheduled backup, started with `DeviceBackup::start_scheduler`.
+///
+/// The first backup starts right away, then one runs every `interval`, as incremental backups
+/// against the previous one. When the device isn't connected once a backup is due,
+/// it starts as soon as the device is plugged back.
+#[derive(Debug, Clone)]
+pub struct BackupScheduler {
+    backup_dir: PathBuf,
+    interval: Duration,
+    retry_interval: Duration,
+    poll_interval: Duration,
+    options: BackupOptions,
+}
+
+impl BackupScheduler {
+    /// # Parameters
+    /// - `backup_dir`: The directory holding the backups.
+    /// - `interval`: The time between the start of a completed backup and the next one.
+    pub fn new<S>(backup_dir: &S, interval: Duration) -> BackupScheduler
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        BackupScheduler {
+            backup_dir: backup_dir.as_ref().to_path_buf(),
+            interval,
+            retry_interval: Duration::from_secs(5 * 60),
+            poll_interval: Duration::from_secs(5),
+            options: BackupOptions::default(),
+        }
+    }
+
+    /// Sets the time before trying again after a failed backup, defaults to 5 minutes.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> BackupScheduler {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Sets how often the device connection is checked, defaults to 5 seconds.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> BackupScheduler {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the options of every backup.
+    pub fn with_options(mut self, options: BackupOptions) -> BackupScheduler {
+        self.options = options;
+        self
+    }
+}
+
+/// Handle to a running scheduler, returned by `DeviceBackup::start_scheduler`.
+///
+/// Dropping the handle stops the scheduler, cancelling the running backup if there's one.
+#[derive(Debug)]
+pub struct SchedulerHandle {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+    /// Stops the scheduler, cancelling the running backup cleanly, and waits for it to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    /// Checks whether the scheduler thread has finished.
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+/// Starts the scheduler thread, which calls `on_event` for everything it does.
+pub(crate) fn spawn_scheduler<F>(
+    udid: String,
+    scheduler: BackupScheduler,
+    mut on_event: F,
+) -> SchedulerHandle
+where
+    F: FnMut(BackupEvent) + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+
+    let handle = thread::spawn(move || {
+        let mut next_backup = Instant::now();
+
+        while !thread_stop.load(Ordering::SeqCst) {
+            if Instant::now() >= next_backup {
+                // Looking the device up again, it may have been plugged back since the last backup
+                if let Some(device) = find_device(&udid) {
+                    let started = Instant::now();
+                    on_event(BackupEvent::Started);
+
+                    let result = super::run_backup(
+                        &device,
+                        &scheduler.backup_dir,
+                        &scheduler.options,
+                        &thread_stop,
+                        |progress| on_event(BackupEvent::Progress(progress.clone())),
+                    );
+
+                    match result {
+                        // Only the scheduler being stopped cancels the backup
+                        Ok(summary) if summary.cancelled => break,
+                        Ok(summary) => {
+                            on_event(BackupEvent::Completed(summary));
+                            next_backup = started + scheduler.interval;
+                        }
+                        Err(err) => {
+                            on_event(BackupEvent::Failed(err));
+                            next_backup = Instant::now() + scheduler.retry_interval;
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            let wait = next_backup
+                .checked_duration_since(Instant::now())
+                .unwrap_or(scheduler.poll_interval)
+                .min(scheduler.poll_interval);
+            sleep_unless_stopped(wait, &thread_stop);
+        }
+    });
+
+    SchedulerHandle {
+        stop,
+        handle: Some(handle),
+    }
+}
+
+fn find_device(udid: &str) -> Option<Device> {
+    idevice::get_devices()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|device| device.get_udid() == udid)
+}
+
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+
+    while !stop.load(Ordering::SeqCst) {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            return;
+        };
+        thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
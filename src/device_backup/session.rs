@@ -0,0 +1,512 @@
+//! The file operations the device asks the host for during a backup or a restore.
+//!
+//! Once a request is sent, the device drives the session: it asks the host to send, receive,
+//! list, move and remove files of the backup directory, until it reports the result of the request.
+
+use crate::{
+    apple_date,
+    device_backup::{
+        mobilebackup2::{self, MobileBackup2Client},
+        progress::{BackupPhase, BackupProgress},
+    },
+    errors::DeviceBackupError,
+};
+use plist_plus::Plist;
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+const CHUNK_SIZE: usize = 32 * 1024;
+
+// The codes prefixing each block of a file transfer
+const CODE_SUCCESS: u8 = 0x00;
+const CODE_ERROR_LOCAL: u8 = 0x06;
+const CODE_ERROR_REMOTE: u8 = 0x0b;
+const CODE_FILE_DATA: u8 = 0x0c;
+
+/// The status code telling the device that some of the files failed
+const MULTI_STATUS: i64 = -13;
+
+/// The longest file name the device sends, anything longer means the stream is out of sync
+const MAX_NAME_LENGTH: u32 = 4096;
+
+/// The longest block of file data the device sends, it splits the files in much smaller blocks,
+/// anything longer means the stream is out of sync
+const MAX_BLOCK_LENGTH: u32 = 16 * 1024 * 1024;
+
+pub(crate) struct Session<'c, 'a, F>
+where
+    F: FnMut(&BackupProgress),
+{
+    client: &'c MobileBackup2Client<'a>,
+    root: PathBuf,
+    cancel: &'c AtomicBool,
+    started: Instant,
+    progress: BackupProgress,
+    on_progress: F,
+}
+
+impl<'c, 'a, F> Session<'c, 'a, F>
+where
+    F: FnMut(&BackupProgress),
+{
+    /// # Parameters
+    /// - `root`: The directory holding the backups, the device paths start with the device UDID.
+    /// - `cancel`: Ends the session cleanly once set.
+    pub(crate) fn new(
+        client: &'c MobileBackup2Client<'a>,
+        root: PathBuf,
+        cancel: &'c AtomicBool,
+        on_progress: F,
+    ) -> Session<'c, 'a, F> {
+        Session {
+            client,
+            root,
+            cancel,
+            started: Instant::now(),
+            progress: BackupProgress::new(),
+            on_progress,
+        }
+    }
+
+    pub(crate) fn progress(&self) -> &BackupProgress {
+        &self.progress
+    }
+
+    pub(crate) fn report(&mut self, phase: BackupPhase) {
+        self.progress.phase = phase;
+        self.progress.elapsed = self.started.elapsed();
+        (self.on_progress)(&self.progress);
+    }
+
+    /// Handles the device messages until it reports the result of the request.
+    ///
+    /// Returns whether the session was cancelled.
+    pub(crate) fn run(&mut self) -> Result<bool, DeviceBackupError> {
+        self.report(BackupPhase::Transferring);
+
+        loop {
+            if self.is_cancelled() {
+                return self.cancel_session();
+            }
+
+            let (message_type, message) = self.client.receive_message()?;
+            self.update_percent(&message_type, &message);
+
+            match message_type.as_str() {
+                "DLMessageDownloadFiles" => self.send_files(&message)?,
+                "DLMessageUploadFiles" => {
+                    if !self.receive_files(&message)? {
+                        return self.cancel_session();
+                    }
+                }
+                "DLMessageGetFreeDiskSpace" => {
+                    let result = fs2::available_space(&self.root).map(Plist::new_uint);
+                    self.respond(result)?;
+                }
+                "DLMessagePurgeDiskSpace" => {
+                    self.client
+                        .send_status_response(-1, Some("Operation not supported"), None)?;
+                }
+                "DLContentsOfDirectory" => {
+                    let result = self
+                        .local_path(&message, 1)
+                        .and_then(|path| Ok(contents_of_directory(&path)?));
+                    self.respond_result(result)?;
+                }
+                "DLMessageCreateDirectory" => {
+                    let result = self
+                        .local_path(&message, 1)
+                        .and_then(|path| Ok(fs::create_dir_all(path)?));
+                    self.respond_result(result.map(|_| Plist::new_dict()))?;
+                }
+                "DLMessageMoveFiles" | "DLMessageMoveItems" => {
+                    let result = self.move_items(&message);
+                    self.respond_result(result.map(|_| Plist::new_dict()))?;
+                }
+                "DLMessageRemoveFiles" | "DLMessageRemoveItems" => {
+                    let result = self.remove_items(&message);
+                    self.respond_result(result.map(|_| Plist::new_dict()))?;
+                }
+                "DLMessageCopyItem" => {
+                    let result = self.local_path(&message, 1).and_then(|source| {
+                        let destination = self.local_path(&message, 2)?;
+                        Ok(copy_item(&source, &destination)?)
+                    });
+                    self.respond_result(result.map(|_| Plist::new_dict()))?;
+                }
+                "DLMessageProcessMessage" => {
+                    mobilebackup2::check_error_code(&message.array_get_item(1)?)?;
+                    self.report(BackupPhase::Finished);
+                    return Ok(false);
+                }
+                "DLMessageDisconnect" => {
+                    self.report(BackupPhase::Finished);
+                    return Ok(false);
+                }
+                other => {
+                    return Err(DeviceBackupError::UnexpectedResponse(format!(
+                        "unsupported {}",
+                        other
+                    )))
+                }
+            }
+
+            self.report(BackupPhase::Transferring);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    fn cancel_session(&mut self) -> Result<bool, DeviceBackupError> {
+        // The device may have closed the connection already, the session is over either way
+        let _ = self.client.disconnect();
+        self.report(BackupPhase::Cancelled);
+        Ok(true)
+    }
+
+    /// Reads the overall progress, which isn't at the same place in every message.
+    fn update_percent(&mut self, message_type: &str, message: &Plist) {
+        let index = match message_type {
+            "DLMessageUploadFiles" => 2,
+            "DLMessageDownloadFiles"
+            | "DLMessageMoveFiles"
+            | "DLMessageMoveItems"
+            | "DLMessageRemoveFiles"
+            | "DLMessageRemoveItems" => 3,
+            _ => return,
+        };
+
+        if let Ok(percent) = message
+            .array_get_item(index)
+            .and_then(|percent| percent.get_real_val())
+        {
+            self.progress.percent = Some(percent);
+        }
+    }
+
+    fn respond(&self, result: io::Result<Plist>) -> Result<(), DeviceBackupError> {
+        match result {
+            Ok(content) => self.client.send_status_response(0, None, Some(content)),
+            Err(e) => {
+                self.client
+                    .send_status_response(device_error_code(&e), Some(&e.to_string()), None)
+            }
+        }
+    }
+
+    fn respond_result(
+        &self,
+        result: Result<Plist, DeviceBackupError>,
+    ) -> Result<(), DeviceBackupError> {
+        match result {
+            Ok(content) => self.respond(Ok(content)),
+            Err(DeviceBackupError::IOError(e)) => self.respond(Err(e)),
+            Err(e) => self
+                .client
+                .send_status_response(-1, Some(&e.to_string()), None),
+        }
+    }
+
+    /// Resolves a device path of the message in the backup directory.
+    fn local_path(&self, message: &Plist, index: u32) -> Result<PathBuf, DeviceBackupError> {
+        let device_path = message.array_get_item(index)?.get_string_val()?;
+        self.resolve(&device_path)
+    }
+
+    /// The device paths are relative to the backup directory, anything going out of it is refused.
+    fn resolve(&self, device_path: &str) -> Result<PathBuf, DeviceBackupError> {
+        let relative = Path::new(device_path.trim_start_matches('/'));
+
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(DeviceBackupError::UnexpectedResponse(format!(
+                "the path {} is outside of the backup directory",
+                device_path
+            )));
+        }
+
+        Ok(self.root.join(relative))
+    }
+
+    /// Sends the files the device asked for (e.g., the previous Manifest and Status plists).
+    fn send_files(&mut self, message: &Plist) -> Result<(), DeviceBackupError> {
+        let files = message.array_get_item(1)?;
+        let mut errors = Plist::new_dict();
+        let mut failed = false;
+
+        for index in 0..files.array_get_size()? {
+            let device_path = files.array_get_item(index)?.get_string_val()?;
+
+            self.client
+                .send_raw(&(device_path.len() as u32).to_be_bytes())?;
+            self.client.send_raw(device_path.as_bytes())?;
+
+            let result = self
+                .resolve(&device_path)
+                .and_then(|path| Ok(File::open(path)?))
+                .and_then(|file| self.send_file_content(file));
+
+            if let Err(e) = result {
+                let (code, description) = match &e {
+                    DeviceBackupError::IOError(e) => (device_error_code(e), e.to_string()),
+                    e => (-1, e.to_string()),
+                };
+
+                let mut block = ((description.len() + 1) as u32).to_be_bytes().to_vec();
+                block.push(CODE_ERROR_LOCAL);
+                block.extend_from_slice(description.as_bytes());
+                self.client.send_raw(&block)?;
+
+                let mut error = Plist::new_dict();
+                error.dict_set_item("DLFileErrorString", description.as_str().into())?;
+                error.dict_set_item("DLFileErrorCode", Plist::new_uint(code as u64))?;
+                errors.dict_set_item(&device_path, error)?;
+                failed = true;
+            }
+        }
+
+        // The end of the files
+        self.client.send_raw(&0u32.to_be_bytes())?;
+
+        match failed {
+            true => {
+                self.client
+                    .send_status_response(MULTI_STATUS, Some("Multi status"), Some(errors))
+            }
+            false => self.client.send_status_response(0, None, None),
+        }
+    }
+
+    fn send_file_content(&self, mut file: File) -> Result<(), DeviceBackupError> {
+        let mut buffer = vec![0; CHUNK_SIZE];
+
+        loop {
+            let count = file.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+
+            let mut block = ((count + 1) as u32).to_be_bytes().to_vec();
+            block.push(CODE_FILE_DATA);
+            block.extend_from_slice(&buffer[..count]);
+            self.client.send_raw(&block)?;
+        }
+
+        let mut success = 1u32.to_be_bytes().to_vec();
+        success.push(CODE_SUCCESS);
+        self.client.send_raw(&success)
+    }
+
+    /// Receives the files of the backup, each one is sent as its name followed by blocks of data.
+    ///
+    /// Returns false if the session was cancelled in the middle.
+    fn receive_files(&mut self, message: &Plist) -> Result<bool, DeviceBackupError> {
+        self.progress.batch_total_bytes = message
+            .array_get_item(3)
+            .and_then(|total| total.get_uint_val())
+            .ok();
+
+        let mut status: io::Result<()> = Ok(());
+
+        loop {
+            if self.is_cancelled() {
+                return Ok(false);
+            }
+
+            // The directory name, unused, the file name already has the whole path
+            let Some(_) = self.receive_name()? else {
+                break;
+            };
+            let Some(device_path) = self.receive_name()? else {
+                break;
+            };
+
+            let mut length = self.receive_block_length()?;
+            if length == 0 {
+                break;
+            }
+            let mut code = self.client.receive_raw(1)?[0];
+
+            let path = self.resolve(&device_path)?;
+            let mut file = match fs::remove_file(&path)
+                .or_else(ignore_not_found)
+                .and_then(|_| File::create(&path))
+            {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    status = Err(e);
+                    None
+                }
+            };
+
+            let mut received_data = false;
+            while code == CODE_FILE_DATA {
+                received_data = true;
+                let data = self.client.receive_raw(length as usize - 1)?;
+                if let Some(writer) = &mut file {
+                    if let Err(e) = writer.write_all(&data) {
+                        status = Err(e);
+                        file = None;
+                    }
+                }
+                self.progress.bytes_received += data.len() as u64;
+                self.report(BackupPhase::Transferring);
+
+                length = self.receive_block_length()?;
+                if length == 0 {
+                    break;
+                }
+                code = self.client.receive_raw(1)?[0];
+            }
+
+            if length == 0 {
+                break;
+            }
+
+            // Either the end of the file, or an error message from the device,
+            // the end of a file that had data is marked as a remote error as well
+            let trailer = self.client.receive_raw(length as usize - 1)?;
+            if code == CODE_ERROR_REMOTE && !received_data && !trailer.is_empty() {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+
+            if file.is_some() {
+                self.progress.files_received += 1;
+            }
+        }
+
+        self.respond(status.map(|_| Plist::new_dict()))?;
+        Ok(true)
+    }
+
+    /// Receives a length prefixed name, `None` once the device has no more files.
+    fn receive_name(&self) -> Result<Option<String>, DeviceBackupError> {
+        let length = self.client.receive_u32()?;
+        if length == 0 {
+            return Ok(None);
+        }
+        if length > MAX_NAME_LENGTH {
+            return Err(DeviceBackupError::UnexpectedResponse(format!(
+                "file name too long ({} bytes)",
+                length
+            )));
+        }
+
+        let name = self.client.receive_raw(length as usize)?;
+        Ok(Some(String::from_utf8_lossy(&name).to_string()))
+    }
+
+    /// Receives the length of the next block of a file (its code included), 0 once the device has no more files.
+    fn receive_block_length(&self) -> Result<u32, DeviceBackupError> {
+        let length = self.client.receive_u32()?;
+        if length > MAX_BLOCK_LENGTH {
+            return Err(DeviceBackupError::UnexpectedResponse(format!(
+                "file block too long ({} bytes)",
+                length
+            )));
+        }
+
+        Ok(length)
+    }
+
+    fn move_items(&self, message: &Plist) -> Result<(), DeviceBackupError> {
+        for item in message.array_get_item(1)? {
+            let source = self.resolve(&item.key.unwrap_or_default())?;
+            let destination = self.resolve(&item.plist.get_string_val()?)?;
+
+            remove_item(&destination)?;
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(source, destination)?;
+        }
+        Ok(())
+    }
+
+    fn remove_items(&self, message: &Plist) -> Result<(), DeviceBackupError> {
+        let items = message.array_get_item(1)?;
+        for index in 0..items.array_get_size()? {
+            let path = self.resolve(&items.array_get_item(index)?.get_string_val()?)?;
+            remove_item(&path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists a directory as the device expects it, with the type, size and modification date of each entry.
+fn contents_of_directory(path: &Path) -> io::Result<Plist> {
+    let mut contents = Plist::new_dict();
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+
+        let file_type = match metadata.file_type() {
+            file_type if file_type.is_dir() => "DLFileTypeDirectory",
+            file_type if file_type.is_file() => "DLFileTypeRegular",
+            _ => "DLFileTypeUnknown",
+        };
+
+        let mut info = Plist::new_dict();
+        let _ = info.dict_set_item("DLFileType", file_type.into());
+        let _ = info.dict_set_item("DLFileSize", Plist::new_uint(metadata.len()));
+        if let Ok(modified) = metadata.modified() {
+            let _ = info.dict_set_item("DLFileModificationDate", apple_date::to_plist(modified));
+        }
+
+        let _ = contents.dict_set_item(&entry.file_name().to_string_lossy(), info);
+    }
+
+    Ok(contents)
+}
+
+fn remove_item(path: &Path) -> io::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    result.or_else(ignore_not_found)
+}
+
+fn copy_item(source: &Path, destination: &Path) -> io::Result<()> {
+    if !source.is_dir() {
+        return fs::copy(source, destination).map(|_| ());
+    }
+
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        copy_item(&entry.path(), &destination.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+fn ignore_not_found(e: io::Error) -> io::Result<()> {
+    match e.kind() {
+        ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    }
+}
+
+/// Maps an IO error to the error codes the device knows.
+fn device_error_code(e: &io::Error) -> i64 {
+    match e.kind() {
+        ErrorKind::NotFound => -6,
+        ErrorKind::AlreadyExists => -7,
+        ErrorKind::NotADirectory => -8,
+        ErrorKind::IsADirectory => -9,
+        ErrorKind::StorageFull => -15,
+        _ => -1,
+    }
+}
@@ -0,0 +1,42 @@
+use crate::errors::{DeviceClientError, DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use rusty_libimobiledevice::error::{InstProxyError, LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceDebugServerError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("Device Client Error: {0}")]
+    DeviceClientError(#[from] DeviceClientError),
+
+    #[error("Installation Proxy Error: {0}")]
+    InstallationProxyError(#[from] InstProxyError),
+
+    #[error("The debugserver isn't available, make sure the developer disk image is mounted")]
+    DebugServerUnavailable,
+
+    #[error("Unexpected reply from the debugserver: {0}")]
+    UnexpectedReply(String),
+
+    #[error("The app couldn't be launched: {0}")]
+    LaunchFailed(String),
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceDebugServerError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceDebugServerError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
@@ -0,0 +1,180 @@
+//! Provides access to the debugserver of iOS devices
+//!
+//! The debugserver launches the apps the way Xcode does, which lets the test launchers pass them
+//! arguments, environment variables (e.g., feature flags) and a working directory.
+//! It needs the developer disk image to be mounted, see `device_image_mounter`.
+//!
+//! ## Features
+//! - Launching an app by its bundle identifier, with its arguments, environment and working directory
+//! - Keeping the launched app stopped at its entry point until it's resumed
+//!
+
+use std::marker::PhantomData;
+
+use rusty_libimobiledevice::service::ServiceClient;
+
+pub(crate) mod errors;
+pub mod options;
+mod protocol;
+
+pub use options::LaunchOptions;
+
+use crate::{
+    device::DeviceClient, devices_collection::SingleDevice, errors::DeviceDebugServerError,
+    service_options::ServiceStartOptions,
+};
+use protocol::DebugServerConnection;
+
+/// The debugserver behind the secure proxy of iOS 14 and later, then the one of the older versions
+const DEBUGSERVER_SERVICES: [&str; 2] = [
+    "com.apple.debugserver.DVTSecureSocketProxy",
+    "com.apple.debugserver",
+];
+
+/// Struct for launching apps through the debugserver
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceDebugServer<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceDebugServer<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceDebugServer<'a, T> {
+        DeviceDebugServer {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl<'a> DeviceDebugServer<'a, SingleDevice> {
+    /// Launches an app, with the given options.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the app (e.g., "com.example.app").
+    /// - `options`: The arguments, environment and working directory of the app, see `LaunchOptions`.
+    ///
+    /// # Errors
+    /// Returns `DeviceDebugServerError::DebugServerUnavailable` if the developer disk image isn't mounted,
+    /// and `DeviceDebugServerError::LaunchFailed` if the device refused to launch the app.
+    pub fn launch(
+        &self,
+        bundle_id: &str,
+        options: &LaunchOptions,
+    ) -> Result<LaunchedApp<'a>, DeviceDebugServerError> {
+        self.device.check_connected::<DeviceDebugServerError>()?;
+
+        let path = self
+            .device
+            .get_device()
+            .new_instproxy_client(crate::config::label("debugserver"))?
+            .get_path_for_bundle_identifier(bundle_id)?;
+
+        let mut connection = DebugServerConnection::new(self.connect()?);
+        connection.send_command("QSetMaxPacketSize:1024")?;
+        if let Some(directory) = &options.working_directory {
+            connection.send_command(&format!(
+                "QSetWorkingDir:{}",
+                protocol::hex_encode(directory)
+            ))?;
+        }
+        for (key, value) in &options.environment {
+            connection.send_command(&protocol::environment_packet(key, value))?;
+        }
+
+        connection.send_command(&protocol::launch_packet(&path, &options.arguments))?;
+        let launched = connection.send_packet("qLaunchSuccess")?;
+        if launched != "OK" {
+            // The error message follows an `E`
+            let reason = launched.strip_prefix('E').unwrap_or(&launched);
+            return Err(DeviceDebugServerError::LaunchFailed(reason.to_string()));
+        }
+
+        let pid = parse_pid(&connection.send_packet("qProcessInfo")?)?;
+
+        let mut app = LaunchedApp {
+            pid,
+            connection: Some(connection),
+        };
+        if !options.wait_for_debugger {
+            app.detach()?;
+        }
+
+        Ok(app)
+    }
+
+    /// Starts the debugserver, through the secure proxy when the device has it.
+    fn connect(&self) -> Result<ServiceClient<'a>, DeviceDebugServerError> {
+        DEBUGSERVER_SERVICES
+            .iter()
+            .find_map(|name| {
+                self.device
+                    .start_custom_service_with_options(name, ServiceStartOptions::new())
+                    .ok()
+            })
+            .ok_or(DeviceDebugServerError::DebugServerUnavailable)
+    }
+}
+
+/// An app launched by `DeviceDebugServer::launch`.
+///
+/// An app launched with `LaunchOptions::wait_for_debugger` stays stopped at its entry point until
+/// it's resumed, dropping it resumes it as well, as the app would be killed with the connection otherwise.
+pub struct LaunchedApp<'a> {
+    pid: u64,
+    connection: Option<DebugServerConnection<'a>>,
+}
+
+impl LaunchedApp<'_> {
+    /// The process identifier of the app
+    pub fn pid(&self) -> u64 {
+        self.pid
+    }
+
+    /// Checks whether the app is still stopped, waiting to be resumed.
+    pub fn is_waiting(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Lets a waiting app run, the debugserver detaches from it.
+    pub fn resume(mut self) -> Result<(), DeviceDebugServerError> {
+        self.detach()
+    }
+
+    /// Kills a waiting app.
+    pub fn kill(mut self) -> Result<(), DeviceDebugServerError> {
+        if let Some(mut connection) = self.connection.take() {
+            // The reply is the exit status of the app, the kill went through either way
+            connection.send_packet("k")?;
+        }
+        Ok(())
+    }
+
+    fn detach(&mut self) -> Result<(), DeviceDebugServerError> {
+        match self.connection.take() {
+            Some(mut connection) => connection.send_command("D"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for LaunchedApp<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.detach() {
+            log::warn!(pid:% = self.pid, error:% = e; "Failed to resume the launched app");
+        }
+    }
+}
+
+/// Reads the pid out of a `qProcessInfo` reply (e.g., "pid:1f4;parent-pid:1;...").
+fn parse_pid(process_info: &str) -> Result<u64, DeviceDebugServerError> {
+    process_info
+        .split(';')
+        .find_map(|field| field.strip_prefix("pid:"))
+        .and_then(|pid| u64::from_str_radix(pid, 16).ok())
+        .ok_or_else(|| DeviceDebugServerError::UnexpectedReply(process_info.to_string()))
+}
@@ -0,0 +1,46 @@
+//! Options for launching the apps through the debugserver.
+
+/// Options for `DeviceDebugServer::launch`.
+///
+/// By default, the app is launched without arguments, in its default environment, and runs right away.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub(crate) arguments: Vec<String>,
+    pub(crate) environment: Vec<(String, String)>,
+    pub(crate) working_directory: Option<String>,
+    pub(crate) wait_for_debugger: bool,
+}
+
+impl LaunchOptions {
+    pub fn new() -> LaunchOptions {
+        LaunchOptions::default()
+    }
+
+    /// Passes the given arguments to the app, after its executable path
+    pub fn with_arguments<I, S>(mut self, arguments: I) -> LaunchOptions
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.arguments = arguments.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets an environment variable of the app (e.g., a feature flag), can be called several times
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> LaunchOptions {
+        self.environment.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the directory the app starts in (e.g., a path of its sandbox)
+    pub fn with_working_directory(mut self, directory: impl Into<String>) -> LaunchOptions {
+        self.working_directory = Some(directory.into());
+        self
+    }
+
+    /// Keeps the app stopped at its entry point until `LaunchedApp::resume` is called
+    pub fn wait_for_debugger(mut self, wait_for_debugger: bool) -> LaunchOptions {
+        self.wait_for_debugger = wait_for_debugger;
+        self
+    }
+}
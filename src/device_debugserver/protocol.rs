@@ -0,0 +1,100 @@
+//! The GDB remote serial protocol spoken by the debugserver.
+//!
+//! Each packet is framed as `$<data>#<checksum>`, the checksum being the sum of the data bytes
+//! modulo 256 in hex, and is acknowledged with a `+`.
+
+use rusty_libimobiledevice::service::ServiceClient;
+
+use crate::{errors::DeviceDebugServerError, plist_service};
+
+/// A connection to the debugserver, exchanging one packet at a time.
+pub(crate) struct DebugServerConnection<'a> {
+    service: ServiceClient<'a>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> DebugServerConnection<'a> {
+    pub(crate) fn new(service: ServiceClient<'a>) -> DebugServerConnection<'a> {
+        DebugServerConnection {
+            service,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Sends a packet and returns the data of its reply.
+    pub(crate) fn send_packet(&mut self, data: &str) -> Result<String, DeviceDebugServerError> {
+        let packet = format!("${}#{:02x}", data, checksum(data.as_bytes()));
+        plist_service::send_all(&self.service, packet.as_bytes())?;
+
+        self.receive_packet()
+    }
+
+    /// Sends a packet expecting an `OK` reply.
+    pub(crate) fn send_command(&mut self, data: &str) -> Result<(), DeviceDebugServerError> {
+        match self.send_packet(data)?.as_str() {
+            "OK" => Ok(()),
+            reply => Err(DeviceDebugServerError::UnexpectedReply(format!(
+                "{} ({})",
+                reply, data
+            ))),
+        }
+    }
+
+    fn receive_packet(&mut self) -> Result<String, DeviceDebugServerError> {
+        loop {
+            // The acknowledgements before the packet are skipped
+            if let Some(start) = self.buffer.iter().position(|&byte| byte == b'$') {
+                let end = self.buffer[start..]
+                    .iter()
+                    .position(|&byte| byte == b'#')
+                    .map(|end| start + end);
+
+                // The two checksum characters follow the `#`
+                if let Some(end) = end.filter(|end| self.buffer.len() >= end + 3) {
+                    let data = String::from_utf8_lossy(&self.buffer[start + 1..end]).into_owned();
+                    self.buffer.drain(..end + 3);
+                    plist_service::send_all(&self.service, b"+")?;
+                    return Ok(data);
+                }
+            }
+
+            let chunk = self.service.receive(1024)?;
+            self.buffer.extend_from_slice(&chunk);
+        }
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Encodes a string as hex, as the packets carrying paths and arguments expect.
+pub(crate) fn hex_encode(text: &str) -> String {
+    text.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Builds the `A` packet launching a program, its arguments being the executable path then the given ones.
+pub(crate) fn launch_packet(path: &str, arguments: &[String]) -> String {
+    let arguments = std::iter::once(path).chain(arguments.iter().map(String::as_str));
+
+    let fields: Vec<String> = arguments
+        .enumerate()
+        .map(|(index, argument)| {
+            let argument = hex_encode(argument);
+            format!("{},{},{}", argument.len(), index, argument)
+        })
+        .collect();
+
+    format!("A{}", fields.join(","))
+}
+
+/// Builds the packet setting an environment variable, hex encoded if it has characters the framing uses.
+pub(crate) fn environment_packet(key: &str, value: &str) -> String {
+    let variable = format!("{}={}", key, value);
+
+    if variable.contains(['#', '$', '}', '*']) {
+        format!("QEnvironmentHexEncoded:{}", hex_encode(&variable))
+    } else {
+        format!("QEnvironment:{}", variable)
+    }
+}
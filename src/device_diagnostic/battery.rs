@@ -0,0 +1,56 @@
+use plist_plus::Plist;
+
+use crate::device_diagnostic::charging::get_uint;
+
+/// Struct representing the wear of the battery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryHealth {
+    /// The number of charge cycles the battery went through
+    pub cycle_count: Option<u64>,
+    /// The capacity the battery was designed for, in mAh
+    pub design_capacity: Option<u64>,
+    /// The capacity the battery can hold now, in mAh
+    pub max_capacity: Option<u64>,
+}
+
+impl BatteryHealth {
+    /// Returns the maximum capacity relative to the design capacity, in percent.
+    ///
+    /// It can go above 100 on new batteries.
+    pub fn health_percentage(&self) -> Option<f64> {
+        let design_capacity = self.design_capacity.filter(|capacity| *capacity > 0)?;
+        Some(self.max_capacity? as f64 / design_capacity as f64 * 100.0)
+    }
+
+    /// Fills the missing values from the IORegistry entry with the ones of the GasGauge diagnostics.
+    pub(crate) fn from_plists(battery: &Plist, gas_gauge: Option<&Plist>) -> BatteryHealth {
+        // The properties are usually nested in an `IORegistry`/`GasGauge` dictionary
+        let battery = battery
+            .dict_get_item("IORegistry")
+            .unwrap_or_else(|_| battery.clone());
+        let gas_gauge = gas_gauge.map(|gas_gauge| {
+            gas_gauge
+                .dict_get_item("GasGauge")
+                .unwrap_or_else(|_| gas_gauge.clone())
+        });
+        let from_gas_gauge = |key: &str| {
+            gas_gauge
+                .as_ref()
+                .and_then(|gas_gauge| get_uint(gas_gauge, key))
+        };
+
+        BatteryHealth {
+            cycle_count: get_uint(&battery, "CycleCount").or_else(|| from_gas_gauge("CycleCount")),
+            design_capacity: get_uint(&battery, "DesignCapacity")
+                .or_else(|| from_gas_gauge("DesignCapacity")),
+            // `MaxCapacity` is a percentage on newer iOS versions, hence the raw value first
+            max_capacity: get_uint(&battery, "AppleRawMaxCapacity")
+                .or_else(|| get_uint(&battery, "NominalChargeCapacity"))
+                .or_else(|| from_gas_gauge("FullChargeCapacity")),
+        }
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        self.cycle_count.is_some() && self.design_capacity.is_some() && self.max_capacity.is_some()
+    }
+}
@@ -0,0 +1,97 @@
+use plist_plus::Plist;
+
+/// Struct representing the power adapter plugged into the device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdapterDetails {
+    /// The power the adapter can deliver, in watts
+    pub watts: Option<u64>,
+    /// The voltage of the adapter, in millivolts
+    pub voltage: Option<u64>,
+    /// The maximum current of the adapter, in milliamps
+    pub current: Option<u64>,
+    /// The name of the adapter, if it reports one
+    pub name: Option<String>,
+    /// Whether it's a wireless (e.g., Qi or MagSafe) charger
+    pub is_wireless: bool,
+}
+
+/// Struct representing the charging state of the device, read from the battery IORegistry entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargingDetails {
+    /// Whether a power source is plugged
+    pub external_connected: bool,
+    /// Whether the battery is being charged
+    pub is_charging: bool,
+    /// Whether the battery is fully charged
+    pub fully_charged: bool,
+    /// The battery current, in milliamps, negative while discharging
+    pub amperage: Option<i64>,
+    /// The battery voltage, in millivolts
+    pub voltage: Option<u64>,
+    /// The adapter details, `None` if nothing is plugged
+    pub adapter: Option<AdapterDetails>,
+}
+
+impl ChargingDetails {
+    /// Checks whether the device is being charged wirelessly.
+    pub fn is_wireless(&self) -> bool {
+        self.adapter
+            .as_ref()
+            .is_some_and(|adapter| adapter.is_wireless)
+    }
+
+    /// Returns the power going into (or out of) the battery, in milliwatts.
+    pub fn battery_power(&self) -> Option<i64> {
+        Some(self.amperage? * self.voltage? as i64 / 1000)
+    }
+
+    pub(crate) fn from_plist(battery: &Plist) -> ChargingDetails {
+        // The properties are usually nested in an `IORegistry` dictionary
+        let properties = battery
+            .dict_get_item("IORegistry")
+            .unwrap_or_else(|_| battery.clone());
+
+        let external_connected = get_bool(&properties, "ExternalConnected");
+        let adapter = properties
+            .dict_get_item("AdapterDetails")
+            .ok()
+            .filter(|_| external_connected)
+            .map(|adapter| AdapterDetails {
+                watts: get_uint(&adapter, "Watts"),
+                voltage: get_uint(&adapter, "AdapterVoltage").or(get_uint(&adapter, "Voltage")),
+                current: get_uint(&adapter, "Current"),
+                name: adapter
+                    .dict_get_item("Name")
+                    .or_else(|_| adapter.dict_get_item("Description"))
+                    .and_then(|name| name.get_string_val())
+                    .ok(),
+                is_wireless: get_bool(&adapter, "IsWireless"),
+            });
+
+        ChargingDetails {
+            external_connected,
+            is_charging: get_bool(&properties, "IsCharging"),
+            fully_charged: get_bool(&properties, "FullyCharged"),
+            // Stored as an unsigned integer, the negative values wrap around
+            amperage: get_uint(&properties, "InstantAmperage")
+                .or(get_uint(&properties, "Amperage"))
+                .map(|amperage| amperage as i64),
+            voltage: get_uint(&properties, "Voltage"),
+            adapter,
+        }
+    }
+}
+
+pub(super) fn get_uint(plist: &Plist, key: &str) -> Option<u64> {
+    plist
+        .dict_get_item(key)
+        .and_then(|value| value.get_uint_val())
+        .ok()
+}
+
+pub(super) fn get_bool(plist: &Plist, key: &str) -> bool {
+    plist
+        .dict_get_item(key)
+        .and_then(|value| value.get_bool_val())
+        .unwrap_or(false)
+}
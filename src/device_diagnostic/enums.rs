@@ -50,3 +50,49 @@ impl Display for DiagnosticType {
         }
     }
 }
+
+/// Enum representing the thermal pressure level of the device.
+///
+/// Anything above `Nominal` means the device is (or is about to be) throttling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermalState {
+    Nominal,
+    Moderate,
+    Heavy,
+    Trapping,
+    Sleeping,
+    Other(u64),
+}
+
+impl ThermalState {
+    /// Checks whether the device is throttling because of the heat.
+    pub fn is_throttling(&self) -> bool {
+        *self != ThermalState::Nominal
+    }
+}
+
+impl From<u64> for ThermalState {
+    fn from(level: u64) -> Self {
+        match level {
+            0 => ThermalState::Nominal,
+            1 => ThermalState::Moderate,
+            2 => ThermalState::Heavy,
+            3 => ThermalState::Trapping,
+            4 => ThermalState::Sleeping,
+            other => ThermalState::Other(other),
+        }
+    }
+}
+
+impl Display for ThermalState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThermalState::Nominal => write!(f, "Nominal"),
+            ThermalState::Moderate => write!(f, "Moderate"),
+            ThermalState::Heavy => write!(f, "Heavy"),
+            ThermalState::Trapping => write!(f, "Trapping"),
+            ThermalState::Sleeping => write!(f, "Sleeping"),
+            ThermalState::Other(level) => write!(f, "Other({})", level),
+        }
+    }
+}
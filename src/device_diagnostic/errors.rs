@@ -1,4 +1,7 @@
-use crate::errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use crate::{
+    device_info::kind::DeviceKind,
+    errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait, UnsupportedDeviceErrorTrait},
+};
 use plist_plus::error::PlistError;
 use rusty_libimobiledevice::error::{DiagnosticsRelayError, LockdowndError};
 use thiserror::Error;
@@ -20,6 +23,21 @@ pub enum DeviceDiagnosticError {
     #[error("Lockdownd Error: {0}")]
     LockdowndError(#[from] LockdowndError),
 
+    #[error("The device doesn't report its thermal state")]
+    ThermalStateUnavailable,
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("The device didn't show up in recovery mode in time")]
+    RecoveryTimeout,
+
+    #[error("The recovery mode devices can't be detected: {0}")]
+    RecoveryUnsupported(String),
+
+    #[error("Not available on this device class ({0})")]
+    UnsupportedDevice(DeviceKind),
+
     #[error("Device not found, make sure it's plugged")]
     DeviceNotFound,
 }
@@ -35,3 +53,9 @@ impl LockdowndErrorTrait for DeviceDiagnosticError {
         Self::LockdowndError(error)
     }
 }
+
+impl UnsupportedDeviceErrorTrait for DeviceDiagnosticError {
+    fn unsupported_device(kind: DeviceKind) -> Self {
+        Self::UnsupportedDevice(kind)
+    }
+}
@@ -2,18 +2,29 @@
 
 use crate::{
     device::DeviceClient,
+    device_info::kind::DeviceKind,
     devices_collection::{DeviceGroup, SingleDevice},
 };
-use enums::{DevicePowerAction, DiagnosticBehavior, DiagnosticType, IORegPlane};
+use battery::BatteryHealth;
+use charging::ChargingDetails;
+use enums::{DevicePowerAction, DiagnosticBehavior, DiagnosticType, IORegPlane, ThermalState};
 use errors::DeviceDiagnosticError;
 use plist_plus::Plist;
 use rusty_libimobiledevice::services::{
     diagnostics_relay::DiagnosticsRelay, lockdownd::LockdowndService,
 };
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 
+pub mod battery;
+pub mod charging;
 pub mod enums;
 pub(crate) mod errors;
+pub mod recovery;
+
+pub use recovery::{wait_for_recovery_device, RecoveryDevice, RecoveryMode};
+
+const THERMAL_IOREG_ENTRY: &str = "IOPMrootDomain";
+const THERMAL_PRESSURE_KEY: &str = "ThermalPressureLevel";
 
 const DIAGNOSTICS_RELAY_SERVICE: &str = "com.apple.mobile.diagnostics_relay";
 
@@ -177,9 +188,12 @@ impl DeviceDiagnostic<'_, SingleDevice> {
     /// A `Plist` containing battery-related information.
     ///
     /// # Errors
-    /// Returns `DeviceDiagnosticError` if the query fails or the device information cannot be retrieved.
+    /// Returns `DeviceDiagnosticError` if the query fails or the device information cannot be retrieved,
+    /// `DeviceDiagnosticError::UnsupportedDevice` if the device has no battery (e.g., Apple TV).
     pub fn get_battery_plist(&self) -> Result<Plist, DeviceDiagnosticError> {
         self.device.check_connected::<DeviceDiagnosticError>()?;
+        self.device
+            .check_supported::<DeviceDiagnosticError>(DeviceKind::has_battery)?;
         let product_version = self
             .device
             .get_device_info()
@@ -200,6 +214,75 @@ impl DeviceDiagnostic<'_, SingleDevice> {
         }
     }
 
+    /// Retrieves the health of the battery, such as the cycle count and the maximum capacity.
+    ///
+    /// The values come from the battery IORegistry entry,
+    /// the GasGauge diagnostics are only queried for the ones it doesn't have.
+    ///
+    /// # Returns
+    /// A `BatteryHealth`, the health percentage is available through `health_percentage`.
+    ///
+    /// # Errors
+    /// Returns `DeviceDiagnosticError` if the query fails.
+    pub fn get_battery_health(&self) -> Result<BatteryHealth, DeviceDiagnosticError> {
+        let battery = self.get_battery_plist()?;
+        let health = BatteryHealth::from_plists(&battery, None);
+
+        if health.is_complete() {
+            return Ok(health);
+        }
+
+        let gas_gauge = self.query_diagnostics(DiagnosticType::GasGauge)?;
+        Ok(BatteryHealth::from_plists(&battery, Some(&gas_gauge)))
+    }
+
+    /// Retrieves the charging state of the device, such as the adapter wattage and the battery current.
+    ///
+    /// # Returns
+    /// A `ChargingDetails` read from the battery IORegistry entry.
+    ///
+    /// # Errors
+    /// Returns `DeviceDiagnosticError` if the query fails.
+    pub fn get_charging_details(&self) -> Result<ChargingDetails, DeviceDiagnosticError> {
+        let battery = self.get_battery_plist()?;
+        Ok(ChargingDetails::from_plist(&battery))
+    }
+
+    /// Retrieves the thermal pressure level of the device.
+    ///
+    /// It's read from the power management entry of the IORegistry,
+    /// useful to discard performance measurements taken while the device was throttling.
+    ///
+    /// # Returns
+    /// The current `ThermalState` of the device.
+    ///
+    /// # Errors
+    /// Returns `DeviceDiagnosticError::ThermalStateUnavailable` if the device doesn't report it.
+    pub fn get_thermal_state(&self) -> Result<ThermalState, DeviceDiagnosticError> {
+        let entry = self.query_ioregentry_key(THERMAL_IOREG_ENTRY)?;
+        // The properties are usually nested in an `IORegistry` dictionary
+        let properties = entry.dict_get_item("IORegistry").unwrap_or(entry);
+
+        properties
+            .dict_get_item(THERMAL_PRESSURE_KEY)
+            .and_then(|level| level.get_uint_val())
+            .map(ThermalState::from)
+            .map_err(|_| DeviceDiagnosticError::ThermalStateUnavailable)
+    }
+
+    /// Reboots the device into the recovery mode, as a restore would.
+    ///
+    /// The device leaves the normal mode, use `wait_for_recovery_device` with its UDID
+    /// to wait for it to show up in recovery mode.
+    pub fn reboot_into_recovery(&self) -> Result<(), DeviceDiagnosticError> {
+        let lockdownd = self
+            .device
+            .get_lockdownd_client::<DeviceDiagnosticError>()?;
+        lockdownd.enter_recovery()?;
+
+        Ok(())
+    }
+
     /// Puts the device to sleep.
     ///
     /// Sends a command to the device to enter sleep mode.
@@ -246,11 +329,13 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     /// Establishes connections to the diagnostics relay services for each device in the group.
     ///
     /// # Returns
-    /// A vector of `DiagnosticsRelay` instances.
+    /// The `DiagnosticsRelay` of each device, along with its UDID.
     ///
     /// # Errors
     /// Returns `DeviceDiagnosticError` if any service cannot be started or any relay cannot be initialized.
-    fn get_diagnostic_relaies(&self) -> Result<Vec<DiagnosticsRelay>, DeviceDiagnosticError> {
+    fn get_diagnostic_relaies(
+        &self,
+    ) -> Result<Vec<(String, DiagnosticsRelay)>, DeviceDiagnosticError> {
         let devices = self.device.get_devices();
         let mut lockdownds = self
             .device
@@ -270,6 +355,7 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
             .zip(devices.iter())
             .map(|(service, device)| {
                 DiagnosticsRelay::new(device, service)
+                    .map(|relay| (device.get_udid(), relay))
                     .map_err(|err| DeviceDiagnosticError::RelayInitializationError(err.to_string()))
             })
             .collect()
@@ -286,7 +372,7 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     /// Returns `DeviceDiagnosticError` if any action fails.
     fn devices_power_action(&self, action: DevicePowerAction) -> Result<(), DeviceDiagnosticError> {
         let relays = self.get_diagnostic_relaies()?;
-        for relay in relays {
+        for (_, relay) in relays {
             match action {
                 DevicePowerAction::Sleep => relay.sleep()?,
                 DevicePowerAction::Restart(flag) => relay.restart(flag as core::ffi::c_uint)?,
@@ -302,21 +388,21 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     /// - `plane`: The IORegistry plane to query.
     ///
     /// # Returns
-    /// A vector of `Plist` containing the queried information for each device.
+    /// The queried information of each device, by UDID.
     ///
     /// # Errors
     /// Returns `DeviceDiagnosticError` if the query fails for any device.
     pub fn query_ioreg_plane_all(
         &self,
         plane: IORegPlane,
-    ) -> Result<Vec<Plist>, DeviceDiagnosticError> {
+    ) -> Result<HashMap<String, Plist>, DeviceDiagnosticError> {
         self.device.check_all_connected::<DeviceDiagnosticError>()?;
         let relays = self.get_diagnostic_relaies()?;
 
-        Ok(relays
+        relays
             .into_iter()
-            .map(|relay| relay.query_ioregistry_plane(plane.to_string()))
-            .collect::<Result<Vec<_>, _>>()?)
+            .map(|(udid, relay)| Ok((udid, relay.query_ioregistry_plane(plane.to_string())?)))
+            .collect::<Result<HashMap<_, _>, DeviceDiagnosticError>>()
     }
 
     /// Queries MobileGestalt information for all devices in the group.
@@ -325,14 +411,14 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     /// - `keys`: A vector of keys to query.
     ///
     /// # Returns
-    /// A vector of `Plist` objects containing the queried MobileGestalt information for each device.
+    /// The queried MobileGestalt information of each device, by UDID.
     ///
     /// # Errors
     /// Returns `DeviceDiagnosticError` if the query fails for any device.
     pub fn query_mobilegestalt_all(
         &self,
         keys: Vec<impl Into<String>>,
-    ) -> Result<Vec<Plist>, DeviceDiagnosticError> {
+    ) -> Result<HashMap<String, Plist>, DeviceDiagnosticError> {
         self.device.check_all_connected::<DeviceDiagnosticError>()?;
         let relays = self.get_diagnostic_relaies()?;
         let mut plist = Plist::new_array();
@@ -341,13 +427,13 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
             plist.array_insert_item(Plist::new_string(&(key.into())), i as u32)?;
         }
 
-        Ok(relays
+        relays
             .into_iter()
-            .map(|relay| {
+            .map(|(udid, relay)| {
                 let plist = plist.clone();
-                relay.query_mobilegestalt(plist)
+                Ok((udid, relay.query_mobilegestalt(plist)?))
             })
-            .collect::<Result<Vec<_>, _>>()?)
+            .collect::<Result<HashMap<_, _>, DeviceDiagnosticError>>()
     }
 
     /// Queries a specific IORegistry entry key for all devices in the group.
@@ -356,7 +442,7 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     /// - `key`: The IORegistry entry key to query.
     ///
     /// # Returns
-    /// A vector of `Plist` objects containing the queried information for each device.
+    /// The queried information of each device, by UDID.
     ///
     /// # Errors
     /// Returns `DeviceDiagnosticError` if the query fails for any device.
@@ -366,16 +452,16 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     pub fn query_ioregentry_key_all(
         &self,
         key: impl Into<String>,
-    ) -> Result<Vec<Plist>, DeviceDiagnosticError> {
+    ) -> Result<HashMap<String, Plist>, DeviceDiagnosticError> {
         self.device.check_all_connected::<DeviceDiagnosticError>()?;
         let relays = self.get_diagnostic_relaies()?;
 
         let key: String = key.into();
 
-        Ok(relays
+        relays
             .into_iter()
-            .map(|relay| relay.query_ioregistry_entry(&key, ""))
-            .collect::<Result<Vec<_>, _>>()?)
+            .map(|(udid, relay)| Ok((udid, relay.query_ioregistry_entry(&key, "")?)))
+            .collect::<Result<HashMap<_, _>, DeviceDiagnosticError>>()
     }
 
     /// Queries diagnostics for all devices in the group.
@@ -384,21 +470,21 @@ impl DeviceDiagnostic<'_, DeviceGroup> {
     /// - `r#type`: The type of diagnostics to query.
     ///
     /// # Returns
-    /// A vector of `Plist` objects containing the diagnostics for each device.
+    /// The diagnostics of each device, by UDID.
     ///
     /// # Errors
     /// Returns `DeviceDiagnosticError` if the query fails for any device.
     pub fn query_diagnostics_all(
         &self,
         r#type: DiagnosticType,
-    ) -> Result<Vec<Plist>, DeviceDiagnosticError> {
+    ) -> Result<HashMap<String, Plist>, DeviceDiagnosticError> {
         self.device.check_all_connected::<DeviceDiagnosticError>()?;
         let relays = self.get_diagnostic_relaies()?;
 
-        Ok(relays
+        relays
             .into_iter()
-            .map(|relay| relay.request_diagnostics(r#type.to_string()))
-            .collect::<Result<Vec<_>, _>>()?)
+            .map(|(udid, relay)| Ok((udid, relay.request_diagnostics(r#type.to_string())?)))
+            .collect::<Result<HashMap<_, _>, DeviceDiagnosticError>>()
     }
 
     /// Sends a sleep command to all devices in the group.
@@ -0,0 +1,178 @@
+//! Waiting for a device rebooted into the recovery mode.
+//!
+//! A device in recovery mode isn't reachable through usbmuxd anymore, it's found by its USB descriptor
+//! instead, matched with the ECID (the chip identifier) the UDID of recent devices carries.
+
+use crate::errors::DeviceDiagnosticError;
+use rusty_libimobiledevice::idevice;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Enum representing the low level mode a device was found in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoveryMode {
+    /// The iBoot recovery mode, entered with `DeviceDiagnostic::reboot_into_recovery`
+    Recovery,
+    /// The Device Firmware Update mode
+    Dfu,
+}
+
+/// A device found in recovery or DFU mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryDevice {
+    /// The chip identifier of the device
+    pub ecid: u64,
+
+    /// The chip identifier of the processor (e.g., `0x8030`), if reported
+    pub cpid: Option<u32>,
+
+    /// The serial number of the device, if reported
+    pub serial_number: Option<String>,
+
+    pub mode: RecoveryMode,
+}
+
+impl RecoveryDevice {
+    /// Parses the serial string of the USB descriptor, such as
+    /// `CPID:8030 CPRV:11 CPFM:03 SCEP:01 BDID:0C ECID:001A2B3C4D5E602E IBFL:3C SRNM:[F2LXXXXXXXXX]`.
+    pub(crate) fn from_usb_serial(serial: &str, mode: RecoveryMode) -> Option<RecoveryDevice> {
+        let field = |name: &str| {
+            serial
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix(name)?.strip_prefix(':'))
+        };
+
+        Some(RecoveryDevice {
+            ecid: u64::from_str_radix(field("ECID")?, 16).ok()?,
+            cpid: field("CPID").and_then(|cpid| u32::from_str_radix(cpid, 16).ok()),
+            serial_number: field("SRNM").map(|srnm| srnm.trim_matches(['[', ']']).to_string()),
+            mode,
+        })
+    }
+}
+
+/// Waits for a device rebooted with `DeviceDiagnostic::reboot_into_recovery` to show up in recovery mode.
+///
+/// It first waits for the device to leave the normal mode, then for its recovery USB device to appear.
+///
+/// Only the UDIDs of the devices since the iPhone XS carry the ECID (`<CPID>-<ECID>`),
+/// and the USB devices are only enumerated on Linux for now.
+///
+/// # Parameters
+/// - `udid`: The UDID the device had in normal mode.
+/// - `timeout`: How long to wait for, rebooting usually takes a few seconds.
+///
+/// # Errors
+/// Returns `DeviceDiagnosticError::RecoveryTimeout` if the device didn't show up in time.
+pub fn wait_for_recovery_device(
+    udid: &str,
+    timeout: Duration,
+) -> Result<RecoveryDevice, DeviceDiagnosticError> {
+    let ecid = ecid_from_udid(udid).ok_or_else(|| {
+        DeviceDiagnosticError::RecoveryUnsupported(format!(
+            "the UDID `{}` doesn't carry the ECID of the device",
+            udid
+        ))
+    })?;
+    let deadline = Instant::now() + timeout;
+
+    while is_in_normal_mode(udid) {
+        wait_until(deadline)?;
+    }
+
+    loop {
+        if let Some(device) = usb::recovery_devices()?
+            .into_iter()
+            .find(|device| device.ecid == ecid)
+        {
+            return Ok(device);
+        }
+        wait_until(deadline)?;
+    }
+}
+
+/// Extracts the ECID of the `<CPID>-<ECID>` UDIDs, e.g., `00008030-001A2B3C4D5E602E`.
+pub(crate) fn ecid_from_udid(udid: &str) -> Option<u64> {
+    match udid.split_once('-') {
+        Some((cpid, ecid)) if cpid.len() == 8 && ecid.len() == 16 => {
+            u64::from_str_radix(ecid, 16).ok()
+        }
+        _ => None,
+    }
+}
+
+fn is_in_normal_mode(udid: &str) -> bool {
+    idevice::get_devices()
+        .unwrap_or_default()
+        .iter()
+        .any(|device| device.get_udid() == udid)
+}
+
+fn wait_until(deadline: Instant) -> Result<(), DeviceDiagnosticError> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(remaining) => {
+            thread::sleep(remaining.min(POLL_INTERVAL));
+            Ok(())
+        }
+        None => Err(DeviceDiagnosticError::RecoveryTimeout),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod usb {
+    use super::{RecoveryDevice, RecoveryMode};
+    use crate::errors::DeviceDiagnosticError;
+    use std::{fs, path::Path};
+
+    const USB_DEVICES: &str = "/sys/bus/usb/devices";
+    const APPLE_VENDOR_ID: &str = "05ac";
+    const DFU_PRODUCT_ID: &str = "1227";
+    const RECOVERY_PRODUCT_IDS: [&str; 4] = ["1280", "1281", "1282", "1283"];
+
+    pub(super) fn recovery_devices() -> Result<Vec<RecoveryDevice>, DeviceDiagnosticError> {
+        let mut devices = Vec::new();
+
+        for entry in fs::read_dir(USB_DEVICES)?.flatten() {
+            let path = entry.path();
+            if read_attribute(&path, "idVendor").as_deref() != Some(APPLE_VENDOR_ID) {
+                continue;
+            }
+
+            let mode = match read_attribute(&path, "idProduct").as_deref() {
+                Some(DFU_PRODUCT_ID) => RecoveryMode::Dfu,
+                Some(product) if RECOVERY_PRODUCT_IDS.contains(&product) => RecoveryMode::Recovery,
+                _ => continue,
+            };
+
+            if let Some(device) = read_attribute(&path, "serial")
+                .and_then(|serial| RecoveryDevice::from_usb_serial(&serial, mode))
+            {
+                devices.push(device);
+            }
+        }
+
+        Ok(devices)
+    }
+
+    fn read_attribute(device: &Path, name: &str) -> Option<String> {
+        fs::read_to_string(device.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod usb {
+    use super::RecoveryDevice;
+    use crate::errors::DeviceDiagnosticError;
+
+    pub(super) fn recovery_devices() -> Result<Vec<RecoveryDevice>, DeviceDiagnosticError> {
+        Err(DeviceDiagnosticError::RecoveryUnsupported(
+            "the USB devices can only be enumerated on Linux".into(),
+        ))
+    }
+}
@@ -0,0 +1,47 @@
+use crate::errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceImageMounterError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("The image mounter failed: {0}")]
+    ImageMounterError(String),
+
+    #[error("No image is mounted with this signature")]
+    ImageNotMounted,
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("No DDI cache directory is set in the configuration")]
+    NoImageCacheDir,
+
+    #[error("The developer disk image isn't cached at {0}")]
+    ImageNotCached(PathBuf),
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceImageMounterError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceImageMounterError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
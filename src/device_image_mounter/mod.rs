@@ -0,0 +1,285 @@
+//! Provides access to the image mounter of iOS devices
+//!
+//! The image mounter keeps track of the disk images mounted on the device, such as the developer
+//! disk image (DDI) the developer services need, and the personalized one of the recent iOS versions.
+//!
+//! ## Features
+//! - Listing the mounted images, with their signatures
+//! - Unmounting an image by its mount path or its signature, e.g., before mounting another version
+//! - Mounting the developer disk image from the cache directory of `RsMobileDeviceConfig`
+//!
+
+use std::{fs, marker::PhantomData, path::PathBuf};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::service::ServiceClient;
+
+pub(crate) mod errors;
+
+use crate::{
+    config::RsMobileDeviceConfig,
+    device::DeviceClient,
+    device_info::{keys::DeviceKeys, version::OsVersion},
+    devices_collection::SingleDevice,
+    errors::DeviceImageMounterError,
+    plist_service,
+};
+
+const IMAGE_MOUNTER_SERVICE: &str = "com.apple.mobile.mobile_image_mounter";
+
+const DEVELOPER_IMAGE_NAME: &str = "DeveloperDiskImage.dmg";
+const DEVELOPER_IMAGE_TYPE: &str = "Developer";
+
+/// Where the uploaded image is kept on the device until it's mounted
+const STAGING_PATH: &str = "/private/var/mobile/Media/PublicStaging/staging.dimage";
+
+/// An image mounted on the device, as listed by `DeviceImageMounter::list_mounted_images`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountedImage {
+    /// The type of the image (e.g., "Developer", "Personalized"), if the device tells it
+    pub image_type: Option<String>,
+
+    /// Where the image is mounted (e.g., "/Developer", "/System/Developer")
+    pub mount_path: Option<String>,
+
+    /// The signature of the image, which tells its version apart
+    pub signature: Vec<u8>,
+}
+
+/// Struct for managing the image mounter service
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceImageMounter<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceImageMounter<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceImageMounter<'a, T> {
+        DeviceImageMounter {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DeviceImageMounter<'_, SingleDevice> {
+    /// Lists the images mounted on the device.
+    ///
+    /// The devices before iOS 14 don't list their images, they answer with an error.
+    pub fn list_mounted_images(&self) -> Result<Vec<MountedImage>, DeviceImageMounterError> {
+        self.device.check_connected::<DeviceImageMounterError>()?;
+
+        let service = self.connect()?;
+        let response = send_command(&service, "CopyDevices", None)?;
+        hangup(&service);
+
+        let entries = response.dict_get_item("EntryList")?;
+        let mut images = Vec::new();
+        for index in 0..entries.array_get_size()? {
+            let entry = entries.array_get_item(index)?;
+            let get_string = |key: &str| {
+                entry
+                    .dict_get_item(key)
+                    .and_then(|value| value.get_string_val())
+                    .ok()
+            };
+
+            images.push(MountedImage {
+                image_type: get_string("DiskImageType"),
+                mount_path: get_string("MountPath"),
+                signature: entry
+                    .dict_get_item("ImageSignature")
+                    .and_then(|signature| signature.get_data_val())
+                    .map(|signature| signature.into_iter().map(|byte| byte as u8).collect())
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(images)
+    }
+
+    /// Unmounts the image mounted at a path.
+    ///
+    /// # Parameters
+    /// - `mount_path`: Where the image is mounted (e.g., "/Developer").
+    pub fn unmount(&self, mount_path: &str) -> Result<(), DeviceImageMounterError> {
+        self.device.check_connected::<DeviceImageMounterError>()?;
+
+        let service = self.connect()?;
+        let result = send_command(&service, "UnmountImage", Some(("MountPath", mount_path)));
+        hangup(&service);
+
+        result.map(|_| ())
+    }
+
+    /// Unmounts the image with the given signature, wherever it's mounted.
+    ///
+    /// # Parameters
+    /// - `signature`: The signature of the image, as listed by `list_mounted_images`.
+    ///
+    /// # Errors
+    /// Returns `DeviceImageMounterError::ImageNotMounted` if no mounted image has this signature.
+    pub fn unmount_signature(&self, signature: &[u8]) -> Result<(), DeviceImageMounterError> {
+        let mount_path = self
+            .list_mounted_images()?
+            .into_iter()
+            .find(|image| image.signature == signature)
+            .and_then(|image| image.mount_path)
+            .ok_or(DeviceImageMounterError::ImageNotMounted)?;
+
+        self.unmount(&mount_path)
+    }
+
+    /// Mounts the developer disk image of the device version, from the DDI cache directory of the configuration.
+    ///
+    /// The images are looked up the way Xcode keeps them, in a directory named after the major and
+    /// minor version (e.g., "<cache>/16.4/DeveloperDiskImage.dmg" and its ".signature"). Only the
+    /// devices before iOS 17 use these images, the later ones need a personalized image.
+    ///
+    /// # Errors
+    /// Returns `DeviceImageMounterError::NoImageCacheDir` if the configuration has no DDI cache directory,
+    /// and `DeviceImageMounterError::ImageNotCached` if the image of the version isn't in it.
+    pub fn mount_developer_image(&self) -> Result<(), DeviceImageMounterError> {
+        self.device.check_connected::<DeviceImageMounterError>()?;
+
+        let (image_path, signature_path) = self.cached_developer_image()?;
+        let image = fs::read(&image_path)?;
+        let signature = fs::read(&signature_path)?;
+
+        let service = self.connect()?;
+        let result = upload_and_mount(&service, &image, &signature);
+        hangup(&service);
+
+        result
+    }
+
+    /// The paths of the cached developer disk image of the device version, and of its signature.
+    fn cached_developer_image(&self) -> Result<(PathBuf, PathBuf), DeviceImageMounterError> {
+        let cache_dir = RsMobileDeviceConfig::global()
+            .ddi_cache_dir()
+            .map(|dir| dir.to_path_buf())
+            .ok_or(DeviceImageMounterError::NoImageCacheDir)?;
+
+        let lockdownd = self
+            .device
+            .get_lockdownd_client::<DeviceImageMounterError>()?;
+        let version = lockdownd
+            .get_value(DeviceKeys::ProductVersion.to_string(), "")?
+            .get_string_val()?
+            .parse::<OsVersion>()
+            .map_err(|_| {
+                DeviceImageMounterError::ImageMounterError("invalid product version".into())
+            })?;
+
+        let image_path = cache_dir
+            .join(format!("{}.{}", version.major, version.minor))
+            .join(DEVELOPER_IMAGE_NAME);
+        let signature_path = image_path.with_extension("dmg.signature");
+        if !image_path.is_file() || !signature_path.is_file() {
+            return Err(DeviceImageMounterError::ImageNotCached(image_path));
+        }
+
+        Ok((image_path, signature_path))
+    }
+
+    /// Starts the image mounter service on the device.
+    fn connect(&self) -> Result<ServiceClient, DeviceImageMounterError> {
+        let device = self.device.get_device();
+        let mut lockdownd = self
+            .device
+            .get_lockdownd_client::<DeviceImageMounterError>()?;
+        let service = lockdownd.start_service(IMAGE_MOUNTER_SERVICE, false)?;
+
+        Ok(ServiceClient::new(device, service)?)
+    }
+}
+
+/// Uploads the image to the staging path of the device, then mounts it from there.
+fn upload_and_mount(
+    service: &ServiceClient,
+    image: &[u8],
+    signature: &[u8],
+) -> Result<(), DeviceImageMounterError> {
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Command", "ReceiveBytes".into())?;
+    request.dict_set_item("ImageType", DEVELOPER_IMAGE_TYPE.into())?;
+    request.dict_set_item("ImageSize", Plist::new_uint(image.len() as u64))?;
+    request.dict_set_item("ImageSignature", Plist::new_data(signature))?;
+    expect_status(&send_request(service, &request)?, "ReceiveBytesAck")?;
+
+    plist_service::send_all(service, image)?;
+    expect_status(
+        &plist_service::receive_plist::<DeviceImageMounterError>(service)?,
+        "Complete",
+    )?;
+
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Command", "MountImage".into())?;
+    request.dict_set_item("ImagePath", STAGING_PATH.into())?;
+    request.dict_set_item("ImageType", DEVELOPER_IMAGE_TYPE.into())?;
+    request.dict_set_item("ImageSignature", Plist::new_data(signature))?;
+    expect_status(&send_request(service, &request)?, "Complete")
+}
+
+/// Fails unless the response has the expected `Status`.
+fn expect_status(response: &Plist, expected: &str) -> Result<(), DeviceImageMounterError> {
+    let status = response
+        .dict_get_item("Status")
+        .and_then(|status| status.get_string_val())
+        .unwrap_or_default();
+    if status != expected {
+        return Err(DeviceImageMounterError::ImageMounterError(format!(
+            "expected the {} status, got \"{}\"",
+            expected, status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Sends a command and receives its response, failing if it carries an `Error` key.
+fn send_command(
+    service: &ServiceClient,
+    command: &str,
+    argument: Option<(&str, &str)>,
+) -> Result<Plist, DeviceImageMounterError> {
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Command", command.into())?;
+    if let Some((key, value)) = argument {
+        request.dict_set_item(key, value.into())?;
+    }
+
+    send_request(service, &request)
+}
+
+/// Sends a request and receives its response, failing if it carries an `Error` key.
+fn send_request(
+    service: &ServiceClient,
+    request: &Plist,
+) -> Result<Plist, DeviceImageMounterError> {
+    plist_service::send_plist(service, request)?;
+
+    let response = plist_service::receive_plist::<DeviceImageMounterError>(service)?;
+    if let Ok(error) = response.dict_get_item("Error") {
+        let description = response
+            .dict_get_item("DetailedError")
+            .and_then(|description| description.get_string_val())
+            .or_else(|_| error.get_display_value())
+            .unwrap_or_default();
+        return Err(DeviceImageMounterError::ImageMounterError(description));
+    }
+
+    Ok(response)
+}
+
+/// Tells the service the session is over, it's closed either way afterwards.
+fn hangup(service: &ServiceClient) {
+    let mut request = Plist::new_dict();
+    if request.dict_set_item("Command", "Hangup".into()).is_ok() {
+        let _ = plist_service::send_plist(service, &request);
+    }
+}
@@ -1,3 +1,5 @@
+/// Enum representing the lockdownd domains the device values are grouped in.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DeviceDomains {
     MobileDebug,
     MobileChaperone,
@@ -31,6 +33,9 @@ pub enum DeviceDomains {
     PurpleBuddy2,
     XCode,
     International,
+    Amfi,
+    MobileDeviceCrashCopy,
+    MobileStorageAnalytics,
     All,
 }
 
@@ -40,7 +45,7 @@ impl DeviceDomains {
             DeviceDomains::All => "".into(),
             DeviceDomains::DiskUsage => "com.apple.disk_usage".into(),
             DeviceDomains::DiskUsageFactory => "com.apple.disk_usage.factory".into(),
-            DeviceDomains::Iqagent => "".into(),
+            DeviceDomains::Iqagent => "com.apple.iqagent".into(),
             DeviceDomains::FMIP => "com.apple.fmip".into(),
             DeviceDomains::Accessibility => "com.apple.Accessibility".into(),
             DeviceDomains::ITunes => "com.apple.iTunes".into(),
@@ -49,6 +54,9 @@ impl DeviceDomains {
             DeviceDomains::PurpleBuddy2 => "com.apple.PurpleBuddy".into(),
             DeviceDomains::XCode => "com.apple.xcode.developerdomain".into(),
             DeviceDomains::International => "com.apple.international".into(),
+            DeviceDomains::Amfi => "com.apple.security.mac.amfi".into(),
+            DeviceDomains::MobileDeviceCrashCopy => "com.apple.MobileDeviceCrashCopy".into(),
+            DeviceDomains::MobileStorageAnalytics => "com.apple.mobile.storage_analytics".into(),
             DeviceDomains::MobileDebug => "com.apple.mobile.debug".into(),
             DeviceDomains::MobileChaperone => "com.apple.mobile.chaperone".into(),
             DeviceDomains::MobileThirdPartyTermination => {
@@ -78,4 +86,49 @@ impl DeviceDomains {
             DeviceDomains::MobileITunesITunes => "com.apple.mobile.iTunes".into(),
         }
     }
+
+    /// Returns an iterator over every known domain, handy to dump all of the device values.
+    ///
+    /// `DeviceDomains::All` (i.e., no domain) is not included.
+    pub fn iter() -> impl Iterator<Item = DeviceDomains> {
+        DOMAINS.into_iter()
+    }
 }
+
+const DOMAINS: [DeviceDomains; 35] = [
+    DeviceDomains::MobileDebug,
+    DeviceDomains::MobileChaperone,
+    DeviceDomains::MobileThirdPartyTermination,
+    DeviceDomains::MobileBattery,
+    DeviceDomains::MobileLockdownd,
+    DeviceDomains::MobileLockdownCache,
+    DeviceDomains::MobileDataSync,
+    DeviceDomains::MobileTetheredSync,
+    DeviceDomains::MobileMobileApplicationUsage,
+    DeviceDomains::MobileBackup,
+    DeviceDomains::MobileNikita,
+    DeviceDomains::MobileRestriction,
+    DeviceDomains::MobileUserPreferences,
+    DeviceDomains::MobileSyncDataClass,
+    DeviceDomains::MobileSoftwareBehavior,
+    DeviceDomains::MobileITunesSQLMusicLibraryPostProcessCommands,
+    DeviceDomains::MobileITunesAccessories,
+    DeviceDomains::MobileITunesStore,
+    DeviceDomains::MobileITunesITunes,
+    DeviceDomains::MobileInternal,
+    DeviceDomains::MobileWirelessLockdown,
+    DeviceDomains::DiskUsage,
+    DeviceDomains::DiskUsageFactory,
+    DeviceDomains::Iqagent,
+    DeviceDomains::FMIP,
+    DeviceDomains::Accessibility,
+    DeviceDomains::ITunes,
+    DeviceDomains::Fairplay,
+    DeviceDomains::PurpleBuddy,
+    DeviceDomains::PurpleBuddy2,
+    DeviceDomains::XCode,
+    DeviceDomains::International,
+    DeviceDomains::Amfi,
+    DeviceDomains::MobileDeviceCrashCopy,
+    DeviceDomains::MobileStorageAnalytics,
+];
@@ -1,4 +1,7 @@
-use crate::errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use crate::{
+    device_info::kind::DeviceKind,
+    errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait, UnsupportedDeviceErrorTrait},
+};
 use plist_plus::error::PlistError;
 use rusty_libimobiledevice::error::LockdowndError;
 use thiserror::Error;
@@ -16,6 +19,24 @@ pub enum DeviceInfoError {
 
     #[error("Device not found, make sure it's plugged")]
     DeviceNotFound,
+
+    #[error("Couldn't parse the version: {0}")]
+    InvalidVersion(String),
+
+    #[error("This can only be done over USB")]
+    RequiresUsb,
+
+    #[error("Not available on this device class ({0})")]
+    UnsupportedDevice(DeviceKind),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Couldn't reach lockdownd without pairing: {0}")]
+    PreloginUnavailable(String),
+
+    #[error("The value watcher stopped before it started")]
+    WatcherStopped,
 }
 
 impl DeviceNotFoundErrorTrait for DeviceInfoError {
@@ -29,3 +50,9 @@ impl LockdowndErrorTrait for DeviceInfoError {
         Self::LockdowndError(error)
     }
 }
+
+impl UnsupportedDeviceErrorTrait for DeviceInfoError {
+    fn unsupported_device(kind: DeviceKind) -> Self {
+        Self::UnsupportedDevice(kind)
+    }
+}
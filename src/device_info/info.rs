@@ -0,0 +1,486 @@
+//! The device information service, built with the `info` feature.
+
+use std::{collections::HashMap, fmt::Display, marker::PhantomData, time::Duration};
+
+use crate::{
+    device::DeviceClient,
+    device_info::{
+        domains::DeviceDomains,
+        errors::DeviceInfoError,
+        keys::DeviceKeys,
+        kind::DeviceKind,
+        prelogin::{self, PreloginValues, QueryType},
+        version::OsVersion,
+        watch::{self, ValueWatcher},
+    },
+    devices_collection::{DeviceGroup, SingleDevice},
+};
+use plist_plus::Plist;
+use rusty_libimobiledevice::error::LockdowndError;
+
+/// Struct for managing device information retrieval
+///
+/// # Type Parameters
+/// - `T`: The type of the device or device group (SingleDevice or DeviceGroup)
+///
+#[derive(Debug)]
+pub struct DeviceInfo<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl Display for DeviceInfo<'_, SingleDevice> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = String::new();
+
+        let output = self
+            .get_plist(None, DeviceDomains::All)
+            .expect("Couldn't display device info");
+
+        // Format each line of the plist data
+        for line in output {
+            text.push_str(&format!(
+                "{}: {}\n",
+                line.key.unwrap_or("unknown".into()),
+                line.plist.get_display_value().unwrap_or("unknown".into())
+            ));
+        }
+
+        write!(f, "{}", text)
+    }
+}
+
+impl Display for DeviceInfo<'_, DeviceGroup> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = String::new();
+
+        let plists = self
+            .get_plist_all(None, DeviceDomains::All)
+            .expect("Couldn't display device info");
+
+        // Iterate over all the devices, in a stable order, and format their information
+        let mut plists: Vec<_> = plists.into_iter().collect();
+        plists.sort_by(|(first, _), (second, _)| first.cmp(second));
+        for (udid, plist) in plists {
+            text.push_str(&format!("{}:\n", udid));
+            for line in plist {
+                text.push_str(&format!(
+                    "\t{}: {}\n",
+                    line.key.unwrap_or("unknown".into()),
+                    line.plist.get_display_value().unwrap_or("unknown".into())
+                ));
+            }
+        }
+
+        write!(f, "{}", text)
+    }
+}
+
+impl DeviceInfo<'_, SingleDevice> {
+    /// Retrieves a plist from a single device.
+    ///
+    /// # Arguments
+    /// - `key`: The specific key to query, `None` retrieves the whole domain.
+    /// - `domain`: The domain within which to search for the key.
+    pub fn get_plist(
+        &self,
+        key: Option<&str>,
+        domain: DeviceDomains,
+    ) -> Result<Plist, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+
+        let lockdownd = self.device.get_lockdownd_client::<DeviceInfoError>()?;
+        match lockdownd.get_value(key.unwrap_or_default(), domain.as_string()) {
+            Ok(output) => Ok(output),
+            // Some domains don't exist on some devices (e.g., the battery one on an Apple TV)
+            Err(err) => match self.get_device_kind()? {
+                kind if !kind.supports_domain(&domain) => {
+                    Err(DeviceInfoError::UnsupportedDevice(kind))
+                }
+                _ => Err(DeviceInfoError::LockdowndError(err)),
+            },
+        }
+    }
+
+    /// Retrieves a whole domain as an XML plist, the same bytes `ideviceinfo -x` prints,
+    /// so the scripts and parsers built around libimobiledevice keep working.
+    ///
+    /// # Arguments
+    /// - `domain`: The domain to dump, `DeviceDomains::All` for the one without a name (as `ideviceinfo -x` does).
+    pub fn to_xml_plist(&self, domain: DeviceDomains) -> Result<String, DeviceInfoError> {
+        // The XML is written by libplist, as it's for ideviceinfo
+        Ok(self.get_plist(None, domain)?.to_xml())
+    }
+
+    /// Retrieves multiple values from a device based on a domain.
+    ///
+    /// # Arguments
+    /// - `domain`: The domain within which to retrieve all values.
+    ///
+    pub fn get_values(
+        &self,
+        domain: DeviceDomains,
+    ) -> Result<HashMap<String, String>, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        let mut dict: HashMap<String, String> = HashMap::new();
+
+        let output = self.get_plist(None, domain)?;
+
+        // Populate the HashMap with device data
+        for line in output {
+            dict.insert(
+                line.key.unwrap_or("unknown".to_string()),
+                line.plist
+                    .get_display_value()
+                    .unwrap_or("unknown".to_string())
+                    .replace('"', ""),
+            );
+        }
+        Ok(dict)
+    }
+
+    /// Retrieves a single value from a device based on a key and domain.
+    ///
+    /// # Arguments
+    /// - `key`: The key to query.
+    /// - `domain`: The domain within which to search for the key.
+    pub fn get_value(
+        &self,
+        key: DeviceKeys,
+        domain: DeviceDomains,
+    ) -> Result<String, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        let values = self.get_values(domain)?;
+
+        if let Some(value) = values.get(&key.to_string()) {
+            return Ok(value.to_owned());
+        }
+
+        // The cellular values are missing from the devices without a modem (e.g., Apple TV)
+        let kind = match domain {
+            DeviceDomains::All => detect_kind(&values),
+            _ => self.get_device_kind()?,
+        };
+        if kind.supports_key(&key) {
+            Err(DeviceInfoError::KeyNotFound)
+        } else {
+            Err(DeviceInfoError::UnsupportedDevice(kind))
+        }
+    }
+
+    /// Retrieves all the values from all the domains.
+    pub fn get_all_values(&self) -> Result<HashMap<String, String>, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        self.get_values(DeviceDomains::All)
+    }
+
+    pub fn get_product_type(&self) -> Result<String, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        self.get_value(DeviceKeys::ProductType, DeviceDomains::All)
+    }
+
+    /// Retrieves the parsed operating system version of the device, along with its build version.
+    pub fn get_product_version(&self) -> Result<OsVersion, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        let values = self.get_values(DeviceDomains::All)?;
+
+        parse_os_version(&values)
+    }
+
+    /// Retrieves the family of the device (e.g., iPhone, Apple TV), from its `DeviceClass` or `ProductType`.
+    pub fn get_device_kind(&self) -> Result<DeviceKind, DeviceInfoError> {
+        self.device.get_device_kind::<DeviceInfoError>()
+    }
+
+    /// Retrieves the values given out without pairing (e.g., `ProductType`, `UniqueDeviceID`),
+    /// such as for the intake of untrusted devices at a kiosk.
+    ///
+    /// No session is started, so the device doesn't ask to trust the host.
+    /// The keys it refuses, which depends on the iOS version, are listed in `unavailable`.
+    pub fn get_prelogin_values(&self) -> Result<PreloginValues, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        prelogin::read_values(self.device.get_device())
+    }
+
+    /// Watches values for changes, by querying them on each interval and comparing with the previous query.
+    ///
+    /// This is a non blocking function, the changes are received from the returned watcher
+    /// until it's stopped or dropped. The first query is made before returning, a change is
+    /// sent only when a value differs from it afterwards.
+    ///
+    /// # Arguments
+    /// - `keys`: The domains and keys to watch (e.g., `(DeviceDomains::MobileBattery, "BatteryCurrentCapacity")`).
+    /// - `interval`: The time between two queries.
+    pub fn watch(
+        &self,
+        keys: &[(DeviceDomains, &str)],
+        interval: Duration,
+    ) -> Result<ValueWatcher, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        watch::start(self.device.get_device().clone(), keys, interval)
+    }
+
+    /// Asks which service answers on the lockdownd port (e.g., `Lockdown`, or `Restore` while being restored).
+    ///
+    /// A single request is sent, without starting a session, so it's cheap enough to label a device
+    /// right after it's attached, even before it's paired.
+    pub fn query_type(&self) -> Result<QueryType, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        prelogin::query_type(self.device.get_device())
+    }
+
+    /// Retrieves the family of the device from its `DeviceClass` only, without starting a session.
+    ///
+    /// Unlike `get_device_kind`, the `ProductType` isn't read, so the devices with an unknown
+    /// class are `DeviceKind::Unknown`.
+    pub fn get_device_class(&self) -> Result<DeviceKind, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+        let device_class = prelogin::read_device_class(self.device.get_device())?
+            .ok_or(DeviceInfoError::KeyNotFound)?;
+
+        Ok(DeviceKind::from_device_class(&device_class))
+    }
+
+    /// Enables or disables the Wi-Fi connections, so the device can be reached over the network afterwards.
+    ///
+    /// It must be done over USB, the device has to be paired with the host.
+    ///
+    /// # Arguments
+    /// - `enabled`: Whether the Wi-Fi connections are allowed.
+    pub fn enable_wifi_connections(&self, enabled: bool) -> Result<(), DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+
+        if self.device.get_device().get_network() {
+            return Err(DeviceInfoError::RequiresUsb);
+        }
+
+        let lockdownd = self.device.get_lockdownd_client::<DeviceInfoError>()?;
+        lockdownd.set_value(
+            "EnableWifiConnections",
+            DeviceDomains::MobileWirelessLockdown.as_string(),
+            enabled.into(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Checks whether the Wi-Fi connections are enabled.
+    pub fn is_wifi_connections_enabled(&self) -> Result<bool, DeviceInfoError> {
+        self.device.check_connected::<DeviceInfoError>()?;
+
+        let lockdownd = self.device.get_lockdownd_client::<DeviceInfoError>()?;
+        match lockdownd.get_value(
+            "EnableWifiConnections",
+            DeviceDomains::MobileWirelessLockdown.as_string(),
+        ) {
+            Ok(value) => Ok(value
+                .get_bool_val()
+                .map_err(|_| LockdowndError::InvalidValue)?),
+            // The key is missing until it's set once
+            Err(LockdowndError::MissingKey) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl DeviceInfo<'_, DeviceGroup> {
+    /// Retrieves plist data for all devices in a group, by UDID.
+    ///
+    /// # Arguments
+    /// - `key`: The specific key to query, `None` retrieves the whole domain.
+    /// - `domain`: The domain within which to search for the key.
+    pub fn get_plist_all(
+        &self,
+        key: Option<&str>,
+        domain: DeviceDomains,
+    ) -> Result<HashMap<String, Plist>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        let lockdownds = self.device.get_lockdownd_clients::<DeviceInfoError>()?;
+
+        let key = key.unwrap_or_default();
+
+        let plists = self
+            .device
+            .get_devices()
+            .iter()
+            .zip(lockdownds)
+            .map(|(device, lockdownd)| {
+                Ok((
+                    device.get_udid(),
+                    lockdownd.get_value(key, domain.as_string())?,
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, DeviceInfoError>>()?;
+
+        Ok(plists)
+    }
+
+    /// Asks which service answers on the lockdownd port of all devices in a group, by UDID, see `query_type`.
+    pub fn query_type_all(&self) -> Result<HashMap<String, QueryType>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+
+        self.device
+            .get_devices()
+            .iter()
+            .map(|device| Ok((device.get_udid(), prelogin::query_type(device)?)))
+            .collect()
+    }
+
+    /// Retrieves the family of all devices in a group from their `DeviceClass` only, by UDID,
+    /// see `get_device_class`.
+    pub fn get_device_class_all(&self) -> Result<HashMap<String, DeviceKind>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+
+        self.device
+            .get_devices()
+            .iter()
+            .map(|device| {
+                let device_class =
+                    prelogin::read_device_class(device)?.ok_or(DeviceInfoError::KeyNotFound)?;
+                Ok((
+                    device.get_udid(),
+                    DeviceKind::from_device_class(&device_class),
+                ))
+            })
+            .collect()
+    }
+
+    /// Retrieves a whole domain as an XML plist for all devices in a group, by UDID,
+    /// the same bytes `ideviceinfo -x` prints for each of them.
+    ///
+    /// # Arguments
+    /// - `domain`: The domain to dump, `DeviceDomains::All` for the one without a name.
+    pub fn to_xml_plist_all(
+        &self,
+        domain: DeviceDomains,
+    ) -> Result<HashMap<String, String>, DeviceInfoError> {
+        Ok(self
+            .get_plist_all(None, domain)?
+            .into_iter()
+            .map(|(udid, plist)| (udid, plist.to_xml()))
+            .collect())
+    }
+
+    /// Retrieves multiple values for all devices in a group based on a domain, by UDID.
+    ///
+    /// # Arguments
+    /// - `domain`: The domain within which to retrieve all values.
+    pub fn get_values_all(
+        &self,
+        domain: DeviceDomains,
+    ) -> Result<HashMap<String, HashMap<String, String>>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        let mut dicts: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+        for (udid, plist) in self.get_plist_all(None, domain)?.into_iter() {
+            let mut device_dict = HashMap::new();
+            for line in plist {
+                device_dict.insert(
+                    line.key.unwrap_or("unknown".to_string()),
+                    line.plist
+                        .get_display_value()
+                        .unwrap_or("unknown".to_string())
+                        .replace('"', ""),
+                );
+            }
+
+            dicts.insert(udid, device_dict);
+        }
+
+        Ok(dicts)
+    }
+
+    /// Retrieves a specific value for all devices in a group based on a key and domain, by UDID.
+    ///
+    /// # Arguments
+    /// - `key`: The key to query.
+    /// - `domain`: The domain within which to search for the key.
+    pub fn get_value_all(
+        &self,
+        key: DeviceKeys,
+        domain: DeviceDomains,
+    ) -> Result<HashMap<String, String>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        let values = self.get_values_all(domain)?;
+
+        values
+            .into_iter()
+            .map(|(udid, value)| {
+                value
+                    .get(&key.to_string())
+                    .cloned() // Convert from &String to String
+                    .map(|value| (udid, value))
+                    .ok_or(DeviceInfoError::KeyNotFound)
+            })
+            .collect::<Result<HashMap<_, _>, _>>()
+    }
+
+    /// Retrieves all values for all devices in a group, by UDID.
+    pub fn get_all_values_all(
+        &self,
+    ) -> Result<HashMap<String, HashMap<String, String>>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        self.get_values_all(DeviceDomains::All)
+    }
+
+    /// Retrieves the product type for all connected device, by UDID.
+    pub fn get_product_type_all(&self) -> Result<HashMap<String, String>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        self.get_value_all(DeviceKeys::ProductType, DeviceDomains::All)
+    }
+
+    /// Retrieves the parsed operating system version for all connected device, by UDID.
+    pub fn get_product_version_all(&self) -> Result<HashMap<String, OsVersion>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        self.get_values_all(DeviceDomains::All)?
+            .into_iter()
+            .map(|(udid, values)| Ok((udid, parse_os_version(&values)?)))
+            .collect()
+    }
+
+    /// Retrieves the family of all connected device, by UDID.
+    pub fn get_device_kind_all(&self) -> Result<HashMap<String, DeviceKind>, DeviceInfoError> {
+        self.device.check_all_connected::<DeviceInfoError>()?;
+        Ok(self
+            .get_values_all(DeviceDomains::All)?
+            .into_iter()
+            .map(|(udid, values)| {
+                let kind = detect_kind(&values);
+                (udid, kind)
+            })
+            .collect())
+    }
+}
+
+fn detect_kind(values: &HashMap<String, String>) -> DeviceKind {
+    DeviceKind::detect(
+        values
+            .get(&DeviceKeys::DeviceClass.to_string())
+            .map(String::as_str),
+        values
+            .get(&DeviceKeys::ProductType.to_string())
+            .map(String::as_str),
+    )
+}
+
+/// Builds an `OsVersion` out of the `ProductVersion` and `BuildVersion` values of a device.
+fn parse_os_version(values: &HashMap<String, String>) -> Result<OsVersion, DeviceInfoError> {
+    let version = values
+        .get(&DeviceKeys::ProductVersion.to_string())
+        .ok_or(DeviceInfoError::KeyNotFound)?
+        .parse::<OsVersion>()?;
+
+    match values.get(&DeviceKeys::BuildVersion.to_string()) {
+        Some(build) => Ok(version.with_build(build)),
+        None => Ok(version),
+    }
+}
+
+impl<'a, T> DeviceInfo<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceInfo<'a, T> {
+        DeviceInfo {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
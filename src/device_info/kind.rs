@@ -0,0 +1,149 @@
+use std::fmt::{self, Display};
+
+use crate::device_info::{domains::DeviceDomains, keys::DeviceKeys};
+
+/// Enum representing the family of a device, to branch on it without matching strings.
+///
+/// The `has_*` and `supports_*` functions tell what the family can have,
+/// an `Unknown` family is assumed to have everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceKind {
+    IPhone,
+    IPad,
+    IPod,
+    AppleTV,
+    Watch,
+    HomePod,
+    VisionPro,
+    /// A family not known by this library
+    Unknown,
+}
+
+impl DeviceKind {
+    /// Detects the family from the `DeviceClass` of the device, or from its `ProductType` (e.g., "iPhone14,2").
+    ///
+    /// # Arguments
+    /// - `device_class`: The `DeviceClass` value (e.g., "iPhone", "AudioAccessory"), if any.
+    /// - `product_type`: The `ProductType` value, used when the class is missing or unknown.
+    pub fn detect(device_class: Option<&str>, product_type: Option<&str>) -> DeviceKind {
+        let from_class = device_class.map_or(DeviceKind::Unknown, DeviceKind::from_device_class);
+        if from_class != DeviceKind::Unknown {
+            return from_class;
+        }
+
+        product_type.map_or(DeviceKind::Unknown, DeviceKind::from_product_type)
+    }
+
+    /// Detects the family from a `DeviceClass` value (e.g., "iPhone", "AppleTV", "RealityDevice").
+    pub fn from_device_class(device_class: &str) -> DeviceKind {
+        match device_class {
+            "iPhone" => DeviceKind::IPhone,
+            "iPad" => DeviceKind::IPad,
+            "iPod" => DeviceKind::IPod,
+            "AppleTV" => DeviceKind::AppleTV,
+            "Watch" => DeviceKind::Watch,
+            "AudioAccessory" => DeviceKind::HomePod,
+            "RealityDevice" => DeviceKind::VisionPro,
+            _ => DeviceKind::Unknown,
+        }
+    }
+
+    /// Detects the family from a `ProductType` value (e.g., "iPad13,4", "Watch6,1").
+    pub fn from_product_type(product_type: &str) -> DeviceKind {
+        // The product types are the family followed by the model numbers
+        let family = product_type.trim_end_matches(|c: char| c.is_ascii_digit() || c == ',');
+        DeviceKind::from_device_class(family)
+    }
+
+    /// Whether the device runs on a battery, the Apple TV and the HomePod don't.
+    pub fn has_battery(&self) -> bool {
+        !matches!(self, DeviceKind::AppleTV | DeviceKind::HomePod)
+    }
+
+    /// Whether the device may have a cellular modem, which the telephony keys come from.
+    ///
+    /// It's only a possibility for the iPhones, iPads and Apple Watches, as not all of them have one.
+    pub fn has_cellular(&self) -> bool {
+        matches!(
+            self,
+            DeviceKind::IPhone | DeviceKind::IPad | DeviceKind::Watch | DeviceKind::Unknown
+        )
+    }
+
+    /// Whether the device has the SpringBoard home screen, tvOS and watchOS use their own.
+    pub fn has_home_screen(&self) -> bool {
+        matches!(
+            self,
+            DeviceKind::IPhone | DeviceKind::IPad | DeviceKind::IPod | DeviceKind::Unknown
+        )
+    }
+
+    /// Whether the screen of the device can be captured, the Apple Watch and the HomePod can't.
+    pub fn has_screen_capture(&self) -> bool {
+        !matches!(self, DeviceKind::Watch | DeviceKind::HomePod)
+    }
+
+    /// Whether the device has the values of a lockdownd domain.
+    pub fn supports_domain(&self, domain: &DeviceDomains) -> bool {
+        match domain {
+            DeviceDomains::MobileBattery => self.has_battery(),
+            _ => true,
+        }
+    }
+
+    /// Whether the device may have a value, the ones of the cellular modem are missing from the others.
+    pub fn supports_key(&self, key: &DeviceKeys) -> bool {
+        let is_cellular_key = matches!(
+            key,
+            DeviceKeys::BasebandActivationTicketVersion
+                | DeviceKeys::BasebandCertId
+                | DeviceKeys::BasebandChipID
+                | DeviceKeys::BasebandKeyHashInformation
+                | DeviceKeys::BasebandMasterKeyHash
+                | DeviceKeys::BasebandRegionSKU
+                | DeviceKeys::BasebandSerialNumber
+                | DeviceKeys::BasebandStatus
+                | DeviceKeys::BasebandVersion
+                | DeviceKeys::CarrierBundleInfoArray
+                | DeviceKeys::IntegratedCircuitCardIdentity
+                | DeviceKeys::InternationalMobileEquipmentIdentity
+                | DeviceKeys::InternationalMobileEquipmentIdentity2
+                | DeviceKeys::InternationalMobileSubscriberIdentity
+                | DeviceKeys::InternationalMobileSubscriberIdentityOverride
+                | DeviceKeys::MobileEquipmentIdentifier
+                | DeviceKeys::MobileSubscriberCountryCode
+                | DeviceKeys::MobileSubscriberNetworkCode
+                | DeviceKeys::PhoneNumber
+                | DeviceKeys::PRIVersionMajor
+                | DeviceKeys::PRIVersionMinor
+                | DeviceKeys::PRIVersionReleaseNo
+                | DeviceKeys::SIM1IsEmbedded
+                | DeviceKeys::SIMStatus
+                | DeviceKeys::SIMTrayStatus
+                | DeviceKeys::CTPostponementInfoPRIVersion
+                | DeviceKeys::CTPostponementInfoServiceProvisioningState
+                | DeviceKeys::CTPostponementStatus
+        );
+
+        !is_cellular_key || self.has_cellular()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceKind::IPhone => "iPhone",
+            DeviceKind::IPad => "iPad",
+            DeviceKind::IPod => "iPod",
+            DeviceKind::AppleTV => "Apple TV",
+            DeviceKind::Watch => "Apple Watch",
+            DeviceKind::HomePod => "HomePod",
+            DeviceKind::VisionPro => "Apple Vision Pro",
+            DeviceKind::Unknown => "Unknown",
+        }
+    }
+}
+
+impl Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
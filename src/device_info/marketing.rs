@@ -0,0 +1,56 @@
+//! The marketing names of the devices, which lockdownd doesn't give out.
+//!
+//! Only the recent iPhones, iPads and iPods are known, the other product types have no name.
+
+/// The marketing name of a product type (e.g., "iPhone14,2" is "iPhone 13 Pro").
+pub fn marketing_name(product_type: &str) -> Option<&'static str> {
+    let name = match product_type {
+        "iPhone10,1" | "iPhone10,4" => "iPhone 8",
+        "iPhone10,2" | "iPhone10,5" => "iPhone 8 Plus",
+        "iPhone10,3" | "iPhone10,6" => "iPhone X",
+        "iPhone11,2" => "iPhone XS",
+        "iPhone11,4" | "iPhone11,6" => "iPhone XS Max",
+        "iPhone11,8" => "iPhone XR",
+        "iPhone12,1" => "iPhone 11",
+        "iPhone12,3" => "iPhone 11 Pro",
+        "iPhone12,5" => "iPhone 11 Pro Max",
+        "iPhone12,8" => "iPhone SE (2nd generation)",
+        "iPhone13,1" => "iPhone 12 mini",
+        "iPhone13,2" => "iPhone 12",
+        "iPhone13,3" => "iPhone 12 Pro",
+        "iPhone13,4" => "iPhone 12 Pro Max",
+        "iPhone14,4" => "iPhone 13 mini",
+        "iPhone14,5" => "iPhone 13",
+        "iPhone14,2" => "iPhone 13 Pro",
+        "iPhone14,3" => "iPhone 13 Pro Max",
+        "iPhone14,6" => "iPhone SE (3rd generation)",
+        "iPhone14,7" => "iPhone 14",
+        "iPhone14,8" => "iPhone 14 Plus",
+        "iPhone15,2" => "iPhone 14 Pro",
+        "iPhone15,3" => "iPhone 14 Pro Max",
+        "iPhone15,4" => "iPhone 15",
+        "iPhone15,5" => "iPhone 15 Plus",
+        "iPhone16,1" => "iPhone 15 Pro",
+        "iPhone16,2" => "iPhone 15 Pro Max",
+        "iPhone17,3" => "iPhone 16",
+        "iPhone17,4" => "iPhone 16 Plus",
+        "iPhone17,1" => "iPhone 16 Pro",
+        "iPhone17,2" => "iPhone 16 Pro Max",
+        "iPhone17,5" => "iPhone 16e",
+        "iPad12,1" | "iPad12,2" => "iPad (9th generation)",
+        "iPad13,18" | "iPad13,19" => "iPad (10th generation)",
+        "iPad13,1" | "iPad13,2" => "iPad Air (4th generation)",
+        "iPad13,16" | "iPad13,17" => "iPad Air (5th generation)",
+        "iPad14,1" | "iPad14,2" => "iPad mini (6th generation)",
+        "iPad13,4" | "iPad13,5" | "iPad13,6" | "iPad13,7" => "iPad Pro 11-inch (3rd generation)",
+        "iPad13,8" | "iPad13,9" | "iPad13,10" | "iPad13,11" => {
+            "iPad Pro 12.9-inch (5th generation)"
+        }
+        "iPad14,3" | "iPad14,4" => "iPad Pro 11-inch (4th generation)",
+        "iPad14,5" | "iPad14,6" => "iPad Pro 12.9-inch (6th generation)",
+        "iPod9,1" => "iPod touch (7th generation)",
+        _ => return None,
+    };
+
+    Some(name)
+}
@@ -0,0 +1,162 @@
+//! The values lockdownd gives out before the device is paired, without starting a session.
+//!
+//! The same connection is used for the quick probes made right after a device is attached
+//! (`QueryType` and the `DeviceClass`), as they don't need the pairing either.
+//!
+//! `LockdowndClient` always does the pairing handshake, which an untrusted device refuses,
+//! so the requests are sent on a raw connection to lockdownd (see `lockdown_connection`).
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::idevice::Device;
+
+use crate::{
+    config,
+    device_info::keys::DeviceKeys,
+    errors::DeviceInfoError,
+    lockdown_connection::{self, LockdownStream},
+};
+
+/// The keys asked for by `DeviceInfo::get_prelogin_values`, not all of them are given out by every iOS version
+pub(crate) const PRELOGIN_KEYS: [DeviceKeys; 8] = [
+    DeviceKeys::ProductType,
+    DeviceKeys::ProductVersion,
+    DeviceKeys::BuildVersion,
+    DeviceKeys::DeviceClass,
+    DeviceKeys::UniqueDeviceID,
+    DeviceKeys::DeviceName,
+    DeviceKeys::HardwareModel,
+    DeviceKeys::ActivationState,
+];
+
+/// The values read from a device without pairing, as returned by `DeviceInfo::get_prelogin_values`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreloginValues {
+    /// The values the device gave out, by key name
+    pub values: HashMap<String, String>,
+
+    /// The keys the device refused or doesn't have, they need a paired session
+    pub unavailable: Vec<String>,
+}
+
+impl PreloginValues {
+    /// Returns the value of a key, if the device gave it out.
+    pub fn get(&self, key: DeviceKeys) -> Option<&str> {
+        self.values.get(&key.to_string()).map(String::as_str)
+    }
+
+    /// Whether the device gave out the value of a key.
+    pub fn is_available(&self, key: DeviceKeys) -> bool {
+        self.values.contains_key(&key.to_string())
+    }
+}
+
+/// The service answering on the lockdownd port, as returned by `DeviceInfo::query_type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryType {
+    /// The device is booted normally ("com.apple.mobile.lockdown")
+    Lockdown,
+
+    /// The device is being restored ("com.apple.mobile.restored")
+    Restore,
+
+    /// Any other service, by its type
+    Other(String),
+}
+
+impl QueryType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            QueryType::Lockdown => "Lockdown",
+            QueryType::Restore => "Restore",
+            QueryType::Other(service_type) => service_type,
+        }
+    }
+}
+
+impl From<&str> for QueryType {
+    fn from(service_type: &str) -> Self {
+        match service_type {
+            "com.apple.mobile.lockdown" => QueryType::Lockdown,
+            "com.apple.mobile.restored" => QueryType::Restore,
+            other => QueryType::Other(other.to_string()),
+        }
+    }
+}
+
+impl Display for QueryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Reads the prelogin keys one by one, a refused key doesn't stop the others.
+pub(crate) fn read_values(device: &Device) -> Result<PreloginValues, DeviceInfoError> {
+    let mut stream = lockdown_connection::connect(device)?;
+    let mut prelogin = PreloginValues::default();
+
+    for key in PRELOGIN_KEYS {
+        let key = key.to_string();
+        match get_value(&mut stream, &key)? {
+            Some(value) => {
+                prelogin.values.insert(key, value);
+            }
+            None => prelogin.unavailable.push(key),
+        }
+    }
+
+    Ok(prelogin)
+}
+
+/// Asks which service answers on the lockdownd port, with a single request.
+pub(crate) fn query_type(device: &Device) -> Result<QueryType, DeviceInfoError> {
+    let mut stream = lockdown_connection::connect(device)?;
+    let response = send_request(&mut stream, "QueryType", None)?;
+
+    let service_type = response
+        .dict_get_item("Type")
+        .and_then(|service_type| service_type.get_string_val())?;
+
+    Ok(QueryType::from(service_type.as_str()))
+}
+
+/// Reads the `DeviceClass` with a single request, `None` if the device refused it.
+pub(crate) fn read_device_class(device: &Device) -> Result<Option<String>, DeviceInfoError> {
+    let mut stream = lockdown_connection::connect(device)?;
+    get_value(&mut stream, &DeviceKeys::DeviceClass.to_string())
+}
+
+/// Sends a `GetValue` request outside of a session, `None` if the device answered with an error.
+fn get_value(stream: &mut LockdownStream, key: &str) -> Result<Option<String>, DeviceInfoError> {
+    let response = send_request(stream, "GetValue", Some(key))?;
+
+    if response.dict_get_item("Error").is_ok() {
+        return Ok(None);
+    }
+
+    Ok(response
+        .dict_get_item("Value")
+        .and_then(|value| value.get_display_value())
+        .ok()
+        .map(|value| value.replace('"', "")))
+}
+
+/// Sends a request outside of a session and returns the response.
+fn send_request(
+    stream: &mut LockdownStream,
+    name: &str,
+    key: Option<&str>,
+) -> Result<Plist, DeviceInfoError> {
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Label", config::label("prelogin").as_str().into())?;
+    request.dict_set_item("Request", name.into())?;
+    if let Some(key) = key {
+        request.dict_set_item("Key", key.into())?;
+    }
+
+    lockdown_connection::exchange(stream, &request)
+}
@@ -0,0 +1,148 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::errors::DeviceInfoError;
+
+/// A parsed operating system version of a device (e.g., "17.4.1" with build "21E236")
+///
+/// Comparisons only take the numeric components into account, the build version is ignored
+#[derive(Debug, Clone, Default)]
+pub struct OsVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+
+    /// The build version of the operating system (e.g., "21E236"), if available
+    pub build: Option<String>,
+}
+
+impl OsVersion {
+    pub fn new(major: u32, minor: u32, patch: u32) -> OsVersion {
+        OsVersion {
+            major,
+            minor,
+            patch,
+            build: None,
+        }
+    }
+
+    /// Attaches a build version to the parsed version
+    pub fn with_build(mut self, build: impl Into<String>) -> OsVersion {
+        self.build = Some(build.into());
+        self
+    }
+}
+
+impl FromStr for OsVersion {
+    type Err = DeviceInfoError;
+
+    /// Parses a version string like "17", "17.4" or "17.4.1", missing components are treated as 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('.');
+
+        let mut next_part = |required: bool| -> Result<u32, DeviceInfoError> {
+            match parts.next() {
+                Some(part) => part
+                    .parse::<u32>()
+                    .map_err(|_| DeviceInfoError::InvalidVersion(s.to_owned())),
+                None if required => Err(DeviceInfoError::InvalidVersion(s.to_owned())),
+                None => Ok(0),
+            }
+        };
+
+        let major = next_part(true)?;
+        let minor = next_part(false)?;
+        let patch = next_part(false)?;
+
+        if parts.next().is_some() {
+            return Err(DeviceInfoError::InvalidVersion(s.to_owned()));
+        }
+
+        Ok(OsVersion::new(major, minor, patch))
+    }
+}
+
+impl Display for OsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(build) = &self.build {
+            write!(f, " ({})", build)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for OsVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OsVersion {}
+
+impl PartialOrd for OsVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OsVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_missing_components_as_zero() {
+        assert_eq!("17".parse::<OsVersion>().unwrap(), OsVersion::new(17, 0, 0));
+        assert_eq!(
+            "17.4".parse::<OsVersion>().unwrap(),
+            OsVersion::new(17, 4, 0)
+        );
+        assert_eq!(
+            " 17.4.1\n".parse::<OsVersion>().unwrap(),
+            OsVersion::new(17, 4, 1)
+        );
+    }
+
+    #[test]
+    fn rejects_the_malformed_versions() {
+        for version in ["", "17.", "17.x", "17.4.1.2", "-1", "17..1"] {
+            assert!(
+                matches!(
+                    version.parse::<OsVersion>(),
+                    Err(DeviceInfoError::InvalidVersion(_))
+                ),
+                "{version:?} was parsed"
+            );
+        }
+    }
+
+    #[test]
+    fn orders_numerically() {
+        let version = |s: &str| s.parse::<OsVersion>().unwrap();
+
+        assert!(version("9.3") < version("10.0"));
+        assert!(version("17.4") < version("17.10"));
+        assert!(version("16.7.8") < version("17"));
+        assert_eq!(version("17"), version("17.0.0"));
+    }
+
+    #[test]
+    fn ignores_the_build_when_comparing() {
+        let older = OsVersion::new(17, 4, 1).with_build("21E236");
+        let newer = OsVersion::new(17, 4, 1).with_build("21E237");
+
+        assert_eq!(older, newer);
+        assert_eq!(older, OsVersion::new(17, 4, 1));
+        assert_eq!(older.to_string(), "17.4.1 (21E236)");
+        assert_eq!(OsVersion::new(17, 4, 1).to_string(), "17.4.1");
+    }
+}
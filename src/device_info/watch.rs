@@ -0,0 +1,208 @@
+//! Watching the values of a device for changes, by querying them again and again.
+//!
+//! lockdownd doesn't tell when a value changes, so the watched domains are read on each interval
+//! and compared with the previous read, the differences are sent through a channel.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rusty_libimobiledevice::{idevice::Device, services::lockdownd::LockdowndClient};
+
+use crate::{device_info::domains::DeviceDomains, errors::DeviceInfoError};
+
+/// How often the stop flag is checked while waiting for the next query
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A change of a watched value, sent by `ValueWatcher`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueChanged {
+    /// The domain of the value
+    pub domain: DeviceDomains,
+
+    /// The key of the value
+    pub key: String,
+
+    /// The previous value, `None` if the device didn't have it
+    pub old: Option<String>,
+
+    /// The new value, `None` if the device doesn't have it anymore
+    pub new: Option<String>,
+}
+
+/// Handle to the thread watching the values, returned by `DeviceInfo::watch`.
+///
+/// The changes are received from it, either with `recv` or by iterating over it,
+/// the iteration ends when the watching stops (e.g., the device got disconnected).
+///
+/// Dropping the handle stops the watching and waits for the thread to finish.
+#[derive(Debug)]
+pub struct ValueWatcher {
+    receiver: Receiver<ValueChanged>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ValueWatcher {
+    /// Returns the receiving end of the changes, e.g., to wait for them with a timeout.
+    pub fn receiver(&self) -> &Receiver<ValueChanged> {
+        &self.receiver
+    }
+
+    /// Waits for the next change, `None` once the watching has stopped.
+    pub fn recv(&self) -> Option<ValueChanged> {
+        self.receiver.recv().ok()
+    }
+
+    /// Stops watching and waits for the thread to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    /// Checks whether the watching thread has finished (e.g., the device got disconnected).
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Iterator for ValueWatcher {
+    type Item = ValueChanged;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl Drop for ValueWatcher {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+/// Starts the watching thread, the first read is made before returning so its failure is returned.
+pub(crate) fn start(
+    device: Device,
+    keys: &[(DeviceDomains, &str)],
+    interval: Duration,
+) -> Result<ValueWatcher, DeviceInfoError> {
+    // Each domain is read once per query, whatever the number of keys watched in it
+    let mut watched: Vec<(DeviceDomains, Vec<String>)> = Vec::new();
+    for (domain, key) in keys {
+        match watched.iter_mut().find(|(watched, _)| watched == domain) {
+            Some((_, keys)) => keys.push(key.to_string()),
+            None => watched.push((*domain, vec![key.to_string()])),
+        }
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+
+    let (sender, receiver) = mpsc::channel();
+    let (ready_sender, ready_receiver) = mpsc::sync_channel(1);
+
+    let handle = thread::spawn(move || {
+        let read = LockdowndClient::new(&device, crate::config::label("value-watcher"))
+            .map_err(DeviceInfoError::from)
+            .and_then(|lockdownd| {
+                let values = read_watched(&lockdownd, &watched)?;
+                Ok((lockdownd, values))
+            });
+        let (lockdownd, mut previous) = match read {
+            Ok(read) => {
+                let _ = ready_sender.send(Ok(()));
+                read
+            }
+            Err(err) => {
+                let _ = ready_sender.send(Err(err));
+                return;
+            }
+        };
+
+        'watch: loop {
+            let next_query = Instant::now() + interval;
+            while Instant::now() < next_query {
+                if thread_stop.load(Ordering::SeqCst) {
+                    break 'watch;
+                }
+                thread::sleep(STOP_CHECK_INTERVAL.min(next_query - Instant::now()));
+            }
+
+            let current = match read_watched(&lockdownd, &watched) {
+                Ok(current) => current,
+                Err(err) => {
+                    log::warn!(udid:% = device.get_udid(), error:% = err; "Failed to query the watched values");
+                    break;
+                }
+            };
+
+            // Both reads are in the order of the watched keys
+            for (((domain, key), new), (_, old)) in current.iter().zip(&previous) {
+                if old != new {
+                    let change = ValueChanged {
+                        domain: *domain,
+                        key: key.clone(),
+                        old: old.clone(),
+                        new: new.clone(),
+                    };
+                    // Nobody is receiving anymore
+                    if sender.send(change).is_err() {
+                        break 'watch;
+                    }
+                }
+            }
+            previous = current;
+        }
+    });
+
+    match ready_receiver.recv() {
+        Ok(Ok(())) => Ok(ValueWatcher {
+            receiver,
+            stop,
+            handle: Some(handle),
+        }),
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(DeviceInfoError::WatcherStopped),
+    }
+}
+
+type WatchedValues = Vec<((DeviceDomains, String), Option<String>)>;
+
+/// Reads the watched keys, in the order they were given, the missing ones are `None`.
+fn read_watched(
+    lockdownd: &LockdowndClient,
+    watched: &[(DeviceDomains, Vec<String>)],
+) -> Result<WatchedValues, DeviceInfoError> {
+    let mut values = Vec::new();
+
+    for (domain, keys) in watched {
+        let mut domain_values: HashMap<String, String> = HashMap::new();
+        for line in lockdownd.get_value("", domain.as_string())? {
+            if let (Some(key), Ok(value)) = (line.key, line.plist.get_display_value()) {
+                domain_values.insert(key, value.replace('"', ""));
+            }
+        }
+
+        for key in keys {
+            values.push(((*domain, key.clone()), domain_values.get(key).cloned()));
+        }
+    }
+
+    Ok(values)
+}
@@ -0,0 +1,303 @@
+//! Options and types for listing the apps installed on the device.
+
+use plist_plus::{error::PlistError, Plist};
+use rusty_libimobiledevice::services::instproxy::InstProxyClient;
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Enum representing which kind of apps to list.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AppType {
+    /// The apps installed by the user
+    #[default]
+    User,
+    /// The apps shipped with iOS
+    System,
+    /// The internal apps (only on internal builds)
+    Internal,
+    /// All of them
+    Any,
+}
+
+impl AppType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AppType::User => "User",
+            AppType::System => "System",
+            AppType::Internal => "Internal",
+            AppType::Any => "Any",
+        }
+    }
+}
+
+/// Options for `DeviceInstaller::list_apps`, mirroring the installation_proxy client options.
+///
+/// By default, it lists the user apps with all their attributes, hidden apps excluded.
+/// Asking for only the needed attributes with `with_attributes` makes the listing a lot faster,
+/// as the whole Info.plist of every app is transferred otherwise.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppListOptions {
+    app_type: AppType,
+    include_hidden: bool,
+    attributes: Option<Vec<String>>,
+}
+
+impl AppListOptions {
+    pub fn new() -> AppListOptions {
+        AppListOptions::default()
+    }
+
+    /// Sets which kind of apps to list
+    pub fn with_app_type(mut self, app_type: AppType) -> AppListOptions {
+        self.app_type = app_type;
+        self
+    }
+
+    /// Includes the hidden apps (tagged as `hidden` or prohibited from launching)
+    pub fn include_hidden(mut self, include_hidden: bool) -> AppListOptions {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Returns only the given attributes of each app (e.g., `CFBundleIdentifier`)
+    pub fn with_attributes<I, S>(mut self, attributes: I) -> AppListOptions
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.attributes = Some(attributes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub(crate) fn include_hidden_apps(&self) -> bool {
+        self.include_hidden
+    }
+
+    /// Builds the installation_proxy client options.
+    ///
+    /// The `SBAppTags` attribute is added to the whitelist when the hidden apps must be filtered out,
+    /// so it shows up in the returned apps as well.
+    pub(crate) fn to_client_options(&self) -> Result<Plist, PlistError> {
+        let mut options = InstProxyClient::client_options_new();
+        options.dict_set_item("ApplicationType", self.app_type.as_str().into())?;
+
+        if self.include_hidden {
+            options.dict_set_item("ShowLaunchProhibitedApps", true.into())?;
+        }
+
+        if let Some(attributes) = &self.attributes {
+            let mut return_attributes = Plist::new_array();
+            for attribute in attributes {
+                return_attributes.array_append_item(attribute.as_str().into())?;
+            }
+            if !self.include_hidden && !attributes.iter().any(|a| a == "SBAppTags") {
+                return_attributes.array_append_item("SBAppTags".into())?;
+            }
+            options.dict_set_item("ReturnAttributes", return_attributes)?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// Checks whether the app dictionary is tagged as hidden
+pub(crate) fn is_hidden(app: &Plist) -> bool {
+    let Ok(tags) = app.dict_get_item("SBAppTags") else {
+        return false;
+    };
+
+    (0..tags.array_get_size().unwrap_or(0)).any(|index| {
+        tags.array_get_item(index)
+            .and_then(|tag| tag.get_string_val())
+            .is_ok_and(|tag| tag == "hidden")
+    })
+}
+
+/// The attributes asked for by `DeviceInstaller::get_app`
+pub(crate) const APP_INFO_ATTRIBUTES: [&str; 12] = [
+    "CFBundleIdentifier",
+    "CFBundleDisplayName",
+    "CFBundleName",
+    "CFBundleVersion",
+    "CFBundleShortVersionString",
+    "SignerIdentity",
+    "Entitlements",
+    "Path",
+    "Container",
+    "GroupContainers",
+    "StaticDiskUsage",
+    "DynamicDiskUsage",
+];
+
+/// The details of an installed app, as returned by `DeviceInstaller::get_app`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppInfo {
+    /// The bundle identifier (e.g., "com.apple.Pages")
+    pub bundle_id: String,
+
+    /// The name shown under the app icon, if any
+    pub name: Option<String>,
+
+    /// The build version (`CFBundleVersion`)
+    pub version: Option<String>,
+
+    /// The marketing version (`CFBundleShortVersionString`)
+    pub short_version: Option<String>,
+
+    /// The identity that signed the app (e.g., "Apple iPhone OS Application Signing")
+    pub signer_identity: Option<String>,
+
+    /// The main entitlements of the app
+    pub entitlements: AppEntitlements,
+
+    /// The path of the app bundle
+    pub bundle_path: Option<String>,
+
+    /// The path of the app data container (Documents, Library, tmp)
+    pub data_container: Option<String>,
+
+    /// The paths of the shared app group containers, by group identifier
+    pub group_containers: HashMap<String, String>,
+
+    /// The size of the app bundle, in bytes
+    pub static_disk_usage: Option<u64>,
+
+    /// The size of the app data, in bytes
+    pub dynamic_disk_usage: Option<u64>,
+}
+
+impl AppInfo {
+    /// The total space taken by the app and its data, in bytes
+    pub fn installed_size(&self) -> u64 {
+        self.static_disk_usage.unwrap_or(0) + self.dynamic_disk_usage.unwrap_or(0)
+    }
+
+    pub(crate) fn from_plist(bundle_id: &str, app: &Plist) -> AppInfo {
+        let string = |key: &str| {
+            app.dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+        let number = |key: &str| {
+            app.dict_get_item(key)
+                .and_then(|value| value.get_uint_val())
+                .ok()
+        };
+
+        let group_containers = app
+            .dict_get_item("GroupContainers")
+            .map(|groups| {
+                groups
+                    .into_iter()
+                    .filter_map(|group| Some((group.key?, group.plist.get_string_val().ok()?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        AppInfo {
+            bundle_id: string("CFBundleIdentifier").unwrap_or_else(|| bundle_id.to_string()),
+            name: string("CFBundleDisplayName").or_else(|| string("CFBundleName")),
+            version: string("CFBundleVersion"),
+            short_version: string("CFBundleShortVersionString"),
+            signer_identity: string("SignerIdentity"),
+            entitlements: app
+                .dict_get_item("Entitlements")
+                .map(|entitlements| AppEntitlements::from_plist(&entitlements))
+                .unwrap_or_default(),
+            bundle_path: string("Path"),
+            data_container: string("Container"),
+            group_containers,
+            static_disk_usage: number("StaticDiskUsage"),
+            dynamic_disk_usage: number("DynamicDiskUsage"),
+        }
+    }
+}
+
+/// A summary of the entitlements of an app.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppEntitlements {
+    /// The `application-identifier` entitlement (the team identifier followed by the bundle identifier)
+    pub application_identifier: Option<String>,
+
+    /// The `com.apple.developer.team-identifier` entitlement
+    pub team_identifier: Option<String>,
+
+    /// Whether the app can be debugged (`get-task-allow`), true for development builds
+    pub get_task_allow: bool,
+
+    /// The app groups the app belongs to
+    pub application_groups: Vec<String>,
+
+    /// The keychain access groups of the app
+    pub keychain_access_groups: Vec<String>,
+
+    /// The names of all the entitlements, sorted
+    pub keys: Vec<String>,
+}
+
+impl AppEntitlements {
+    fn from_plist(entitlements: &Plist) -> AppEntitlements {
+        let string = |key: &str| {
+            entitlements
+                .dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+        let strings = |key: &str| {
+            let Ok(array) = entitlements.dict_get_item(key) else {
+                return Vec::new();
+            };
+            (0..array.array_get_size().unwrap_or(0))
+                .filter_map(|index| {
+                    array
+                        .array_get_item(index)
+                        .and_then(|value| value.get_string_val())
+                        .ok()
+                })
+                .collect()
+        };
+
+        let mut keys: Vec<String> = entitlements
+            .clone()
+            .into_iter()
+            .filter_map(|item| item.key)
+            .collect();
+        keys.sort();
+
+        AppEntitlements {
+            application_identifier: string("application-identifier"),
+            team_identifier: string("com.apple.developer.team-identifier"),
+            get_task_allow: entitlements
+                .dict_get_item("get-task-allow")
+                .and_then(|value| value.get_bool_val())
+                .unwrap_or(false),
+            application_groups: strings("com.apple.security.application-groups"),
+            keychain_access_groups: strings("keychain-access-groups"),
+            keys,
+        }
+    }
+}
+
+/// Compares two bundle versions (e.g., "1.10.2" and "1.9"), component by component.
+///
+/// The numeric components are compared as numbers, the others as strings, and missing components count as 0.
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_components = a.split('.');
+    let mut b_components = b.split('.');
+
+    loop {
+        let ordering = match (a_components.next(), b_components.next()) {
+            (None, None) => return Ordering::Equal,
+            (a, b) => {
+                let (a, b) = (a.unwrap_or("0"), b.unwrap_or("0"));
+                match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => a.cmp(b),
+                }
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
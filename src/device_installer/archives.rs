@@ -0,0 +1,44 @@
+//! Options for archiving the apps, so they can be removed while keeping their data.
+
+use plist_plus::{error::PlistError, Plist};
+use rusty_libimobiledevice::services::instproxy::InstProxyClient;
+
+/// Options for `DeviceInstaller::archive_app`.
+///
+/// By default, the app and its data are archived and the app is uninstalled.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArchiveOptions {
+    skip_uninstall: bool,
+    application_only: bool,
+}
+
+impl ArchiveOptions {
+    pub fn new() -> ArchiveOptions {
+        ArchiveOptions::default()
+    }
+
+    /// Keeps the app installed after archiving it
+    pub fn skip_uninstall(mut self, skip_uninstall: bool) -> ArchiveOptions {
+        self.skip_uninstall = skip_uninstall;
+        self
+    }
+
+    /// Archives only the app bundle, without its data
+    pub fn application_only(mut self, application_only: bool) -> ArchiveOptions {
+        self.application_only = application_only;
+        self
+    }
+
+    pub(crate) fn to_client_options(&self) -> Result<Plist, PlistError> {
+        let mut options = InstProxyClient::client_options_new();
+
+        if self.skip_uninstall {
+            options.dict_set_item("SkipUninstall", true.into())?;
+        }
+        if self.application_only {
+            options.dict_set_item("ArchiveType", "ApplicationOnly".into())?;
+        }
+
+        Ok(options)
+    }
+}
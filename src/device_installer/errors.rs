@@ -1,8 +1,10 @@
 use plist_plus::error::PlistError;
-use rusty_libimobiledevice::error::{AfcError, InstProxyError};
+use rusty_libimobiledevice::error::{AfcError, InstProxyError, LockdowndError, ServiceError};
 use thiserror::Error;
 
-use crate::errors::{AFCClientErrorTrait, DeviceNotFoundErrorTrait};
+use crate::errors::{
+    AFCClientErrorTrait, DeviceAfcError, DeviceNotFoundErrorTrait, LockdowndErrorTrait,
+};
 
 #[derive(Debug, Error)]
 pub enum DeviceInstallerError {
@@ -27,6 +29,34 @@ pub enum DeviceInstallerError {
     #[error("Installation Proxy Error: {0}")]
     InstallationProxyError(#[from] InstProxyError),
 
+    #[error("The app `{0}` is not installed")]
+    AppNotFound(String),
+
+    #[error("The given package is not an ipa")]
+    NotAnIpa,
+
+    #[error("Refusing to downgrade `{bundle_id}` from {installed_version} to {new_version}")]
+    Downgrade {
+        bundle_id: String,
+        installed_version: String,
+        new_version: String,
+    },
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("The provisioning profiles service failed with status {0}")]
+    MisagentError(u64),
+
+    #[error("The given path is not an `.app` directory")]
+    NotAnAppBundle,
+
+    #[error("AFC Error: {0}")]
+    DeviceAfcError(#[from] DeviceAfcError),
+
     #[error("Device not found, make sure it's plugged")]
     DeviceNotFound,
 }
@@ -42,3 +72,9 @@ impl DeviceNotFoundErrorTrait for DeviceInstallerError {
         Self::DeviceNotFound
     }
 }
+
+impl LockdowndErrorTrait for DeviceInstallerError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
@@ -6,9 +6,15 @@
 //! ## Features
 //! - Installing from bytes
 //! - Supporting ipa and ipcc packages
+//! - Installing unpacked, developer signed, app bundles
+//! - Listing the installed apps
+//! - Archiving apps and restoring them from their archives
+//! - Inspecting the provisioning profiles and their expiry
+//! - Reporting the install progress to a `ProgressObserver`
 //!
 
 use std::{
+    cmp::{Ordering as CmpOrdering, Reverse},
     collections::HashMap,
     ffi::OsStr,
     fmt::Display,
@@ -17,7 +23,7 @@ use std::{
     path::Path,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex, PoisonError,
     },
 };
 
@@ -28,10 +34,21 @@ use rusty_libimobiledevice::services::{
 };
 use zip::ZipArchive;
 
+pub mod apps;
+pub mod archives;
 pub(crate) mod errors;
+pub mod provisioning;
+
+pub use apps::{AppEntitlements, AppInfo, AppListOptions, AppType};
+pub use archives::ArchiveOptions;
+pub use provisioning::{AppProvisioning, ProvisioningProfile};
 
 use crate::{
-    device::DeviceClient, devices_collection::SingleDevice, errors::DeviceInstallerError,
+    device::DeviceClient,
+    device_afc::{transfer, TransferOptions},
+    devices_collection::SingleDevice,
+    errors::DeviceInstallerError,
+    progress::{ProgressObserver, ProgressUpdate},
     RecursiveFind,
 };
 
@@ -58,6 +75,12 @@ enum PackageType {
     Unknown,
 }
 
+/// Enum to represent the installation_proxy command used to install a package.
+enum InstallCommand {
+    Install,
+    Upgrade, // Keeps the data of the installed app
+}
+
 impl Display for PackageType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -69,6 +92,196 @@ impl Display for PackageType {
 }
 
 impl DeviceInstaller<'_, SingleDevice> {
+    /// Lists the apps installed on the device.
+    ///
+    /// Each app is a dictionary of its attributes (e.g., `CFBundleIdentifier`, `CFBundleVersion`),
+    /// limited to the ones asked for in the options, if any.
+    ///
+    /// # Parameters
+    /// - `options`: Which apps and attributes to list, see `AppListOptions`.
+    pub fn list_apps(&self, options: AppListOptions) -> Result<Vec<Plist>, DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let installation_client = self.instproxy_client()?;
+
+        let apps = installation_client.browse(Some(options.to_client_options()?))?;
+
+        let mut listed_apps = Vec::new();
+        for index in 0..apps.array_get_size()? {
+            let app = apps.array_get_item(index)?;
+
+            if !options.include_hidden_apps() && apps::is_hidden(&app) {
+                continue;
+            }
+            listed_apps.push(app);
+        }
+
+        Ok(listed_apps)
+    }
+
+    /// Retrieves the details of an installed app, including its versions, signature and disk usage.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the app.
+    pub fn get_app(&self, bundle_id: &str) -> Result<AppInfo, DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let installation_client = self.instproxy_client()?;
+
+        let mut return_attributes = Plist::new_array();
+        for attribute in apps::APP_INFO_ATTRIBUTES {
+            return_attributes.array_append_item(attribute.into())?;
+        }
+        let mut options = InstProxyClient::client_options_new();
+        options.dict_set_item("ReturnAttributes", return_attributes)?;
+
+        let result = installation_client.lookup(vec![bundle_id.to_string()], Some(options))?;
+
+        // The result is a dictionary keyed by the bundle identifiers found
+        let app = result
+            .dict_get_item(bundle_id)
+            .map_err(|_| DeviceInstallerError::AppNotFound(bundle_id.to_string()))?;
+
+        Ok(AppInfo::from_plist(bundle_id, &app))
+    }
+
+    /// Lists the apps along with the space they take, the biggest first.
+    ///
+    /// The size of each app is its bundle (`StaticDiskUsage`) plus its data (`DynamicDiskUsage`),
+    /// see `AppInfo::installed_size`.
+    ///
+    /// # Parameters
+    /// - `app_type`: Which kind of apps to report.
+    pub fn get_storage_report(
+        &self,
+        app_type: AppType,
+    ) -> Result<Vec<AppInfo>, DeviceInstallerError> {
+        let options = AppListOptions::new()
+            .with_app_type(app_type)
+            .include_hidden(true)
+            .with_attributes(apps::APP_INFO_ATTRIBUTES);
+
+        let mut report = self
+            .list_apps(options)?
+            .iter()
+            .filter_map(|app| {
+                let bundle_id = app
+                    .dict_get_item("CFBundleIdentifier")
+                    .and_then(|bundle_id| bundle_id.get_string_val())
+                    .ok()?;
+                Some(AppInfo::from_plist(&bundle_id, app))
+            })
+            .collect::<Vec<_>>();
+
+        report.sort_by_key(|app| Reverse(app.installed_size()));
+
+        Ok(report)
+    }
+
+    /// Lists the provisioning profiles installed on the device.
+    pub fn list_provisioning_profiles(
+        &self,
+    ) -> Result<Vec<ProvisioningProfile>, DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+        provisioning::copy_profiles(self.device.get_device())
+    }
+
+    /// Retrieves the entitlements of an installed app, along with the provisioning profiles covering it.
+    ///
+    /// The apps from the App Store have no profile, the development and enterprise ones stop launching
+    /// once all their profiles expire, see `AppProvisioning::expiration_date`.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the app.
+    pub fn get_app_provisioning(
+        &self,
+        bundle_id: &str,
+    ) -> Result<AppProvisioning, DeviceInstallerError> {
+        let app = self.get_app(bundle_id)?;
+
+        let mut profiles = match &app.entitlements.application_identifier {
+            Some(application_identifier) => self
+                .list_provisioning_profiles()?
+                .into_iter()
+                .filter(|profile| profile.matches(application_identifier))
+                .collect(),
+            None => Vec::new(),
+        };
+        profiles.sort_by_key(|profile| Reverse(profile.expiration_date));
+
+        Ok(AppProvisioning {
+            bundle_id: app.bundle_id,
+            signer_identity: app.signer_identity,
+            entitlements: app.entitlements,
+            profiles,
+        })
+    }
+
+    /// Archives an app, and uninstalls it unless told otherwise, so it can be restored later with its data.
+    ///
+    /// This is a blocking function, it returns once the archive is done.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the app.
+    /// - `options`: How to archive the app, see `ArchiveOptions`.
+    pub fn archive_app(
+        &self,
+        bundle_id: &str,
+        options: ArchiveOptions,
+    ) -> Result<(), DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let installation_client = self.instproxy_client()?;
+        installation_client.archive(bundle_id, Some(options.to_client_options()?))?;
+
+        Ok(())
+    }
+
+    /// Lists the archived apps, sorted by bundle identifier.
+    pub fn list_archives(&self) -> Result<Vec<AppInfo>, DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let installation_client = self.instproxy_client()?;
+        let archives = installation_client.lookup_archives(None)?;
+
+        // The archives are a dictionary keyed by the bundle identifiers
+        let mut archived_apps: Vec<AppInfo> = archives
+            .into_iter()
+            .filter_map(|archive| Some(AppInfo::from_plist(&archive.key?, &archive.plist)))
+            .collect();
+        archived_apps.sort_by(|a, b| a.bundle_id.cmp(&b.bundle_id));
+
+        Ok(archived_apps)
+    }
+
+    /// Restores an archived app along with its data.
+    ///
+    /// This is a blocking function, it returns once the app is restored.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the archived app.
+    pub fn restore_archive(&self, bundle_id: &str) -> Result<(), DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let installation_client = self.instproxy_client()?;
+        installation_client.restore(bundle_id, None)?;
+
+        Ok(())
+    }
+
+    /// Deletes the archive of an app, its data is lost.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the archived app.
+    pub fn remove_archive(&self, bundle_id: &str) -> Result<(), DeviceInstallerError> {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let installation_client = self.instproxy_client()?;
+        installation_client.remove_archive(bundle_id, None)?;
+
+        Ok(())
+    }
+
     /// Installs a package from a given file path.
     ///
     /// # Parameters
@@ -93,7 +306,7 @@ impl DeviceInstaller<'_, SingleDevice> {
 
         let mut cursor = Cursor::new(file_content);
 
-        self._install_package(&mut cursor, options, None)
+        self._install_package(&mut cursor, options, None, InstallCommand::Install)
     }
 
     /// Installs a package from a given file path with a callback of the progress.
@@ -122,7 +335,12 @@ impl DeviceInstaller<'_, SingleDevice> {
 
         let mut cursor = Cursor::new(file_content);
 
-        self._install_package(&mut cursor, options, Some(Box::new(callback)))
+        self._install_package(
+            &mut cursor,
+            options,
+            Some(Box::new(callback)),
+            InstallCommand::Install,
+        )
     }
 
     /// Installs a package from a reader (e.g., bytes from memory or a stream).
@@ -140,7 +358,7 @@ impl DeviceInstaller<'_, SingleDevice> {
         options: Option<HashMap<&str, &str>>,
     ) -> Result<(), DeviceInstallerError> {
         self.device.check_connected::<DeviceInstallerError>()?;
-        self._install_package(package_file, options, None)
+        self._install_package(package_file, options, None, InstallCommand::Install)
     }
 
     /// Installs a package from a reader (e.g., bytes from memory or a stream) with a progress callback.
@@ -162,7 +380,180 @@ impl DeviceInstaller<'_, SingleDevice> {
         F: Fn(CommandPlist, StatusPlist) + Send + Sync + 'static,
     {
         self.device.check_connected::<DeviceInstallerError>()?;
-        self._install_package(package_file, options, Some(Box::new(callback)))
+        self._install_package(
+            package_file,
+            options,
+            Some(Box::new(callback)),
+            InstallCommand::Install,
+        )
+    }
+
+    /// Installs an unpacked, developer signed, `.app` bundle, the way Xcode does.
+    ///
+    /// # Parameters
+    /// - `app_path`: Path to the `.app` directory.
+    /// - `options`: Optional installation options.
+    ///
+    /// The bundle is uploaded as is to the staging folder, then installed as a `Developer` package.
+    pub fn install_app_bundle<S>(
+        &self,
+        app_path: &S,
+        options: Option<HashMap<&str, &str>>,
+    ) -> Result<(), DeviceInstallerError>
+    where
+        S: AsRef<OsStr> + ?Sized,
+    {
+        self.device.check_connected::<DeviceInstallerError>()?;
+        self._install_app_bundle(Path::new(app_path.as_ref()), options, None)
+    }
+
+    /// Installs an unpacked, developer signed, `.app` bundle with a callback of the progress.
+    ///
+    /// # Parameters
+    /// - `app_path`: Path to the `.app` directory.
+    /// - `options`: Optional installation options.
+    ///
+    /// This method works similarly to `install_app_bundle`.
+    pub fn install_app_bundle_with_callback<S, F>(
+        &self,
+        app_path: &S,
+        options: Option<HashMap<&str, &str>>,
+        callback: F,
+    ) -> Result<(), DeviceInstallerError>
+    where
+        S: AsRef<OsStr> + ?Sized,
+        F: Fn(CommandPlist, StatusPlist) + Send + Sync + 'static,
+    {
+        self.device.check_connected::<DeviceInstallerError>()?;
+        self._install_app_bundle(
+            Path::new(app_path.as_ref()),
+            options,
+            Some(Box::new(callback)),
+        )
+    }
+
+    /// Upgrades an app from an IPA package, keeping its data, with a callback of the progress.
+    ///
+    /// Unlike the install functions, it refuses to install a version older than the installed one,
+    /// and fails with `DeviceInstallerError::Downgrade` instead.
+    /// The app is simply installed if it isn't already.
+    ///
+    /// # Parameters
+    /// - `package_path`: Path to the IPA package.
+    /// - `on_progress`: The callback receiving the installation status.
+    pub fn upgrade_ipa<S, F>(
+        &self,
+        package_path: &S,
+        on_progress: F,
+    ) -> Result<(), DeviceInstallerError>
+    where
+        S: AsRef<OsStr> + ?Sized,
+        F: Fn(CommandPlist, StatusPlist) + Send + Sync + 'static,
+    {
+        self.device.check_connected::<DeviceInstallerError>()?;
+
+        let mut cursor = Cursor::new(std::fs::read(Path::new(package_path.as_ref()))?);
+
+        if !matches!(
+            self.determine_file_package_type(&mut cursor)?,
+            PackageType::Ipa
+        ) {
+            return Err(DeviceInstallerError::NotAnIpa);
+        }
+
+        self.check_downgrade(&mut cursor)?;
+
+        self._install_package(
+            &mut cursor,
+            None,
+            Some(Box::new(on_progress)),
+            InstallCommand::Upgrade,
+        )
+    }
+
+    fn _install_app_bundle(
+        &self,
+        app_path: &Path,
+        options: Option<HashMap<&str, &str>>,
+        callback: Option<Box<dyn Fn(CommandPlist, StatusPlist) + Send + Sync>>,
+    ) -> Result<(), DeviceInstallerError> {
+        let app_name = match app_path.file_name() {
+            Some(name) if app_path.is_dir() && app_path.extension() == Some(OsStr::new("app")) => {
+                name.to_string_lossy().to_string()
+            }
+            _ => return Err(DeviceInstallerError::NotAnAppBundle),
+        };
+
+        let afc_client = self.device.get_afc_client::<DeviceInstallerError>()?;
+
+        self.check_or_create_path(&afc_client, PKG_PATH)?;
+
+        let remote_app_path = format!("/{}/{}", PKG_PATH, app_name);
+
+        // Leftovers of a previous installation would be mixed with the new files
+        let _ = afc_client.remove_path_and_contents(remote_app_path.as_str());
+
+        transfer::upload_dir(
+            &afc_client,
+            app_path,
+            &remote_app_path,
+            TransferOptions {
+                skip_unchanged: false,
+                preserve_mtime: false,
+            },
+            |_| {},
+        )?;
+
+        let mut package_options = InstProxyClient::client_options_new();
+        if let Some(dict) = options {
+            for (key, value) in dict {
+                package_options.dict_set_item(key, value.into())?;
+            }
+        }
+        package_options.dict_set_item("PackageType", "Developer".into())?;
+
+        self._run_install_command(
+            remote_app_path,
+            package_options,
+            callback,
+            InstallCommand::Install,
+        )
+    }
+
+    /// Fails if the IPA package is older than the installed app.
+    fn check_downgrade<T: Read + Seek>(&self, file: &mut T) -> Result<(), DeviceInstallerError> {
+        let Some(info) = self.get_ipa_info(file)? else {
+            return Ok(());
+        };
+        let string = |key: &str| {
+            info.dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+
+        let (Some(bundle_id), Some(new_version)) =
+            (string("CFBundleIdentifier"), string("CFBundleVersion"))
+        else {
+            return Ok(());
+        };
+
+        let installed_version = match self.get_app(&bundle_id) {
+            Ok(app) => app.version,
+            Err(DeviceInstallerError::AppNotFound(_)) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if let Some(installed_version) = installed_version {
+            if apps::compare_versions(&new_version, &installed_version) == CmpOrdering::Less {
+                return Err(DeviceInstallerError::Downgrade {
+                    bundle_id,
+                    installed_version,
+                    new_version,
+                });
+            }
+        }
+
+        Ok(())
     }
 
     fn _install_package<T: Read + Seek>(
@@ -170,8 +561,8 @@ impl DeviceInstaller<'_, SingleDevice> {
         file: &mut T,
         options: Option<HashMap<&str, &str>>,
         callback: Option<Box<dyn Fn(CommandPlist, StatusPlist) + Send + Sync>>,
+        command: InstallCommand,
     ) -> Result<(), DeviceInstallerError> {
-        let device = self.device.get_device();
         let afc_client = self.device.get_afc_client::<DeviceInstallerError>()?;
 
         self.check_or_create_path(&afc_client, PKG_PATH)?;
@@ -188,12 +579,6 @@ impl DeviceInstaller<'_, SingleDevice> {
 
         self.upload_package(&afc_client, &package_type, file)?;
 
-        let installation_client =
-            Arc::new(device.new_instproxy_client("rsmobiledevice-deviceinstaller")?);
-
-        let completed = Arc::new(AtomicBool::new(false));
-        let completed_clone = Arc::clone(&completed);
-
         let remote_packages_path = match package_type {
             PackageType::Ipcc => {
                 package_options.dict_set_item("PackageType", "CarrierBundle".into())?;
@@ -207,28 +592,54 @@ impl DeviceInstaller<'_, SingleDevice> {
             PackageType::Unknown => return Err(DeviceInstallerError::UnknownPackage),
         };
 
-        installation_client.install_with_callback(
-            remote_packages_path,
-            Some(package_options),
-            Some(Box::new(move |cmd: CommandPlist, status: StatusPlist| {
-                if let Some(ref cb) = callback {
-                    // this would keep the client in scope and thus continue with the callback
-                    //
-                    // we would only stop if there is a key named `Error`
-                    // or the `PercentComplete` is `100`
-                    // or the `Status` is `Complete` ( for ipcc packages )
-                    let condition = status.rfind("Error").is_some()
-                        || status.rfind("PercentComplete").is_some_and(|n| &n == "100")
-                        || status.rfind("Status").is_some_and(|s| &s == "Complete");
-
-                    if condition {
-                        completed_clone.store(true, Ordering::SeqCst);
-                    }
+        self._run_install_command(remote_packages_path, package_options, callback, command)
+    }
+
+    /// Runs the installation_proxy command on an uploaded package, waiting for it to complete.
+    fn _run_install_command(
+        &self,
+        remote_packages_path: String,
+        package_options: Plist,
+        callback: Option<Box<dyn Fn(CommandPlist, StatusPlist) + Send + Sync>>,
+        command: InstallCommand,
+    ) -> Result<(), DeviceInstallerError> {
+        let installation_client = Arc::new(self.instproxy_client()?);
+
+        let completed = Arc::new(AtomicBool::new(false));
+        let completed_clone = Arc::clone(&completed);
+
+        let status_callback: Box<dyn Fn(CommandPlist, StatusPlist) + Send + Sync> =
+            Box::new(move |cmd: CommandPlist, status: StatusPlist| {
+                // this would keep the client in scope and thus continue with the callback
+                //
+                // we would only stop if there is a key named `Error`
+                // or the `PercentComplete` is `100`
+                // or the `Status` is `Complete` ( for ipcc packages )
+                let condition = status.rfind("Error").is_some()
+                    || status.rfind("PercentComplete").is_some_and(|n| &n == "100")
+                    || status.rfind("Status").is_some_and(|s| &s == "Complete");
+
+                if condition {
+                    completed_clone.store(true, Ordering::SeqCst);
+                }
 
+                if let Some(ref cb) = callback {
                     cb(cmd, status); // Execute the callback
                 }
-            })),
-        )?;
+            });
+
+        match command {
+            InstallCommand::Install => installation_client.install_with_callback(
+                remote_packages_path,
+                Some(package_options),
+                Some(status_callback),
+            )?,
+            InstallCommand::Upgrade => installation_client.upgrade_with_callback(
+                remote_packages_path,
+                Some(package_options),
+                Some(status_callback),
+            )?,
+        }
 
         // Wait for the callback to signal completion
         while !completed.load(Ordering::SeqCst) {
@@ -350,9 +761,23 @@ impl DeviceInstaller<'_, SingleDevice> {
 
     /// Extracts the bundle ID from the IPA package.
     fn get_bundle_id<T: Read + Seek>(&self, file: &mut T) -> Result<String, DeviceInstallerError> {
-        let mut zip_file = ZipArchive::new(file)?;
+        let bundle_id = self.get_ipa_info(file)?.and_then(|plist| {
+            plist
+                .dict_get_item("CFBundleIdentifier")
+                .and_then(|p| p.get_string_val())
+                .ok()
+        });
+
+        Ok(bundle_id.unwrap_or_default())
+    }
 
-        let mut bundle_id = String::new();
+    /// Reads the Info.plist of the app inside the IPA package (`Payload/<name>.app/Info.plist`).
+    fn get_ipa_info<T: Read + Seek>(
+        &self,
+        file: &mut T,
+    ) -> Result<Option<Plist>, DeviceInstallerError> {
+        file.seek(SeekFrom::Start(0))?;
+        let mut zip_file = ZipArchive::new(file)?;
 
         for i in 0..zip_file.len() {
             let mut file = zip_file.by_index(i)?;
@@ -362,24 +787,24 @@ impl DeviceInstaller<'_, SingleDevice> {
                 None => continue,
             };
 
-            for path in inner_file_path.iter() {
-                if path.to_str() == Some("Info.plist")
-                    && inner_file_path.to_str().unwrap_or_default().len() == 3
-                {
-                    let mut plist_content = String::new();
-                    file.read_to_string(&mut plist_content)?;
-
-                    let plist = Plist::from_xml(plist_content)?;
-                    if let Ok(bid) = plist
-                        .dict_get_item("CFBundleIdentifier")
-                        .and_then(|p| p.get_string_val())
-                    {
-                        bundle_id = bid;
-                    }
-                }
+            // Only the Info.plist of the app itself, not the ones of its frameworks or plugins
+            if inner_file_path.ends_with("Info.plist") && inner_file_path.iter().count() == 3 {
+                let mut plist_content = Vec::new();
+                file.read_to_end(&mut plist_content)?;
+
+                // The Info.plist is either in the XML or binary format
+                return Ok(Some(Plist::from_memory(plist_content)?));
             }
         }
-        Ok(bundle_id)
+
+        Ok(None)
+    }
+
+    fn instproxy_client(&self) -> Result<InstProxyClient, DeviceInstallerError> {
+        Ok(self
+            .device
+            .get_device()
+            .new_instproxy_client(crate::config::label("deviceinstaller"))?)
     }
 
     fn check_or_create_path(
@@ -405,3 +830,40 @@ impl<'a, T> DeviceInstaller<'a, T> {
         }
     }
 }
+
+/// Adapts a `ProgressObserver` to the install callbacks, such as `install_from_path_with_callback`.
+///
+/// The updates are in percent, with the installation_proxy status (e.g., "CopyingApplication") as the phase.
+pub fn observe_install<O>(observer: O) -> impl Fn(CommandPlist, StatusPlist) + Send + Sync + 'static
+where
+    O: ProgressObserver + Send + 'static,
+{
+    let observer = Mutex::new(observer);
+
+    move |_command: CommandPlist, status: StatusPlist| {
+        let phase = status
+            .rfind("Status")
+            .unwrap_or_else(|| "Installing".into());
+        let percent = status
+            .rfind("PercentComplete")
+            .and_then(|percent| percent.parse().ok());
+        let error = status
+            .rfind("ErrorDescription")
+            .or_else(|| status.rfind("Error"));
+
+        let completed = percent == Some(100) || phase == "Complete";
+
+        observer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .on_progress(&ProgressUpdate {
+                phase: &phase,
+                current: match completed {
+                    true => 100,
+                    false => percent.unwrap_or(0),
+                },
+                total: Some(100),
+                message: error.as_deref(),
+            });
+    }
+}
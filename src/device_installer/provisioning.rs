@@ -0,0 +1,182 @@
+//! Provisioning profiles installed on the device, through the misagent service.
+
+use crate::{
+    apple_date, device_installer::apps::AppEntitlements, errors::DeviceInstallerError,
+    plist_service,
+};
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    idevice::Device, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
+use std::time::{Duration, SystemTime};
+
+const MISAGENT_SERVICE: &str = "com.apple.misagent";
+
+/// A provisioning profile installed on the device.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProvisioningProfile {
+    /// The name of the profile
+    pub name: Option<String>,
+
+    /// The unique identifier of the profile
+    pub uuid: Option<String>,
+
+    /// The name of the team the profile belongs to
+    pub team_name: Option<String>,
+
+    /// The identifiers of the team the profile belongs to
+    pub team_identifiers: Vec<String>,
+
+    /// The app identifier the profile is for (e.g., "ABCDE12345.com.example.*")
+    pub application_identifier: Option<String>,
+
+    /// Whether the profile works on every device, which is the case of the enterprise profiles
+    pub provisions_all_devices: bool,
+
+    /// When the profile was created
+    pub creation_date: Option<SystemTime>,
+
+    /// When the profile expires
+    pub expiration_date: Option<SystemTime>,
+}
+
+impl ProvisioningProfile {
+    /// Checks whether the profile is expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::ZERO)
+    }
+
+    /// Checks whether the profile expires within the given time, e.g., to warn before it lapses
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        self.expiration_date
+            .is_some_and(|expiration| expiration <= SystemTime::now() + duration)
+    }
+
+    /// Checks whether the profile covers the given app identifier, wildcards included
+    pub fn matches(&self, application_identifier: &str) -> bool {
+        let Some(pattern) = &self.application_identifier else {
+            return false;
+        };
+
+        match pattern.strip_suffix('*') {
+            Some(prefix) => application_identifier.starts_with(prefix),
+            None => pattern == application_identifier,
+        }
+    }
+
+    /// Parses a profile from its signed (CMS) data, the plist is embedded as is inside the signature.
+    pub(crate) fn from_signed_data(data: &[u8]) -> Option<ProvisioningProfile> {
+        let start = find(data, b"<?xml")?;
+        let end = find(&data[start..], b"</plist>")? + start + b"</plist>".len();
+        let xml = String::from_utf8_lossy(&data[start..end]).to_string();
+
+        let profile = Plist::from_xml(xml).ok()?;
+
+        let string = |plist: &Plist, key: &str| {
+            plist
+                .dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+        let date = |key: &str| apple_date::get_date(&profile, key);
+
+        let team_identifiers = profile
+            .dict_get_item("TeamIdentifier")
+            .map(|teams| {
+                (0..teams.array_get_size().unwrap_or(0))
+                    .filter_map(|index| {
+                        teams
+                            .array_get_item(index)
+                            .and_then(|team| team.get_string_val())
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(ProvisioningProfile {
+            name: string(&profile, "Name"),
+            uuid: string(&profile, "UUID"),
+            team_name: string(&profile, "TeamName"),
+            team_identifiers,
+            application_identifier: profile
+                .dict_get_item("Entitlements")
+                .ok()
+                .and_then(|entitlements| string(&entitlements, "application-identifier")),
+            provisions_all_devices: profile
+                .dict_get_item("ProvisionsAllDevices")
+                .and_then(|value| value.get_bool_val())
+                .unwrap_or(false),
+            creation_date: date("CreationDate"),
+            expiration_date: date("ExpirationDate"),
+        })
+    }
+}
+
+/// The signing details of an installed app, as returned by `DeviceInstaller::get_app_provisioning`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppProvisioning {
+    /// The bundle identifier of the app
+    pub bundle_id: String,
+
+    /// The identity that signed the app
+    pub signer_identity: Option<String>,
+
+    /// The entitlements of the app
+    pub entitlements: AppEntitlements,
+
+    /// The installed profiles covering the app, the one expiring last first
+    pub profiles: Vec<ProvisioningProfile>,
+}
+
+impl AppProvisioning {
+    /// The expiration date of the profile expiring last, which is when the app stops launching
+    pub fn expiration_date(&self) -> Option<SystemTime> {
+        self.profiles
+            .iter()
+            .filter_map(|profile| profile.expiration_date)
+            .max()
+    }
+}
+
+/// Copies all the provisioning profiles installed on the device.
+pub(crate) fn copy_profiles(
+    device: &Device,
+) -> Result<Vec<ProvisioningProfile>, DeviceInstallerError> {
+    let mut lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client"))?;
+    let service = lockdownd.start_service(MISAGENT_SERVICE, true)?;
+    let service = ServiceClient::new(device, service)?;
+
+    let mut request = Plist::new_dict();
+    request.dict_set_item("MessageType", "CopyAll".into())?;
+    request.dict_set_item("ProfileType", "Provisioning".into())?;
+    plist_service::send_plist(&service, &request)?;
+
+    let response = plist_service::receive_plist::<DeviceInstallerError>(&service)?;
+
+    let status = response
+        .dict_get_item("Status")
+        .and_then(|status| status.get_uint_val())
+        .unwrap_or(0);
+    if status != 0 {
+        return Err(DeviceInstallerError::MisagentError(status));
+    }
+
+    let payload = response.dict_get_item("Payload")?;
+
+    let profiles = (0..payload.array_get_size()?)
+        .filter_map(|index| {
+            let data = payload.array_get_item(index).ok()?.get_data_val().ok()?;
+            let data: Vec<u8> = data.into_iter().map(|byte| byte as u8).collect();
+            ProvisioningProfile::from_signed_data(&data)
+        })
+        .collect();
+
+    Ok(profiles)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
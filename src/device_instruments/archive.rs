@@ -0,0 +1,335 @@
+//! The NSKeyedArchiver format the instruments messages are encoded with, over binary plists.
+//!
+//! Only the classes the instruments exchange are handled (strings, numbers, arrays, dictionaries
+//! and data), the objects of the other classes are decoded as dictionaries of their fields.
+//! The binary plists themselves are read and written by libplist.
+
+use std::collections::HashMap;
+
+use plist_plus::{error::PlistError, Plist, PlistType};
+
+use crate::errors::DeviceInstrumentsError;
+
+/// The objects nested deeper than this are decoded as `Null`, an archive can reference itself
+const MAX_DEPTH: usize = 64;
+
+/// A value decoded from, or encoded into, an archive.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArchivedValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    String(String),
+    Data(Vec<u8>),
+    Array(Vec<ArchivedValue>),
+    /// Encoded as an NSSet, the sets received are decoded as arrays
+    Set(Vec<ArchivedValue>),
+    /// The keys that aren't strings (e.g., numbers) are turned into strings
+    Dictionary(HashMap<String, ArchivedValue>),
+}
+
+impl ArchivedValue {
+    /// The value as an integer, the reals are truncated
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ArchivedValue::Integer(value) => Some(*value),
+            ArchivedValue::Real(value) => Some(*value as i64),
+            ArchivedValue::Bool(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i64().and_then(|value| u64::try_from(value).ok())
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ArchivedValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[ArchivedValue]> {
+        match self {
+            ArchivedValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// The value of a key, if it's a dictionary
+    pub fn get(&self, key: &str) -> Option<&ArchivedValue> {
+        match self {
+            ArchivedValue::Dictionary(values) => values.get(key),
+            _ => None,
+        }
+    }
+}
+
+fn invalid(reason: &str) -> DeviceInstrumentsError {
+    DeviceInstrumentsError::InvalidArchive(reason.to_string())
+}
+
+/// Encodes a value the way NSKeyedArchiver does.
+pub(crate) fn archive(value: &ArchivedValue) -> Result<Vec<u8>, DeviceInstrumentsError> {
+    let mut archiver = Archiver {
+        objects: vec![Plist::new_string("$null")],
+        classes: HashMap::new(),
+    };
+    let root = archiver.add(value)?;
+
+    let mut top = Plist::new_dict();
+    top.dict_set_item("root", Plist::new_uid(root))?;
+    let mut objects = Plist::new_array();
+    for object in archiver.objects {
+        objects.array_append_item(object)?;
+    }
+
+    let mut plist = Plist::new_dict();
+    plist.dict_set_item("$version", Plist::new_uint(100000))?;
+    plist.dict_set_item("$archiver", "NSKeyedArchiver".into())?;
+    plist.dict_set_item("$top", top)?;
+    plist.dict_set_item("$objects", objects)?;
+
+    Ok(plist.to_bin())
+}
+
+/// Decodes an NSKeyedArchiver archive.
+pub(crate) fn unarchive(data: &[u8]) -> Result<ArchivedValue, DeviceInstrumentsError> {
+    let plist = Plist::from_memory(data.to_vec()).map_err(|_| invalid("not a plist"))?;
+
+    let root = plist
+        .dict_get_item("$top")
+        .and_then(|top| top.dict_get_item("root"))
+        .map_err(|_| invalid("no root object"))?;
+    let objects = plist
+        .dict_get_item("$objects")
+        .ok()
+        .filter(|objects| objects.plist_type == PlistType::Array)
+        .ok_or_else(|| invalid("no objects"))?;
+    let objects = (0..objects.array_get_size()?)
+        .map(|index| objects.array_get_item(index))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Unarchiver { objects }.resolve(&root, 0))
+}
+
+struct Archiver {
+    objects: Vec<Plist>,
+    classes: HashMap<&'static str, u64>,
+}
+
+impl Archiver {
+    /// Adds an object, returning its UID.
+    fn add(&mut self, value: &ArchivedValue) -> Result<u64, PlistError> {
+        let object = match value {
+            ArchivedValue::Null => return Ok(0),
+            ArchivedValue::Bool(value) => Plist::new_bool(*value),
+            // The negative integers are kept as their two's complement
+            ArchivedValue::Integer(value) => Plist::new_uint(*value as u64),
+            ArchivedValue::Real(value) => Plist::new_real(*value),
+            ArchivedValue::String(value) => Plist::new_string(value),
+            ArchivedValue::Data(value) => Plist::new_data(value),
+            ArchivedValue::Array(values) | ArchivedValue::Set(values) => {
+                let index = self.reserve();
+                let mut objects = Plist::new_array();
+                for value in values {
+                    objects.array_append_item(Plist::new_uid(self.add(value)?))?;
+                }
+                let class = match value {
+                    ArchivedValue::Set(_) => self.class("NSSet")?,
+                    _ => self.class("NSArray")?,
+                };
+
+                let mut container = Plist::new_dict();
+                container.dict_set_item("NS.objects", objects)?;
+                container.dict_set_item("$class", Plist::new_uid(class))?;
+                self.objects[index as usize] = container;
+                return Ok(index);
+            }
+            ArchivedValue::Dictionary(values) => {
+                let index = self.reserve();
+                let (mut keys, mut objects) = (Plist::new_array(), Plist::new_array());
+                for (key, value) in values {
+                    let key = self.add(&ArchivedValue::String(key.clone()))?;
+                    keys.array_append_item(Plist::new_uid(key))?;
+                    objects.array_append_item(Plist::new_uid(self.add(value)?))?;
+                }
+                let class = self.class("NSDictionary")?;
+
+                let mut container = Plist::new_dict();
+                container.dict_set_item("NS.keys", keys)?;
+                container.dict_set_item("NS.objects", objects)?;
+                container.dict_set_item("$class", Plist::new_uid(class))?;
+                self.objects[index as usize] = container;
+                return Ok(index);
+            }
+        };
+
+        self.objects.push(object);
+        Ok(self.objects.len() as u64 - 1)
+    }
+
+    /// Keeps a place for a container, so it comes before its content as NSKeyedArchiver does.
+    fn reserve(&mut self) -> u64 {
+        self.objects.push(Plist::new_bool(false));
+        self.objects.len() as u64 - 1
+    }
+
+    fn class(&mut self, name: &'static str) -> Result<u64, PlistError> {
+        if let Some(uid) = self.classes.get(name) {
+            return Ok(*uid);
+        }
+
+        let mut classes = Plist::new_array();
+        classes.array_append_item(name.into())?;
+        classes.array_append_item("NSObject".into())?;
+
+        let mut class = Plist::new_dict();
+        class.dict_set_item("$classname", name.into())?;
+        class.dict_set_item("$classes", classes)?;
+        self.objects.push(class);
+
+        let uid = self.objects.len() as u64 - 1;
+        self.classes.insert(name, uid);
+        Ok(uid)
+    }
+}
+
+struct Unarchiver {
+    objects: Vec<Plist>,
+}
+
+impl Unarchiver {
+    /// Decodes a value, following it if it's a reference to an archived object.
+    fn resolve(&self, value: &Plist, depth: usize) -> ArchivedValue {
+        if depth > MAX_DEPTH {
+            return ArchivedValue::Null;
+        }
+
+        match value.plist_type {
+            PlistType::Uid => match self.object(value) {
+                Some(object) => self.decode_object(object, depth + 1),
+                None => ArchivedValue::Null,
+            },
+            _ => self.decode_object(value, depth + 1),
+        }
+    }
+
+    /// The archived object a UID refers to
+    fn object(&self, uid: &Plist) -> Option<&Plist> {
+        let uid = usize::try_from(uid.get_uid_val().ok()?).ok()?;
+        self.objects.get(uid)
+    }
+
+    fn decode_object(&self, object: &Plist, depth: usize) -> ArchivedValue {
+        match object.plist_type {
+            PlistType::Boolean => object
+                .get_bool_val()
+                .map_or(ArchivedValue::Null, ArchivedValue::Bool),
+            PlistType::Integer => object.get_uint_val().map_or(ArchivedValue::Null, |value| {
+                ArchivedValue::Integer(value as i64)
+            }),
+            PlistType::Real => object
+                .get_real_val()
+                .map_or(ArchivedValue::Null, ArchivedValue::Real),
+            PlistType::Date => {
+                object
+                    .get_date_val()
+                    .map_or(ArchivedValue::Null, |(seconds, microseconds)| {
+                        ArchivedValue::Real(seconds as f64 + microseconds as f64 / 1_000_000.0)
+                    })
+            }
+            PlistType::String | PlistType::Key => match object.get_string_val() {
+                Ok(value) if value == "$null" => ArchivedValue::Null,
+                Ok(value) => ArchivedValue::String(value),
+                Err(_) => ArchivedValue::Null,
+            },
+            PlistType::Data => object.get_data_val().map_or(ArchivedValue::Null, |data| {
+                ArchivedValue::Data(data.into_iter().map(|byte| byte as u8).collect())
+            }),
+            PlistType::Array => ArchivedValue::Array(
+                (0..object.array_get_size().unwrap_or(0))
+                    .filter_map(|index| object.array_get_item(index).ok())
+                    .map(|item| self.resolve(&item, depth))
+                    .collect(),
+            ),
+            PlistType::Dictionary => self.decode_dictionary(object, depth),
+            PlistType::Uid | PlistType::Unknown | PlistType::None => ArchivedValue::Null,
+        }
+    }
+
+    fn decode_dictionary(&self, object: &Plist, depth: usize) -> ArchivedValue {
+        let class = object
+            .dict_get_item("$class")
+            .map(|class| self.class_name(&class))
+            .unwrap_or_default();
+        let field = |key: &str| {
+            object
+                .dict_get_item(key)
+                .map(|value| self.resolve(&value, depth))
+        };
+
+        match class.as_str() {
+            "NSArray" | "NSMutableArray" | "NSSet" | "NSMutableSet" | "NSOrderedSet" => {
+                field("NS.objects").unwrap_or(ArchivedValue::Array(Vec::new()))
+            }
+            "NSDictionary" | "NSMutableDictionary" => {
+                let mut dictionary = HashMap::new();
+                if let (Ok(ArchivedValue::Array(keys)), Ok(ArchivedValue::Array(values))) =
+                    (field("NS.keys"), field("NS.objects"))
+                {
+                    for (key, value) in keys.into_iter().zip(values) {
+                        dictionary.insert(key_string(key), value);
+                    }
+                }
+                ArchivedValue::Dictionary(dictionary)
+            }
+            "NSString" | "NSMutableString" => field("NS.string").unwrap_or(ArchivedValue::Null),
+            "NSData" | "NSMutableData" => field("NS.data").unwrap_or(ArchivedValue::Null),
+            "NSDate" => field("NS.time").unwrap_or(ArchivedValue::Null),
+            "NSNull" => ArchivedValue::Null,
+            // Any other class, by its fields, along with its name
+            _ => {
+                let mut fields: HashMap<String, ArchivedValue> = object
+                    .clone()
+                    .into_iter()
+                    .filter_map(|item| {
+                        let key = item.key?;
+                        (key != "$class").then(|| {
+                            let value = self.resolve(&item.plist, depth);
+                            (key, value)
+                        })
+                    })
+                    .collect();
+                if !class.is_empty() {
+                    fields.insert("$class".into(), ArchivedValue::String(class));
+                }
+                ArchivedValue::Dictionary(fields)
+            }
+        }
+    }
+
+    fn class_name(&self, class: &Plist) -> String {
+        let class = match class.plist_type {
+            PlistType::Uid => self.object(class),
+            _ => Some(class),
+        };
+
+        class
+            .and_then(|class| class.dict_get_item("$classname").ok())
+            .and_then(|name| name.get_string_val().ok())
+            .unwrap_or_default()
+    }
+}
+
+fn key_string(key: ArchivedValue) -> String {
+    match key {
+        ArchivedValue::String(key) => key,
+        ArchivedValue::Integer(key) => key.to_string(),
+        ArchivedValue::Real(key) => key.to_string(),
+        ArchivedValue::Bool(key) => key.to_string(),
+        key => format!("{:?}", key),
+    }
+}
@@ -0,0 +1,554 @@
+//! The DTX messaging the instruments service speaks.
+//!
+//! Each message has a 32 bytes header, then a payload made of the auxiliary arguments and an
+//! archived object (the selector of a call, or the value of a reply). The services are reached
+//! through channels, opened on the channel 0 by their identifier.
+
+use std::{collections::HashMap, time::Instant};
+
+use rusty_libimobiledevice::{
+    error::ServiceError, idevice::Device, service::ServiceClient,
+    services::lockdownd::LockdowndClient,
+};
+
+use super::archive::{self, ArchivedValue};
+use crate::{config::RsMobileDeviceConfig, errors::DeviceInstrumentsError};
+
+const DTX_MAGIC: u32 = 0x1F3D_5B79;
+const HEADER_SIZE: usize = 32;
+const PAYLOAD_HEADER_SIZE: usize = 16;
+const AUXILIARY_MAGIC: u64 = 0x1F0;
+/// The message carries an archived object, and maybe auxiliary arguments
+const MESSAGE_TYPE_OBJECT: u32 = 2;
+/// The message carries an archived error
+const MESSAGE_TYPE_ERROR: u32 = 3;
+const EXPECTS_REPLY_FLAG: u32 = 0x1000;
+const COMPRESSION_MASK: u32 = 0xFF000;
+
+/// The instruments behind the secure proxy of iOS 14 and later, then the one of the older versions
+const SECURE_SERVICE: &str = "com.apple.instruments.remoteserver.DVTSecureSocketProxy";
+const LEGACY_SERVICE: &str = "com.apple.instruments.remoteserver";
+
+/// An auxiliary argument of a call.
+pub(crate) enum AuxiliaryValue {
+    Int(u32),
+    Object(ArchivedValue),
+}
+
+/// A message received from the device.
+#[derive(Debug)]
+pub(crate) struct DtxMessage {
+    pub(crate) channel: i32,
+    pub(crate) identifier: u32,
+    pub(crate) conversation_index: u32,
+    /// The selector of a call, or the value of a reply
+    pub(crate) object: Option<ArchivedValue>,
+}
+
+impl DtxMessage {
+    /// The selector, if the message is a call
+    pub(crate) fn selector(&self) -> Option<&str> {
+        self.object.as_ref().and_then(|object| object.as_str())
+    }
+}
+
+/// A connection to the instruments service.
+pub(crate) struct DtxConnection<'a> {
+    service: ServiceClient<'a>,
+    reader: FrameReader,
+    next_identifier: u32,
+    next_channel: i32,
+}
+
+impl<'a> DtxConnection<'a> {
+    /// Connects to the instruments service and exchanges the capabilities.
+    pub(crate) fn connect(device: &'a Device) -> Result<DtxConnection<'a>, DeviceInstrumentsError> {
+        let mut lockdownd = LockdowndClient::new(device, crate::config::label("instruments"))?;
+
+        let service = match lockdownd.start_service(SECURE_SERVICE, false) {
+            Ok(service) => ServiceClient::new(device, service)?,
+            Err(_) => {
+                let service = lockdownd
+                    .start_service(LEGACY_SERVICE, false)
+                    .map_err(|_| DeviceInstrumentsError::InstrumentsUnavailable)?;
+                let service = ServiceClient::new(device, service)?;
+                // The older service only uses SSL for the handshake
+                service.disable_ssl()?;
+                service
+            }
+        };
+
+        let mut connection = DtxConnection {
+            service,
+            reader: FrameReader::default(),
+            next_identifier: 1,
+            next_channel: 1,
+        };
+        connection.handshake()?;
+
+        Ok(connection)
+    }
+
+    fn handshake(&mut self) -> Result<(), DeviceInstrumentsError> {
+        let capabilities = ArchivedValue::Dictionary(HashMap::from([
+            (
+                "com.apple.private.DTXBlockCompression".to_string(),
+                ArchivedValue::Integer(0),
+            ),
+            (
+                "com.apple.private.DTXConnection".to_string(),
+                ArchivedValue::Integer(1),
+            ),
+        ]));
+        self.send(
+            0,
+            "_notifyOfPublishedCapabilities:",
+            &[AuxiliaryValue::Object(capabilities)],
+            false,
+        )?;
+
+        // The device answers with its own capabilities
+        loop {
+            let message = self.receive_blocking()?;
+            if message.selector() == Some("_notifyOfPublishedCapabilities:") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Opens a channel to an instruments service (e.g., "com.apple.instruments.server.services.networking").
+    pub(crate) fn open_channel(&mut self, identifier: &str) -> Result<i32, DeviceInstrumentsError> {
+        let channel = self.next_channel;
+        self.next_channel += 1;
+
+        self.call(
+            0,
+            "_requestChannelWithCode:identifier:",
+            &[
+                AuxiliaryValue::Int(channel as u32),
+                AuxiliaryValue::Object(ArchivedValue::String(identifier.to_string())),
+            ],
+        )?;
+
+        Ok(channel)
+    }
+
+    /// Calls a method and waits for its reply, the messages received meanwhile are dropped.
+    pub(crate) fn call(
+        &mut self,
+        channel: i32,
+        selector: &str,
+        arguments: &[AuxiliaryValue],
+    ) -> Result<Option<ArchivedValue>, DeviceInstrumentsError> {
+        let identifier = self.send(channel, selector, arguments, true)?;
+
+        loop {
+            let message = self.receive_blocking()?;
+            if message.identifier != identifier || message.conversation_index == 0 {
+                continue;
+            }
+
+            // A failed call is answered with an NSError
+            if let Some(error) = message
+                .object
+                .as_ref()
+                .filter(|object| object.get("NSCode").is_some())
+            {
+                return Err(DeviceInstrumentsError::CallFailed(format!(
+                    "{} ({:?})",
+                    selector, error
+                )));
+            }
+            return Ok(message.object);
+        }
+    }
+
+    /// Sends a call, returning the identifier of its message.
+    pub(crate) fn send(
+        &mut self,
+        channel: i32,
+        selector: &str,
+        arguments: &[AuxiliaryValue],
+        expects_reply: bool,
+    ) -> Result<u32, DeviceInstrumentsError> {
+        let identifier = self.next_identifier;
+        self.next_identifier += 1;
+
+        let auxiliary = encode_auxiliary(arguments)?;
+        let selector = archive::archive(&ArchivedValue::String(selector.to_string()))?;
+        let message = encode_message(
+            identifier,
+            channel,
+            MESSAGE_TYPE_OBJECT,
+            expects_reply,
+            &auxiliary,
+            &selector,
+        );
+
+        crate::plist_service::send_all(&self.service, &message)?;
+        Ok(identifier)
+    }
+
+    /// Waits for the next message, up to the request timeout of the configuration.
+    fn receive_blocking(&mut self) -> Result<DtxMessage, DeviceInstrumentsError> {
+        let deadline = Instant::now() + RsMobileDeviceConfig::global().request_timeout();
+
+        while Instant::now() < deadline {
+            if let Some(message) = self.receive(500)? {
+                return Ok(message);
+            }
+        }
+        Err(DeviceInstrumentsError::Timeout)
+    }
+
+    /// Waits for the next message, `None` if nothing came in before the timeout.
+    pub(crate) fn receive(
+        &mut self,
+        timeout_ms: u32,
+    ) -> Result<Option<DtxMessage>, DeviceInstrumentsError> {
+        loop {
+            if let Some(message) = self.reader.next_message()? {
+                return Ok(Some(message));
+            }
+
+            match self.service.receive_with_timeout(64 * 1024, timeout_ms) {
+                Ok(data) if data.is_empty() => return Ok(None),
+                Ok(data) => self.reader.push(&data),
+                Err(ServiceError::Timeout) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+/// Accumulates the received bytes and splits them into messages.
+#[derive(Debug, Default)]
+struct FrameReader {
+    buffer: Vec<u8>,
+    fragments: HashMap<u32, Vec<u8>>,
+}
+
+impl FrameReader {
+    fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Takes the next complete message out of the buffer, putting the fragments back together.
+    fn next_message(&mut self) -> Result<Option<DtxMessage>, DeviceInstrumentsError> {
+        loop {
+            if self.buffer.len() < HEADER_SIZE {
+                return Ok(None);
+            }
+
+            let header = &self.buffer[..HEADER_SIZE];
+            let field = |offset: usize| {
+                u32::from_le_bytes([
+                    header[offset],
+                    header[offset + 1],
+                    header[offset + 2],
+                    header[offset + 3],
+                ])
+            };
+            if field(0) != DTX_MAGIC {
+                return Err(DeviceInstrumentsError::InvalidMessage(
+                    "bad magic".to_string(),
+                ));
+            }
+            let fragment = u16::from_le_bytes([header[8], header[9]]);
+            let fragments = u16::from_le_bytes([header[10], header[11]]);
+            let length = field(12) as usize;
+            let identifier = field(16);
+            let conversation_index = field(20);
+            let channel = field(24) as i32;
+
+            // The first fragment of a fragmented message only announces it, without any data
+            if fragments > 1 && fragment == 0 {
+                self.buffer.drain(..HEADER_SIZE);
+                self.fragments.insert(identifier, Vec::new());
+                continue;
+            }
+            if self.buffer.len() < HEADER_SIZE + length {
+                return Ok(None);
+            }
+
+            let data: Vec<u8> = self
+                .buffer
+                .drain(..HEADER_SIZE + length)
+                .skip(HEADER_SIZE)
+                .collect();
+            let payload = match fragments {
+                0 | 1 => data,
+                _ => {
+                    let collected = self.fragments.entry(identifier).or_default();
+                    collected.extend_from_slice(&data);
+                    if fragment + 1 < fragments {
+                        continue;
+                    }
+                    self.fragments.remove(&identifier).unwrap_or_default()
+                }
+            };
+
+            let object = decode_payload(&payload)?;
+            return Ok(Some(DtxMessage {
+                channel,
+                identifier,
+                conversation_index,
+                object,
+            }));
+        }
+    }
+}
+
+/// Builds a message sent as a single fragment, starting its conversation.
+fn encode_message(
+    identifier: u32,
+    channel: i32,
+    message_type: u32,
+    expects_reply: bool,
+    auxiliary: &[u8],
+    object: &[u8],
+) -> Vec<u8> {
+    let mut flags = message_type;
+    if expects_reply {
+        flags |= EXPECTS_REPLY_FLAG;
+    }
+
+    let length = PAYLOAD_HEADER_SIZE + auxiliary.len() + object.len();
+    let mut message = Vec::with_capacity(HEADER_SIZE + length);
+    message.extend_from_slice(&DTX_MAGIC.to_le_bytes());
+    message.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+    // A single fragment
+    message.extend_from_slice(&0u16.to_le_bytes());
+    message.extend_from_slice(&1u16.to_le_bytes());
+    message.extend_from_slice(&(length as u32).to_le_bytes());
+    message.extend_from_slice(&identifier.to_le_bytes());
+    // The conversation index, a call starts its conversation
+    message.extend_from_slice(&0u32.to_le_bytes());
+    message.extend_from_slice(&channel.to_le_bytes());
+    message.extend_from_slice(&(expects_reply as u32).to_le_bytes());
+
+    message.extend_from_slice(&flags.to_le_bytes());
+    message.extend_from_slice(&(auxiliary.len() as u32).to_le_bytes());
+    message.extend_from_slice(&((auxiliary.len() + object.len()) as u64).to_le_bytes());
+    message.extend_from_slice(auxiliary);
+    message.extend_from_slice(object);
+    message
+}
+
+/// Reads the archived object of a payload, the auxiliary arguments the device sends aren't needed.
+fn decode_payload(payload: &[u8]) -> Result<Option<ArchivedValue>, DeviceInstrumentsError> {
+    if payload.len() < PAYLOAD_HEADER_SIZE {
+        return Ok(None);
+    }
+
+    let flags = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+    if flags & COMPRESSION_MASK != 0 {
+        return Err(DeviceInstrumentsError::InvalidMessage(
+            "compressed messages aren't supported".to_string(),
+        ));
+    }
+    let auxiliary_length =
+        u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]) as usize;
+    let total_length = u64::from_le_bytes(payload[8..16].try_into().unwrap_or_default()) as usize;
+
+    let body = &payload[PAYLOAD_HEADER_SIZE..];
+    if auxiliary_length > total_length || total_length > body.len() {
+        return Err(DeviceInstrumentsError::InvalidMessage(
+            "bad payload length".to_string(),
+        ));
+    }
+
+    let object = &body[auxiliary_length..total_length];
+    match flags & 0xFF {
+        _ if object.is_empty() => Ok(None),
+        MESSAGE_TYPE_OBJECT | MESSAGE_TYPE_ERROR => Ok(Some(archive::unarchive(object)?)),
+        // The other messages carry raw data, such as the buffers of the profiling
+        _ => Ok(Some(ArchivedValue::Data(object.to_vec()))),
+    }
+}
+
+fn encode_auxiliary(arguments: &[AuxiliaryValue]) -> Result<Vec<u8>, DeviceInstrumentsError> {
+    if arguments.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut items = Vec::new();
+    for argument in arguments {
+        // Each item is preceded by an empty dictionary marker
+        items.extend_from_slice(&0x0Au32.to_le_bytes());
+        match argument {
+            AuxiliaryValue::Object(value) => {
+                let value = archive::archive(value)?;
+                items.extend_from_slice(&2u32.to_le_bytes());
+                items.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                items.extend_from_slice(&value);
+            }
+            AuxiliaryValue::Int(value) => {
+                items.extend_from_slice(&3u32.to_le_bytes());
+                items.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    let mut auxiliary = AUXILIARY_MAGIC.to_le_bytes().to_vec();
+    auxiliary.extend_from_slice(&(items.len() as u64).to_le_bytes());
+    auxiliary.extend_from_slice(&items);
+    Ok(auxiliary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message type carrying raw data, so the tests don't need the archives
+    const MESSAGE_TYPE_DATA: u32 = 1;
+
+    fn header(identifier: u32, fragment: u16, fragments: u16, length: usize) -> Vec<u8> {
+        let mut header = encode_message(identifier, 7, MESSAGE_TYPE_DATA, false, &[], &[]);
+        header.truncate(HEADER_SIZE);
+        header[8..10].copy_from_slice(&fragment.to_le_bytes());
+        header[10..12].copy_from_slice(&fragments.to_le_bytes());
+        header[12..16].copy_from_slice(&(length as u32).to_le_bytes());
+        header
+    }
+
+    fn read_all(data: &[u8]) -> Result<Vec<DtxMessage>, DeviceInstrumentsError> {
+        let mut reader = FrameReader::default();
+        reader.push(data);
+
+        let mut messages = Vec::new();
+        while let Some(message) = reader.next_message()? {
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    #[test]
+    fn encodes_the_auxiliary_integers() {
+        let auxiliary =
+            encode_auxiliary(&[AuxiliaryValue::Int(7), AuxiliaryValue::Int(0x0102_0304)]).unwrap();
+
+        let mut expected = AUXILIARY_MAGIC.to_le_bytes().to_vec();
+        expected.extend_from_slice(&24u64.to_le_bytes());
+        for value in [7u32, 0x0102_0304] {
+            expected.extend_from_slice(&0x0Au32.to_le_bytes());
+            expected.extend_from_slice(&3u32.to_le_bytes());
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+
+        assert_eq!(auxiliary, expected);
+        assert!(encode_auxiliary(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn round_trips_a_message() {
+        let auxiliary = encode_auxiliary(&[AuxiliaryValue::Int(1)]).unwrap();
+        let message = encode_message(5, 3, MESSAGE_TYPE_DATA, false, &auxiliary, b"data");
+
+        assert_eq!(
+            message.len(),
+            HEADER_SIZE + PAYLOAD_HEADER_SIZE + auxiliary.len() + 4
+        );
+
+        let messages = read_all(&message).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].identifier, 5);
+        assert_eq!(messages[0].channel, 3);
+        assert_eq!(messages[0].conversation_index, 0);
+        assert_eq!(
+            messages[0].object,
+            Some(ArchivedValue::Data(b"data".to_vec()))
+        );
+    }
+
+    #[test]
+    fn flags_the_calls_expecting_a_reply() {
+        let message = encode_message(1, 1, MESSAGE_TYPE_OBJECT, true, &[], b"selector");
+
+        // In the message header, then in the payload one
+        assert_eq!(u32::from_le_bytes(message[28..32].try_into().unwrap()), 1);
+        assert_eq!(
+            u32::from_le_bytes(message[32..36].try_into().unwrap()),
+            MESSAGE_TYPE_OBJECT | EXPECTS_REPLY_FLAG
+        );
+    }
+
+    #[test]
+    fn waits_for_the_whole_message() {
+        let message = encode_message(1, 1, MESSAGE_TYPE_DATA, false, &[], b"data");
+        let mut reader = FrameReader::default();
+
+        reader.push(&message[..HEADER_SIZE - 1]);
+        assert!(reader.next_message().unwrap().is_none());
+        reader.push(&message[HEADER_SIZE - 1..message.len() - 1]);
+        assert!(reader.next_message().unwrap().is_none());
+        reader.push(&message[message.len() - 1..]);
+        assert!(reader.next_message().unwrap().is_some());
+        assert!(reader.buffer.is_empty());
+    }
+
+    #[test]
+    fn puts_the_fragments_back_together() {
+        let message = encode_message(9, 7, MESSAGE_TYPE_DATA, false, &[], b"fragmented data");
+        let payload = &message[HEADER_SIZE..];
+        let (first, second) = payload.split_at(10);
+
+        let data = [
+            header(9, 0, 3, payload.len()),
+            header(9, 1, 3, first.len()),
+            first.to_vec(),
+            // Another message in between the fragments
+            encode_message(10, 7, MESSAGE_TYPE_DATA, false, &[], b"single"),
+            header(9, 2, 3, second.len()),
+            second.to_vec(),
+        ]
+        .concat();
+
+        let messages = read_all(&data).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].identifier, 10);
+        assert_eq!(
+            messages[0].object,
+            Some(ArchivedValue::Data(b"single".to_vec()))
+        );
+        assert_eq!(messages[1].identifier, 9);
+        assert_eq!(
+            messages[1].object,
+            Some(ArchivedValue::Data(b"fragmented data".to_vec()))
+        );
+    }
+
+    #[test]
+    fn rejects_the_malformed_headers() {
+        let mut message = encode_message(1, 1, MESSAGE_TYPE_DATA, false, &[], b"data");
+        message[0] ^= 0xFF;
+        assert!(matches!(
+            read_all(&message),
+            Err(DeviceInstrumentsError::InvalidMessage(_))
+        ));
+
+        // The auxiliary length past the payload
+        let mut message = encode_message(1, 1, MESSAGE_TYPE_DATA, false, &[], b"data");
+        message[36..40].copy_from_slice(&100u32.to_le_bytes());
+        assert!(matches!(
+            read_all(&message),
+            Err(DeviceInstrumentsError::InvalidMessage(_))
+        ));
+
+        // A compression type in the flags
+        let message = encode_message(1, 1, MESSAGE_TYPE_DATA | 0x2000, false, &[], b"data");
+        assert!(matches!(
+            read_all(&message),
+            Err(DeviceInstrumentsError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn reads_a_message_without_payload() {
+        let messages = read_all(&header(4, 0, 1, 0)).unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].identifier, 4);
+        assert_eq!(messages[0].object, None);
+    }
+}
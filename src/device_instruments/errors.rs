@@ -0,0 +1,51 @@
+use crate::errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceInstrumentsError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error(
+        "The instruments service isn't available, make sure the developer disk image is mounted"
+    )]
+    InstrumentsUnavailable,
+
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+
+    #[error("Invalid instruments message: {0}")]
+    InvalidMessage(String),
+
+    #[error("The instruments refused the call: {0}")]
+    CallFailed(String),
+
+    #[error("The instruments didn't answer in time")]
+    Timeout,
+
+    #[error("The monitor stopped before it started")]
+    MonitorStopped,
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceInstrumentsError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceInstrumentsError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
@@ -0,0 +1,86 @@
+//! Provides access to the instruments of iOS devices
+//!
+//! The instruments service is what Xcode's Instruments app talks to, it exposes its services
+//! (the network statistics, the profiling, and so on) as DTX channels.
+//! It needs the developer disk image to be mounted, see `device_image_mounter`.
+//!
+//! ## Features
+//! - Monitoring the network usage by process (bytes in and out, connections), sampled on an interval
+//! - Sampling the callstacks of a process over a duration, as collapsed stacks for flamegraphs
+//!
+
+use std::{marker::PhantomData, time::Duration};
+
+pub mod archive;
+mod dtx;
+pub(crate) mod errors;
+pub mod network;
+pub mod profiler;
+
+pub use archive::ArchivedValue;
+pub use network::{NetworkMonitor, NetworkSample, ProcessNetworkStats};
+pub use profiler::StackProfile;
+
+use crate::{
+    device::DeviceClient, devices_collection::SingleDevice, errors::DeviceInstrumentsError,
+};
+
+/// Struct for using the instruments of a device
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceInstruments<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceInstruments<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceInstruments<'a, T> {
+        DeviceInstruments {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DeviceInstruments<'_, SingleDevice> {
+    /// Monitors the network usage of the processes, so the traffic can be attributed to the app under test.
+    ///
+    /// This is a non blocking function, the samples are received from the returned monitor
+    /// until it's stopped or dropped.
+    ///
+    /// # Parameters
+    /// - `interval`: The time between two samples.
+    ///
+    /// # Errors
+    /// Returns `DeviceInstrumentsError::InstrumentsUnavailable` if the developer disk image isn't mounted.
+    pub fn network_monitor(
+        &self,
+        interval: Duration,
+    ) -> Result<NetworkMonitor, DeviceInstrumentsError> {
+        self.device.check_connected::<DeviceInstrumentsError>()?;
+        NetworkMonitor::start(self.device.get_device().clone(), interval)
+    }
+
+    /// Samples the callstacks of a process, e.g., to find where an app spends its time.
+    ///
+    /// This blocks for the whole duration, the frames are the raw return addresses of the user
+    /// callstacks, see `StackProfile::to_collapsed` for a flamegraph input.
+    ///
+    /// # Parameters
+    /// - `pid`: The process identifier of the process to profile.
+    /// - `duration`: The time to sample the process for.
+    ///
+    /// # Errors
+    /// Returns `DeviceInstrumentsError::InstrumentsUnavailable` if the developer disk image isn't mounted.
+    pub fn time_profile(
+        &self,
+        pid: u32,
+        duration: Duration,
+    ) -> Result<StackProfile, DeviceInstrumentsError> {
+        self.device.check_connected::<DeviceInstrumentsError>()?;
+        profiler::profile(self.device.get_device(), pid, duration)
+    }
+}
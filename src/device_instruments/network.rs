@@ -0,0 +1,237 @@
+//! The per-process network statistics of the instruments.
+//!
+//! The networking service reports each connection when it's opened, with the process owning it,
+//! then its counters as they change. The counters are summed by process and sent on each interval.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rusty_libimobiledevice::idevice::Device;
+
+use super::{
+    archive::ArchivedValue,
+    dtx::{DtxConnection, DtxMessage},
+};
+use crate::errors::DeviceInstrumentsError;
+
+const NETWORKING_SERVICE: &str = "com.apple.instruments.server.services.networking";
+
+/// The kinds of the networking messages, and where their values are
+const CONNECTION_DETECTION: i64 = 1;
+const CONNECTION_UPDATE: i64 = 2;
+const DETECTION_PID: usize = 3;
+const DETECTION_SERIAL: usize = 6;
+const UPDATE_RX_BYTES: usize = 1;
+const UPDATE_TX_BYTES: usize = 3;
+const UPDATE_SERIAL: usize = 9;
+
+/// The network usage of a process, since the monitoring started.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessNetworkStats {
+    pub pid: u32,
+
+    /// The bytes received over the connections of the process
+    pub bytes_in: u64,
+
+    /// The bytes sent over the connections of the process
+    pub bytes_out: u64,
+
+    /// The connections the process opened
+    pub connections: usize,
+}
+
+/// A sample of the network usage, sent by `NetworkMonitor` on each interval.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkSample {
+    /// The processes that had any connection, by pid
+    pub processes: Vec<ProcessNetworkStats>,
+}
+
+impl NetworkSample {
+    /// The usage of a process, if it had any connection
+    pub fn process(&self, pid: u32) -> Option<&ProcessNetworkStats> {
+        self.processes.iter().find(|process| process.pid == pid)
+    }
+}
+
+/// Handle to the thread monitoring the network, returned by `DeviceInstruments::network_monitor`.
+///
+/// The samples are received from it, either with `recv` or by iterating over it,
+/// the iteration ends when the monitoring stops (e.g., the device got disconnected).
+///
+/// Dropping the handle stops the monitoring and waits for the thread to finish.
+#[derive(Debug)]
+pub struct NetworkMonitor {
+    receiver: Receiver<NetworkSample>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl NetworkMonitor {
+    /// Starts monitoring, the connection is made before returning so its failure is returned.
+    pub(crate) fn start(
+        device: Device,
+        interval: Duration,
+    ) -> Result<NetworkMonitor, DeviceInstrumentsError> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let (sender, receiver) = mpsc::channel();
+        let (ready_sender, ready_receiver) = mpsc::sync_channel(1);
+
+        let handle = thread::spawn(move || {
+            let started = DtxConnection::connect(&device).and_then(|mut connection| {
+                let channel = connection.open_channel(NETWORKING_SERVICE)?;
+                connection.send(channel, "startMonitoring", &[], false)?;
+                Ok((connection, channel))
+            });
+            let (mut connection, channel) = match started {
+                Ok(started) => {
+                    let _ = ready_sender.send(Ok(()));
+                    started
+                }
+                Err(err) => {
+                    let _ = ready_sender.send(Err(err));
+                    return;
+                }
+            };
+
+            // The connections by serial number, with their process and counters
+            let mut connections: HashMap<u64, (u32, u64, u64)> = HashMap::new();
+            let mut next_sample = Instant::now() + interval;
+
+            while !thread_stop.load(Ordering::SeqCst) {
+                match connection.receive(500) {
+                    Ok(Some(message)) if message.channel.abs() == channel => {
+                        record(&message, &mut connections)
+                    }
+                    Ok(_) => (),
+                    Err(err) => {
+                        log::warn!(udid:% = device.get_udid(), error:% = err; "Failed to receive the network statistics");
+                        break;
+                    }
+                }
+
+                if Instant::now() >= next_sample {
+                    next_sample += interval;
+                    // Nobody is receiving anymore
+                    if sender.send(sample(&connections)).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            let _ = connection.send(channel, "stopMonitoring", &[], false);
+        });
+
+        match ready_receiver.recv() {
+            Ok(Ok(())) => Ok(NetworkMonitor {
+                receiver,
+                stop,
+                handle: Some(handle),
+            }),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(DeviceInstrumentsError::MonitorStopped),
+        }
+    }
+
+    /// Returns the receiving end of the samples, e.g., to wait for them with a timeout.
+    pub fn receiver(&self) -> &Receiver<NetworkSample> {
+        &self.receiver
+    }
+
+    /// Waits for the next sample, `None` once the monitoring has stopped.
+    pub fn recv(&self) -> Option<NetworkSample> {
+        self.receiver.recv().ok()
+    }
+
+    /// Stops monitoring and waits for the thread to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    /// Checks whether the monitoring thread has finished (e.g., the device got disconnected).
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Iterator for NetworkMonitor {
+    type Item = NetworkSample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl Drop for NetworkMonitor {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+/// Records a networking message, each one is an array of its kind and its values.
+fn record(message: &DtxMessage, connections: &mut HashMap<u64, (u32, u64, u64)>) {
+    let Some([kind, ArchivedValue::Array(values)]) =
+        message.object.as_ref().and_then(ArchivedValue::as_array)
+    else {
+        return;
+    };
+    let value = |index: usize| values.get(index).and_then(ArchivedValue::as_u64);
+
+    match kind.as_i64() {
+        Some(CONNECTION_DETECTION) => {
+            if let (Some(pid), Some(serial)) = (value(DETECTION_PID), value(DETECTION_SERIAL)) {
+                connections.entry(serial).or_default().0 = pid as u32;
+            }
+        }
+        Some(CONNECTION_UPDATE) => {
+            if let Some(serial) = value(UPDATE_SERIAL) {
+                // The counters are the totals of the connection
+                let connection = connections.entry(serial).or_default();
+                connection.1 = value(UPDATE_RX_BYTES).unwrap_or(connection.1);
+                connection.2 = value(UPDATE_TX_BYTES).unwrap_or(connection.2);
+            }
+        }
+        // The network interfaces
+        _ => (),
+    }
+}
+
+fn sample(connections: &HashMap<u64, (u32, u64, u64)>) -> NetworkSample {
+    let mut processes: HashMap<u32, ProcessNetworkStats> = HashMap::new();
+    for (pid, bytes_in, bytes_out) in connections.values() {
+        let process = processes
+            .entry(*pid)
+            .or_insert_with(|| ProcessNetworkStats {
+                pid: *pid,
+                ..Default::default()
+            });
+        process.bytes_in += bytes_in;
+        process.bytes_out += bytes_out;
+        process.connections += 1;
+    }
+
+    let mut processes: Vec<_> = processes.into_values().collect();
+    processes.sort_by_key(|process| process.pid);
+
+    NetworkSample { processes }
+}
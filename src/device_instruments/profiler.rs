@@ -0,0 +1,340 @@
+//! The sampling time profiler of the instruments.
+//!
+//! The core profiling service streams kdebug buffers, where each sample of a thread is the info of
+//! the thread (its process) followed by its user callstack, split over as many events as needed.
+//! The callstacks are the raw return addresses, they aren't symbolicated.
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use rusty_libimobiledevice::idevice::Device;
+
+use super::{
+    archive::ArchivedValue,
+    dtx::{AuxiliaryValue, DtxConnection},
+};
+use crate::errors::DeviceInstrumentsError;
+
+const CORE_PROFILE_SERVICE: &str = "com.apple.instruments.server.services.coreprofilesessiontap";
+
+/// The size of a kdebug event (`kd_buf`) on 64 bits devices
+const EVENT_SIZE: usize = 64;
+
+/// The events of the kperf samples, the function bits (the lowest two) are masked out
+const THREAD_INFO_EVENT: u32 = 0x2501_0004;
+const USER_STACK_HEADER_EVENT: u32 = 0x2502_0018;
+const USER_STACK_DATA_EVENT: u32 = 0x2502_0010;
+
+/// The deepest callstack asked for, the frame counts the device sends above it are clamped to it
+const MAX_CALLSTACK_DEPTH: usize = 128;
+
+/// The buffers that aren't kdebug events, a stackshot and the configuration plists
+const KCDATA_MAGIC: &[u8] = &[0x07, 0x58, 0xA2, 0x59];
+const BPLIST_MAGIC: &[u8] = b"bplist";
+
+/// The stack samples of a process, returned by `DeviceInstruments::time_profile`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackProfile {
+    pid: u32,
+    /// The callstacks, from the outermost frame, with the times they were sampled
+    stacks: HashMap<Vec<u64>, u64>,
+}
+
+impl StackProfile {
+    /// The process identifier of the profiled process
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// The number of samples taken of the process, over all its threads
+    pub fn sample_count(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// The distinct callstacks, from the outermost frame, with the times they were sampled.
+    pub fn stacks(&self) -> impl Iterator<Item = (&[u64], u64)> {
+        self.stacks
+            .iter()
+            .map(|(stack, count)| (stack.as_slice(), *count))
+    }
+
+    /// Formats the samples as collapsed stacks, the input of the flamegraph tools.
+    ///
+    /// Each line is a callstack, its frames from the outermost one separated by `;`,
+    /// then the times it was sampled (e.g., "0x1a2b3c;0x1a2d4e 12").
+    pub fn to_collapsed(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, count)| {
+                let mut line = String::new();
+                for (index, frame) in stack.iter().enumerate() {
+                    if index > 0 {
+                        line.push(';');
+                    }
+                    let _ = write!(line, "{:#x}", frame);
+                }
+                let _ = write!(line, " {}", count);
+                line
+            })
+            .collect();
+        lines.sort();
+
+        lines.join("\n")
+    }
+}
+
+/// Samples the callstacks of a process for the given duration.
+pub(crate) fn profile(
+    device: &Device,
+    pid: u32,
+    duration: Duration,
+) -> Result<StackProfile, DeviceInstrumentsError> {
+    let mut connection = DtxConnection::connect(device)?;
+    let channel = connection.open_channel(CORE_PROFILE_SERVICE)?;
+
+    connection.send(
+        channel,
+        "setConfig:",
+        &[AuxiliaryValue::Object(configuration())],
+        false,
+    )?;
+    connection.send(channel, "start", &[], false)?;
+
+    let mut sampler = StackSampler::new(pid);
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        if let Some(message) = connection.receive(500)? {
+            if let (true, Some(ArchivedValue::Data(buffer))) =
+                (message.channel.abs() == channel, &message.object)
+            {
+                sampler.feed(buffer);
+            }
+        }
+    }
+
+    // The samples are all in, the profiling stops with the connection otherwise
+    let _ = connection.send(channel, "stop", &[], false);
+
+    Ok(sampler.profile)
+}
+
+/// The configuration of the time profiler, sampling the callstacks of the running threads.
+fn configuration() -> ArchivedValue {
+    let trigger = ArchivedValue::Dictionary(HashMap::from([
+        // The kdebug classes to trace
+        (
+            "kdf2".to_string(),
+            ArchivedValue::Set(vec![
+                ArchivedValue::Integer(630784000),
+                ArchivedValue::Integer(833617920),
+                ArchivedValue::Integer(830472456),
+            ]),
+        ),
+        // A timer trigger, sampling the thread info and the user callstacks
+        ("tk".to_string(), ArchivedValue::Integer(3)),
+        (
+            "ta".to_string(),
+            ArchivedValue::Array(vec![
+                ArchivedValue::Array(vec![ArchivedValue::Integer(3)]),
+                ArchivedValue::Array(vec![ArchivedValue::Integer(0)]),
+                ArchivedValue::Array(vec![ArchivedValue::Integer(2)]),
+                ArchivedValue::Array(vec![
+                    ArchivedValue::Integer(1),
+                    ArchivedValue::Integer(1),
+                    ArchivedValue::Integer(0),
+                ]),
+            ]),
+        ),
+        // The deepest callstack
+        (
+            "csd".to_string(),
+            ArchivedValue::Integer(MAX_CALLSTACK_DEPTH as i64),
+        ),
+        ("uuid".to_string(), ArchivedValue::String(session_uuid())),
+    ]));
+
+    ArchivedValue::Dictionary(HashMap::from([
+        ("rp".to_string(), ArchivedValue::Integer(100)),
+        ("bm".to_string(), ArchivedValue::Integer(0)),
+        ("ur".to_string(), ArchivedValue::Integer(500)),
+        ("tc".to_string(), ArchivedValue::Array(vec![trigger])),
+    ]))
+}
+
+/// A unique identifier for the session, formatted as a UUID.
+fn session_uuid() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let process = std::process::id() as u128;
+
+    format!(
+        "{:08X}-{:04X}-4{:03X}-8{:03X}-{:012X}",
+        (nanos >> 32) as u32,
+        (nanos >> 16) as u16,
+        (nanos & 0xFFF) as u16,
+        (process & 0xFFF) as u16,
+        (nanos ^ (process << 32)) & 0xFFFF_FFFF_FFFF
+    )
+}
+
+/// A callstack being put back together from its events.
+struct PendingStack {
+    expected: usize,
+    frames: Vec<u64>,
+}
+
+/// Collects the callstacks of a process out of the kdebug events.
+struct StackSampler {
+    profile: StackProfile,
+    /// The process of each thread, by thread identifier
+    threads: HashMap<u64, u32>,
+    pending: HashMap<u64, PendingStack>,
+}
+
+impl StackSampler {
+    fn new(pid: u32) -> StackSampler {
+        StackSampler {
+            profile: StackProfile {
+                pid,
+                ..Default::default()
+            },
+            threads: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    fn feed(&mut self, buffer: &[u8]) {
+        if buffer.starts_with(KCDATA_MAGIC) || buffer.starts_with(BPLIST_MAGIC) {
+            return;
+        }
+
+        for event in buffer.chunks_exact(EVENT_SIZE) {
+            let word = |index: usize| {
+                u64::from_le_bytes(
+                    event[index * 8..index * 8 + 8]
+                        .try_into()
+                        .unwrap_or_default(),
+                )
+            };
+            let arguments = [word(1), word(2), word(3), word(4)];
+            let thread = word(5);
+            let debug_id = u32::from_le_bytes(event[48..52].try_into().unwrap_or_default());
+
+            match debug_id & !0x3 {
+                THREAD_INFO_EVENT => {
+                    self.threads.insert(thread, arguments[0] as u32);
+                }
+                USER_STACK_HEADER_EVENT => {
+                    // The count comes from the device, a bogus one mustn't make the allocation fail
+                    let expected = (arguments[1] as usize).min(MAX_CALLSTACK_DEPTH);
+                    self.pending.insert(
+                        thread,
+                        PendingStack {
+                            expected,
+                            frames: Vec::with_capacity(expected),
+                        },
+                    );
+                }
+                USER_STACK_DATA_EVENT => self.push_frames(thread, &arguments),
+                _ => (),
+            }
+        }
+    }
+
+    fn push_frames(&mut self, thread: u64, frames: &[u64]) {
+        let Some(pending) = self.pending.get_mut(&thread) else {
+            return;
+        };
+        let missing = pending.expected - pending.frames.len();
+        pending.frames.extend(frames.iter().take(missing).copied());
+        if pending.frames.len() < pending.expected {
+            return;
+        }
+
+        let Some(PendingStack { mut frames, .. }) = self.pending.remove(&thread) else {
+            return;
+        };
+        if self.threads.get(&thread) != Some(&self.profile.pid) || frames.is_empty() {
+            return;
+        }
+
+        // The frames come from the innermost one
+        frames.reverse();
+        *self.profile.stacks.entry(frames).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PID: u32 = 42;
+    const THREAD: u64 = 7;
+
+    fn event(debug_id: u32, arguments: [u64; 4]) -> Vec<u8> {
+        let mut event = vec![0u8; EVENT_SIZE];
+        for (index, argument) in arguments.iter().enumerate() {
+            event[(index + 1) * 8..(index + 2) * 8].copy_from_slice(&argument.to_le_bytes());
+        }
+        event[40..48].copy_from_slice(&THREAD.to_le_bytes());
+        event[48..52].copy_from_slice(&debug_id.to_le_bytes());
+        event
+    }
+
+    #[test]
+    fn puts_the_callstack_back_together() {
+        let buffer = [
+            event(THREAD_INFO_EVENT, [PID as u64, 0, 0, 0]),
+            event(USER_STACK_HEADER_EVENT, [0, 5, 0, 0]),
+            event(USER_STACK_DATA_EVENT, [0x50, 0x40, 0x30, 0x20]),
+            event(USER_STACK_DATA_EVENT, [0x10, 0xFF, 0xFF, 0xFF]),
+        ]
+        .concat();
+
+        let mut sampler = StackSampler::new(PID);
+        sampler.feed(&buffer);
+
+        let stacks = sampler.profile.stacks().collect::<Vec<_>>();
+        assert_eq!(stacks, [(&[0x10, 0x20, 0x30, 0x40, 0x50][..], 1)]);
+    }
+
+    #[test]
+    fn ignores_the_other_processes() {
+        let buffer = [
+            event(THREAD_INFO_EVENT, [PID as u64 + 1, 0, 0, 0]),
+            event(USER_STACK_HEADER_EVENT, [0, 1, 0, 0]),
+            event(USER_STACK_DATA_EVENT, [0x10, 0, 0, 0]),
+        ]
+        .concat();
+
+        let mut sampler = StackSampler::new(PID);
+        sampler.feed(&buffer);
+
+        assert_eq!(sampler.profile.sample_count(), 0);
+    }
+
+    #[test]
+    fn clamps_an_oversized_frame_count() {
+        let mut buffer = vec![
+            event(THREAD_INFO_EVENT, [PID as u64, 0, 0, 0]),
+            event(USER_STACK_HEADER_EVENT, [0, u64::MAX, 0, 0]),
+        ];
+        buffer.extend(
+            (0..MAX_CALLSTACK_DEPTH as u64 / 4)
+                .map(|index| event(USER_STACK_DATA_EVENT, [index; 4])),
+        );
+
+        let mut sampler = StackSampler::new(PID);
+        sampler.feed(&buffer.concat());
+
+        let stacks = sampler.profile.stacks().collect::<Vec<_>>();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].0.len(), MAX_CALLSTACK_DEPTH);
+    }
+}
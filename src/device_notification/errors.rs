@@ -0,0 +1,34 @@
+use crate::errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceNotificationError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("The observing thread stopped before being ready")]
+    ObserverStopped,
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceNotificationError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceNotificationError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
@@ -0,0 +1,232 @@
+//! Provides access to the notification proxy of iOS devices
+//!
+//! The notification proxy relays the system notifications between the host and the device,
+//! e.g., to know when an app got installed, or to tell the device a sync is starting.
+//!
+//! ## Features
+//! - Posting notifications to the device
+//! - Observing the notifications of the device in the background
+//! - Typed well-known notifications
+//!
+
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    error::ServiceError, idevice, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
+
+pub(crate) mod errors;
+pub mod notifications;
+
+pub use notifications::Notification;
+
+use crate::{
+    device::DeviceClient, devices_collection::SingleDevice, errors::DeviceNotificationError,
+    plist_service,
+};
+
+const NOTIFICATION_PROXY_SERVICE: &str = "com.apple.mobile.notification_proxy";
+
+/// Struct for exchanging notifications with a device
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceNotification<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceNotification<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceNotification<'a, T> {
+        DeviceNotification {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DeviceNotification<'_, SingleDevice> {
+    /// Posts a notification to the device.
+    ///
+    /// # Parameters
+    /// - `notification`: The notification to post.
+    pub fn post(&self, notification: &Notification) -> Result<(), DeviceNotificationError> {
+        self.device.check_connected::<DeviceNotificationError>()?;
+
+        let service = connect(self.device.get_device())?;
+        send_command(&service, "PostNotification", Some(notification))?;
+        send_command(&service, "Shutdown", None)?;
+
+        Ok(())
+    }
+
+    /// Observes the given notifications, calling the callback each time the device posts one of them.
+    ///
+    /// This is a non blocking function, the notifications are observed until the returned handle is stopped or dropped.
+    ///
+    /// # Parameters
+    /// - `notifications`: The notifications to observe.
+    /// - `callback`: The callback receiving the posted notifications.
+    pub fn observe<F>(
+        &self,
+        notifications: &[Notification],
+        callback: F,
+    ) -> Result<NotificationHandle, DeviceNotificationError>
+    where
+        F: Fn(Notification) + Send + 'static,
+    {
+        self.device.check_connected::<DeviceNotificationError>()?;
+
+        let device = self.device.get_device().clone();
+        let notifications = notifications.to_vec();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        // The connection is made in the thread, the setup result is sent back so it can be returned
+        let (ready_sender, ready_receiver) = crossbeam_channel::bounded(1);
+
+        let handle = thread::spawn(move || {
+            let service = match connect(&device).and_then(|service| {
+                for notification in &notifications {
+                    send_command(&service, "ObserveNotification", Some(notification))?;
+                }
+                Ok(service)
+            }) {
+                Ok(service) => {
+                    let _ = ready_sender.send(Ok(()));
+                    service
+                }
+                Err(err) => {
+                    let _ = ready_sender.send(Err(err));
+                    return;
+                }
+            };
+
+            while !thread_stop.load(Ordering::SeqCst) {
+                match receive_relayed(&service) {
+                    Ok(Some(Relayed::Notification(notification))) => callback(notification),
+                    Ok(Some(Relayed::ProxyDeath)) => break,
+                    Ok(None) => (),
+                    Err(err) => {
+                        log::warn!(udid:% = device.get_udid(), error:% = err; "Failed to receive a notification");
+                        break;
+                    }
+                }
+            }
+
+            let _ = send_command(&service, "Shutdown", None);
+        });
+
+        match ready_receiver.recv() {
+            Ok(Ok(())) => Ok(NotificationHandle {
+                stop,
+                handle: Some(handle),
+            }),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(DeviceNotificationError::ObserverStopped),
+        }
+    }
+}
+
+/// Handle to the thread observing the notifications, returned by `DeviceNotification::observe`.
+///
+/// Dropping the handle stops the observing and waits for the thread to finish.
+#[derive(Debug)]
+pub struct NotificationHandle {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl NotificationHandle {
+    /// Stops observing and waits for the thread to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    /// Checks whether the observing thread has finished (e.g., the device got disconnected).
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for NotificationHandle {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+enum Relayed {
+    Notification(Notification),
+    ProxyDeath,
+}
+
+fn connect(device: &idevice::Device) -> Result<ServiceClient<'_>, DeviceNotificationError> {
+    let mut lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client"))?;
+    let lockdownd_service = lockdownd.start_service(NOTIFICATION_PROXY_SERVICE, true)?;
+
+    Ok(ServiceClient::new(device, lockdownd_service)?)
+}
+
+fn send_command(
+    service: &ServiceClient,
+    command: &str,
+    notification: Option<&Notification>,
+) -> Result<(), DeviceNotificationError> {
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Command", command.into())?;
+    if let Some(notification) = notification {
+        request.dict_set_item("Name", notification.as_str().into())?;
+    }
+
+    Ok(plist_service::send_plist(service, &request)?)
+}
+
+/// Waits a bit for a message from the proxy, `None` if nothing came in, so the stop flag can be checked.
+fn receive_relayed(service: &ServiceClient) -> Result<Option<Relayed>, DeviceNotificationError> {
+    let mut length = match service.receive_with_timeout(4, 500) {
+        Ok(data) if data.is_empty() => return Ok(None),
+        Ok(data) => data,
+        Err(ServiceError::Timeout) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if length.len() < 4 {
+        length.extend(plist_service::receive_exact(service, 4 - length.len())?);
+    }
+    let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]);
+
+    let message = Plist::from_memory(plist_service::receive_exact(service, length as usize)?)?;
+
+    let string = |key: &str| {
+        message
+            .dict_get_item(key)
+            .and_then(|value| value.get_string_val())
+            .ok()
+    };
+
+    Ok(match string("Command").as_deref() {
+        Some("RelayNotification") => string("Name").map(|name| Relayed::Notification(name.into())),
+        Some("ProxyDeath") => Some(Relayed::ProxyDeath),
+        _ => None,
+    })
+}
@@ -0,0 +1,77 @@
+use std::fmt::Display;
+
+/// Generates the `Notification` enum along with the conversions from and to the raw names.
+macro_rules! notifications {
+    ($($(#[$doc:meta])* $variant:ident => $name:literal,)*) => {
+        /// Enum representing the notifications exchanged with the device.
+        ///
+        /// The well-known ones are typed, any other name is kept in `Other`.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum Notification {
+            $($(#[$doc])* $variant,)*
+            /// A notification not known by this library, with its raw name
+            Other(String),
+        }
+
+        impl Notification {
+            /// The raw name of the notification (e.g., "com.apple.mobile.application_installed")
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Notification::$variant => $name,)*
+                    Notification::Other(name) => name,
+                }
+            }
+        }
+
+        impl From<&str> for Notification {
+            fn from(name: &str) -> Self {
+                match name {
+                    $($name => Notification::$variant,)*
+                    other => Notification::Other(other.to_string()),
+                }
+            }
+        }
+    };
+}
+
+notifications! {
+    // Sent by the host
+    SyncWillStart => "com.apple.itunes-mobdev.syncWillStart",
+    SyncDidStart => "com.apple.itunes-mobdev.syncDidStart",
+    SyncDidFinish => "com.apple.itunes-mobdev.syncDidFinish",
+    SyncLockRequest => "com.apple.itunes-mobdev.syncLockRequest",
+
+    // Sent by the device
+    SyncCancelRequest => "com.apple.itunes-client.syncCancelRequest",
+    SyncSuspendRequest => "com.apple.itunes-client.syncSuspendRequest",
+    SyncResumeRequest => "com.apple.itunes-client.syncResumeRequest",
+    AppInstalled => "com.apple.mobile.application_installed",
+    AppUninstalled => "com.apple.mobile.application_uninstalled",
+    BackupDomainChanged => "com.apple.mobile.backup.domain_changed",
+    DataSyncDomainChanged => "com.apple.mobile.data_sync.domain_changed",
+    DeveloperImageMounted => "com.apple.mobile.developer_image_mounted",
+    DeviceNameChanged => "com.apple.mobile.lockdown.device_name_changed",
+    TimezoneChanged => "com.apple.mobile.lockdown.timezone_changed",
+    TrustedHostAttached => "com.apple.mobile.lockdown.trusted_host_attached",
+    HostAttached => "com.apple.mobile.lockdown.host_attached",
+    HostDetached => "com.apple.mobile.lockdown.host_detached",
+    ActivationState => "com.apple.mobile.lockdown.activation_state",
+    BrickState => "com.apple.mobile.lockdown.brick_state",
+    DiskUsageChanged => "com.apple.mobile.lockdown.disk_usage_changed",
+    RegistrationFailed => "com.apple.mobile.lockdown.registration_failed",
+    AttemptActivation => "com.apple.springboard.attemptactivation",
+    AddressBookPreferenceChanged => "com.apple.AddressBook.PreferenceChanged",
+    LanguageChanged => "com.apple.language.changed",
+}
+
+impl From<String> for Notification {
+    fn from(name: String) -> Self {
+        Notification::from(name.as_str())
+    }
+}
+
+impl Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,34 @@
+use crate::errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DevicePreboardError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("The preboard service failed: {0}")]
+    PreboardError(String),
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DevicePreboardError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DevicePreboardError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
@@ -0,0 +1,153 @@
+//! Provides access to the preboard service of iOS devices
+//!
+//! The preboard service prepares the data protection stash bag before the first unlock,
+//! which some managed workflows (e.g., software updates applied without the user) rely on.
+//!
+//! ## Features
+//! - Creating a stash bag, with the status of the passcode dialog shown on the device
+//! - Committing a stash bag
+//!
+
+use std::marker::PhantomData;
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::service::ServiceClient;
+
+pub(crate) mod errors;
+
+use crate::{
+    device::DeviceClient, devices_collection::SingleDevice, errors::DevicePreboardError,
+    plist_service,
+};
+
+const PREBOARD_SERVICE: &str = "com.apple.preboardservice_v2";
+
+/// Enum representing the status updates sent while a stash bag is created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreboardStatus {
+    /// The device shows the passcode dialog, the user has to enter it
+    ShowDialog,
+    /// The passcode dialog was dismissed
+    HideDialog,
+}
+
+/// Struct for managing the preboard service
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DevicePreboard<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DevicePreboard<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DevicePreboard<'a, T> {
+        DevicePreboard {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DevicePreboard<'_, SingleDevice> {
+    /// Creates a stash bag, the device asks the user for the passcode.
+    ///
+    /// This is a blocking function, it returns once the user entered the passcode or dismissed the dialog.
+    ///
+    /// # Parameters
+    /// - `manifest`: The manifest to create the stash bag for, if any.
+    /// - `on_status`: The callback receiving the dialog status updates.
+    pub fn create_stashbag<F>(
+        &self,
+        manifest: Option<Plist>,
+        on_status: F,
+    ) -> Result<Plist, DevicePreboardError>
+    where
+        F: Fn(PreboardStatus),
+    {
+        self.device.check_connected::<DevicePreboardError>()?;
+
+        let service = self.connect()?;
+        send_command(&service, "CreateStashbag", manifest)?;
+
+        loop {
+            let response = receive_response(&service)?;
+
+            if has_true(&response, "ShowDialog") {
+                on_status(PreboardStatus::ShowDialog);
+            } else if has_true(&response, "HideDialog") {
+                on_status(PreboardStatus::HideDialog);
+            } else {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Commits a stash bag created with `create_stashbag`.
+    ///
+    /// # Parameters
+    /// - `manifest`: The manifest the stash bag was created for, if any.
+    pub fn commit_stashbag(&self, manifest: Option<Plist>) -> Result<(), DevicePreboardError> {
+        self.device.check_connected::<DevicePreboardError>()?;
+
+        let service = self.connect()?;
+        send_command(&service, "CommitStashbag", manifest)?;
+
+        let response = receive_response(&service)?;
+        if !has_true(&response, "StashbagCommitComplete") {
+            return Err(DevicePreboardError::PreboardError(
+                "the commit wasn't completed".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Starts the preboard service on the device.
+    fn connect(&self) -> Result<ServiceClient, DevicePreboardError> {
+        let device = self.device.get_device();
+        let mut lockdownd = self.device.get_lockdownd_client::<DevicePreboardError>()?;
+        let service = lockdownd.start_service(PREBOARD_SERVICE, true)?;
+
+        Ok(ServiceClient::new(device, service)?)
+    }
+}
+
+fn send_command(
+    service: &ServiceClient,
+    command: &str,
+    manifest: Option<Plist>,
+) -> Result<(), DevicePreboardError> {
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Command", command.into())?;
+    if let Some(manifest) = manifest {
+        request.dict_set_item("Manifest", manifest)?;
+    }
+
+    Ok(plist_service::send_plist(service, &request)?)
+}
+
+/// Receives a response, failing if it carries an `Error` key.
+fn receive_response(service: &ServiceClient) -> Result<Plist, DevicePreboardError> {
+    let response = plist_service::receive_plist::<DevicePreboardError>(service)?;
+
+    if let Ok(error) = response.dict_get_item("Error") {
+        let description = response
+            .dict_get_item("ErrorString")
+            .and_then(|description| description.get_string_val())
+            .or_else(|_| error.get_display_value())
+            .unwrap_or_default();
+        return Err(DevicePreboardError::PreboardError(description));
+    }
+
+    Ok(response)
+}
+
+fn has_true(response: &Plist, key: &str) -> bool {
+    response
+        .dict_get_item(key)
+        .and_then(|value| value.get_bool_val())
+        .unwrap_or(false)
+}
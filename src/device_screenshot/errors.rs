@@ -0,0 +1,61 @@
+use crate::{
+    device_info::kind::DeviceKind,
+    errors::{
+        DeviceNotFoundErrorTrait, LockdowndErrorTrait, UnexpectedResponseErrorTrait,
+        UnsupportedDeviceErrorTrait,
+    },
+};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceScreenshotError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Unexpected response from the screenshot service: {0}")]
+    UnexpectedResponse(String),
+
+    #[error("The recording thread panicked")]
+    RecordingPanicked,
+
+    #[error("Not available on this device class ({0})")]
+    UnsupportedDevice(DeviceKind),
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceScreenshotError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceScreenshotError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
+
+impl UnsupportedDeviceErrorTrait for DeviceScreenshotError {
+    fn unsupported_device(kind: DeviceKind) -> Self {
+        Self::UnsupportedDevice(kind)
+    }
+}
+
+impl UnexpectedResponseErrorTrait for DeviceScreenshotError {
+    fn unexpected_response(description: String) -> Self {
+        Self::UnexpectedResponse(description)
+    }
+}
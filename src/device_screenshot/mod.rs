@@ -0,0 +1,168 @@
+//! Provides screenshots and screen recordings of iOS devices
+//!
+//! The screenshot service needs the developer disk image to be mounted on the device.
+//!
+//! ## Features
+//! - Taking screenshots
+//! - Recording the screen as a series of frames, to a directory or a callback
+//! - Taking screenshots periodically, e.g., to monitor kiosks
+//!
+
+use std::{fs, marker::PhantomData, path::Path, time::Duration};
+
+pub(crate) mod errors;
+pub mod recording;
+pub mod screenshotr;
+
+pub use recording::{RecordingOptions, ScreenFrame, ScreenRecording};
+pub use screenshotr::{ImageFormat, Screenshot};
+
+use crate::{
+    device::DeviceClient, device_info::kind::DeviceKind, devices_collection::SingleDevice,
+    errors::DeviceScreenshotError,
+};
+use rusty_libimobiledevice::error::LockdowndError;
+use screenshotr::ScreenshotrClient;
+
+/// Struct for capturing the screen of a device
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceScreenshot<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceScreenshot<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceScreenshot<'a, T> {
+        DeviceScreenshot {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DeviceScreenshot<'_, SingleDevice> {
+    /// Takes a screenshot of the device screen.
+    pub fn take_screenshot(&self) -> Result<Screenshot, DeviceScreenshotError> {
+        self.device.check_connected::<DeviceScreenshotError>()?;
+
+        let client = ScreenshotrClient::connect(self.device.get_device())
+            .map_err(|err| self.explain_connect_error(err))?;
+        client.take()
+    }
+
+    /// Records the screen to a directory, as numbered images (`frame_000000.png`, `frame_000001.png`, ...).
+    ///
+    /// This is a non blocking function, the recording runs until the returned handle is stopped or dropped,
+    /// or the limits of the options are reached.
+    ///
+    /// # Parameters
+    /// - `dir`: The directory to write the frames to, created if it doesn't exist.
+    /// - `options`: The frame rate and the limits of the recording.
+    pub fn record_screen<S>(
+        &self,
+        dir: &S,
+        options: RecordingOptions,
+    ) -> Result<ScreenRecording, DeviceScreenshotError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        self.record_screen_with_callback(options, frames_writer(dir.as_ref(), "frame")?)
+    }
+
+    /// Takes a screenshot every `every` in the background, writing them as numbered images
+    /// (`screenshot_000000.png`, `screenshot_000001.png`, ...).
+    ///
+    /// This is a non blocking function, use the returned handle to stop early, or wait for the last screenshot.
+    /// Unlike the recording, the service is connected for each screenshot, so the device isn't kept busy in between.
+    ///
+    /// # Parameters
+    /// - `every`: The time between two screenshots.
+    /// - `count`: The number of screenshots to take, `None` to take them until stopped.
+    /// - `dir`: The directory to write the screenshots to, created if it doesn't exist.
+    pub fn capture_interval<S>(
+        &self,
+        every: Duration,
+        count: Option<u64>,
+        dir: &S,
+    ) -> Result<ScreenRecording, DeviceScreenshotError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let mut options = RecordingOptions::new()
+            .with_frame_interval(every)
+            .with_connect_per_frame(true);
+        if let Some(count) = count {
+            options = options.with_max_frames(count);
+        }
+
+        self.record_screen_with_callback(options, frames_writer(dir.as_ref(), "screenshot")?)
+    }
+
+    /// Records the screen, delivering every frame to the callback.
+    ///
+    /// This is a non blocking function, the recording runs until the returned handle is stopped or dropped,
+    /// the limits of the options are reached, or the callback returns an error.
+    ///
+    /// # Parameters
+    /// - `options`: The frame rate and the limits of the recording.
+    /// - `on_frame`: The callback receiving the frames.
+    pub fn record_screen_with_callback<F>(
+        &self,
+        options: RecordingOptions,
+        on_frame: F,
+    ) -> Result<ScreenRecording, DeviceScreenshotError>
+    where
+        F: FnMut(ScreenFrame) -> Result<(), DeviceScreenshotError> + Send + 'static,
+    {
+        self.device.check_connected::<DeviceScreenshotError>()?;
+        self.device
+            .check_supported::<DeviceScreenshotError>(DeviceKind::has_screen_capture)?;
+
+        Ok(recording::spawn_recording(
+            self.device.get_device().clone(),
+            options,
+            on_frame,
+        ))
+    }
+}
+
+impl DeviceScreenshot<'_, SingleDevice> {
+    /// Tells apart a device without the screenshot service (e.g., Apple Watch) from a missing developer disk image.
+    fn explain_connect_error(&self, err: DeviceScreenshotError) -> DeviceScreenshotError {
+        match err {
+            DeviceScreenshotError::LockdowndError(
+                LockdowndError::InvalidService | LockdowndError::MissingService,
+            ) => match self.device.get_device_kind::<DeviceScreenshotError>() {
+                Ok(kind) if !kind.has_screen_capture() => {
+                    DeviceScreenshotError::UnsupportedDevice(kind)
+                }
+                _ => err,
+            },
+            err => err,
+        }
+    }
+}
+
+/// Creates the directory and returns a callback writing each frame as `{prefix}_{index}.{extension}` in it.
+fn frames_writer(
+    dir: &Path,
+    prefix: &'static str,
+) -> Result<impl FnMut(ScreenFrame) -> Result<(), DeviceScreenshotError>, DeviceScreenshotError> {
+    let dir = dir.to_path_buf();
+    fs::create_dir_all(&dir)?;
+
+    Ok(move |frame: ScreenFrame| {
+        let file_name = format!(
+            "{}_{:06}.{}",
+            prefix,
+            frame.index,
+            frame.screenshot.format.extension()
+        );
+        fs::write(dir.join(file_name), &frame.screenshot.data)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,219 @@
+//! Screen recording made of consecutive screenshots.
+//!
+//! The H.264 stream Xcode and QuickTime record comes from a USB interface libimobiledevice has no access to,
+//! so the recording is a series of frames taken through the screenshot service, at a few frames per second.
+//! The frames can be turned into a video afterwards, e.g., `ffmpeg -framerate 2 -i frame_%06d.png out.mp4`.
+
+use crate::device_screenshot::{
+    errors::DeviceScreenshotError,
+    screenshotr::{Screenshot, ScreenshotrClient},
+};
+use rusty_libimobiledevice::idevice::Device;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Options for the screen recording.
+///
+/// By default, it records 2 frames per second until it's stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingOptions {
+    frame_interval: Duration,
+    max_frames: Option<u64>,
+    max_duration: Option<Duration>,
+    connect_per_frame: bool,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        RecordingOptions {
+            frame_interval: Duration::from_millis(500),
+            max_frames: None,
+            max_duration: None,
+            connect_per_frame: false,
+        }
+    }
+}
+
+impl RecordingOptions {
+    pub fn new() -> RecordingOptions {
+        RecordingOptions::default()
+    }
+
+    /// Sets the wanted frames per second, the actual rate is limited by how fast the device takes screenshots
+    pub fn with_fps(mut self, fps: f64) -> RecordingOptions {
+        self.frame_interval = Duration::from_secs_f64(1.0 / fps.max(0.001));
+        self
+    }
+
+    /// Stops the recording after the given number of frames
+    pub fn with_max_frames(mut self, max_frames: u64) -> RecordingOptions {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Stops the recording after the given time
+    pub fn with_max_duration(mut self, max_duration: Duration) -> RecordingOptions {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Sets the time between two frames directly
+    pub(crate) fn with_frame_interval(mut self, frame_interval: Duration) -> RecordingOptions {
+        self.frame_interval = frame_interval;
+        self
+    }
+
+    /// Connects to the service for each frame instead of keeping the connection open,
+    /// for the long intervals where the device could close an idle connection
+    pub(crate) fn with_connect_per_frame(mut self, connect_per_frame: bool) -> RecordingOptions {
+        self.connect_per_frame = connect_per_frame;
+        self
+    }
+}
+
+/// A frame of a screen recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenFrame {
+    /// The number of the frame, starting from 0
+    pub index: u64,
+
+    /// The time since the start of the recording
+    pub elapsed: Duration,
+
+    /// The screenshot itself
+    pub screenshot: Screenshot,
+}
+
+/// Handle to a running screen recording, returned by the `record_screen*` and `capture_interval` functions.
+///
+/// Dropping the handle stops the recording and waits for the thread to finish.
+#[derive(Debug)]
+pub struct ScreenRecording {
+    stop: Arc<AtomicBool>,
+    frames: Arc<AtomicU64>,
+    handle: Option<JoinHandle<Result<(), DeviceScreenshotError>>>,
+}
+
+impl ScreenRecording {
+    /// Returns the number of frames captured so far
+    pub fn frames_captured(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    /// Checks whether the recording has finished, by itself or because of an error.
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    /// Stops the recording and waits for the last frame to be delivered.
+    ///
+    /// Returns the number of captured frames, or the error that ended the recording early.
+    pub fn stop(mut self) -> Result<u64, DeviceScreenshotError> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.join_thread()
+    }
+
+    /// Waits for the recording to finish by itself (i.e., the maximum frames or duration is reached).
+    pub fn join(mut self) -> Result<u64, DeviceScreenshotError> {
+        self.join_thread()
+    }
+
+    fn join_thread(&mut self) -> Result<u64, DeviceScreenshotError> {
+        if let Some(handle) = self.handle.take() {
+            handle
+                .join()
+                .map_err(|_| DeviceScreenshotError::RecordingPanicked)??;
+        }
+        Ok(self.frames_captured())
+    }
+}
+
+impl Drop for ScreenRecording {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.join_thread();
+    }
+}
+
+/// Starts the recording thread, which calls `on_frame` for every frame.
+pub(crate) fn spawn_recording<F>(
+    device: Device,
+    options: RecordingOptions,
+    mut on_frame: F,
+) -> ScreenRecording
+where
+    F: FnMut(ScreenFrame) -> Result<(), DeviceScreenshotError> + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let frames = Arc::new(AtomicU64::new(0));
+
+    let thread_stop = Arc::clone(&stop);
+    let thread_frames = Arc::clone(&frames);
+
+    let handle = thread::spawn(move || {
+        let client = match options.connect_per_frame {
+            true => None,
+            false => Some(ScreenshotrClient::connect(&device)?),
+        };
+        let start = Instant::now();
+        let mut index = 0;
+
+        while !thread_stop.load(Ordering::SeqCst) {
+            if options.max_frames.is_some_and(|max| index >= max)
+                || options
+                    .max_duration
+                    .is_some_and(|max| start.elapsed() >= max)
+            {
+                break;
+            }
+
+            let screenshot = match &client {
+                Some(client) => client.take()?,
+                None => ScreenshotrClient::connect(&device)?.take()?,
+            };
+            on_frame(ScreenFrame {
+                index,
+                elapsed: start.elapsed(),
+                screenshot,
+            })?;
+
+            index += 1;
+            thread_frames.store(index, Ordering::Relaxed);
+
+            // Keeping the pace from the start, so a slow frame doesn't delay all the next ones
+            let next_frame = options.frame_interval * index as u32;
+            if let Some(wait) = next_frame.checked_sub(start.elapsed()) {
+                sleep_unless_stopped(wait, &thread_stop);
+            }
+        }
+
+        Ok(())
+    });
+
+    ScreenRecording {
+        stop,
+        frames,
+        handle: Some(handle),
+    }
+}
+
+/// Sleeps for the given time, waking up early if the recording is stopped.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+
+    while !stop.load(Ordering::SeqCst) {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            return;
+        };
+        thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,103 @@
+//! Client of the `com.apple.mobile.screenshotr` service.
+//!
+//! It speaks the DeviceLink protocol (see `plist_service::DeviceLinkClient`).
+//! The service is only available once the developer disk image is mounted.
+
+use crate::{device_screenshot::errors::DeviceScreenshotError, plist_service::DeviceLinkClient};
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    idevice::Device, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
+
+const SCREENSHOTR_SERVICE: &str = "com.apple.mobile.screenshotr";
+
+/// Enum representing the format of a screenshot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Tiff,
+    Unknown,
+}
+
+impl ImageFormat {
+    /// Detects the format from the magic bytes of the image
+    fn detect(data: &[u8]) -> ImageFormat {
+        if data.starts_with(b"\x89PNG") {
+            ImageFormat::Png
+        } else if data.starts_with(b"MM\x00\x2a") || data.starts_with(b"II\x2a\x00") {
+            ImageFormat::Tiff
+        } else {
+            ImageFormat::Unknown
+        }
+    }
+
+    /// The usual file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Tiff => "tiff",
+            ImageFormat::Unknown => "bin",
+        }
+    }
+}
+
+/// A screenshot of the device screen, PNG on recent iOS versions and TIFF on the older ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screenshot {
+    /// The encoded image
+    pub data: Vec<u8>,
+
+    /// The format of the image
+    pub format: ImageFormat,
+}
+
+impl Screenshot {
+    pub(crate) fn new(data: Vec<u8>) -> Screenshot {
+        Screenshot {
+            format: ImageFormat::detect(&data),
+            data,
+        }
+    }
+}
+
+/// A connection to the screenshot service, after the DeviceLink version exchange.
+pub(crate) struct ScreenshotrClient<'a> {
+    client: DeviceLinkClient<'a>,
+}
+
+impl<'a> ScreenshotrClient<'a> {
+    pub(crate) fn connect(
+        device: &'a Device,
+    ) -> Result<ScreenshotrClient<'a>, DeviceScreenshotError> {
+        let mut lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client"))?;
+        let lockdownd_service = lockdownd.start_service(SCREENSHOTR_SERVICE, true)?;
+
+        Ok(ScreenshotrClient {
+            client: DeviceLinkClient::connect::<DeviceScreenshotError>(ServiceClient::new(
+                device,
+                lockdownd_service,
+            )?)?,
+        })
+    }
+
+    /// Takes a single screenshot.
+    pub(crate) fn take(&self) -> Result<Screenshot, DeviceScreenshotError> {
+        let mut request = Plist::new_dict();
+        request.dict_set_item("MessageType", "ScreenShotRequest".into())?;
+        self.client
+            .send_process_message::<DeviceScreenshotError>(request)?;
+
+        let reply = self
+            .client
+            .expect_message::<DeviceScreenshotError>("DLMessageProcessMessage")?;
+        let data = reply
+            .array_get_item(1)
+            .and_then(|reply| reply.dict_get_item("ScreenShotData"))
+            .and_then(|data| data.get_data_val())
+            .map_err(|_| DeviceScreenshotError::UnexpectedResponse("no ScreenShotData".into()))?;
+
+        Ok(Screenshot::new(
+            data.into_iter().map(|byte| byte as u8).collect(),
+        ))
+    }
+}
@@ -0,0 +1,53 @@
+/// Enum representing the orientation of the device interface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterfaceOrientation {
+    Portrait,
+    PortraitUpsideDown,
+    LandscapeRight,
+    LandscapeLeft,
+    Unknown,
+}
+
+impl InterfaceOrientation {
+    pub fn is_portrait(&self) -> bool {
+        matches!(
+            self,
+            InterfaceOrientation::Portrait | InterfaceOrientation::PortraitUpsideDown
+        )
+    }
+
+    pub fn is_landscape(&self) -> bool {
+        matches!(
+            self,
+            InterfaceOrientation::LandscapeRight | InterfaceOrientation::LandscapeLeft
+        )
+    }
+}
+
+impl From<u64> for InterfaceOrientation {
+    fn from(value: u64) -> Self {
+        match value {
+            1 => InterfaceOrientation::Portrait,
+            2 => InterfaceOrientation::PortraitUpsideDown,
+            3 => InterfaceOrientation::LandscapeRight,
+            4 => InterfaceOrientation::LandscapeLeft,
+            _ => InterfaceOrientation::Unknown,
+        }
+    }
+}
+
+/// Enum representing the wallpapers of the device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wallpaper {
+    HomeScreen,
+    LockScreen,
+}
+
+impl Wallpaper {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Wallpaper::HomeScreen => "homescreen",
+            Wallpaper::LockScreen => "lockscreen",
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{
+    device_info::kind::DeviceKind,
+    errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait, UnsupportedDeviceErrorTrait},
+};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DeviceSpringboardError {
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("Lockdownd Error: {0}")]
+    LockdowndError(#[from] LockdowndError),
+
+    #[error("The springboard service didn't return the `{0}` key")]
+    MissingKey(String),
+
+    #[error("Not available on this device class ({0})")]
+    UnsupportedDevice(DeviceKind),
+
+    #[error("Device not found, make sure it's plugged")]
+    DeviceNotFound,
+}
+
+impl DeviceNotFoundErrorTrait for DeviceSpringboardError {
+    fn device_not_found() -> Self {
+        Self::DeviceNotFound
+    }
+}
+
+impl LockdowndErrorTrait for DeviceSpringboardError {
+    fn lockdownd_error(error: LockdowndError) -> Self {
+        Self::LockdowndError(error)
+    }
+}
+
+impl UnsupportedDeviceErrorTrait for DeviceSpringboardError {
+    fn unsupported_device(kind: DeviceKind) -> Self {
+        Self::UnsupportedDevice(kind)
+    }
+}
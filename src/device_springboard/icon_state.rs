@@ -0,0 +1,154 @@
+//! Typed model of the home screen layout (the "icon state").
+//!
+//! SpringBoard describes the layout as an array of pages, the first one being the dock,
+//! each page being an array of items: apps, folders (with their own pages), web clips and widgets.
+
+use plist_plus::{error::PlistError, Plist};
+
+/// The home screen layout of a device, as returned by `DeviceSpringboard::get_icon_state`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IconLayout {
+    /// The items of the dock
+    pub dock: Vec<IconItem>,
+
+    /// The home screen pages, from left to right
+    pub pages: Vec<Vec<IconItem>>,
+}
+
+/// An item of the home screen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IconItem {
+    /// An app, by its bundle identifier
+    App(String),
+
+    /// A folder, with its own pages
+    Folder {
+        name: String,
+        pages: Vec<Vec<IconItem>>,
+    },
+
+    /// Anything else (web clips, widgets, ...), kept as is so it's not lost when the layout is set back
+    Other(Plist),
+}
+
+impl IconItem {
+    /// The bundle identifiers of the apps in this item, the folders included
+    pub fn bundle_ids(&self) -> Vec<&str> {
+        match self {
+            IconItem::App(bundle_id) => vec![bundle_id.as_str()],
+            IconItem::Folder { pages, .. } => pages
+                .iter()
+                .flatten()
+                .flat_map(|item| item.bundle_ids())
+                .collect(),
+            IconItem::Other(_) => Vec::new(),
+        }
+    }
+
+    fn from_plist(item: Plist) -> IconItem {
+        let string = |key: &str| {
+            item.dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+
+        if string("listType").is_some_and(|list_type| list_type == "folder") {
+            return IconItem::Folder {
+                name: string("displayName").unwrap_or_default(),
+                pages: item
+                    .dict_get_item("iconLists")
+                    .map(pages_from_plist)
+                    .unwrap_or_default(),
+            };
+        }
+
+        // `bundleIdentifier` in the format version 2, `displayIdentifier` before
+        if let Some(bundle_id) = string("bundleIdentifier").or_else(|| string("displayIdentifier"))
+        {
+            // Web clips have a display identifier too, but no bundle
+            if item.dict_get_item("webClipURL").is_err() {
+                return IconItem::App(bundle_id);
+            }
+        }
+
+        IconItem::Other(item)
+    }
+
+    fn to_plist(&self) -> Result<Plist, PlistError> {
+        match self {
+            IconItem::App(bundle_id) => {
+                let mut item = Plist::new_dict();
+                item.dict_set_item("displayIdentifier", bundle_id.as_str().into())?;
+                item.dict_set_item("bundleIdentifier", bundle_id.as_str().into())?;
+                Ok(item)
+            }
+            IconItem::Folder { name, pages } => {
+                let mut item = Plist::new_dict();
+                item.dict_set_item("displayName", name.as_str().into())?;
+                item.dict_set_item("listType", "folder".into())?;
+                item.dict_set_item("iconLists", pages_to_plist(pages)?)?;
+                Ok(item)
+            }
+            IconItem::Other(item) => Ok(item.clone()),
+        }
+    }
+}
+
+impl IconLayout {
+    /// The bundle identifiers of all the apps on the home screen and the dock, the folders included
+    pub fn bundle_ids(&self) -> Vec<&str> {
+        self.dock
+            .iter()
+            .chain(self.pages.iter().flatten())
+            .flat_map(|item| item.bundle_ids())
+            .collect()
+    }
+
+    pub(crate) fn from_plist(state: Plist) -> IconLayout {
+        let mut pages = pages_from_plist(state);
+
+        let dock = match pages.is_empty() {
+            true => Vec::new(),
+            false => pages.remove(0),
+        };
+
+        IconLayout { dock, pages }
+    }
+
+    pub(crate) fn to_plist(&self) -> Result<Plist, PlistError> {
+        let mut state = Plist::new_array();
+        state.array_append_item(items_to_plist(&self.dock)?)?;
+        for page in &self.pages {
+            state.array_append_item(items_to_plist(page)?)?;
+        }
+        Ok(state)
+    }
+}
+
+fn pages_from_plist(pages: Plist) -> Vec<Vec<IconItem>> {
+    (0..pages.array_get_size().unwrap_or(0))
+        .filter_map(|index| pages.array_get_item(index).ok())
+        .map(|page| {
+            (0..page.array_get_size().unwrap_or(0))
+                .filter_map(|index| page.array_get_item(index).ok())
+                .map(IconItem::from_plist)
+                .collect()
+        })
+        .collect()
+}
+
+fn pages_to_plist(pages: &[Vec<IconItem>]) -> Result<Plist, PlistError> {
+    let mut array = Plist::new_array();
+    for page in pages {
+        array.array_append_item(items_to_plist(page)?)?;
+    }
+    Ok(array)
+}
+
+fn items_to_plist(items: &[IconItem]) -> Result<Plist, PlistError> {
+    let mut array = Plist::new_array();
+    for item in items {
+        array.array_append_item(item.to_plist()?)?;
+    }
+    Ok(array)
+}
@@ -0,0 +1,186 @@
+//! Provides access to the SpringBoard (the home screen) of iOS devices
+//!
+//! ## Features
+//! - Fetching the app icons as PNG images
+//! - Fetching the wallpapers and the interface orientation
+//! - Exporting and importing the home screen layout
+//!
+
+use std::marker::PhantomData;
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{error::LockdowndError, service::ServiceClient};
+
+pub mod enums;
+pub(crate) mod errors;
+pub mod icon_state;
+
+pub use enums::{InterfaceOrientation, Wallpaper};
+pub use icon_state::{IconItem, IconLayout};
+
+use crate::{
+    device::DeviceClient, device_info::kind::DeviceKind, devices_collection::SingleDevice,
+    errors::DeviceSpringboardError, plist_service,
+};
+
+const SPRINGBOARD_SERVICE: &str = "com.apple.springboardservices";
+
+/// Struct for interacting with the SpringBoard services
+///
+/// # Type Parameters
+/// - `T`: A marker type that determines whether it's targeting a single device or a group of devices.
+///
+#[derive(Debug)]
+pub struct DeviceSpringboard<'a, T> {
+    device: &'a DeviceClient<T>,
+    _p: PhantomData<T>,
+}
+
+impl<'a, T> DeviceSpringboard<'a, T> {
+    pub fn new(device: &'a DeviceClient<T>) -> DeviceSpringboard<'a, T> {
+        DeviceSpringboard {
+            device,
+            _p: PhantomData::<T>,
+        }
+    }
+}
+
+impl DeviceSpringboard<'_, SingleDevice> {
+    /// Retrieves the icon of an app as PNG data.
+    ///
+    /// # Parameters
+    /// - `bundle_id`: The bundle identifier of the app.
+    pub fn get_app_icon(&self, bundle_id: &str) -> Result<Vec<u8>, DeviceSpringboardError> {
+        self.device.check_connected::<DeviceSpringboardError>()?;
+
+        let mut request = Plist::new_dict();
+        request.dict_set_item("command", "getIconPNGData".into())?;
+        request.dict_set_item("bundleId", bundle_id.into())?;
+
+        let response = self.request(&request)?;
+
+        self.get_data(&response, "pngData")
+    }
+
+    /// Retrieves the home screen wallpaper as PNG data.
+    pub fn get_home_screen_wallpaper(&self) -> Result<Vec<u8>, DeviceSpringboardError> {
+        self.device.check_connected::<DeviceSpringboardError>()?;
+
+        let mut request = Plist::new_dict();
+        request.dict_set_item("command", "getHomeScreenWallpaperPNGData".into())?;
+
+        let response = self.request(&request)?;
+
+        self.get_data(&response, "pngData")
+    }
+
+    /// Retrieves a preview of a wallpaper as PNG data, as shown in the wallpaper settings (iOS 15+).
+    ///
+    /// # Parameters
+    /// - `wallpaper`: Which wallpaper to preview.
+    pub fn get_wallpaper_preview(
+        &self,
+        wallpaper: Wallpaper,
+    ) -> Result<Vec<u8>, DeviceSpringboardError> {
+        self.device.check_connected::<DeviceSpringboardError>()?;
+
+        let mut request = Plist::new_dict();
+        request.dict_set_item("command", "getWallpaperPreviewImage".into())?;
+        request.dict_set_item("wallpaperName", wallpaper.as_str().into())?;
+
+        let response = self.request(&request)?;
+
+        self.get_data(&response, "pngData")
+    }
+
+    /// Retrieves the current orientation of the interface.
+    pub fn get_interface_orientation(
+        &self,
+    ) -> Result<InterfaceOrientation, DeviceSpringboardError> {
+        self.device.check_connected::<DeviceSpringboardError>()?;
+
+        let mut request = Plist::new_dict();
+        request.dict_set_item("command", "getInterfaceOrientation".into())?;
+
+        let response = self.request(&request)?;
+
+        let orientation = response
+            .dict_get_item("interfaceOrientation")
+            .and_then(|orientation| orientation.get_uint_val())
+            .map_err(|_| DeviceSpringboardError::MissingKey("interfaceOrientation".into()))?;
+
+        Ok(InterfaceOrientation::from(orientation))
+    }
+
+    /// Retrieves the home screen layout: the dock, the pages and the folders.
+    pub fn get_icon_state(&self) -> Result<IconLayout, DeviceSpringboardError> {
+        self.device.check_connected::<DeviceSpringboardError>()?;
+
+        let mut request = Plist::new_dict();
+        request.dict_set_item("command", "getIconState".into())?;
+        request.dict_set_item("formatVersion", "2".into())?;
+
+        // Unlike the other requests, the response is the array of pages itself
+        let response = self.request(&request)?;
+
+        Ok(IconLayout::from_plist(response))
+    }
+
+    /// Sets the home screen layout, e.g., one retrieved with `get_icon_state` from a reference device.
+    ///
+    /// The apps missing from the layout are moved to the last pages (or the App Library),
+    /// and the ones not installed are ignored by SpringBoard.
+    ///
+    /// # Parameters
+    /// - `layout`: The layout to set.
+    pub fn set_icon_state(&self, layout: &IconLayout) -> Result<(), DeviceSpringboardError> {
+        self.device.check_connected::<DeviceSpringboardError>()?;
+
+        let mut request = Plist::new_dict();
+        request.dict_set_item("command", "setIconState".into())?;
+        request.dict_set_item("iconState", layout.to_plist()?)?;
+
+        // There is no response to this request
+        let service = self.connect()?;
+        plist_service::send_plist(&service, &request)?;
+
+        Ok(())
+    }
+
+    /// Starts the springboard service on the device.
+    fn connect(&self) -> Result<ServiceClient, DeviceSpringboardError> {
+        let device = self.device.get_device();
+        let mut lockdownd = self
+            .device
+            .get_lockdownd_client::<DeviceSpringboardError>()?;
+        let service = match lockdownd.start_service(SPRINGBOARD_SERVICE, true) {
+            Ok(service) => service,
+            // tvOS and watchOS don't have the SpringBoard home screen
+            Err(err @ (LockdowndError::InvalidService | LockdowndError::MissingService)) => {
+                self.device
+                    .check_supported::<DeviceSpringboardError>(DeviceKind::has_home_screen)?;
+                return Err(err.into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(ServiceClient::new(device, service)?)
+    }
+
+    /// Sends a request on a new connection and waits for its response.
+    fn request(&self, request: &Plist) -> Result<Plist, DeviceSpringboardError> {
+        let service = self.connect()?;
+
+        plist_service::send_plist(&service, request)?;
+        plist_service::receive_plist(&service)
+    }
+
+    fn get_data(&self, response: &Plist, key: &str) -> Result<Vec<u8>, DeviceSpringboardError> {
+        let data = response
+            .dict_get_item(key)
+            .and_then(|data| data.get_data_val())
+            .map_err(|_| DeviceSpringboardError::MissingKey(key.to_string()))?;
+
+        Ok(data.into_iter().map(|byte| byte as u8).collect())
+    }
+}
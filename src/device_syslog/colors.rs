@@ -0,0 +1,96 @@
+//! Colors of the console output.
+//!
+//! The colors are only written when the `ColorMode` allows it, `Auto` follows the `NO_COLOR`
+//! convention (<https://no-color.org>) and disables them when the output isn't a terminal.
+
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+/// Enum representing when the console output is colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorMode {
+    /// Colored if the output is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    /// Always colored
+    Always,
+    /// Never colored
+    Never,
+}
+
+impl ColorMode {
+    /// Checks whether the standard output should be colored
+    pub fn use_colors(&self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Enum representing the terminal colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// One of the 256 colors of the extended palette
+    Fixed(u8),
+    /// The terminal default color, i.e., not colored
+    Default,
+}
+
+impl Color {
+    /// Wraps the text in the ANSI escape codes of the color
+    pub fn paint(&self, text: &str) -> String {
+        let code = match self {
+            Color::Black => "30".to_string(),
+            Color::Red => "31".to_string(),
+            Color::Green => "32".to_string(),
+            Color::Yellow => "33".to_string(),
+            Color::Blue => "34".to_string(),
+            Color::Magenta => "35".to_string(),
+            Color::Cyan => "36".to_string(),
+            Color::White => "37".to_string(),
+            Color::Fixed(index) => format!("38;5;{}", index),
+            Color::Default => return text.to_string(),
+        };
+
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    }
+}
+
+/// The color of each field of a log entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorScheme {
+    pub date: Color,
+    pub device: Color,
+    pub process: Color,
+    pub pid: Color,
+    pub severity: Color,
+    pub message: Color,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        ColorScheme {
+            date: Color::Blue,
+            device: Color::Green,
+            process: Color::Cyan,
+            pid: Color::Default,
+            severity: Color::Red,
+            message: Color::White,
+        }
+    }
+}
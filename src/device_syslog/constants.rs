@@ -1,3 +1,9 @@
+/// The default pattern of the syslog lines, see `DeviceSysLog::set_log_pattern` for the capture groups
+pub const DEFAULT_LOG_PATTERN: &str = r"^(?P<date>\w{3}\s+\d{1,2}\s+\d{2}:\d{2}:\d{2})\s+(?P<device>\S+)\s+(?P<process>[^\[\(<]+(?:\([^\)]+\))?)(?:\[(?P<pid>\d+)\])?\s*(?:<(?P<severity>\w+)>:\s*)?(?P<message>.+)$";
+
+/// The default number of bytes asked for on each read of the syslog service, see `DeviceSysLog::set_read_chunk_size`
+pub const DEFAULT_READ_CHUNK_SIZE: u32 = 1024;
+
 pub(crate) const QUITE: [&str; 75] = [
     "CircleJoinRequested",
     "CommCenter",
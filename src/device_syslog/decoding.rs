@@ -0,0 +1,187 @@
+//! Decoding the raw bytes of the syslog relay into log lines.
+//!
+//! The relay escapes the bytes above 0x7F the way `vis(3)` does (e.g., "\M-C\M-)" for "é"),
+//! they're turned back into the original bytes before being decoded as UTF-8.
+
+/// Enum representing what to do with the bytes of a log line that aren't valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InvalidBytesPolicy {
+    /// Replaces them with the replacement character (U+FFFD)
+    #[default]
+    Replace,
+    /// Writes them as `\xNN` escapes, so the original bytes can be recovered
+    Escape,
+}
+
+/// The longest a line can get while waiting for its end, in bytes
+const MAX_PENDING_LINE: usize = 64 * 1024;
+
+/// Accumulates the bytes received from the relay and hands over the complete lines.
+///
+/// The end of a line split between two reads is kept until the rest comes in.
+#[derive(Debug, Default)]
+pub(crate) struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Adds the received bytes, returning the raw lines they completed, without their end.
+    ///
+    /// The lines end with a new line, a null character, or both, a carriage return before the end is dropped
+    /// and the empty lines are skipped. A line growing past 64 KiB without an end is handed over as is.
+    pub(crate) fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.pending.extend_from_slice(data);
+
+        let end = match self
+            .pending
+            .iter()
+            .rposition(|&byte| byte == b'\n' || byte == 0)
+        {
+            Some(end) => end + 1,
+            // A runaway line is let through rather than kept forever
+            None if self.pending.len() >= MAX_PENDING_LINE => self.pending.len(),
+            None => return Vec::new(),
+        };
+        let complete: Vec<u8> = self.pending.drain(..end).collect();
+
+        complete
+            .split(|&byte| byte == b'\n' || byte == 0)
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+}
+
+/// Decodes a raw line of the relay, with its escaped bytes restored.
+pub(crate) fn decode_line(raw: &[u8], policy: InvalidBytesPolicy) -> String {
+    let bytes = unvis(raw);
+
+    match policy {
+        InvalidBytesPolicy::Replace => String::from_utf8_lossy(&bytes).into_owned(),
+        InvalidBytesPolicy::Escape => {
+            let mut line = String::with_capacity(bytes.len());
+            for chunk in bytes.utf8_chunks() {
+                line.push_str(chunk.valid());
+                for byte in chunk.invalid() {
+                    line.push_str(&format!("\\x{:02x}", byte));
+                }
+            }
+            line
+        }
+    }
+}
+
+/// Restores the `\M-c` (meta) and `\M^c` (meta control) escapes, the rest is kept as is.
+fn unvis(raw: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut index = 0;
+
+    while index < raw.len() {
+        match raw[index..] {
+            [b'\\', b'M', b'-', c, ..] => {
+                bytes.push(c | 0x80);
+                index += 4;
+            }
+            [b'\\', b'M', b'^', c, ..] => {
+                bytes.push((c ^ 0x40) | 0x80);
+                index += 4;
+            }
+            _ => {
+                bytes.push(raw[index]);
+                index += 1;
+            }
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_buffer_keeps_the_split_line() {
+        let mut lines = LineBuffer::default();
+
+        assert_eq!(lines.push(b"first\nsec"), vec![b"first".to_vec()]);
+        assert!(lines.push(b"on").is_empty());
+        assert_eq!(
+            lines.push(b"d\nthird\n"),
+            vec![b"second".to_vec(), b"third".to_vec()]
+        );
+    }
+
+    #[test]
+    fn line_buffer_splits_on_null_characters() {
+        let mut lines = LineBuffer::default();
+
+        assert_eq!(
+            lines.push(b"first\n\0second\0"),
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+    }
+
+    #[test]
+    fn line_buffer_drops_the_carriage_returns() {
+        let mut lines = LineBuffer::default();
+
+        assert_eq!(lines.push(b"first\r\nsecond\r"), vec![b"first".to_vec()]);
+        // The carriage return of a line split right before its end
+        assert_eq!(lines.push(b"\n"), vec![b"second".to_vec()]);
+        // Only the one before the end
+        assert_eq!(lines.push(b"a\rb\n"), vec![b"a\rb".to_vec()]);
+    }
+
+    #[test]
+    fn line_buffer_hands_over_a_runaway_line() {
+        let mut lines = LineBuffer::default();
+
+        assert!(lines.push(&[b'a'; MAX_PENDING_LINE - 1]).is_empty());
+        assert_eq!(
+            lines.push(b"b"),
+            vec![[&[b'a'; MAX_PENDING_LINE - 1][..], b"b"].concat()]
+        );
+        assert_eq!(lines.push(b"c\n"), vec![b"c".to_vec()]);
+    }
+
+    #[test]
+    fn decode_line_restores_the_meta_escapes() {
+        assert_eq!(
+            decode_line(br"caf\M-C\M-)", InvalidBytesPolicy::Replace),
+            "café"
+        );
+    }
+
+    #[test]
+    fn decode_line_restores_the_meta_control_escapes() {
+        // "€" is E2 82 AC, 0x82 being a control character once its high bit is cleared
+        assert_eq!(
+            decode_line(br"\M-b\M^B\M-, 5", InvalidBytesPolicy::Replace),
+            "€ 5"
+        );
+    }
+
+    #[test]
+    fn decode_line_keeps_the_other_backslashes() {
+        assert_eq!(
+            decode_line(br"C:\path \n \M-", InvalidBytesPolicy::Replace),
+            r"C:\path \n \M-"
+        );
+    }
+
+    #[test]
+    fn decode_line_follows_the_invalid_bytes_policy() {
+        let truncated = br"bad \M-C end";
+
+        assert_eq!(
+            decode_line(truncated, InvalidBytesPolicy::Replace),
+            "bad \u{FFFD} end"
+        );
+        assert_eq!(
+            decode_line(truncated, InvalidBytesPolicy::Escape),
+            r"bad \xc3 end"
+        );
+    }
+}
@@ -3,7 +3,8 @@ use crate::{
     errors::{DeviceNotFoundErrorTrait, LockdowndErrorTrait},
 };
 use crossbeam_channel::SendError;
-use rusty_libimobiledevice::error::LockdowndError;
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{LockdowndError, ServiceError};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -14,11 +15,29 @@ pub enum DeviceSysLogError {
     #[error("Lockdownd Error: {0}")]
     LockdowndError(#[from] LockdowndError),
 
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
     #[error("Device not found, make sure it's plugged")]
     DeviceNotFound,
 
     #[error("The device logging didn't stop to the specified duration")]
     Timeout,
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Plist Error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("The log pattern is missing the `message` capture group")]
+    InvalidLogPattern,
+
+    #[error("Unexpected response from the device: {0}")]
+    UnexpectedResponse(String),
+
+    #[error("The syslog service stopped: {0}")]
+    ServiceDied(String),
 }
 
 impl LockdowndErrorTrait for DeviceSysLogError {
@@ -1,18 +1,31 @@
-use crate::device_syslog::{constants::QUITE, LogsData};
+use crate::device_syslog::{constants::QUITE, LogLevel, LogsData};
+use chrono::NaiveDateTime;
 use regex::Regex;
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::Not};
 
 /// Enum representing different types of log filters.
 ///
 /// This enum allows the application of various filters to log entries, such as matching patterns, excluding certain processes, or triggering actions based on specific conditions.
 ///
+/// The filters don't keep any state between the entries, to only start logging once a pattern matches,
+/// use `DeviceSysLog::capture_window` instead.
+///
 /// The filters can be categorized into:
 /// - **Match**: Matches a specific regular expression.
-/// - **Trigger**: Used for triggering actions on a log, but this is not yet implemented.
+/// - **MatchRegex**: The same as `Match`, which already takes a compiled `Regex`.
+/// - **ExcludeRegex**: Filters out the entries where the pattern matches.
 /// - **Untrigger**: Matches a regular expression, if it was found, it stop the logging
 /// - **Process**: Filters logs based on the process name.
 /// - **Exclude**: Filters logs by excluding certain processes.
 /// - **OneShot**: Doesn't log anything up until it finds the pattern then it stops
+/// - **MinSeverity**: Only logs the entries with a severity equal or higher than the given level,
+///   entries without a recognized severity are logged. It always applies on the severity regardless of the `FilterPart`
+/// - **Pid**: Only logs the entries coming from the given process IDs, entries without a PID are ignored.
+///   It always applies on the PID regardless of the `FilterPart`
+/// - **Since**: Only logs the entries dated at or after the given date, entries without a parsable date are logged.
+///   It always applies on the date regardless of the `FilterPart`
+/// - **Until**: Only logs the entries dated at or before the given date, entries without a parsable date are logged.
+///   It always applies on the date regardless of the `FilterPart`
 /// - **Quiet**: Filters out noisy process defined by `libimobiledevice` list.
 /// - **KernelOnly**: Used to only log the kernel.
 /// - **NoKernel**: Used to log everything but kernel
@@ -20,11 +33,16 @@ use std::collections::HashSet;
 #[derive(Debug, Clone)]
 pub enum LogFilter {
     Match(Regex),
-    Trigger(Regex),
+    MatchRegex(Regex),
+    ExcludeRegex(Regex),
     Untrigger(Regex),
     Process(HashSet<String>),
     Exclude(HashSet<String>),
     OneShot(Regex),
+    MinSeverity(LogLevel),
+    Pid(HashSet<u32>),
+    Since(NaiveDateTime),
+    Until(NaiveDateTime),
     Quiet,
     KernelOnly,
     NoKernel,
@@ -58,6 +76,87 @@ pub enum LogAction {
     Break,
 }
 
+/// A composition of multiple `LogFilter`s using AND/OR/NOT semantics.
+///
+/// Each filter in the chain is paired with the `FilterPart` it applies to, and a chain can be inverted
+/// with `!chain`, which allows building conditions like "process is MyApp AND severity >= Error":
+///
+/// ```ignore
+/// let chain = LogFilterChain::new(LogFilter::Process(["MyApp".into()].into()), FilterPart::Process)
+///     .and(LogFilter::MinSeverity(LogLevel::Error));
+/// ```
+///
+/// A `LogAction::Break` from any evaluated filter always stops the logging, regardless of the composition.
+#[derive(Debug, Clone)]
+pub enum LogFilterChain {
+    Filter(LogFilter, FilterPart),
+    And(Box<LogFilterChain>, Box<LogFilterChain>),
+    Or(Box<LogFilterChain>, Box<LogFilterChain>),
+    Not(Box<LogFilterChain>),
+}
+
+impl LogFilterChain {
+    /// Creates a chain out of a single filter applied on the given part
+    pub fn new(filter: LogFilter, filter_part: FilterPart) -> LogFilterChain {
+        LogFilterChain::Filter(filter, filter_part)
+    }
+
+    /// Logs the entry only if both this chain and `other` log it
+    pub fn and(self, other: impl Into<LogFilterChain>) -> LogFilterChain {
+        LogFilterChain::And(Box::new(self), Box::new(other.into()))
+    }
+
+    /// Logs the entry if either this chain or `other` logs it
+    pub fn or(self, other: impl Into<LogFilterChain>) -> LogFilterChain {
+        LogFilterChain::Or(Box::new(self), Box::new(other.into()))
+    }
+
+    /// Applies the whole chain to the given log entry.
+    ///
+    /// The evaluation is short-circuited, `and` doesn't evaluate its right side if the left side ignored the entry,
+    /// and `or` doesn't evaluate it if the left side logged it.
+    pub fn apply(&self, logs_data: &LogsData) -> LogAction {
+        match self {
+            LogFilterChain::Filter(filter, filter_part) => filter.apply(logs_data, filter_part),
+            LogFilterChain::And(left, right) => match left.apply(logs_data) {
+                LogAction::Log => right.apply(logs_data),
+                action => action,
+            },
+            LogFilterChain::Or(left, right) => match left.apply(logs_data) {
+                LogAction::Continue => right.apply(logs_data),
+                action => action,
+            },
+            LogFilterChain::Not(chain) => match chain.apply(logs_data) {
+                LogAction::Log => LogAction::Continue,
+                LogAction::Continue => LogAction::Log,
+                LogAction::Break => LogAction::Break,
+            },
+        }
+    }
+}
+
+impl Not for LogFilterChain {
+    type Output = LogFilterChain;
+
+    /// Inverts the result of this chain, `LogAction::Break` is kept as is
+    fn not(self) -> Self::Output {
+        LogFilterChain::Not(Box::new(self))
+    }
+}
+
+impl From<LogFilter> for LogFilterChain {
+    /// Applies the filter on all the parts of the log entry
+    fn from(filter: LogFilter) -> Self {
+        LogFilterChain::Filter(filter, FilterPart::All)
+    }
+}
+
+impl From<(LogFilter, FilterPart)> for LogFilterChain {
+    fn from((filter, filter_part): (LogFilter, FilterPart)) -> Self {
+        LogFilterChain::Filter(filter, filter_part)
+    }
+}
+
 impl LogFilter {
     /// Applies the filter to the given log entry based on the specified part of the log.
     ///
@@ -76,6 +175,34 @@ impl LogFilter {
     /// - `LogAction::Continue` if the log is ignored.
     /// - `LogAction::Break` if it must stop the logging
     pub fn apply(&self, logs_data: &LogsData, filter_part: &FilterPart) -> LogAction {
+        match self {
+            LogFilter::MinSeverity(min_level) => {
+                return match logs_data.level() {
+                    Some(level) if level < *min_level => LogAction::Continue,
+                    _ => LogAction::Log,
+                };
+            }
+            LogFilter::Pid(pids) => {
+                return match logs_data.pid.and_then(|pid| pid.parse::<u32>().ok()) {
+                    Some(pid) if pids.contains(&pid) => LogAction::Log,
+                    _ => LogAction::Continue,
+                };
+            }
+            LogFilter::Since(since) => {
+                return match logs_data.timestamp() {
+                    Some(date) if date < *since => LogAction::Continue,
+                    _ => LogAction::Log,
+                };
+            }
+            LogFilter::Until(until) => {
+                return match logs_data.timestamp() {
+                    Some(date) if date > *until => LogAction::Continue,
+                    _ => LogAction::Log,
+                };
+            }
+            _ => (),
+        }
+
         match filter_part {
             FilterPart::All => {
                 return apply_match_on_part(
@@ -113,7 +240,7 @@ impl LogFilter {
         /// Returns a `LogAction` based on the filter's action.
         fn apply_match_on_part(filter: &LogFilter, parts: &[Option<&str>]) -> LogAction {
             match filter {
-                LogFilter::Match(pattern) => {
+                LogFilter::Match(pattern) | LogFilter::MatchRegex(pattern) => {
                     for part in parts.iter().flatten() {
                         if pattern.is_match(part) {
                             return LogAction::Log;
@@ -121,7 +248,14 @@ impl LogFilter {
                     }
                     LogAction::Continue
                 }
-                LogFilter::Trigger(_) => todo!(),
+                LogFilter::ExcludeRegex(pattern) => {
+                    for part in parts.iter().flatten() {
+                        if pattern.is_match(part) {
+                            return LogAction::Continue;
+                        }
+                    }
+                    LogAction::Log
+                }
                 LogFilter::Untrigger(pattern) => {
                     for part in parts.iter().flatten() {
                         if pattern.is_match(part) {
@@ -158,6 +292,11 @@ impl LogFilter {
                     }
                     LogAction::Continue
                 }
+                // Handled before looking at the parts
+                LogFilter::MinSeverity(_)
+                | LogFilter::Pid(_)
+                | LogFilter::Since(_)
+                | LogFilter::Until(_) => LogAction::Log,
                 LogFilter::Quiet => {
                     for part in parts.iter().flatten() {
                         if QUITE.contains(part) {
@@ -0,0 +1,83 @@
+//! Layouts of the log entries written by the sinks.
+//!
+//! The built-in formatters cover the plain, colored and JSON layouts, any other layout
+//! (e.g., RFC5424 or logfmt) can be written by implementing `LogFormatter`, then used with
+//! `StdoutSink::with_formatter` or `FileSink::with_formatter`.
+
+use crate::device_syslog::{ColorScheme, LogsData};
+use std::{fmt, sync::Arc};
+
+/// Trait for types that turn a log entry into a line of text.
+///
+/// Closures taking a `&LogsData` and returning a `String` implement this trait as well.
+pub trait LogFormatter: Send + Sync {
+    /// Formats a single log entry, without the trailing newline.
+    fn format(&self, logs_data: &LogsData) -> String;
+}
+
+impl<F> LogFormatter for F
+where
+    F: Fn(&LogsData) -> String + Send + Sync,
+{
+    fn format(&self, logs_data: &LogsData) -> String {
+        self(logs_data)
+    }
+}
+
+/// The `[date] device process [pid] <severity>: message` layout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainFormatter;
+
+impl LogFormatter for PlainFormatter {
+    fn format(&self, logs_data: &LogsData) -> String {
+        logs_data.get_parsed_log()
+    }
+}
+
+/// The plain layout, with the fields colored for the terminal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColoredFormatter {
+    color_scheme: ColorScheme,
+}
+
+impl ColoredFormatter {
+    pub fn new(color_scheme: ColorScheme) -> ColoredFormatter {
+        ColoredFormatter { color_scheme }
+    }
+}
+
+impl LogFormatter for ColoredFormatter {
+    fn format(&self, logs_data: &LogsData) -> String {
+        logs_data.get_parsed_log_colored(&self.color_scheme)
+    }
+}
+
+/// One JSON object per entry, see `DeviceSysLog::log_to_file_json` for the fields.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormatter;
+
+impl LogFormatter for JsonFormatter {
+    fn format(&self, logs_data: &LogsData) -> String {
+        logs_data.get_parsed_log_json()
+    }
+}
+
+/// A formatter shared between the clones of a sink.
+#[derive(Clone)]
+pub(crate) struct SharedFormatter(Arc<dyn LogFormatter>);
+
+impl SharedFormatter {
+    pub(crate) fn new(formatter: impl LogFormatter + 'static) -> SharedFormatter {
+        SharedFormatter(Arc::new(formatter))
+    }
+
+    pub(crate) fn format(&self, logs_data: &LogsData) -> String {
+        self.0.format(logs_data)
+    }
+}
+
+impl fmt::Debug for SharedFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LogFormatter")
+    }
+}
@@ -0,0 +1,95 @@
+use crate::device_syslog::LoggerCommand;
+use crossbeam_channel::Sender;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+/// The workers whose handles were detached, stopped and waited for when their `DeviceSysLog` is dropped.
+pub(crate) type DetachedWorkers = Arc<Mutex<Vec<(Sender<LoggerCommand>, JoinHandle<()>)>>>;
+
+/// Handle to a running logging thread, returned by the `log_to_*` functions.
+///
+/// Dropping the handle stops the logging and waits for the thread to finish,
+/// so it must be kept alive for as long as the logs are wanted, or `detach`ed.
+#[derive(Debug)]
+pub struct SysLogHandle {
+    command_sender: Sender<LoggerCommand>,
+    handle: Option<JoinHandle<()>>,
+    dropped_lines: Arc<AtomicU64>,
+    detached: DetachedWorkers,
+}
+
+impl SysLogHandle {
+    pub(crate) fn new(
+        command_sender: Sender<LoggerCommand>,
+        handle: JoinHandle<()>,
+        dropped_lines: Arc<AtomicU64>,
+        detached: DetachedWorkers,
+    ) -> SysLogHandle {
+        SysLogHandle {
+            command_sender,
+            handle: Some(handle),
+            dropped_lines,
+            detached,
+        }
+    }
+
+    /// Returns the number of log entries dropped because the bounded queue was full.
+    ///
+    /// It's always 0 if no bounded queue was set, see `DeviceSysLog::set_bounded_queue`.
+    pub fn dropped_lines(&self) -> u64 {
+        self.dropped_lines.load(Ordering::Relaxed)
+    }
+
+    /// Stops the logging and waits for the thread to finish.
+    ///
+    /// Once this returns, no more logs will be delivered to the callback or sink.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    /// Waits for the logging to finish by itself (e.g., timeout or a `Break` from the filters).
+    pub fn join(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Checks whether the logging thread has finished.
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    /// Lets the logging thread run in the background without being tied to this handle.
+    ///
+    /// The logging can still be stopped with `DeviceSysLog::stop_logging`, and the thread is handed over
+    /// to the `DeviceSysLog` that started it, which stops it and waits for it to finish once dropped.
+    pub fn detach(mut self) {
+        if let Some(handle) = self.handle.take() {
+            if let Ok(mut detached) = self.detached.lock() {
+                detached.push((self.command_sender.clone(), handle));
+            }
+        }
+    }
+
+    fn stop_and_join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            // The thread may have already finished and dropped its receiver
+            let _ = self.command_sender.send(LoggerCommand::StopLogging);
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SysLogHandle {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
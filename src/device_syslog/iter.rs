@@ -0,0 +1,75 @@
+//! Pull-based consumption of the device logs.
+//!
+//! Instead of handing a callback to the logging thread, the entries can be consumed
+//! with a normal `for` loop (`LogsIter`), or as a `Stream` with the `async` feature (`LogsStream`).
+
+use crate::device_syslog::{errors::DeviceSysLogError, LogsDataOwned, SysLogHandle};
+use crossbeam_channel::Receiver;
+
+/// Iterator over the logs of a device, created with `DeviceSysLog::iter`.
+///
+/// The iterator blocks until the next entry is available, and ends once the logging stops.
+/// Dropping it stops the logging.
+#[derive(Debug)]
+pub struct LogsIter {
+    logs_receiver: Receiver<Result<LogsDataOwned, DeviceSysLogError>>,
+    _handle: Option<SysLogHandle>,
+}
+
+impl LogsIter {
+    pub(crate) fn new(
+        logs_receiver: Receiver<Result<LogsDataOwned, DeviceSysLogError>>,
+        handle: Option<SysLogHandle>,
+    ) -> LogsIter {
+        LogsIter {
+            logs_receiver,
+            _handle: handle,
+        }
+    }
+}
+
+impl Iterator for LogsIter {
+    type Item = Result<LogsDataOwned, DeviceSysLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.logs_receiver.recv().ok()
+    }
+}
+
+/// Stream over the logs of a device, created with `DeviceSysLog::stream`.
+///
+/// The stream ends once the logging stops, dropping it stops the logging.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct LogsStream {
+    logs_receiver:
+        futures_channel::mpsc::UnboundedReceiver<Result<LogsDataOwned, DeviceSysLogError>>,
+    _handle: Option<SysLogHandle>,
+}
+
+#[cfg(feature = "async")]
+impl LogsStream {
+    pub(crate) fn new(
+        logs_receiver: futures_channel::mpsc::UnboundedReceiver<
+            Result<LogsDataOwned, DeviceSysLogError>,
+        >,
+        handle: Option<SysLogHandle>,
+    ) -> LogsStream {
+        LogsStream {
+            logs_receiver,
+            _handle: handle,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for LogsStream {
+    type Item = Result<LogsDataOwned, DeviceSysLogError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        std::pin::Pin::new(&mut self.logs_receiver).poll_next(cx)
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Enum representing the severity level of a log entry.
+///
+/// The levels are ordered from the least to the most severe, so they can be compared:
+/// `Debug < Info < Notice < Warning < Error < Fault`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Fault,
+}
+
+impl FromStr for LogLevel {
+    type Err = ();
+
+    /// Parses the severity as it appears in the syslog (e.g., "Notice", "Error"), case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "notice" => Ok(LogLevel::Notice),
+            "warning" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            "fault" | "critical" | "alert" | "emergency" => Ok(LogLevel::Fault),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "Debug"),
+            LogLevel::Info => write!(f, "Info"),
+            LogLevel::Notice => write!(f, "Notice"),
+            LogLevel::Warning => write!(f, "Warning"),
+            LogLevel::Error => write!(f, "Error"),
+            LogLevel::Fault => write!(f, "Fault"),
+        }
+    }
+}
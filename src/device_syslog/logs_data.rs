@@ -1,4 +1,7 @@
+use crate::device_syslog::{constants::DEFAULT_LOG_PATTERN, timestamp, ColorScheme, LogLevel};
+use chrono::NaiveDateTime;
 use regex::{Captures, Regex};
+use std::sync::OnceLock;
 
 /// Struct to store parsed log data
 ///
@@ -25,9 +28,154 @@ pub struct LogsData<'a> {
 
     /// The actual log message
     pub message: &'a str,
+
+    /// The UDID of the device the log entry was received from, set by the logging thread
+    pub udid: Option<&'a str>,
+}
+
+/// An owned version of `LogsData`, which can be stored or sent across threads
+///
+/// It's created from a `LogsData` with `LogsData::to_owned`
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogsDataOwned {
+    date: String,
+    device: String,
+    process: String,
+    pid: Option<String>,
+    severity: Option<String>,
+    message: String,
+    udid: Option<String>,
+}
+
+impl LogsDataOwned {
+    /// The date the log entry was created (e.g., "Dec 20 14:22:15")
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    /// The device name or identifier that generated the log entry
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    /// The process name that generated the log entry
+    pub fn process(&self) -> &str {
+        &self.process
+    }
+
+    /// The process ID associated with the log entry, if available
+    pub fn pid(&self) -> Option<&str> {
+        self.pid.as_deref()
+    }
+
+    /// The severity level of the log entry (e.g., "error", "info"), if available
+    pub fn severity(&self) -> Option<&str> {
+        self.severity.as_deref()
+    }
+
+    /// The actual log message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The UDID of the device the log entry was received from
+    pub fn udid(&self) -> Option<&str> {
+        self.udid.as_deref()
+    }
+
+    /// Returns the parsed severity level of the log entry, if it was found and recognized
+    pub fn level(&self) -> Option<LogLevel> {
+        self.as_logs_data().level()
+    }
+
+    /// Returns the parsed date of the log entry, see `LogsData::timestamp`
+    pub fn timestamp(&self) -> Option<NaiveDateTime> {
+        self.as_logs_data().timestamp()
+    }
+
+    /// Borrows the entry as a `LogsData`, to be used with the filters and sinks
+    pub fn as_logs_data(&self) -> LogsData<'_> {
+        LogsData {
+            date: &self.date,
+            device: &self.device,
+            process: &self.process,
+            pid: self.pid.as_deref(),
+            severity: self.severity.as_deref(),
+            message: &self.message,
+            udid: self.udid.as_deref(),
+        }
+    }
+}
+
+impl From<&LogsData<'_>> for LogsDataOwned {
+    fn from(value: &LogsData<'_>) -> Self {
+        LogsDataOwned {
+            date: value.date.to_owned(),
+            device: value.device.to_owned(),
+            process: value.process.to_owned(),
+            pid: value.pid.map(str::to_owned),
+            severity: value.severity.map(str::to_owned),
+            message: value.message.to_owned(),
+            udid: value.udid.map(str::to_owned),
+        }
+    }
 }
 
 impl<'a> LogsData<'a> {
+    /// The date the log entry was created (e.g., "Dec 20 14:22:15")
+    pub fn date(&self) -> &'a str {
+        self.date
+    }
+
+    /// The device name or identifier that generated the log entry
+    pub fn device(&self) -> &'a str {
+        self.device
+    }
+
+    /// The process name that generated the log entry
+    pub fn process(&self) -> &'a str {
+        self.process
+    }
+
+    /// The process ID associated with the log entry, if available
+    pub fn pid(&self) -> Option<&'a str> {
+        self.pid
+    }
+
+    /// The severity level of the log entry (e.g., "error", "info"), if available
+    pub fn severity(&self) -> Option<&'a str> {
+        self.severity
+    }
+
+    /// The actual log message
+    pub fn message(&self) -> &'a str {
+        self.message
+    }
+
+    /// The UDID of the device the log entry was received from
+    pub fn udid(&self) -> Option<&'a str> {
+        self.udid
+    }
+
+    /// Copies the entry into a `LogsDataOwned`, which doesn't borrow from the logging buffer
+    pub fn to_owned(&self) -> LogsDataOwned {
+        LogsDataOwned::from(self)
+    }
+
+    /// Returns the parsed severity level of the log entry, if it was found and recognized
+    pub fn level(&self) -> Option<LogLevel> {
+        self.severity.and_then(|s| s.parse().ok())
+    }
+
+    /// Returns the parsed date of the log entry, in the local time
+    ///
+    /// The raw date doesn't have a year, so it's inferred from the current date,
+    /// see `timestamp::parse_log_date_relative_to` for more control over it.
+    pub fn timestamp(&self) -> Option<NaiveDateTime> {
+        timestamp::parse_log_date(self.date)
+    }
+
     /// Returns a formatted string representation of the log entry
     ///
     /// This method formats the log data into a structured string like:
@@ -47,29 +195,66 @@ impl<'a> LogsData<'a> {
         )
     }
 
+    /// Returns a JSON object representation of the log entry
+    ///
+    /// The object is written on a single line, with the `pid` as a number and the missing fields as `null`:
+    /// `{"date":"...","device":"...","process":"...","pid":123,"severity":"...","message":"...","udid":"..."}`
+    pub(crate) fn get_parsed_log_json(&self) -> String {
+        format!(
+            r#"{{"date":{},"device":{},"process":{},"pid":{},"severity":{},"message":{},"udid":{}}}"#,
+            json_string(self.date),
+            json_string(self.device),
+            json_string(self.process),
+            self.pid
+                .and_then(|pid| pid.parse::<u32>().ok())
+                .map_or("null".to_string(), |pid| pid.to_string()),
+            self.severity.map_or("null".to_string(), json_string),
+            json_string(self.message),
+            self.udid.map_or("null".to_string(), json_string)
+        )
+    }
+
     /// Returns a colored, formatted string representation of the log entry
     ///
-    /// This method formats the log data into a structured string with ANSI color codes for terminal output:
-    /// - Date in blue
-    /// - Device in green
-    /// - Process in cyan
-    /// - PID in red (if available)
-    /// - Severity in red (if available)
-    /// - Message in white
+    /// This method formats the log data like `get_parsed_log`, with the ANSI color codes of the given scheme
+    /// around each field for terminal output.
     ///
-    pub(crate) fn get_parsed_log_colored(&self) -> String {
+    pub(crate) fn get_parsed_log_colored(&self, scheme: &ColorScheme) -> String {
         format!(
-            "[\x1b[34m{}\x1b[0m] \x1b[32m{}\x1b[0m \x1b[36m{}\x1b[0m [{}] <\x1b[31m{}\x1b[0m>: \x1b[37m{}\x1b[0m",
-            self.date,
-            self.device,
-            self.process,
-            self.pid.map_or("None", |d: &str| d),
-            self.severity.map_or("None", |d: &str| d),
-            self.message
+            "[{}] {} {} [{}] <{}>: {}",
+            scheme.date.paint(self.date),
+            scheme.device.paint(self.device),
+            scheme.process.paint(self.process),
+            scheme.pid.paint(self.pid.map_or("None", |d: &str| d)),
+            scheme
+                .severity
+                .paint(self.severity.map_or("None", |d: &str| d)),
+            scheme.message.paint(self.message)
         )
     }
 }
 
+/// Helper function to quote and escape a string as a JSON string literal
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
 /// Helper function to process a log line and extract structured data
 ///
 /// This function attempts to match a log line against a regular expression and extract fields like `date`,
@@ -91,16 +276,57 @@ fn process_log_line<'a>(line: &'a str, log_regex: &Regex) -> Option<LogsData<'a>
         captures.name(name).map_or(default, |m| m.as_str())
     }
 
-    log_regex.captures(line).map(|captures| LogsData {
+    // Only the first line of a multi-line entry (e.g., a backtrace) has the header
+    let first_line = line.split('\n').next().unwrap_or(line);
+
+    log_regex.captures(first_line).map(|captures| LogsData {
         date: get_capture(&captures, "date", "unknown"),
         device: get_capture(&captures, "device", "unknown"),
         process: get_capture(&captures, "process", "unknown"),
         pid: captures.name("pid").map(|m| m.as_str()), // Optional field
         severity: captures.name("severity").map(|m| m.as_str()), // Optional field
-        message: get_capture(&captures, "message", "unknown"),
+        // The continuation lines belong to the message
+        message: match captures.name("message") {
+            Some(message) if first_line.len() < line.len() => &line[message.start()..],
+            Some(message) => message.as_str(),
+            None => "unknown",
+        },
+        udid: None,
     })
 }
 
+/// Compiled once, it's by far the most expensive part of the parsing
+fn default_log_regex() -> &'static Regex {
+    static LOG_REGEX: OnceLock<Regex> = OnceLock::new();
+    LOG_REGEX.get_or_init(|| Regex::new(DEFAULT_LOG_PATTERN).expect("Couldn't create a new regex"))
+}
+
+/// Checks whether a line starts a new entry, the others continue the previous one.
+pub(crate) fn is_entry_start(line: &str, log_regex: Option<&Regex>) -> bool {
+    log_regex
+        .unwrap_or_else(|| default_log_regex())
+        .is_match(line)
+}
+
+impl<'a> LogsData<'a> {
+    /// Parses a raw log line with a custom pattern
+    ///
+    /// The pattern uses the same named capture groups as the default one (`date`, `device`, `process`, `pid`,
+    /// `severity` and `message`), the missing ones are set to "unknown" or `None`.
+    ///
+    /// # Arguments
+    ///
+    /// - `line`: The raw log line as a string slice.
+    /// - `log_regex`: The pattern to parse the line with.
+    ///
+    /// # Returns
+    ///
+    /// A `LogsData` instance containing the parsed log information if the line matched, or a default values.
+    pub fn parse_with(line: &'a str, log_regex: &Regex) -> LogsData<'a> {
+        process_log_line(line, log_regex).unwrap_or_default()
+    }
+}
+
 impl<'a> From<&'a str> for LogsData<'a> {
     /// Converts a raw log line into a `LogsData` instance
     ///
@@ -116,8 +342,6 @@ impl<'a> From<&'a str> for LogsData<'a> {
     ///
     /// A `LogsData` instance containing the parsed log information if found, or a default values.
     fn from(value: &'a str) -> Self {
-        let log_regex = Regex::new(r"^(?P<date>\w{3}\s+\d{1,2}\s+\d{2}:\d{2}:\d{2})\s+(?P<device>\S+)\s+(?P<process>[^\[\(<]+(?:\([^\)]+\))?)(?:\[(?P<pid>\d+)\])?\s*(?:<(?P<severity>\w+)>:\s*)?(?P<message>.+)$").expect("Couldn't create a new regex");
-
-        process_log_line(value, &log_regex).unwrap_or_default()
+        LogsData::parse_with(value, default_log_regex())
     }
 }
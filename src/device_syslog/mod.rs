@@ -7,25 +7,71 @@
 //! - Start and stop logging from devices.
 //! - Filter logs based on specific criteria.
 //! - Output logs to custom destinations (stdout, files, or user-defined callbacks).
+//! - Keeps the multi-line entries (e.g., backtraces) together, and the non UTF-8 bytes recoverable.
 
+pub mod colors;
 pub mod constants;
+pub mod decoding;
 pub(crate) mod errors;
 pub mod filters;
+pub mod formatter;
+pub mod handle;
+pub mod iter;
+pub mod log_level;
 pub mod logs_data;
-pub use filters::{FilterPart, LogAction, LogFilter};
-pub use logs_data::LogsData;
+pub mod os_trace;
+pub mod queue;
+pub mod reconnect;
+pub mod ring_buffer;
+pub mod rotation;
+pub mod sinks;
+pub mod stats;
+pub mod timestamp;
+pub mod window;
+pub use colors::{Color, ColorMode, ColorScheme};
+pub use decoding::InvalidBytesPolicy;
+pub use filters::{FilterPart, LogAction, LogFilter, LogFilterChain};
+pub use formatter::{ColoredFormatter, JsonFormatter, LogFormatter, PlainFormatter};
+pub use handle::SysLogHandle;
+pub use iter::LogsIter;
+#[cfg(feature = "async")]
+pub use iter::LogsStream;
+pub use log_level::LogLevel;
+pub use logs_data::{LogsData, LogsDataOwned};
+pub use os_trace::{OsTraceEntry, OsTraceLevel};
+pub use queue::{OverflowPolicy, QueueConfig};
+pub use reconnect::{ReconnectEvent, ReconnectPolicy};
+pub use ring_buffer::LogRingBuffer;
+pub use rotation::{Compression, RotationPolicy};
+#[cfg(feature = "tracing")]
+pub use sinks::TracingSink;
+pub use sinks::{ChannelSink, FileFormat, FileSink, LogSink, StdoutSink};
+pub use stats::{LogStats, LogStatsSnapshot};
+pub use window::WindowEnd;
 
+use constants::DEFAULT_READ_CHUNK_SIZE;
+use decoding::LineBuffer;
 use errors::DeviceSysLogError;
+use handle::DetachedWorkers;
+use queue::LogQueue;
+use reconnect::ReconnectConfig;
+use regex::Regex;
+use window::WindowCapture;
 
-use crate::{device::DeviceClient, devices_collection::SingleDevice};
+use crate::{
+    device::DeviceClient,
+    devices_collection::{DeviceGroup, SingleDevice},
+};
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use rusty_libimobiledevice::service::ServiceClient;
+use rusty_libimobiledevice::{
+    error::ServiceError, idevice, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
 use std::{
     fs::{self, OpenOptions},
-    io::Write,
+    io::BufWriter,
     path::Path,
-    sync::Arc,
-    thread::{self, JoinHandle},
+    sync::{atomic::AtomicU64, Arc, Mutex},
+    thread,
     time::{Duration, Instant},
 };
 
@@ -44,27 +90,47 @@ pub enum LoggerCommand {
 ///
 /// `DeviceSysLog` is a high-level interface for interacting with the syslog service of iOS devices.
 ///
+/// Dropping it stops the logging threads whose handles were detached, and waits for them to finish,
+/// the other threads are tied to their `SysLogHandle`.
+///
 /// # Type Parameters
 /// - `T`: Determines whether the logger operates on a single device or a group of devices.
 #[derive(Debug)]
 pub struct DeviceSysLog<T> {
     devices: Arc<DeviceClient<T>>,
-    sender: Sender<LoggerCommand>,
-    receiver: Arc<Receiver<LoggerCommand>>,
-    filter: Arc<LogFilter>,
-    filter_part: Arc<FilterPart>,
+    workers: Mutex<Vec<Sender<LoggerCommand>>>,
+    detached: DetachedWorkers,
+    filter: Arc<LogFilterChain>,
+    queue_config: Option<QueueConfig>,
+    reconnect: ReconnectConfig,
+    color_mode: ColorMode,
+    color_scheme: ColorScheme,
+    stats: Option<LogStats>,
+    ring_buffer: Option<LogRingBuffer>,
+    log_pattern: Option<Arc<Regex>>,
+    read_chunk_size: u32,
+    multiline: bool,
+    invalid_bytes: InvalidBytesPolicy,
     _phantom: std::marker::PhantomData<T>,
 }
 
 impl<T> DeviceSysLog<T> {
     pub fn new(devices: DeviceClient<T>) -> DeviceSysLog<T> {
-        let (tx, rx) = unbounded();
         DeviceSysLog {
             devices: Arc::new(devices),
-            sender: tx,
-            receiver: Arc::new(rx),
-            filter: Arc::new(LogFilter::Nothing),
-            filter_part: Arc::new(FilterPart::All),
+            workers: Mutex::new(Vec::new()),
+            detached: Arc::new(Mutex::new(Vec::new())),
+            filter: Arc::new(LogFilter::Nothing.into()),
+            queue_config: None,
+            reconnect: ReconnectConfig::default(),
+            color_mode: ColorMode::default(),
+            color_scheme: ColorScheme::default(),
+            stats: None,
+            ring_buffer: None,
+            log_pattern: None,
+            read_chunk_size: DEFAULT_READ_CHUNK_SIZE,
+            multiline: true,
+            invalid_bytes: InvalidBytesPolicy::default(),
             _phantom: std::marker::PhantomData::<T>,
         }
     }
@@ -73,102 +139,510 @@ impl<T> DeviceSysLog<T> {
     ///
     /// This is useful when creating multiple DeviceSysLog from a single client
     pub fn new_from_arc(devices: Arc<DeviceClient<T>>) -> DeviceSysLog<T> {
-        let (tx, rx) = unbounded();
         DeviceSysLog {
             devices,
-            sender: tx,
-            receiver: Arc::new(rx),
-            filter: Arc::new(LogFilter::Nothing),
-            filter_part: Arc::new(FilterPart::All),
+            workers: Mutex::new(Vec::new()),
+            detached: Arc::new(Mutex::new(Vec::new())),
+            filter: Arc::new(LogFilter::Nothing.into()),
+            queue_config: None,
+            reconnect: ReconnectConfig::default(),
+            color_mode: ColorMode::default(),
+            color_scheme: ColorScheme::default(),
+            stats: None,
+            ring_buffer: None,
+            log_pattern: None,
+            read_chunk_size: DEFAULT_READ_CHUNK_SIZE,
+            multiline: true,
+            invalid_bytes: InvalidBytesPolicy::default(),
             _phantom: std::marker::PhantomData::<T>,
         }
     }
-}
 
-impl DeviceSysLog<SingleDevice> {
-    /// Internal method to start the logging service on a separate thread with timeout.
+    /// Sets the log filter for this `DeviceSysLog` instance.
+    ///
+    /// # Parameters
+    /// - `filter`: The filter logic to apply to logs.
+    /// - `filter_part`: Specifies which parts of the log to apply the filter on.
+    pub fn set_filter(&mut self, filter: LogFilter, filter_part: FilterPart) {
+        self.filter = Arc::new(LogFilterChain::new(filter, filter_part));
+    }
+
+    /// Puts a bounded queue between the logging thread and the callback or sink.
+    ///
+    /// The callback then runs on its own thread, so it can't slow down the reading of the logs,
+    /// and once the queue is full, the `overflow_policy` decides what happens to the new entries.
+    /// The number of dropped entries is available with `SysLogHandle::dropped_lines`.
+    ///
+    /// This applies to the logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `capacity`: The maximum number of entries waiting for the callback.
+    /// - `overflow_policy`: What to do when the queue is full.
+    pub fn set_bounded_queue(&mut self, capacity: usize, overflow_policy: OverflowPolicy) {
+        self.queue_config = Some(QueueConfig {
+            capacity,
+            overflow_policy,
+        });
+    }
+
+    /// Sets how to reconnect when the connection to the syslog service drops
+    /// (e.g., the device slept, locked, or was briefly unplugged), `None` stops the logging instead.
+    ///
+    /// By default, it reconnects every second, forever.
+    /// This applies to the logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `policy`: The reconnection policy, or `None` to disable it.
+    pub fn set_reconnect(&mut self, policy: Option<ReconnectPolicy>) {
+        self.reconnect.policy = policy;
+    }
+
+    /// Sets a callback that is called on the connection events (disconnected, reconnected, ...).
+    ///
+    /// The callback runs on the logging thread, so it should return quickly.
+    /// This applies to the logging started after calling it.
     ///
     /// # Parameters
+    /// - `callback`: A function to handle the `ReconnectEvent`s, along with the UDID of the device.
+    pub fn on_reconnect_event<F>(&mut self, callback: F)
+    where
+        F: Fn(&str, ReconnectEvent) + 'static + Sync + Send,
+    {
+        self.reconnect.callback = Some(Arc::new(callback));
+    }
+
+    /// Sets a callback that is called when the logging stops on an error, e.g., when the service couldn't
+    /// be started, or the connection was lost and not got back (see `set_reconnect`).
+    ///
+    /// Without it, the logs just stop coming, so it's the place to show that the device is disconnected.
+    /// The callback runs on the logging thread, this applies to the logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `callback`: A function to handle the error, along with the UDID of the device.
+    pub fn on_error<F>(&mut self, callback: F)
+    where
+        F: Fn(&str, &DeviceSysLogError) + 'static + Sync + Send,
+    {
+        self.reconnect.on_error = Some(Arc::new(callback));
+    }
+
+    /// Sets how many bytes are asked for on each read of the syslog service (1024 by default).
+    ///
+    /// The lines split between two reads are put back together either way,
+    /// a bigger chunk means fewer reads on busy devices.
+    /// This applies to the logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `size`: The number of bytes per read, at least 1.
+    pub fn set_read_chunk_size(&mut self, size: u32) {
+        self.read_chunk_size = size.max(1);
+    }
+
+    /// Enables or disables the joining of the multi-line entries (enabled by default).
+    ///
+    /// The lines without the header of an entry (e.g., the frames of a backtrace) are appended
+    /// to the message of the previous entry, which is then delivered once the next one starts,
+    /// or once the device is quiet for a moment. Disabled, each line is an entry on its own.
+    /// This applies to the logging started after calling it.
+    pub fn set_multiline(&mut self, enabled: bool) {
+        self.multiline = enabled;
+    }
+
+    /// Sets what to do with the bytes of the logs that aren't valid UTF-8 (replaced by default).
+    ///
+    /// This applies to the logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `policy`: Whether to replace or escape the invalid bytes.
+    pub fn set_invalid_bytes_policy(&mut self, policy: InvalidBytesPolicy) {
+        self.invalid_bytes = policy;
+    }
+
+    /// Sets when the console output of the `log_to_stdout*` functions is colored.
+    ///
+    /// By default (`ColorMode::Auto`), it's colored unless `NO_COLOR` is set or the output isn't a terminal.
+    ///
+    /// # Parameters
+    /// - `color_mode`: When to color the output.
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) {
+        self.color_mode = color_mode;
+    }
+
+    /// Sets the colors of each field in the console output of the `log_to_stdout*` functions.
+    ///
+    /// # Parameters
+    /// - `color_scheme`: The color of each field.
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.color_scheme = color_scheme;
+    }
+
+    /// Creates the sink of the `log_to_stdout*` functions, following the colors settings
+    fn stdout_sink(&self) -> StdoutSink {
+        StdoutSink::with_color_scheme(self.color_mode, self.color_scheme)
+    }
+
+    /// Counts every received entry in the given collector, before the filters are applied.
+    ///
+    /// Keep a clone of `stats` to read the counts while logging, to only count the filtered entries,
+    /// use it as a sink instead. This applies to the syslog relay logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `stats`: The collector to count the entries in.
+    pub fn collect_stats(&mut self, stats: LogStats) {
+        self.stats = Some(stats);
+    }
+
+    /// Keeps the most recent received entries in the given buffer, before the filters are applied.
+    ///
+    /// Keep a clone of `ring_buffer` to dump the entries when needed, to only keep the filtered entries,
+    /// use it as a sink instead. This applies to the syslog relay logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `ring_buffer`: The buffer to keep the entries in.
+    pub fn set_ring_buffer(&mut self, ring_buffer: LogRingBuffer) {
+        self.ring_buffer = Some(ring_buffer);
+    }
+
+    /// Sets a custom pattern to parse the syslog lines with, for the devices or locales with a different layout.
+    ///
+    /// The pattern must have a `message` named capture group, and can have the `date`, `device`, `process`,
+    /// `pid` and `severity` ones, see `constants::DEFAULT_LOG_PATTERN` for the default pattern.
+    /// This applies to the logging started after calling it.
+    ///
+    /// # Parameters
+    /// - `pattern`: The pattern to parse the lines with.
+    pub fn set_log_pattern(&mut self, pattern: Regex) -> Result<(), DeviceSysLogError> {
+        if !pattern
+            .capture_names()
+            .flatten()
+            .any(|name| name == "message")
+        {
+            return Err(DeviceSysLogError::InvalidLogPattern);
+        }
+
+        self.log_pattern = Some(Arc::new(pattern));
+        Ok(())
+    }
+
+    /// Sets a composition of filters for this `DeviceSysLog` instance, replacing any previous filter.
+    ///
+    /// # Parameters
+    /// - `chain`: The filters chain to apply to logs.
+    pub fn set_filter_chain(&mut self, chain: impl Into<LogFilterChain>) {
+        self.filter = Arc::new(chain.into());
+    }
+
+    /// Signals all the logging threads started by this instance to stop.
+    ///
+    /// This doesn't wait for the threads to finish, use `SysLogHandle::stop` for that.
+    pub fn stop_logging(&self) -> Result<(), DeviceSysLogError> {
+        if let Ok(mut workers) = self.workers.lock() {
+            for worker in workers.drain(..) {
+                // The thread may have already finished by itself
+                let _ = worker.send(LoggerCommand::StopLogging);
+            }
+        }
+        Ok(())
+    }
+
+    /// Internal method to create the commands channel of a new logging thread, already set to start.
+    fn _register_worker(&self) -> (Sender<LoggerCommand>, Receiver<LoggerCommand>) {
+        let (command_sender, command_receiver) = unbounded();
+        // The receiver is alive at this point, so this can't fail
+        let _ = command_sender.send(LoggerCommand::StartLogging);
+
+        if let Ok(mut workers) = self.workers.lock() {
+            workers.push(command_sender.clone());
+        }
+
+        (command_sender, command_receiver)
+    }
+
+    /// Internal method to start the logging service of a device on a separate thread with timeout.
+    ///
+    /// # Parameters
+    /// - `device`: The device to read the logs from.
     /// - `callback`: A function to handle the `LogsData` objects received from the device.
     /// - `timeout_duration`: The timeout duration for the logging process.
     ///
+    /// Each thread gets its own commands channel, so stopping one doesn't affect the others.
+    fn _start_worker(
+        &self,
+        device: idevice::Device,
+        output: impl LogOutput + 'static,
+        timeout_duration: Option<Duration>,
+        timeout_callback: Option<Box<dyn Fn() + Sync + Send>>,
+    ) -> SysLogHandle {
+        self._start_worker_with_error(device, output, timeout_duration, timeout_callback, None)
+    }
 
-    fn _start_service(
+    /// Internal method to start a logging thread that also reports its fatal error to `on_error`,
+    /// on top of the `on_error` callback of the instance.
+    fn _start_worker_with_error(
         &self,
-        callback: impl Fn(LogsData) + 'static + Sync + Send,
+        mut device: idevice::Device,
+        output: impl LogOutput + 'static,
         timeout_duration: Option<Duration>,
         timeout_callback: Option<Box<dyn Fn() + Sync + Send>>,
-    ) -> JoinHandle<()> {
-        let devices_clone = Arc::clone(&self.devices);
-        let receiver_clone = Arc::clone(&self.receiver);
+        on_error: Option<WorkerErrorCallback>,
+    ) -> SysLogHandle {
         let filter_clone = Arc::clone(&self.filter);
-        let filter_part = Arc::clone(&self.filter_part);
 
-        thread::spawn(move || {
-            let mut current_status: LoggerCommand = LoggerCommand::StopLogging;
+        let dropped_lines = Arc::new(AtomicU64::new(0));
+        let output = Arc::new(output);
+        let callback: Box<dyn Fn(LogsData) + Sync + Send> = match self.queue_config {
+            Some(config) => {
+                let output = Arc::clone(&output);
+                let queue = LogQueue::new(
+                    config,
+                    move |logs| output.log(logs),
+                    Arc::clone(&dropped_lines),
+                );
+                Box::new(move |logs| queue.push(&logs))
+            }
+            None => {
+                let output = Arc::clone(&output);
+                Box::new(move |logs| output.log(logs))
+            }
+        };
+
+        let (command_sender, receiver_clone) = self._register_worker();
+
+        let reconnect = self.reconnect.clone();
+        let stats = self.stats.clone();
+        let ring_buffer = self.ring_buffer.clone();
+        let log_pattern = self.log_pattern.clone();
+        let read_chunk_size = self.read_chunk_size;
+        let multiline = self.multiline;
+        let invalid_bytes = self.invalid_bytes;
 
-            let device = devices_clone.get_device();
-            let mut lockdownd = devices_clone
-                .get_lockdownd_client::<DeviceSysLogError>()
-                .expect("Couldn't get the device lockdown client");
-            let lockdownd_service = lockdownd
-                .start_service(DEVICE_SYSLOG_SERVICE, true)
-                .expect("Couldn't start the syslog service");
-            let service = ServiceClient::new(device, lockdownd_service)
-                .expect("Couldn't create a syslog service client");
+        let handle = thread::spawn(move || {
+            let mut current_status: LoggerCommand = LoggerCommand::StopLogging;
 
             let timeout_start = Instant::now();
 
             let timeout_callback = timeout_callback.unwrap_or_else(|| Box::new(|| {}));
             let timeout_duration = timeout_duration.unwrap_or_else(|| Duration::from_secs(0));
 
-            'log: loop {
+            // Picks up the new commands, returns true once the timeout is reached
+            let timed_out = |status: &mut LoggerCommand| {
                 if let Ok(command) = receiver_clone.try_recv() {
-                    current_status = command;
+                    *status = command;
                 }
 
                 if !timeout_duration.is_zero() && timeout_start.elapsed() >= timeout_duration {
                     timeout_callback();
-                    break;
+                    return true;
+                }
+                false
+            };
+
+            let udid = device.get_udid();
+
+            // Parses a complete entry and hands it over, returns true once the filters asked to stop
+            let emit = |entry: &str| {
+                let mut logs_data = match &log_pattern {
+                    Some(pattern) => LogsData::parse_with(entry, pattern),
+                    None => LogsData::from(entry),
+                };
+                logs_data.udid = Some(&udid);
+
+                if let Some(stats) = &stats {
+                    stats.record(&logs_data);
+                }
+                if let Some(ring_buffer) = &ring_buffer {
+                    ring_buffer.push(&logs_data);
+                }
+                match filter_clone.apply(&logs_data) {
+                    LogAction::Continue => false,
+                    LogAction::Break => {
+                        callback(logs_data);
+                        true
+                    }
+                    LogAction::Log => {
+                        callback(logs_data);
+                        false
+                    }
+                }
+            };
+            let flush = |entry: &mut Option<String>| entry.take().is_some_and(|entry| emit(&entry));
+
+            let report = |error: DeviceSysLogError| {
+                reconnect.report(&udid, &error);
+                if let Some(on_error) = &on_error {
+                    on_error(&error);
                 }
+            };
+            // Why the connection was lost, reported if it's not got back
+            let mut last_error = String::new();
+
+            let mut connected_once = false;
+            let mut attempts: u32 = 0;
+
+            'connection: loop {
+                match connect_syslog(&device) {
+                    Ok(service) => {
+                        if attempts > 0 {
+                            reconnect.notify(&udid, ReconnectEvent::Reconnected);
+                            attempts = 0;
+                        }
+                        connected_once = true;
 
-                match current_status {
-                    LoggerCommand::StartLogging => match service.receive(1024) {
-                        Ok(data) => {
-                            let logs_raw_string = String::from_utf8_lossy(&data);
+                        // The end of a line split between two reads, kept until the rest comes in
+                        let mut lines = LineBuffer::default();
+                        // The entry being read, kept until the next one starts
+                        let mut entry: Option<String> = None;
 
-                            for line in logs_raw_string.split_terminator('\n') {
-                                let line = line.trim_matches('\0'); // Remove null characters
+                        'log: loop {
+                            if timed_out(&mut current_status) {
+                                break 'connection;
+                            }
+
+                            match current_status {
+                                // Using a timeout so the commands are checked even if the device is quiet
+                                LoggerCommand::StartLogging => {
+                                    match service.receive_with_timeout(read_chunk_size, 500) {
+                                        Ok(data) => {
+                                            for raw_line in lines.push(&data) {
+                                                let line =
+                                                    decoding::decode_line(&raw_line, invalid_bytes);
+
+                                                // A line without the header continues the entry (e.g., a backtrace)
+                                                if let Some(entry) = entry.as_mut().filter(|_| {
+                                                    multiline
+                                                        && !logs_data::is_entry_start(
+                                                            &line,
+                                                            log_pattern.as_deref(),
+                                                        )
+                                                }) {
+                                                    entry.push('\n');
+                                                    entry.push_str(&line);
+                                                    continue;
+                                                }
 
-                                let logs_data = LogsData::from(line);
-                                match filter_clone.apply(&logs_data, &filter_part) {
-                                    LogAction::Continue => continue 'log,
-                                    LogAction::Break => {
-                                        callback(logs_data);
-                                        break 'log;
+                                                if let Some(previous) = entry.replace(line) {
+                                                    if emit(&previous) {
+                                                        break 'connection;
+                                                    }
+                                                }
+                                            }
+
+                                            // Without the continuation lines, there's nothing to wait for
+                                            if !multiline && flush(&mut entry) {
+                                                break 'connection;
+                                            }
+                                        }
+                                        // The device is quiet, the last entry won't get more lines
+                                        Err(ServiceError::Timeout) => {
+                                            if flush(&mut entry) {
+                                                break 'connection;
+                                            }
+                                            output.idle();
+                                        }
+                                        Err(err) => {
+                                            if flush(&mut entry) {
+                                                break 'connection;
+                                            }
+                                            if reconnect.policy.is_none() {
+                                                log::warn!(udid:% = udid, error:% = err; "Failed to receive the logs");
+                                            }
+                                            last_error = err.to_string();
+                                            reconnect.notify(
+                                                &udid,
+                                                ReconnectEvent::Disconnected(err.to_string()),
+                                            );
+                                            break 'log;
+                                        }
                                     }
-                                    LogAction::Log => callback(logs_data),
                                 }
+                                LoggerCommand::StopLogging => break 'connection,
                             }
                         }
-                        Err(err) => {
-                            eprintln!("Failed to receive data: {}", err);
-                            thread::sleep(Duration::from_secs(1));
-                        }
-                    },
-                    LoggerCommand::StopLogging => break 'log,
+                    }
+                    Err(err) if !connected_once => {
+                        log::error!(udid:% = udid, error:% = err; "Couldn't start the syslog service");
+                        report(err);
+                        break 'connection;
+                    }
+                    Err(_) => reconnect.notify(&udid, ReconnectEvent::AttemptFailed(attempts)),
+                }
+
+                // The connection was lost, or the last attempt to get it back failed
+                let Some(policy) = reconnect.policy else {
+                    report(DeviceSysLogError::ServiceDied(last_error));
+                    break 'connection;
+                };
+                if policy.max_attempts.is_some_and(|max| attempts >= max) {
+                    reconnect.notify(&udid, ReconnectEvent::GaveUp);
+                    report(DeviceSysLogError::ServiceDied(format!(
+                        "{}, not back after {} attempts",
+                        last_error, attempts
+                    )));
+                    break 'connection;
+                }
+                attempts += 1;
+
+                // Waiting in small steps so the commands and the timeout are still honored
+                let wait_start = Instant::now();
+                while wait_start.elapsed() < policy.retry_interval {
+                    if timed_out(&mut current_status)
+                        || matches!(current_status, LoggerCommand::StopLogging)
+                    {
+                        break 'connection;
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+
+                // The device gets a new handle once it's plugged back
+                if let Ok(new_device) = idevice::get_device(udid.clone()) {
+                    device = new_device;
                 }
             }
-        })
+        });
+
+        SysLogHandle::new(
+            command_sender,
+            handle,
+            dropped_lines,
+            Arc::clone(&self.detached),
+        )
     }
-    /// Sets the log filter for this `DeviceSysLog` instance.
-    ///
-    /// # Parameters
-    /// - `filter`: The filter logic to apply to logs.
-    /// - `filter_part`: Specifies which parts of the log to apply the filter on.
-    pub fn set_filter(&mut self, filter: LogFilter, filter_part: FilterPart) {
-        self.filter = filter.into();
-        self.filter_part = filter_part.into();
+}
+
+impl<T> Drop for DeviceSysLog<T> {
+    fn drop(&mut self) {
+        let detached: Vec<_> = match self.detached.lock() {
+            Ok(mut detached) => detached.drain(..).collect(),
+            Err(_) => return,
+        };
+
+        // Signaling them all first, so they stop together
+        for (command_sender, _) in &detached {
+            let _ = command_sender.send(LoggerCommand::StopLogging);
+        }
+        for (_, handle) in detached {
+            // Dropped from its own callback, a thread can't wait for itself
+            if handle.thread().id() != thread::current().id() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl DeviceSysLog<SingleDevice> {
+    /// Internal method to start the logging service of the device on a separate thread with timeout.
+    fn _start_service(
+        &self,
+        output: impl LogOutput + 'static,
+        timeout_duration: Option<Duration>,
+        timeout_callback: Option<Box<dyn Fn() + Sync + Send>>,
+    ) -> SysLogHandle {
+        self._start_worker(
+            self.devices.get_device().clone(),
+            output,
+            timeout_duration,
+            timeout_callback,
+        )
     }
 
     /// Logs to a custom destination using the provided callback function.
@@ -177,12 +651,11 @@ impl DeviceSysLog<SingleDevice> {
     ///
     /// # Parameters
     /// - `callback`: A function to process the `LogsData`.
-    pub fn log_to_custom<F>(&self, callback: F) -> Result<JoinHandle<()>, DeviceSysLogError>
+    pub fn log_to_custom<F>(&self, callback: F) -> Result<SysLogHandle, DeviceSysLogError>
     where
         F: Fn(LogsData) + 'static + Sync + Send,
     {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
         Ok(self._start_service(callback, None, None))
     }
 
@@ -197,12 +670,11 @@ impl DeviceSysLog<SingleDevice> {
         &self,
         callback: F,
         timeout_duration: Duration,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError>
+    ) -> Result<SysLogHandle, DeviceSysLogError>
     where
         F: Fn(LogsData) + 'static + Sync + Send,
     {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
         Ok(self._start_service(callback, Some(timeout_duration), None))
     }
 
@@ -219,13 +691,12 @@ impl DeviceSysLog<SingleDevice> {
         callback: F,
         timeout_duration: Duration,
         timeout_callback: F2,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError>
+    ) -> Result<SysLogHandle, DeviceSysLogError>
     where
         F: Fn(LogsData) + 'static + Sync + Send,
         F2: Fn() + 'static + Sync + Send,
     {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
         Ok(self._start_service(
             callback,
             Some(timeout_duration),
@@ -233,19 +704,240 @@ impl DeviceSysLog<SingleDevice> {
         ))
     }
 
-    /// Logs to the console (stdout).
+    /// Logs the structured entries of the unified logging system, through the os_trace relay.
+    ///
+    /// Unlike the syslog relay, it doesn't drop entries on recent iOS versions, and the entries
+    /// carry their subsystem and category. The filters apply on the `OsTraceEntry::with_logs_data` view.
+    /// The bounded queue doesn't apply to it, the reconnection settings and the `on_error` callback do.
     ///
     /// This is a non blocking function
-    pub fn log_to_stdout(&self) -> Result<JoinHandle<()>, DeviceSysLogError> {
+    ///
+    /// # Parameters
+    /// - `callback`: A function to process the `OsTraceEntry`.
+    pub fn log_os_trace<F>(&self, callback: F) -> Result<SysLogHandle, DeviceSysLogError>
+    where
+        F: Fn(&OsTraceEntry) + 'static + Sync + Send,
+    {
+        self._log_os_trace(callback, None)
+    }
+
+    /// Internal method to start the os_trace relay logging, with a function called while the device is quiet.
+    fn _log_os_trace(
+        &self,
+        callback: impl Fn(&OsTraceEntry) + 'static + Sync + Send,
+        on_idle: Option<Box<dyn Fn() + Send>>,
+    ) -> Result<SysLogHandle, DeviceSysLogError> {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
-        Ok(self._start_service(
-            |logs| println!("{}", logs.get_parsed_log_colored()),
-            None,
-            None,
+
+        let (command_sender, command_receiver) = self._register_worker();
+        let handle = os_trace::spawn_worker(
+            self.devices.get_device().clone(),
+            Arc::clone(&self.filter),
+            self.reconnect.clone(),
+            callback,
+            on_idle,
+            command_receiver,
+        );
+
+        Ok(SysLogHandle::new(
+            command_sender,
+            handle,
+            Arc::new(AtomicU64::new(0)),
+            Arc::clone(&self.detached),
         ))
     }
 
+    /// Logs the entries of the os_trace relay to the given sink, see `log_os_trace`.
+    ///
+    /// This is a non blocking function
+    ///
+    /// # Parameters
+    /// - `sink`: The destination of the logs, see `LogSink`.
+    pub fn log_os_trace_to_sink<S>(&self, sink: S) -> Result<SysLogHandle, DeviceSysLogError>
+    where
+        S: LogSink + Send + 'static,
+    {
+        let output = Arc::new(sink_output(sink));
+        let idle_output = Arc::clone(&output);
+        self._log_os_trace(
+            move |entry| entry.with_logs_data(|logs| output.log(logs)),
+            Some(Box::new(move || idle_output.idle())),
+        )
+    }
+
+    /// Logs to the given sink.
+    ///
+    /// This is a non blocking function
+    ///
+    /// # Parameters
+    /// - `sink`: The destination of the logs, see `LogSink`.
+    pub fn log_to_sink<S>(&self, sink: S) -> Result<SysLogHandle, DeviceSysLogError>
+    where
+        S: LogSink + Send + 'static,
+    {
+        self.devices.check_connected::<DeviceSysLogError>()?;
+        Ok(self._start_service(sink_output(sink), None, None))
+    }
+
+    /// Logs to a channel, returning its receiving end along with the logging handle.
+    ///
+    /// This is useful to consume the logs on another thread or runtime without writing a callback.
+    ///
+    /// This is a non blocking function
+    pub fn log_to_channel(
+        &self,
+    ) -> Result<(Receiver<LogsDataOwned>, SysLogHandle), DeviceSysLogError> {
+        let (logs_sender, logs_receiver) = unbounded();
+        let handle = self.log_to_sink(ChannelSink::new(logs_sender))?;
+        Ok((logs_receiver, handle))
+    }
+
+    /// Returns an iterator over the logs, as an alternative to the callback based functions.
+    ///
+    /// The logging runs in the background and the iterator blocks until the next entry is available,
+    /// if the device isn't connected, the iterator yields the error and ends.
+    ///
+    /// Dropping the iterator stops the logging.
+    pub fn iter(&self) -> LogsIter {
+        let (logs_sender, logs_receiver) = unbounded();
+
+        let handle = match self.devices.check_connected::<DeviceSysLogError>() {
+            Ok(()) => {
+                let error_sender = logs_sender.clone();
+                Some(self._start_worker_with_error(
+                    self.devices.get_device().clone(),
+                    move |logs: LogsData| {
+                        let _ = logs_sender.send(Ok(logs.to_owned()));
+                    },
+                    None,
+                    None,
+                    Some(Box::new(move |error| {
+                        let _ = error_sender.send(Err(owned_error(error)));
+                    })),
+                ))
+            }
+            Err(err) => {
+                let _ = logs_sender.send(Err(err));
+                None
+            }
+        };
+
+        LogsIter::new(logs_receiver, handle)
+    }
+
+    /// Captures the logs for the given duration, then stops.
+    ///
+    /// This is a blocking function
+    ///
+    /// # Parameters
+    /// - `duration`: How long to capture the logs for.
+    pub fn capture_for(&self, duration: Duration) -> Result<Vec<LogsDataOwned>, DeviceSysLogError> {
+        self.devices.check_connected::<DeviceSysLogError>()?;
+
+        let (logs_sender, logs_receiver) = unbounded();
+        self._start_service(
+            sink_output(ChannelSink::new(logs_sender)),
+            Some(duration),
+            None,
+        )
+        .join();
+
+        Ok(logs_receiver.try_iter().collect())
+    }
+
+    /// Captures the given number of log entries (after filtering), then stops.
+    ///
+    /// Fewer entries are returned if the filters stopped the logging before.
+    ///
+    /// This is a blocking function
+    ///
+    /// # Parameters
+    /// - `count`: The number of entries to capture.
+    pub fn capture_lines(&self, count: usize) -> Result<Vec<LogsDataOwned>, DeviceSysLogError> {
+        self.iter().take(count).collect()
+    }
+
+    /// Captures a window of logs around a trigger and writes it to a file.
+    ///
+    /// Nothing is written until an entry matches `trigger`, then the `context_lines` entries before it,
+    /// the entry itself, and the following ones are written up until the window `end`s, which stops the logging.
+    /// The patterns are matched against the whole formatted entry.
+    ///
+    /// This is a blocking function, returning the number of written entries, which is 0
+    /// if the logging stopped before the trigger matched.
+    ///
+    /// # Parameters
+    /// - `trigger`: The pattern starting the window.
+    /// - `end`: When the window ends.
+    /// - `context_lines`: How many entries before the trigger to include.
+    /// - `file_path`: Path to the file where the window should be saved, it's appended to if it exists.
+    pub fn capture_window<S>(
+        &self,
+        trigger: Regex,
+        end: WindowEnd,
+        context_lines: usize,
+        file_path: &S,
+    ) -> Result<usize, DeviceSysLogError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        self.devices.check_connected::<DeviceSysLogError>()?;
+
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(file_path.as_ref())?;
+        let mut window = WindowCapture::new(trigger, end, context_lines, BufWriter::new(file));
+
+        // The iterator is dropped when leaving, which stops the logging
+        for logs in self.iter() {
+            if window.feed(&logs?)? {
+                break;
+            }
+        }
+
+        Ok(window.finish()?)
+    }
+
+    /// Returns a stream over the logs, the async version of `iter`.
+    ///
+    /// Dropping the stream stops the logging.
+    #[cfg(feature = "async")]
+    pub fn stream(&self) -> LogsStream {
+        let (logs_sender, logs_receiver) = futures_channel::mpsc::unbounded();
+
+        let handle = match self.devices.check_connected::<DeviceSysLogError>() {
+            Ok(()) => {
+                let error_sender = logs_sender.clone();
+                Some(self._start_worker_with_error(
+                    self.devices.get_device().clone(),
+                    move |logs: LogsData| {
+                        let _ = logs_sender.unbounded_send(Ok(logs.to_owned()));
+                    },
+                    None,
+                    None,
+                    Some(Box::new(move |error| {
+                        let _ = error_sender.unbounded_send(Err(owned_error(error)));
+                    })),
+                ))
+            }
+            Err(err) => {
+                let _ = logs_sender.unbounded_send(Err(err));
+                None
+            }
+        };
+
+        LogsStream::new(logs_receiver, handle)
+    }
+
+    /// Logs to the console (stdout).
+    ///
+    /// This is a non blocking function
+    pub fn log_to_stdout(&self) -> Result<SysLogHandle, DeviceSysLogError> {
+        self.devices.check_connected::<DeviceSysLogError>()?;
+        Ok(self._start_service(sink_output(self.stdout_sink()), None, None))
+    }
+
     /// Logs to the console (stdout) with a timeout.
     ///
     /// This is a non blocking function
@@ -255,11 +947,10 @@ impl DeviceSysLog<SingleDevice> {
     pub fn log_to_stdout_with_timeout(
         &self,
         timeout_duration: Duration,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError> {
+    ) -> Result<SysLogHandle, DeviceSysLogError> {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
         Ok(self._start_service(
-            |logs| println!("{}", logs.get_parsed_log_colored()),
+            sink_output(self.stdout_sink()),
             Some(timeout_duration),
             None,
         ))
@@ -277,78 +968,73 @@ impl DeviceSysLog<SingleDevice> {
         &self,
         timeout_duration: Duration,
         timeout_callback: F,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError>
+    ) -> Result<SysLogHandle, DeviceSysLogError>
     where
         F: Fn() + Send + Sync + 'static,
     {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
         Ok(self._start_service(
-            |logs| println!("{}", logs.get_parsed_log_colored()),
+            sink_output(self.stdout_sink()),
             Some(timeout_duration),
             Some(Box::new(timeout_callback)),
         ))
     }
 
-    fn _log_to_file<S>(
+    fn _log_to_file(
         &self,
-        file_path: &S,
+        file_sink: FileSink,
         timeout_duration: Option<Duration>,
         timeout_callback: Option<Box<dyn Fn() + Send + Sync>>,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError>
-    where
-        S: AsRef<Path> + ?Sized + Sync,
-    {
+    ) -> Result<SysLogHandle, DeviceSysLogError> {
         self.devices.check_connected::<DeviceSysLogError>()?;
-        self.sender.send(LoggerCommand::StartLogging)?;
 
-        let file_path = file_path.as_ref().to_path_buf();
-        Ok(self._start_service(
-            move |logs| {
-                let resolved_path = match fs::canonicalize(&file_path) {
-                    Ok(path) => path,
-                    Err(_) => file_path.to_owned(),
-                };
-                let mut file = match OpenOptions::new()
-                    .append(true)
-                    .create(true)
-                    .open(&resolved_path)
-                {
-                    Ok(file) => file,
-                    Err(e) => {
-                        eprintln!(
-                            "Critical error: Failed to open log file at {:?}: {}",
-                            resolved_path, e
-                        );
-                        return;
-                    }
-                };
+        Ok(self._start_service(sink_output(file_sink), timeout_duration, timeout_callback))
+    }
 
-                if let Err(e) = file.write_all(logs.get_parsed_log().as_bytes()) {
-                    eprintln!("Error writing to file: {}", e);
-                    return;
-                }
+    /// Logs to a specified file.
+    ///
+    /// This is a non blocking function
+    ///
+    /// # Parameters
+    /// - `file_path`: Path to the file where logs should be saved.
+    pub fn log_to_file<S>(&self, file_path: &S) -> Result<SysLogHandle, DeviceSysLogError>
+    where
+        S: AsRef<Path> + ?Sized + Sync,
+    {
+        self._log_to_file(FileSink::new(file_path), None, None)
+    }
 
-                if let Err(e) = file.flush() {
-                    eprintln!("Error flushing to file: {}", e);
-                }
-            },
-            timeout_duration,
-            timeout_callback,
-        ))
+    /// Logs to a specified file in the JSON Lines format, one JSON object per log entry.
+    ///
+    /// Each object has the `date`, `device`, `process`, `pid`, `severity`, `message` and `udid` fields.
+    ///
+    /// This is a non blocking function
+    ///
+    /// # Parameters
+    /// - `file_path`: Path to the file where logs should be saved.
+    pub fn log_to_file_json<S>(&self, file_path: &S) -> Result<SysLogHandle, DeviceSysLogError>
+    where
+        S: AsRef<Path> + ?Sized + Sync,
+    {
+        self._log_to_file(FileSink::json(file_path), None, None)
     }
 
-    /// Logs to a specified file.
+    /// Logs to a specified file, rotating it following the given policy.
     ///
     /// This is a non blocking function
     ///
     /// # Parameters
     /// - `file_path`: Path to the file where logs should be saved.
-    pub fn log_to_file<S>(&self, file_path: &S) -> Result<JoinHandle<()>, DeviceSysLogError>
+    /// - `rotation`: When to rotate the file and how many rotated files to keep.
+    pub fn log_to_file_rotating<S>(
+        &self,
+        file_path: &S,
+        rotation: RotationPolicy,
+    ) -> Result<SysLogHandle, DeviceSysLogError>
     where
         S: AsRef<Path> + ?Sized + Sync,
     {
-        self._log_to_file(file_path, None, None)
+        self._log_to_file(FileSink::new(file_path).with_rotation(rotation), None, None)
     }
 
     /// Logs to a specified file with timeout.
@@ -362,11 +1048,11 @@ impl DeviceSysLog<SingleDevice> {
         &self,
         file_path: &S,
         timeout_duration: Duration,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError>
+    ) -> Result<SysLogHandle, DeviceSysLogError>
     where
         S: AsRef<Path> + ?Sized + Sync,
     {
-        self._log_to_file(file_path, Some(timeout_duration), None)
+        self._log_to_file(FileSink::new(file_path), Some(timeout_duration), None)
     }
     /// Logs to a specified file with timeout.
     /// The timeout_callback will be called once the timeout is triggred
@@ -382,20 +1068,234 @@ impl DeviceSysLog<SingleDevice> {
         file_path: &S,
         timeout_duration: Duration,
         timeout_callback: F,
-    ) -> Result<JoinHandle<()>, DeviceSysLogError>
+    ) -> Result<SysLogHandle, DeviceSysLogError>
     where
         S: AsRef<Path> + ?Sized + Sync,
         F: Fn() + Send + Sync + 'static,
     {
         self._log_to_file(
-            file_path,
+            FileSink::new(file_path),
             Some(timeout_duration),
             Some(Box::new(timeout_callback)),
         )
     }
+}
 
-    pub fn stop_logging(&self) -> Result<(), DeviceSysLogError> {
-        self.sender.send(LoggerCommand::StopLogging)?;
-        Ok(())
+impl DeviceSysLog<DeviceGroup> {
+    /// Internal method to start one logging thread per device, all sharing the same callback.
+    ///
+    /// # Parameters
+    /// - `callback`: A function to handle the `LogsData` objects received from the devices.
+    /// - `timeout_duration`: The timeout duration for the logging process.
+    fn _start_services(
+        &self,
+        output: impl LogOutput + 'static,
+        timeout_duration: Option<Duration>,
+    ) -> Vec<SysLogHandle> {
+        let output = Arc::new(output);
+
+        self.devices
+            .get_devices()
+            .iter()
+            .map(|device| {
+                self._start_worker(
+                    device.clone(),
+                    SharedOutput(Arc::clone(&output)),
+                    timeout_duration,
+                    None,
+                )
+            })
+            .collect()
+    }
+
+    /// Logs all the devices to a custom destination using the provided callback function.
+    ///
+    /// The callback is shared by all the devices, use `LogsData::udid` to tell them apart.
+    ///
+    /// This is a non blocking function, returning one handle per device
+    ///
+    /// # Parameters
+    /// - `callback`: A function to process the `LogsData`.
+    pub fn log_to_custom<F>(&self, callback: F) -> Result<Vec<SysLogHandle>, DeviceSysLogError>
+    where
+        F: Fn(LogsData) + 'static + Sync + Send,
+    {
+        self.devices.check_all_connected::<DeviceSysLogError>()?;
+        Ok(self._start_services(callback, None))
+    }
+
+    /// Logs all the devices to a custom destination with a timeout using the provided callback function.
+    ///
+    /// This is a non blocking function, returning one handle per device
+    ///
+    /// # Parameters
+    /// - `callback`: A function to process the `LogsData`.
+    /// - `timeout_duration`: The timeout duration for the logging process.
+    pub fn log_to_custom_with_timeout<F>(
+        &self,
+        callback: F,
+        timeout_duration: Duration,
+    ) -> Result<Vec<SysLogHandle>, DeviceSysLogError>
+    where
+        F: Fn(LogsData) + 'static + Sync + Send,
+    {
+        self.devices.check_all_connected::<DeviceSysLogError>()?;
+        Ok(self._start_services(callback, Some(timeout_duration)))
+    }
+
+    /// Logs all the devices to the same sink.
+    ///
+    /// This is a non blocking function, returning one handle per device
+    ///
+    /// # Parameters
+    /// - `sink`: The destination of the logs, see `LogSink`.
+    pub fn log_to_sink<S>(&self, sink: S) -> Result<Vec<SysLogHandle>, DeviceSysLogError>
+    where
+        S: LogSink + Send + 'static,
+    {
+        self.devices.check_all_connected::<DeviceSysLogError>()?;
+        Ok(self._start_services(sink_output(sink), None))
+    }
+
+    /// Logs each device to its own sink.
+    ///
+    /// This is a non blocking function, returning one handle per device
+    ///
+    /// # Parameters
+    /// - `make_sink`: A function creating the sink of a device from its UDID.
+    pub fn log_to_sink_per_device<F, S>(
+        &self,
+        make_sink: F,
+    ) -> Result<Vec<SysLogHandle>, DeviceSysLogError>
+    where
+        F: Fn(&str) -> S,
+        S: LogSink + Send + 'static,
+    {
+        self.devices.check_all_connected::<DeviceSysLogError>()?;
+
+        Ok(self
+            .devices
+            .get_devices()
+            .iter()
+            .map(|device| {
+                let sink = make_sink(&device.get_udid());
+                self._start_worker(device.clone(), sink_output(sink), None, None)
+            })
+            .collect())
+    }
+
+    /// Logs all the devices to a channel, returning its receiving end along with the logging handles.
+    ///
+    /// This is a non blocking function
+    pub fn log_to_channel(
+        &self,
+    ) -> Result<(Receiver<LogsDataOwned>, Vec<SysLogHandle>), DeviceSysLogError> {
+        let (logs_sender, logs_receiver) = unbounded();
+        let handles = self.log_to_sink(ChannelSink::new(logs_sender))?;
+        Ok((logs_receiver, handles))
+    }
+
+    /// Logs all the devices to the console (stdout).
+    ///
+    /// This is a non blocking function, returning one handle per device
+    pub fn log_to_stdout(&self) -> Result<Vec<SysLogHandle>, DeviceSysLogError> {
+        self.log_to_sink(self.stdout_sink())
+    }
+
+    /// Logs each device to its own file in the given directory, named after its UDID (`{udid}.log`).
+    ///
+    /// The directory is created if it doesn't exist.
+    ///
+    /// This is a non blocking function, returning one handle per device
+    ///
+    /// # Parameters
+    /// - `dir_path`: Path to the directory where the logs should be saved.
+    pub fn log_to_files<S>(&self, dir_path: &S) -> Result<Vec<SysLogHandle>, DeviceSysLogError>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let dir_path = dir_path.as_ref();
+        fs::create_dir_all(dir_path)?;
+
+        self.log_to_sink_per_device(|udid| FileSink::new(&dir_path.join(format!("{}.log", udid))))
+    }
+}
+
+/// Starts the syslog relay service on the given device.
+fn connect_syslog(device: &idevice::Device) -> Result<ServiceClient<'_>, DeviceSysLogError> {
+    let mut lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client"))?;
+    let lockdownd_service = lockdownd.start_service(DEVICE_SYSLOG_SERVICE, true)?;
+    Ok(ServiceClient::new(device, lockdownd_service)?)
+}
+
+/// A callback reporting the fatal error of a single logging thread.
+type WorkerErrorCallback = Box<dyn Fn(&DeviceSysLogError) + Send>;
+
+/// Copies the error the logging stopped on, to hand it over to an iterator or a stream.
+fn owned_error(error: &DeviceSysLogError) -> DeviceSysLogError {
+    match error {
+        DeviceSysLogError::ServiceDied(reason) => DeviceSysLogError::ServiceDied(reason.clone()),
+        error => DeviceSysLogError::ServiceDied(error.to_string()),
+    }
+}
+
+/// The destination of the entries of a logging thread, either a callback or a `LogSink`.
+trait LogOutput: Send + Sync {
+    fn log(&self, logs: LogsData);
+
+    /// Called while the device is quiet, see `LogSink::idle`
+    fn idle(&self) {}
+}
+
+impl<F> LogOutput for F
+where
+    F: Fn(LogsData) + Send + Sync,
+{
+    fn log(&self, logs: LogsData) {
+        self(logs)
+    }
+}
+
+/// A `LogSink` shared with the logging thread.
+struct SinkOutput<S>(Mutex<S>);
+
+impl<S> LogOutput for SinkOutput<S>
+where
+    S: LogSink + Send,
+{
+    fn log(&self, logs: LogsData) {
+        if let Ok(mut sink) = self.0.lock() {
+            sink.write(&logs);
+        }
+    }
+
+    fn idle(&self) {
+        if let Ok(mut sink) = self.0.lock() {
+            sink.idle();
+        }
+    }
+}
+
+/// An output shared by the logging threads of several devices.
+struct SharedOutput<O>(Arc<O>);
+
+impl<O> LogOutput for SharedOutput<O>
+where
+    O: LogOutput,
+{
+    fn log(&self, logs: LogsData) {
+        self.0.log(logs)
+    }
+
+    fn idle(&self) {
+        self.0.idle()
     }
 }
+
+/// Wraps a `LogSink` into an output that can be shared with the logging thread.
+fn sink_output<S>(sink: S) -> SinkOutput<S>
+where
+    S: LogSink + Send + 'static,
+{
+    SinkOutput(Mutex::new(sink))
+}
@@ -0,0 +1,597 @@
+//! Structured unified logging through the `com.apple.os_trace_relay` service.
+//!
+//! Unlike the syslog relay, which sends already formatted text lines and drops some of them on recent iOS versions,
+//! the os_trace relay streams binary records, which carry the subsystem and category of each entry.
+//! The activity identifiers aren't decoded, their place in the record header isn't known.
+//!
+//! After the `StartActivity` request, each record is sent as a `0x02` byte, a 4 bytes little-endian length
+//! and the record itself.
+
+use crate::{
+    device_syslog::{
+        errors::DeviceSysLogError, reconnect::ReconnectConfig, LogAction, LogFilterChain, LogLevel,
+        LoggerCommand, LogsData, ReconnectEvent,
+    },
+    plist_service,
+};
+use chrono::{DateTime, Local, NaiveDateTime};
+use crossbeam_channel::Receiver;
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    error::ServiceError, idevice, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
+use std::{
+    fmt,
+    path::Path,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const OS_TRACE_RELAY_SERVICE: &str = "com.apple.os_trace_relay";
+
+/// Asks for every message (`MessageFilter`) of every process (`Pid`)
+const START_ACTIVITY_REQUEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Request</key>
+	<string>StartActivity</string>
+	<key>MessageFilter</key>
+	<integer>65535</integer>
+	<key>Pid</key>
+	<integer>-1</integer>
+	<key>StreamFlags</key>
+	<integer>60</integer>
+</dict>
+</plist>
+"#;
+
+const FRAME_MARKER: u8 = 0x02;
+
+/// The size of the fixed part of a record, before the strings
+const RECORD_HEADER_SIZE: usize = 129;
+
+/// The largest record or response accepted from the device, a bigger length means the stream is out of sync
+const MAX_RECORD_SIZE: usize = 1024 * 1024;
+
+/// Enum representing the level of an os_trace entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OsTraceLevel {
+    Notice,
+    Info,
+    Debug,
+    UserAction,
+    Error,
+    Fault,
+    Unknown(u8),
+}
+
+impl OsTraceLevel {
+    /// Returns the equivalent syslog level, if any
+    pub fn as_log_level(&self) -> Option<LogLevel> {
+        match self {
+            OsTraceLevel::Notice | OsTraceLevel::UserAction => Some(LogLevel::Notice),
+            OsTraceLevel::Info => Some(LogLevel::Info),
+            OsTraceLevel::Debug => Some(LogLevel::Debug),
+            OsTraceLevel::Error => Some(LogLevel::Error),
+            OsTraceLevel::Fault => Some(LogLevel::Fault),
+            OsTraceLevel::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<u8> for OsTraceLevel {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => OsTraceLevel::Notice,
+            0x01 => OsTraceLevel::Info,
+            0x02 => OsTraceLevel::Debug,
+            0x03 => OsTraceLevel::UserAction,
+            0x10 => OsTraceLevel::Error,
+            0x11 => OsTraceLevel::Fault,
+            value => OsTraceLevel::Unknown(value),
+        }
+    }
+}
+
+impl fmt::Display for OsTraceLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OsTraceLevel::Notice => write!(f, "Notice"),
+            OsTraceLevel::Info => write!(f, "Info"),
+            OsTraceLevel::Debug => write!(f, "Debug"),
+            OsTraceLevel::UserAction => write!(f, "UserAction"),
+            OsTraceLevel::Error => write!(f, "Error"),
+            OsTraceLevel::Fault => write!(f, "Fault"),
+            OsTraceLevel::Unknown(value) => write!(f, "Unknown({})", value),
+        }
+    }
+}
+
+/// Struct to store a parsed os_trace record
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsTraceEntry {
+    /// The process ID that generated the entry
+    pub pid: u32,
+
+    /// The date the entry was created, in the local time
+    pub timestamp: NaiveDateTime,
+
+    /// The level of the entry
+    pub level: OsTraceLevel,
+
+    /// The path of the process executable (e.g., "/usr/libexec/locationd")
+    pub filename: String,
+
+    /// The name of the image (library or executable) the entry comes from
+    pub image_name: String,
+
+    /// The actual log message
+    pub message: String,
+
+    /// The subsystem of the entry (e.g., "com.apple.locationd.Core"), if set
+    pub subsystem: Option<String>,
+
+    /// The category of the entry within its subsystem, if set
+    pub category: Option<String>,
+}
+
+impl OsTraceEntry {
+    /// Parses a single record, as sent by the os_trace relay (without its frame marker and length).
+    ///
+    /// Returns `None` if the record is truncated.
+    pub fn parse(record: &[u8]) -> Option<OsTraceEntry> {
+        if record.len() < RECORD_HEADER_SIZE {
+            return None;
+        }
+
+        let u16_at = |offset: usize| u16::from_le_bytes([record[offset], record[offset + 1]]);
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([
+                record[offset],
+                record[offset + 1],
+                record[offset + 2],
+                record[offset + 3],
+            ])
+        };
+
+        let pid = u32_at(9);
+        let seconds = u32_at(55);
+        let microseconds = u32_at(63);
+        let level = OsTraceLevel::from(record[68]);
+        let image_name_size = u16_at(107) as usize;
+        let message_size = u16_at(109) as usize;
+        let subsystem_size = u32_at(117) as usize;
+        let category_size = u32_at(121) as usize;
+
+        let mut strings = &record[RECORD_HEADER_SIZE..];
+
+        // The filename is the only string without a known size, it ends at its null character
+        let filename_size = strings.iter().position(|&byte| byte == 0)? + 1;
+        let filename = take_string(&mut strings, filename_size)?;
+        let image_name = take_string(&mut strings, image_name_size)?;
+        let message = take_string(&mut strings, message_size)?;
+        let (subsystem, category) = if subsystem_size > 0 {
+            (
+                Some(take_string(&mut strings, subsystem_size)?),
+                Some(take_string(&mut strings, category_size)?),
+            )
+        } else {
+            (None, None)
+        };
+
+        let timestamp = DateTime::from_timestamp(seconds as i64, microseconds.checked_mul(1000)?)?
+            .with_timezone(&Local)
+            .naive_local();
+
+        Some(OsTraceEntry {
+            pid,
+            timestamp,
+            level,
+            filename,
+            image_name,
+            message,
+            subsystem,
+            category,
+        })
+    }
+
+    /// The process name, taken from the executable path
+    pub fn process(&self) -> &str {
+        Path::new(&self.filename)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.filename)
+    }
+
+    /// Calls `f` with a `LogsData` view of the entry, to be used with the filters and sinks.
+    pub fn with_logs_data<R>(&self, f: impl FnOnce(LogsData) -> R) -> R {
+        let date = self.timestamp.format("%b %e %H:%M:%S").to_string();
+        let pid = self.pid.to_string();
+        let severity = self.level.to_string();
+
+        f(LogsData {
+            date: &date,
+            device: &self.image_name,
+            process: self.process(),
+            pid: Some(&pid),
+            severity: Some(&severity),
+            message: &self.message,
+            udid: None,
+        })
+    }
+}
+
+/// Takes a null terminated string of `size` bytes (including the null character) from the front of `data`.
+fn take_string(data: &mut &[u8], size: usize) -> Option<String> {
+    if data.len() < size {
+        return None;
+    }
+
+    let (string, rest) = data.split_at(size);
+    *data = rest;
+
+    let string = string.split(|&byte| byte == 0).next().unwrap_or_default();
+    Some(String::from_utf8_lossy(string).into_owned())
+}
+
+/// Accumulates the received bytes and splits them into records.
+#[derive(Debug, Default)]
+struct FrameReader {
+    buffer: Vec<u8>,
+}
+
+impl FrameReader {
+    fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete record, `None` if it isn't all received yet.
+    ///
+    /// Fails if the length of the record is above `MAX_RECORD_SIZE`, the stream is out of sync then.
+    fn next_record(&mut self) -> Result<Option<Vec<u8>>, DeviceSysLogError> {
+        // Skips anything before the next frame, which only happens if the stream got out of sync
+        let Some(start) = self.buffer.iter().position(|&byte| byte == FRAME_MARKER) else {
+            self.buffer.clear();
+            return Ok(None);
+        };
+        self.buffer.drain(..start);
+
+        if self.buffer.len() < 5 {
+            return Ok(None);
+        }
+
+        let length = u32::from_le_bytes([
+            self.buffer[1],
+            self.buffer[2],
+            self.buffer[3],
+            self.buffer[4],
+        ]) as usize;
+        if length > MAX_RECORD_SIZE {
+            return Err(DeviceSysLogError::UnexpectedResponse(format!(
+                "os_trace record of {} bytes",
+                length
+            )));
+        }
+        if self.buffer.len() < 5 + length {
+            return Ok(None);
+        }
+
+        let record = self.buffer[5..5 + length].to_vec();
+        self.buffer.drain(..5 + length);
+        Ok(Some(record))
+    }
+}
+
+/// Starts the os_trace relay service on the given device and sends the `StartActivity` request.
+fn start_activity(device: &idevice::Device) -> Result<ServiceClient<'_>, DeviceSysLogError> {
+    let mut lockdownd = LockdowndClient::new(device, crate::config::label("lockdownd-client"))?;
+    let lockdownd_service = lockdownd.start_service(OS_TRACE_RELAY_SERVICE, true)?;
+    let service = ServiceClient::new(device, lockdownd_service)?;
+
+    plist_service::send_plist_xml(&service, START_ACTIVITY_REQUEST)?;
+
+    // Unlike the requests, the response is prefixed with the size of its length, then the length, both little-endian
+    let length_size = plist_service::receive_exact(&service, 4)?;
+    let length_size = u32::from_le_bytes([
+        length_size[0],
+        length_size[1],
+        length_size[2],
+        length_size[3],
+    ]) as usize;
+    if length_size > std::mem::size_of::<u64>() {
+        return Err(DeviceSysLogError::UnexpectedResponse(format!(
+            "StartActivity response length of {} bytes",
+            length_size
+        )));
+    }
+    let length = plist_service::receive_exact(&service, length_size)?
+        .iter()
+        .rev()
+        .fold(0u64, |length, &byte| (length << 8) | byte as u64);
+    if length > MAX_RECORD_SIZE as u64 {
+        return Err(DeviceSysLogError::UnexpectedResponse(format!(
+            "StartActivity response of {} bytes",
+            length
+        )));
+    }
+
+    let response = Plist::from_memory(plist_service::receive_exact(&service, length as usize)?)?;
+    let status = response.dict_get_item("Status")?.get_string_val()?;
+    if status != "RequestSuccessful" {
+        return Err(DeviceSysLogError::UnexpectedResponse(status));
+    }
+
+    Ok(service)
+}
+
+/// Spawns the thread reading the os_trace records of the device.
+///
+/// The entries are filtered through their `LogsData` view, and the thread stops on `LoggerCommand::StopLogging`.
+/// A lost connection is got back following the reconnection settings, the same way as the syslog relay.
+pub(crate) fn spawn_worker(
+    mut device: idevice::Device,
+    filter: Arc<LogFilterChain>,
+    reconnect: ReconnectConfig,
+    callback: impl Fn(&OsTraceEntry) + 'static + Sync + Send,
+    on_idle: Option<Box<dyn Fn() + Send>>,
+    command_receiver: Receiver<LoggerCommand>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let udid = device.get_udid();
+        let mut current_status: LoggerCommand = LoggerCommand::StopLogging;
+
+        // Why the connection was lost, reported if it's not got back
+        let mut last_error = String::new();
+
+        let mut connected_once = false;
+        let mut attempts: u32 = 0;
+
+        'connection: loop {
+            match start_activity(&device) {
+                Ok(service) => {
+                    if attempts > 0 {
+                        reconnect.notify(&udid, ReconnectEvent::Reconnected);
+                        attempts = 0;
+                    }
+                    connected_once = true;
+
+                    let mut frames = FrameReader::default();
+
+                    'log: loop {
+                        if let Ok(command) = command_receiver.try_recv() {
+                            current_status = command;
+                        }
+
+                        match current_status {
+                            // Using a timeout so the commands are checked even if the device is quiet
+                            LoggerCommand::StartLogging => {
+                                match service.receive_with_timeout(4096, 500) {
+                                    Ok(data) => {
+                                        frames.push(&data);
+
+                                        loop {
+                                            let record = match frames.next_record() {
+                                                Ok(Some(record)) => record,
+                                                Ok(None) => break,
+                                                Err(err) => {
+                                                    if reconnect.policy.is_none() {
+                                                        log::warn!(udid:% = udid, error:% = err; "Lost the os_trace records framing");
+                                                    }
+                                                    last_error = err.to_string();
+                                                    reconnect.notify(
+                                                        &udid,
+                                                        ReconnectEvent::Disconnected(
+                                                            err.to_string(),
+                                                        ),
+                                                    );
+                                                    break 'log;
+                                                }
+                                            };
+                                            let Some(entry) = OsTraceEntry::parse(&record) else {
+                                                continue;
+                                            };
+
+                                            match entry.with_logs_data(|logs_data| {
+                                                filter.apply(&logs_data)
+                                            }) {
+                                                LogAction::Continue => (),
+                                                LogAction::Break => {
+                                                    callback(&entry);
+                                                    break 'connection;
+                                                }
+                                                LogAction::Log => callback(&entry),
+                                            }
+                                        }
+                                    }
+                                    Err(ServiceError::Timeout) => {
+                                        if let Some(on_idle) = &on_idle {
+                                            on_idle();
+                                        }
+                                    }
+                                    Err(err) => {
+                                        if reconnect.policy.is_none() {
+                                            log::warn!(udid:% = udid, error:% = err; "Failed to receive the os_trace records");
+                                        }
+                                        last_error = err.to_string();
+                                        reconnect.notify(
+                                            &udid,
+                                            ReconnectEvent::Disconnected(err.to_string()),
+                                        );
+                                        break 'log;
+                                    }
+                                }
+                            }
+                            LoggerCommand::StopLogging => break 'connection,
+                        }
+                    }
+                }
+                Err(err) if !connected_once => {
+                    log::error!(udid:% = udid, error:% = err; "Couldn't start the os_trace relay");
+                    reconnect.report(&udid, &err);
+                    break 'connection;
+                }
+                Err(_) => reconnect.notify(&udid, ReconnectEvent::AttemptFailed(attempts)),
+            }
+
+            // The connection was lost, or the last attempt to get it back failed
+            let Some(policy) = reconnect.policy else {
+                reconnect.report(&udid, &DeviceSysLogError::ServiceDied(last_error));
+                break 'connection;
+            };
+            if policy.max_attempts.is_some_and(|max| attempts >= max) {
+                reconnect.notify(&udid, ReconnectEvent::GaveUp);
+                reconnect.report(
+                    &udid,
+                    &DeviceSysLogError::ServiceDied(format!(
+                        "{}, not back after {} attempts",
+                        last_error, attempts
+                    )),
+                );
+                break 'connection;
+            }
+            attempts += 1;
+
+            // Waiting in small steps so the commands are still honored
+            let wait_start = Instant::now();
+            while wait_start.elapsed() < policy.retry_interval {
+                if let Ok(command) = command_receiver.try_recv() {
+                    current_status = command;
+                }
+                if matches!(current_status, LoggerCommand::StopLogging) {
+                    break 'connection;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+
+            // The device gets a new handle once it's plugged back
+            if let Ok(new_device) = idevice::get_device(udid.clone()) {
+                device = new_device;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: u8, subsystem: Option<(&str, &str)>) -> Vec<u8> {
+        let image_name = b"locationd\0";
+        let message = b"Location updated\0";
+        let (subsystem, category) = subsystem.unwrap_or_default();
+        let (subsystem, category) = if subsystem.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            (
+                [subsystem.as_bytes(), b"\0"].concat(),
+                [category.as_bytes(), b"\0"].concat(),
+            )
+        };
+
+        let mut record = vec![0; RECORD_HEADER_SIZE];
+        record[9..13].copy_from_slice(&123u32.to_le_bytes());
+        record[55..59].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+        record[63..67].copy_from_slice(&250_000u32.to_le_bytes());
+        record[68] = level;
+        record[107..109].copy_from_slice(&(image_name.len() as u16).to_le_bytes());
+        record[109..111].copy_from_slice(&(message.len() as u16).to_le_bytes());
+        record[117..121].copy_from_slice(&(subsystem.len() as u32).to_le_bytes());
+        record[121..125].copy_from_slice(&(category.len() as u32).to_le_bytes());
+
+        record.extend_from_slice(b"/usr/libexec/locationd\0");
+        record.extend_from_slice(image_name);
+        record.extend_from_slice(message);
+        record.extend_from_slice(&subsystem);
+        record.extend_from_slice(&category);
+        record
+    }
+
+    #[test]
+    fn parses_a_record() {
+        let entry =
+            OsTraceEntry::parse(&record(0x10, Some(("com.apple.locationd", "Core")))).unwrap();
+
+        let timestamp = DateTime::from_timestamp(1_700_000_000, 250_000_000)
+            .unwrap()
+            .with_timezone(&Local)
+            .naive_local();
+
+        assert_eq!(entry.pid, 123);
+        assert_eq!(entry.timestamp, timestamp);
+        assert_eq!(entry.level, OsTraceLevel::Error);
+        assert_eq!(entry.filename, "/usr/libexec/locationd");
+        assert_eq!(entry.process(), "locationd");
+        assert_eq!(entry.image_name, "locationd");
+        assert_eq!(entry.message, "Location updated");
+        assert_eq!(entry.subsystem.as_deref(), Some("com.apple.locationd"));
+        assert_eq!(entry.category.as_deref(), Some("Core"));
+    }
+
+    #[test]
+    fn parses_a_record_without_subsystem() {
+        let entry = OsTraceEntry::parse(&record(0x42, None)).unwrap();
+
+        assert_eq!(entry.level, OsTraceLevel::Unknown(0x42));
+        assert_eq!(entry.message, "Location updated");
+        assert_eq!(entry.subsystem, None);
+        assert_eq!(entry.category, None);
+    }
+
+    #[test]
+    fn rejects_the_truncated_records() {
+        let record = record(0x00, Some(("com.apple.locationd", "Core")));
+
+        assert_eq!(OsTraceEntry::parse(&record[..RECORD_HEADER_SIZE - 1]), None);
+        assert_eq!(OsTraceEntry::parse(&record[..RECORD_HEADER_SIZE + 4]), None);
+        assert_eq!(OsTraceEntry::parse(&record[..record.len() - 1]), None);
+    }
+
+    #[test]
+    fn rejects_an_overflowing_microseconds() {
+        let mut record = record(0x00, None);
+        record[63..67].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert_eq!(OsTraceEntry::parse(&record), None);
+    }
+
+    #[test]
+    fn frame_reader_waits_for_the_whole_record() {
+        let record = record(0x00, None);
+        let mut frame = vec![FRAME_MARKER];
+        frame.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&record);
+
+        let mut reader = FrameReader::default();
+        reader.push(b"garbage");
+        reader.push(&frame[..100]);
+        assert_eq!(reader.next_record().unwrap(), None);
+
+        reader.push(&frame[100..]);
+        reader.push(&frame[..3]);
+        assert_eq!(reader.next_record().unwrap(), Some(record));
+        assert_eq!(reader.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn frame_reader_rejects_an_oversized_length() {
+        let mut reader = FrameReader::default();
+        reader.push(&[FRAME_MARKER]);
+        reader.push(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            reader.next_record(),
+            Err(DeviceSysLogError::UnexpectedResponse(_))
+        ));
+    }
+
+    #[test]
+    fn frame_reader_drops_the_bytes_without_a_frame() {
+        let mut reader = FrameReader::default();
+        reader.push(&[0xFF; 4096]);
+
+        assert_eq!(reader.next_record().unwrap(), None);
+        assert!(reader.buffer.is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+//! A bounded queue between the thread reading the device logs and the callback consuming them.
+//!
+//! Without it, the callback runs on the reading thread, so a slow callback slows down the reading
+//! and the unread logs pile up on the device side.
+
+use crate::device_syslog::{LogsData, LogsDataOwned};
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// Enum representing what to do with a new log entry when the queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OverflowPolicy {
+    /// Waits for the callback to free a slot, nothing is dropped
+    #[default]
+    Block,
+    /// Drops the oldest queued entry to make room for the new one
+    DropOldest,
+    /// Drops the new entry
+    DropNewest,
+}
+
+/// Configuration of the bounded logs queue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueConfig {
+    /// The maximum number of entries waiting for the callback
+    pub capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+}
+
+/// The queue itself, it owns the thread running the callback.
+///
+/// Dropping it lets the callback drain the remaining entries, then joins its thread.
+pub(crate) struct LogQueue {
+    sender: Option<Sender<LogsDataOwned>>,
+    receiver: Receiver<LogsDataOwned>,
+    overflow_policy: OverflowPolicy,
+    dropped_lines: Arc<AtomicU64>,
+    consumer: Option<JoinHandle<()>>,
+}
+
+impl LogQueue {
+    pub(crate) fn new(
+        config: QueueConfig,
+        callback: impl Fn(LogsData) + 'static + Sync + Send,
+        dropped_lines: Arc<AtomicU64>,
+    ) -> LogQueue {
+        // A zero capacity channel would make every entry a rendezvous
+        let (sender, receiver) = bounded::<LogsDataOwned>(config.capacity.max(1));
+        let consumer_receiver = receiver.clone();
+
+        let consumer = thread::spawn(move || {
+            for logs in consumer_receiver.iter() {
+                callback(logs.as_logs_data());
+            }
+        });
+
+        LogQueue {
+            sender: Some(sender),
+            receiver,
+            overflow_policy: config.overflow_policy,
+            dropped_lines,
+            consumer: Some(consumer),
+        }
+    }
+
+    /// Queues an entry, following the overflow policy if the queue is full.
+    pub(crate) fn push(&self, logs_data: &LogsData) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let mut logs = logs_data.to_owned();
+
+        match self.overflow_policy {
+            OverflowPolicy::Block => {
+                let _ = sender.send(logs);
+            }
+            OverflowPolicy::DropNewest => {
+                if let Err(TrySendError::Full(_)) = sender.try_send(logs) {
+                    self.dropped_lines.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            OverflowPolicy::DropOldest => {
+                while let Err(TrySendError::Full(rejected)) = sender.try_send(logs) {
+                    if self.receiver.try_recv().is_ok() {
+                        self.dropped_lines.fetch_add(1, Ordering::Relaxed);
+                    }
+                    logs = rejected;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for LogQueue {
+    fn drop(&mut self) {
+        // Disconnects the channel so the consumer stops once it drained the queue
+        self.sender.take();
+
+        if let Some(consumer) = self.consumer.take() {
+            let _ = consumer.join();
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Reconnection to the syslog service when the connection drops.
+//!
+//! The connection is lost whenever the device sleeps, locks, or is briefly unplugged,
+//! the logging thread then waits for the device to come back and starts the service again.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::device_syslog::errors::DeviceSysLogError;
+
+/// Enum representing the connection events reported while logging.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconnectEvent {
+    /// The connection to the syslog service was lost, with the reason
+    Disconnected(String),
+    /// A reconnection attempt failed, with the attempt number (starting from 1)
+    AttemptFailed(u32),
+    /// The syslog service was re-established
+    Reconnected,
+    /// The maximum number of attempts was reached, the logging stops
+    GaveUp,
+}
+
+/// Configuration of the reconnection attempts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    /// How long to wait between two attempts
+    pub retry_interval: Duration,
+
+    /// The maximum number of attempts before giving up, `None` retries forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            retry_interval: Duration::from_secs(1),
+            max_attempts: None,
+        }
+    }
+}
+
+pub(crate) type ReconnectCallback = Arc<dyn Fn(&str, ReconnectEvent) + Send + Sync>;
+pub(crate) type ErrorCallback = Arc<dyn Fn(&str, &DeviceSysLogError) + Send + Sync>;
+
+/// The reconnection settings of a `DeviceSysLog`, along with the callback of the fatal errors,
+/// shared with its logging threads.
+#[derive(Clone)]
+pub(crate) struct ReconnectConfig {
+    pub(crate) policy: Option<ReconnectPolicy>,
+    pub(crate) callback: Option<ReconnectCallback>,
+    pub(crate) on_error: Option<ErrorCallback>,
+}
+
+impl ReconnectConfig {
+    pub(crate) fn notify(&self, udid: &str, event: ReconnectEvent) {
+        if let Some(callback) = &self.callback {
+            callback(udid, event);
+        }
+    }
+
+    /// Reports the error the logging stopped on.
+    pub(crate) fn report(&self, udid: &str, error: &DeviceSysLogError) {
+        if let Some(on_error) = &self.on_error {
+            on_error(udid, error);
+        }
+    }
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            policy: Some(ReconnectPolicy::default()),
+            callback: None,
+            on_error: None,
+        }
+    }
+}
+
+impl fmt::Debug for ReconnectConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectConfig")
+            .field("policy", &self.policy)
+            .field(
+                "callback",
+                &self.callback.as_ref().map(|_| "Fn(&str, ReconnectEvent)"),
+            )
+            .field(
+                "on_error",
+                &self
+                    .on_error
+                    .as_ref()
+                    .map(|_| "Fn(&str, &DeviceSysLogError)"),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,113 @@
+//! An in-memory buffer keeping the most recent log entries.
+//!
+//! It's meant to be dumped when something happens (e.g., a crash or a trigger), to get the entries that
+//! led to it without persisting everything.
+
+use crate::device_syslog::{LogSink, LogsData, LogsDataOwned};
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// A buffer of the last `capacity` log entries, the older ones are dropped as the new ones come in.
+///
+/// It's cheap to clone, the clones share the same entries, so one can be given to
+/// `DeviceSysLog::set_ring_buffer` while another is dumped on demand.
+/// It can also be used as a `LogSink` to only keep the entries that passed the filters.
+#[derive(Debug, Clone)]
+pub struct LogRingBuffer {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<LogsDataOwned>>>,
+}
+
+impl LogRingBuffer {
+    pub fn new(capacity: usize) -> LogRingBuffer {
+        LogRingBuffer {
+            capacity,
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// The maximum number of kept entries
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of entries currently kept
+    pub fn len(&self) -> usize {
+        self.entries.lock().map_or(0, |entries| entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds an entry, dropping the oldest one if the buffer is full
+    pub fn push(&self, logs_data: &LogsData) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() == self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(logs_data.to_owned());
+        }
+    }
+
+    /// Returns a copy of all the kept entries, the oldest first
+    pub fn snapshot(&self) -> Vec<LogsDataOwned> {
+        self.last(self.capacity)
+    }
+
+    /// Returns a copy of the last `count` entries, the oldest first
+    pub fn last(&self, count: usize) -> Vec<LogsDataOwned> {
+        self.entries
+            .lock()
+            .map(|entries| {
+                let skip = entries.len().saturating_sub(count);
+                entries.iter().skip(skip).cloned().collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Removes all the kept entries
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Writes all the kept entries to a file, one per line, returning the number of written entries.
+    ///
+    /// # Arguments
+    /// - `file_path`: Path to the file, it's appended to if it exists.
+    pub fn dump_to_file<S>(&self, file_path: &S) -> io::Result<usize>
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(file_path.as_ref())?;
+        let mut writer = BufWriter::new(file);
+
+        let entries = self.snapshot();
+        for entry in &entries {
+            writeln!(writer, "{}", entry.as_logs_data().get_parsed_log())?;
+        }
+        writer.flush()?;
+
+        Ok(entries.len())
+    }
+}
+
+impl LogSink for LogRingBuffer {
+    fn write(&mut self, logs_data: &LogsData) {
+        self.push(logs_data);
+    }
+}
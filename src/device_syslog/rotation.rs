@@ -0,0 +1,276 @@
+//! Rotation and compression of the log files written by `FileSink`.
+//!
+//! Rotated files are named after the log file with an index suffix, `device.log.1` being the most recent,
+//! and the oldest ones are removed once there are more than `max_files`.
+//! Compressed files get an extra `.gz` suffix (e.g., `device.log.1.gz`).
+
+use flate2::{write::GzEncoder, Compression as GzLevel};
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Configuration of when and how the log files are rotated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RotationPolicy {
+    /// Rotates once the file would grow past this size, in bytes
+    pub max_size: Option<u64>,
+
+    /// Rotates when the (UTC) day changes
+    pub daily: bool,
+
+    /// The number of rotated files to keep, 0 discards the content on rotation
+    pub max_files: usize,
+}
+
+impl RotationPolicy {
+    /// Rotates once the file reaches `max_size` bytes, keeping `max_files` rotated files
+    pub fn by_size(max_size: u64, max_files: usize) -> RotationPolicy {
+        RotationPolicy {
+            max_size: Some(max_size),
+            daily: false,
+            max_files,
+        }
+    }
+
+    /// Rotates every day, keeping `max_files` rotated files
+    pub fn daily(max_files: usize) -> RotationPolicy {
+        RotationPolicy {
+            max_size: None,
+            daily: true,
+            max_files,
+        }
+    }
+
+    /// Checks whether a file must be rotated before writing `incoming` more bytes to it.
+    ///
+    /// # Arguments
+    /// - `current_size`: The current size of the file.
+    /// - `incoming`: The number of bytes about to be written.
+    /// - `file_day`: The day the file was started at, as returned by `current_day`.
+    pub(crate) fn should_rotate(&self, current_size: u64, incoming: u64, file_day: u64) -> bool {
+        let too_big = self
+            .max_size
+            .is_some_and(|max_size| current_size > 0 && current_size + incoming > max_size);
+        let new_day = self.daily && current_day() != file_day;
+
+        too_big || new_day
+    }
+}
+
+/// Enum representing which log files a `FileSink` compresses with gzip.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Compression {
+    /// Nothing is compressed
+    #[default]
+    None,
+    /// Only the rotated files are compressed (`device.log.1.gz`)
+    Rotated,
+    /// The rotated files, and the log file itself once the logging is done (`device.log.gz`)
+    All,
+}
+
+impl Compression {
+    pub(crate) fn rotated(&self) -> bool {
+        *self != Compression::None
+    }
+}
+
+/// Returns the number of days since the UNIX epoch (UTC).
+pub(crate) fn current_day() -> u64 {
+    day_of(SystemTime::now())
+}
+
+/// Returns the day a file was last modified at, or today if it's unknown.
+pub(crate) fn file_day(file_path: &Path) -> u64 {
+    fs::metadata(file_path)
+        .and_then(|metadata| metadata.modified())
+        .map_or_else(|_| current_day(), day_of)
+}
+
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / SECONDS_PER_DAY)
+}
+
+/// Returns the path of the rotated file with the given index (e.g., `device.log.2` or `device.log.2.gz`).
+pub(crate) fn rotated_path(file_path: &Path, index: usize, compressed: bool) -> PathBuf {
+    let suffix = if compressed {
+        format!(".{}.gz", index)
+    } else {
+        format!(".{}", index)
+    };
+    with_suffix(file_path, &suffix)
+}
+
+fn with_suffix(file_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(file_path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Shifts the rotated files by one and moves the current file to index 1,
+/// removing the ones past `max_files`.
+///
+/// If `compressed` is set, the current file is gzipped on the way.
+/// `fs::rename` replaces an existing destination on every platform, Windows included.
+pub(crate) fn rotate_files(file_path: &Path, max_files: usize, compressed: bool) -> io::Result<()> {
+    if max_files == 0 {
+        return remove_if_exists(file_path);
+    }
+
+    remove_if_exists(&rotated_path(file_path, max_files, compressed))?;
+
+    for index in (1..max_files).rev() {
+        let from = rotated_path(file_path, index, compressed);
+        if from.exists() {
+            fs::rename(&from, rotated_path(file_path, index + 1, compressed))?;
+        }
+    }
+
+    if !file_path.exists() {
+        return Ok(());
+    }
+
+    let first = rotated_path(file_path, 1, compressed);
+    if compressed {
+        // Compressed next to the destination first, so a failure never leaves a partial `.gz` behind
+        let temp_path = with_suffix(&first, ".tmp");
+        gzip_to(file_path, &temp_path, false)?;
+        fs::rename(&temp_path, &first)?;
+        fs::remove_file(file_path)
+    } else {
+        fs::rename(file_path, &first)
+    }
+}
+
+/// Compresses a finished log file to `<file>.gz` and removes the original.
+///
+/// If the `.gz` file already exists, the new content is appended to it as another gzip member,
+/// which the gzip tools read back as a single stream.
+pub(crate) fn compress_finished(file_path: &Path) -> io::Result<()> {
+    if !file_path.exists() {
+        return Ok(());
+    }
+
+    gzip_to(file_path, &with_suffix(file_path, ".gz"), true)?;
+    fs::remove_file(file_path)
+}
+
+fn gzip_to(from: &Path, to: &Path, append: bool) -> io::Result<()> {
+    let mut input = File::open(from)?;
+    let output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(to)?;
+
+    let mut encoder = GzEncoder::new(output, GzLevel::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()
+}
+
+fn remove_if_exists(file_path: &Path) -> io::Result<()> {
+    match fs::remove_file(file_path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+    use std::io::Read;
+
+    #[test]
+    fn names_the_rotated_files_after_the_log_file() {
+        let file_path = Path::new("logs/device.log");
+
+        assert_eq!(
+            rotated_path(file_path, 1, false),
+            Path::new("logs/device.log.1")
+        );
+        assert_eq!(
+            rotated_path(file_path, 12, true),
+            Path::new("logs/device.log.12.gz")
+        );
+    }
+
+    #[test]
+    fn rotates_by_size_only_past_the_limit() {
+        let policy = RotationPolicy::by_size(100, 3);
+        let today = current_day();
+
+        assert!(!policy.should_rotate(0, 500, today));
+        assert!(!policy.should_rotate(60, 40, today));
+        assert!(policy.should_rotate(60, 41, today));
+    }
+
+    #[test]
+    fn rotates_daily_on_a_new_day() {
+        let policy = RotationPolicy::daily(3);
+        let today = current_day();
+
+        assert!(!policy.should_rotate(1_000_000, 100, today));
+        assert!(policy.should_rotate(0, 0, today - 1));
+    }
+
+    #[test]
+    fn shifts_the_rotated_files_and_drops_the_oldest() {
+        let dir = TempDir::create("shift").unwrap();
+        let file_path = dir.path().join("device.log");
+
+        for content in ["first", "second", "third"] {
+            fs::write(&file_path, content).unwrap();
+            rotate_files(&file_path, 2, false).unwrap();
+        }
+
+        assert!(!file_path.exists());
+        assert_eq!(
+            fs::read_to_string(rotated_path(&file_path, 1, false)).unwrap(),
+            "third"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&file_path, 2, false)).unwrap(),
+            "second"
+        );
+        assert!(!rotated_path(&file_path, 3, false).exists());
+    }
+
+    #[test]
+    fn compresses_the_rotated_file() {
+        let dir = TempDir::create("compress").unwrap();
+        let file_path = dir.path().join("device.log");
+
+        fs::write(&file_path, "compressed").unwrap();
+        rotate_files(&file_path, 1, true).unwrap();
+
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(File::open(rotated_path(&file_path, 1, true)).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+
+        assert_eq!(content, "compressed");
+        assert!(!file_path.exists());
+        assert!(!with_suffix(&rotated_path(&file_path, 1, true), ".tmp").exists());
+    }
+
+    #[test]
+    fn discards_the_content_without_rotated_files() {
+        let dir = TempDir::create("discard").unwrap();
+        let file_path = dir.path().join("device.log");
+
+        fs::write(&file_path, "discarded").unwrap();
+        rotate_files(&file_path, 0, false).unwrap();
+
+        assert!(!file_path.exists());
+        assert!(!rotated_path(&file_path, 1, false).exists());
+    }
+}
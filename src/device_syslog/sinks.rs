@@ -0,0 +1,404 @@
+//! Destinations for the device logs.
+//!
+//! A `LogSink` receives every log entry that passed the filters, the built-in sinks cover
+//! the console, files and channels, but any type implementing the trait can be used.
+
+use crate::device_syslog::{
+    colors::{ColorMode, ColorScheme},
+    formatter::{ColoredFormatter, JsonFormatter, LogFormatter, PlainFormatter, SharedFormatter},
+    rotation::{self, Compression, RotationPolicy},
+    LogsData, LogsDataOwned,
+};
+use crossbeam_channel::Sender;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Trait for types that consume the device logs.
+///
+/// Closures taking a `&LogsData` implement this trait as well.
+pub trait LogSink {
+    /// Writes a single log entry to the sink.
+    fn write(&mut self, logs_data: &LogsData);
+
+    /// Called by the logging thread whenever the device stayed quiet for a while (about half a second),
+    /// e.g., to write out the buffered entries. Does nothing by default.
+    fn idle(&mut self) {}
+}
+
+impl<F> LogSink for F
+where
+    F: FnMut(&LogsData),
+{
+    fn write(&mut self, logs_data: &LogsData) {
+        self(logs_data)
+    }
+}
+
+/// Sink that prints the logs to the console (stdout).
+///
+/// Whether it's colored is decided once, when the sink is created.
+#[derive(Debug, Clone)]
+pub struct StdoutSink {
+    formatter: SharedFormatter,
+}
+
+impl StdoutSink {
+    pub fn new(color_mode: ColorMode) -> StdoutSink {
+        StdoutSink::with_color_scheme(color_mode, ColorScheme::default())
+    }
+
+    pub fn with_color_scheme(color_mode: ColorMode, color_scheme: ColorScheme) -> StdoutSink {
+        if color_mode.use_colors() {
+            StdoutSink::with_formatter(ColoredFormatter::new(color_scheme))
+        } else {
+            StdoutSink::with_formatter(PlainFormatter)
+        }
+    }
+
+    /// Creates a sink printing the entries in the layout of the given formatter
+    pub fn with_formatter(formatter: impl LogFormatter + 'static) -> StdoutSink {
+        StdoutSink {
+            formatter: SharedFormatter::new(formatter),
+        }
+    }
+}
+
+impl Default for StdoutSink {
+    fn default() -> Self {
+        StdoutSink::new(ColorMode::Auto)
+    }
+}
+
+impl LogSink for StdoutSink {
+    fn write(&mut self, logs_data: &LogsData) {
+        println!("{}", self.formatter.format(logs_data));
+    }
+}
+
+/// Enum representing the layout of the log entries written by a `FileSink`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FileFormat {
+    /// The plain `[date] device process [pid] <severity>: message` layout
+    #[default]
+    Plain,
+    /// One JSON object per line, suitable for log ingestion pipelines
+    JsonLines,
+}
+
+impl FileFormat {
+    fn formatter(&self) -> SharedFormatter {
+        match self {
+            FileFormat::Plain => SharedFormatter::new(PlainFormatter),
+            FileFormat::JsonLines => SharedFormatter::new(JsonFormatter),
+        }
+    }
+}
+
+const DEFAULT_FLUSH_LINES: usize = 100;
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sink that appends the logs to a file, the file is created if it doesn't exist.
+///
+/// The file is kept open and written through a buffer, which is flushed every `flush_lines` entries,
+/// once `flush_interval` passed since the last flush (even if the device went quiet), and when the sink is dropped.
+///
+/// The file can be rotated with `with_rotation` so it doesn't grow forever,
+/// and compressed with `with_compression`.
+#[derive(Debug)]
+pub struct FileSink {
+    file_path: PathBuf,
+    formatter: SharedFormatter,
+    rotation: Option<RotationPolicy>,
+    compression: Compression,
+    file_day: u64,
+    flush_lines: usize,
+    flush_interval: Duration,
+    writer: Option<BufWriter<File>>,
+    resolved_path: PathBuf,
+    current_size: u64,
+    pending_lines: usize,
+    last_flush: Instant,
+}
+
+impl FileSink {
+    pub fn new<S>(file_path: &S) -> FileSink
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        FileSink::with_format(file_path, FileFormat::Plain)
+    }
+
+    /// Creates a sink that writes the logs in the JSON Lines format
+    pub fn json<S>(file_path: &S) -> FileSink
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        FileSink::with_format(file_path, FileFormat::JsonLines)
+    }
+
+    pub fn with_format<S>(file_path: &S, format: FileFormat) -> FileSink
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        FileSink {
+            file_path: file_path.as_ref().to_path_buf(),
+            formatter: format.formatter(),
+            rotation: None,
+            compression: Compression::None,
+            file_day: rotation::file_day(file_path.as_ref()),
+            flush_lines: DEFAULT_FLUSH_LINES,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            writer: None,
+            resolved_path: file_path.as_ref().to_path_buf(),
+            current_size: 0,
+            pending_lines: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Writes the entries in the layout of the given formatter, replacing the format
+    pub fn with_formatter(mut self, formatter: impl LogFormatter + 'static) -> FileSink {
+        self.formatter = SharedFormatter::new(formatter);
+        self
+    }
+
+    /// Rotates the file following the given policy
+    pub fn with_rotation(mut self, rotation: RotationPolicy) -> FileSink {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Compresses the log files with gzip.
+    ///
+    /// With `Compression::All`, the file is compressed once the sink is dropped (i.e., the logging is done),
+    /// so a cloned sink should not outlive the original one.
+    pub fn with_compression(mut self, compression: Compression) -> FileSink {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets when the buffered entries are written to the file.
+    ///
+    /// By default, it's every 100 entries or every second, `flush_lines` of 1 writes every entry right away.
+    ///
+    /// # Arguments
+    /// - `flush_lines`: The maximum number of entries kept in the buffer.
+    /// - `flush_interval`: The maximum time an entry is kept in the buffer, give or take the half a second
+    ///   between the idle checks of a quiet device.
+    pub fn with_flush_policy(mut self, flush_lines: usize, flush_interval: Duration) -> FileSink {
+        self.flush_lines = flush_lines;
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Writes the buffered entries to the file
+    pub fn flush(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(e) = writer.flush() {
+                log::error!(path:? = self.resolved_path, error:% = e; "Failed to flush the log file");
+            }
+        }
+        self.pending_lines = 0;
+        self.last_flush = Instant::now();
+    }
+
+    /// Opens the file if it isn't already, returns false if it couldn't be opened.
+    fn ensure_open(&mut self) -> bool {
+        if self.writer.is_some() {
+            return true;
+        }
+
+        self.resolved_path = match fs::canonicalize(&self.file_path) {
+            Ok(path) => path,
+            Err(_) => self.file_path.to_owned(),
+        };
+
+        match OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.resolved_path)
+        {
+            Ok(file) => {
+                self.current_size = file.metadata().map_or(0, |metadata| metadata.len());
+                self.writer = Some(BufWriter::new(file));
+                true
+            }
+            Err(e) => {
+                log::error!(path:? = self.resolved_path, error:% = e; "Failed to open the log file");
+                false
+            }
+        }
+    }
+
+    /// Flushes and closes the file, it's opened again on the next entry.
+    fn close(&mut self) {
+        self.flush();
+        self.writer = None;
+    }
+
+    /// Rotates the file if the policy requires it before writing `incoming` bytes.
+    fn rotate_if_needed(&mut self, incoming: u64) {
+        let Some(rotation) = self.rotation.clone() else {
+            return;
+        };
+
+        if !rotation.should_rotate(self.current_size, incoming, self.file_day) {
+            return;
+        }
+
+        // The file must be closed to be renamed on Windows
+        self.close();
+        if let Err(e) = rotation::rotate_files(
+            &self.resolved_path,
+            rotation.max_files,
+            self.compression.rotated(),
+        ) {
+            log::warn!(path:? = self.resolved_path, error:% = e; "Failed to rotate the log file");
+        }
+        self.file_day = rotation::current_day();
+    }
+}
+
+impl Clone for FileSink {
+    /// Clones the settings of the sink, the clone opens the file on its own
+    fn clone(&self) -> Self {
+        FileSink {
+            file_path: self.file_path.clone(),
+            formatter: self.formatter.clone(),
+            rotation: self.rotation.clone(),
+            compression: self.compression,
+            file_day: self.file_day,
+            flush_lines: self.flush_lines,
+            flush_interval: self.flush_interval,
+            writer: None,
+            resolved_path: self.resolved_path.clone(),
+            current_size: 0,
+            pending_lines: 0,
+            last_flush: Instant::now(),
+        }
+    }
+}
+
+impl LogSink for FileSink {
+    fn write(&mut self, logs_data: &LogsData) {
+        let line = self.formatter.format(logs_data) + "\n";
+
+        if !self.ensure_open() {
+            return;
+        }
+        self.rotate_if_needed(line.len() as u64);
+        if !self.ensure_open() {
+            return;
+        }
+
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        if let Err(e) = writer.write_all(line.as_bytes()) {
+            log::error!(path:? = self.resolved_path, error:% = e; "Failed to write to the log file");
+            // Opened again on the next entry, in case the file was removed or the disk got full
+            self.writer = None;
+            return;
+        }
+
+        self.current_size += line.len() as u64;
+        self.pending_lines += 1;
+
+        if self.pending_lines >= self.flush_lines
+            || self.last_flush.elapsed() >= self.flush_interval
+        {
+            self.flush();
+        }
+    }
+
+    fn idle(&mut self) {
+        if self.pending_lines > 0 && self.last_flush.elapsed() >= self.flush_interval {
+            self.flush();
+        }
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        self.close();
+
+        if self.compression != Compression::All {
+            return;
+        }
+
+        if let Err(e) = rotation::compress_finished(&self.resolved_path) {
+            log::warn!(path:? = self.resolved_path, error:% = e; "Failed to compress the log file");
+        }
+    }
+}
+
+/// Sink that sends owned copies of the log entries through a channel.
+#[derive(Debug, Clone)]
+pub struct ChannelSink {
+    sender: Sender<LogsDataOwned>,
+}
+
+impl ChannelSink {
+    pub fn new(sender: Sender<LogsDataOwned>) -> ChannelSink {
+        ChannelSink { sender }
+    }
+}
+
+impl LogSink for ChannelSink {
+    fn write(&mut self, logs_data: &LogsData) {
+        // The receiver being dropped only means nobody is interested in the logs anymore
+        let _ = self.sender.send(logs_data.to_owned());
+    }
+}
+
+/// Sink that forwards the logs as `tracing` events, so they show up along with the application's own logs.
+///
+/// The events have the `device_syslog` target, and the `process`, `pid`, `severity`, `device`, `udid`
+/// and `date` fields, the level is taken from the entry severity (`Info` if it isn't recognized).
+/// Enabling the `log` feature of `tracing` forwards them to the `log` crate as well.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Default)]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl TracingSink {
+    pub fn new() -> TracingSink {
+        TracingSink
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl LogSink for TracingSink {
+    fn write(&mut self, logs_data: &LogsData) {
+        use crate::device_syslog::LogLevel;
+
+        // The level of a tracing event must be known at compile time, hence the macro
+        macro_rules! forward {
+            ($level:expr) => {
+                tracing::event!(
+                    target: "device_syslog",
+                    $level,
+                    process = logs_data.process,
+                    pid = logs_data.pid,
+                    severity = logs_data.severity,
+                    device = logs_data.device,
+                    udid = logs_data.udid,
+                    date = logs_data.date,
+                    "{}",
+                    logs_data.message
+                )
+            };
+        }
+
+        match logs_data.level() {
+            Some(LogLevel::Debug) => forward!(tracing::Level::DEBUG),
+            Some(LogLevel::Info | LogLevel::Notice) | None => forward!(tracing::Level::INFO),
+            Some(LogLevel::Warning) => forward!(tracing::Level::WARN),
+            Some(LogLevel::Error | LogLevel::Fault) => forward!(tracing::Level::ERROR),
+        }
+    }
+}
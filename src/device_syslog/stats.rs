@@ -0,0 +1,157 @@
+//! Statistics about the received logs, to find out what's flooding a device.
+//!
+//! A `LogStats` counts the entries per process and per severity since it was created (or reset),
+//! and keeps the per process counts of the last `window` to rank the noisiest processes.
+
+use crate::device_syslog::{LogLevel, LogSink, LogsData};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+/// The counts of a `LogStats` at a given time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogStatsSnapshot {
+    /// The total number of entries
+    pub total: u64,
+
+    /// The number of entries per process
+    pub per_process: HashMap<String, u64>,
+
+    /// The number of entries per severity, for the entries with a recognized severity
+    pub per_severity: HashMap<LogLevel, u64>,
+
+    /// The number of entries without a recognized severity
+    pub unknown_severity: u64,
+}
+
+#[derive(Debug)]
+struct StatsInner {
+    snapshot: LogStatsSnapshot,
+    started: Instant,
+    /// Per process counts of each second of the window, the oldest first
+    recent: VecDeque<(u64, HashMap<String, u64>)>,
+}
+
+/// A collector of statistics about the logs.
+///
+/// It's cheap to clone, the clones share the same counts, so one can be given to
+/// `DeviceSysLog::collect_stats` while another is read on demand.
+/// It can also be used as a `LogSink` to only count the entries that passed the filters.
+#[derive(Debug, Clone)]
+pub struct LogStats {
+    window: Duration,
+    inner: Arc<Mutex<StatsInner>>,
+}
+
+impl Default for LogStats {
+    fn default() -> Self {
+        LogStats::new(DEFAULT_WINDOW)
+    }
+}
+
+impl LogStats {
+    /// Creates a collector ranking the noisiest processes over the given sliding window
+    pub fn new(window: Duration) -> LogStats {
+        LogStats {
+            window,
+            inner: Arc::new(Mutex::new(StatsInner {
+                snapshot: LogStatsSnapshot::default(),
+                started: Instant::now(),
+                recent: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Counts a log entry
+    pub fn record(&self, logs_data: &LogsData) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        inner.snapshot.total += 1;
+        *inner
+            .snapshot
+            .per_process
+            .entry(logs_data.process.to_string())
+            .or_default() += 1;
+        match logs_data.level() {
+            Some(level) => *inner.snapshot.per_severity.entry(level).or_default() += 1,
+            None => inner.snapshot.unknown_severity += 1,
+        }
+
+        let second = inner.started.elapsed().as_secs();
+        self.prune(&mut inner, second);
+        match inner.recent.back_mut() {
+            Some((bucket_second, counts)) if *bucket_second == second => {
+                *counts.entry(logs_data.process.to_string()).or_default() += 1;
+            }
+            _ => {
+                let counts = HashMap::from([(logs_data.process.to_string(), 1)]);
+                inner.recent.push_back((second, counts));
+            }
+        }
+    }
+
+    /// Returns the counts since the collector was created or reset
+    pub fn snapshot(&self) -> LogStatsSnapshot {
+        self.inner
+            .lock()
+            .map(|inner| inner.snapshot.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the `count` processes with the most entries over the sliding window, the noisiest first
+    pub fn top_processes(&self, count: usize) -> Vec<(String, u64)> {
+        let Ok(mut inner) = self.inner.lock() else {
+            return Vec::new();
+        };
+
+        let second = inner.started.elapsed().as_secs();
+        self.prune(&mut inner, second);
+
+        let mut totals: HashMap<&str, u64> = HashMap::new();
+        for (_, counts) in &inner.recent {
+            for (process, process_count) in counts {
+                *totals.entry(process).or_default() += process_count;
+            }
+        }
+
+        let mut totals: Vec<(String, u64)> = totals
+            .into_iter()
+            .map(|(process, process_count)| (process.to_string(), process_count))
+            .collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals.truncate(count);
+        totals
+    }
+
+    /// Clears all the counts
+    pub fn reset(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.snapshot = LogStatsSnapshot::default();
+            inner.recent.clear();
+        }
+    }
+
+    /// Drops the buckets that went out of the window
+    fn prune(&self, inner: &mut StatsInner, second: u64) {
+        let window = self.window.as_secs().max(1);
+        while inner
+            .recent
+            .front()
+            .is_some_and(|(bucket_second, _)| second - bucket_second >= window)
+        {
+            inner.recent.pop_front();
+        }
+    }
+}
+
+impl LogSink for LogStats {
+    fn write(&mut self, logs_data: &LogsData) {
+        self.record(logs_data);
+    }
+}
@@ -0,0 +1,105 @@
+//! Parsing of the log entries dates.
+//!
+//! The syslog dates look like `Jan  3 12:00:01`, without a year, so it's inferred from a reference date
+//! (usually now), an entry that would be in the future is considered to be from the previous year,
+//! which handles the logs from December read in January.
+
+use chrono::{Datelike, Duration, Local, NaiveDateTime};
+
+const LOG_DATE_FORMAT: &str = "%Y %b %d %H:%M:%S";
+
+/// Parses a raw log date (e.g., "Jan  3 12:00:01"), inferring its year from the current local date.
+pub fn parse_log_date(raw_date: &str) -> Option<NaiveDateTime> {
+    parse_log_date_relative_to(raw_date, Local::now().naive_local())
+}
+
+/// Parses a raw log date (e.g., "Jan  3 12:00:01"), inferring its year from the given reference date.
+///
+/// # Arguments
+/// - `raw_date`: The date as found in the log entry.
+/// - `reference`: The date the log entry was received at, the result is never more than a day after it.
+pub fn parse_log_date_relative_to(
+    raw_date: &str,
+    reference: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    // The day is padded with spaces, which chrono doesn't accept as is
+    let raw_date = raw_date.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // A day of margin, for the clocks of the device and the host that don't agree
+    let latest = reference + Duration::days(1);
+
+    [reference.year(), reference.year() - 1]
+        .into_iter()
+        .filter_map(|year| {
+            NaiveDateTime::parse_from_str(&format!("{} {}", year, raw_date), LOG_DATE_FORMAT).ok()
+        })
+        .find(|date| *date <= latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, min, sec))
+            .unwrap()
+    }
+
+    #[test]
+    fn takes_the_year_of_the_reference() {
+        assert_eq!(
+            parse_log_date_relative_to("Jun 14 09:30:00", date(2024, 6, 14, 10, 0, 0)),
+            Some(date(2024, 6, 14, 9, 30, 0))
+        );
+    }
+
+    #[test]
+    fn accepts_the_padded_days() {
+        assert_eq!(
+            parse_log_date_relative_to("Jan  3 12:00:01", date(2024, 1, 5, 0, 0, 0)),
+            Some(date(2024, 1, 3, 12, 0, 1))
+        );
+    }
+
+    #[test]
+    fn puts_the_december_entries_read_in_january_in_the_previous_year() {
+        assert_eq!(
+            parse_log_date_relative_to("Dec 31 23:59:59", date(2025, 1, 1, 0, 0, 5)),
+            Some(date(2024, 12, 31, 23, 59, 59))
+        );
+    }
+
+    #[test]
+    fn allows_a_day_of_clock_skew() {
+        assert_eq!(
+            parse_log_date_relative_to("Mar  2 08:00:00", date(2024, 3, 1, 12, 0, 0)),
+            Some(date(2024, 3, 2, 8, 0, 0))
+        );
+        assert_eq!(
+            parse_log_date_relative_to("Mar  3 08:00:00", date(2024, 3, 1, 12, 0, 0)),
+            Some(date(2023, 3, 3, 8, 0, 0))
+        );
+    }
+
+    #[test]
+    fn finds_the_leap_day_in_the_previous_year() {
+        assert_eq!(
+            parse_log_date_relative_to("Feb 29 10:00:00", date(2025, 1, 10, 0, 0, 0)),
+            Some(date(2024, 2, 29, 10, 0, 0))
+        );
+    }
+
+    #[test]
+    fn rejects_the_malformed_dates() {
+        let reference = date(2024, 6, 14, 10, 0, 0);
+
+        assert_eq!(parse_log_date_relative_to("", reference), None);
+        assert_eq!(
+            parse_log_date_relative_to("Foo 14 09:30:00", reference),
+            None
+        );
+        assert_eq!(parse_log_date_relative_to("Jun 14", reference), None);
+    }
+}
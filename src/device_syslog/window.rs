@@ -0,0 +1,98 @@
+//! Capturing a window of logs around a trigger, like `idevicesyslog --trigger/--untrigger`.
+
+use crate::device_syslog::LogsDataOwned;
+use regex::Regex;
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+/// Enum representing when a triggered window ends.
+#[derive(Debug, Clone)]
+pub enum WindowEnd {
+    /// Ends once an entry matches the pattern, that entry is included
+    Untrigger(Regex),
+    /// Ends after the given number of entries following the trigger
+    Lines(usize),
+}
+
+/// State of a window capture, fed with every log entry.
+///
+/// Entries are kept in a ring of `context_lines` until the trigger matches, then the ring
+/// and everything after it are written up until the window ends.
+pub(crate) struct WindowCapture<W: Write> {
+    trigger: Regex,
+    end: WindowEnd,
+    context_lines: usize,
+    context: VecDeque<String>,
+    triggered: bool,
+    lines_after_trigger: usize,
+    lines_written: usize,
+    writer: W,
+}
+
+impl<W: Write> WindowCapture<W> {
+    pub(crate) fn new(
+        trigger: Regex,
+        end: WindowEnd,
+        context_lines: usize,
+        writer: W,
+    ) -> WindowCapture<W> {
+        WindowCapture {
+            trigger,
+            end,
+            context_lines,
+            context: VecDeque::with_capacity(context_lines),
+            triggered: false,
+            lines_after_trigger: 0,
+            lines_written: 0,
+            writer,
+        }
+    }
+
+    /// Feeds an entry to the capture, returning whether the window has ended.
+    pub(crate) fn feed(&mut self, logs: &LogsDataOwned) -> io::Result<bool> {
+        let line = logs.as_logs_data().get_parsed_log();
+
+        if !self.triggered {
+            if !self.trigger.is_match(&line) {
+                if self.context_lines > 0 {
+                    if self.context.len() == self.context_lines {
+                        self.context.pop_front();
+                    }
+                    self.context.push_back(line);
+                }
+                return Ok(false);
+            }
+
+            self.triggered = true;
+            while let Some(context_line) = self.context.pop_front() {
+                self.write_line(&context_line)?;
+            }
+            self.write_line(&line)?;
+
+            return Ok(matches!(self.end, WindowEnd::Lines(0)));
+        }
+
+        self.write_line(&line)?;
+        self.lines_after_trigger += 1;
+
+        Ok(match &self.end {
+            WindowEnd::Untrigger(pattern) => pattern.is_match(&line),
+            WindowEnd::Lines(count) => self.lines_after_trigger >= *count,
+        })
+    }
+
+    /// Flushes the written window, returning the number of written entries.
+    pub(crate) fn finish(mut self) -> io::Result<usize> {
+        self.writer.flush()?;
+        Ok(self.lines_written)
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.lines_written += 1;
+        Ok(())
+    }
+}
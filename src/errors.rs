@@ -1,10 +1,35 @@
-use rusty_libimobiledevice::error::{AfcError, IdeviceError, LockdowndError};
+use plist_plus::error::PlistError;
+use rusty_libimobiledevice::error::{AfcError, IdeviceError, LockdowndError, ServiceError};
 use thiserror::Error;
 
-pub use crate::{
-    device_diagnostic::errors::DeviceDiagnosticError, device_info::errors::DeviceInfoError,
-    device_installer::errors::DeviceInstallerError, device_syslog::errors::DeviceSysLogError,
-};
+use crate::device_info::kind::DeviceKind;
+
+pub use crate::{device_info::errors::DeviceInfoError, pair_record::errors::PairRecordError};
+
+#[cfg(feature = "afc")]
+pub use crate::device_afc::errors::DeviceAfcError;
+#[cfg(feature = "debugserver")]
+pub use crate::device_debugserver::errors::DeviceDebugServerError;
+#[cfg(feature = "diagnostic")]
+pub use crate::device_diagnostic::errors::DeviceDiagnosticError;
+#[cfg(feature = "image_mounter")]
+pub use crate::device_image_mounter::errors::DeviceImageMounterError;
+#[cfg(feature = "installer")]
+pub use crate::device_installer::errors::DeviceInstallerError;
+#[cfg(feature = "instruments")]
+pub use crate::device_instruments::errors::DeviceInstrumentsError;
+#[cfg(feature = "notification")]
+pub use crate::device_notification::errors::DeviceNotificationError;
+#[cfg(feature = "preboard")]
+pub use crate::device_preboard::errors::DevicePreboardError;
+#[cfg(feature = "screenshot")]
+pub use crate::device_screenshot::errors::DeviceScreenshotError;
+#[cfg(feature = "springboard")]
+pub use crate::device_springboard::errors::DeviceSpringboardError;
+#[cfg(feature = "syslog")]
+pub use crate::device_syslog::errors::DeviceSysLogError;
+#[cfg(feature = "backup")]
+pub use crate::{backup::errors::BackupError, device_backup::errors::DeviceBackupError};
 
 pub trait DeviceNotFoundErrorTrait {
     fn device_not_found() -> Self;
@@ -18,6 +43,16 @@ pub trait AFCClientErrorTrait {
     fn afcclient_error(error: AfcError) -> Self;
 }
 
+pub trait UnsupportedDeviceErrorTrait {
+    fn unsupported_device(kind: DeviceKind) -> Self;
+}
+
+/// The errors of the services speaking DeviceLink, see `plist_service::DeviceLinkClient`
+#[cfg(any(feature = "backup", feature = "screenshot"))]
+pub(crate) trait UnexpectedResponseErrorTrait {
+    fn unexpected_response(description: String) -> Self;
+}
+
 #[derive(Debug, Error)]
 pub enum DeviceClientError {
     #[error("IDevice Error: {0}")]
@@ -31,6 +66,46 @@ pub enum DeviceClientError {
 
     #[error("AFC Client Error: {0}")]
     AFCClientError(#[from] AfcError),
+
+    #[error("Service Error: {0}")]
+    ServiceError(#[from] ServiceError),
+
+    #[error("Plist Error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("The device is locked, unlock it or use the escrow bag")]
+    DeviceLocked,
+
+    #[error("Invalid device name: {0}")]
+    InvalidDeviceName(String),
+
+    #[error("The device name wasn't applied, it's still `{0}`")]
+    DeviceNameNotApplied(String),
+
+    #[error("The lockdownd request has no `Request` key")]
+    InvalidLockdowndRequest,
+
+    #[error("The lockdownd request `{0}` failed: {1}")]
+    LockdowndRequestFailed(String, String),
+
+    #[error("The device refused the power assertion: {0}")]
+    PowerAssertionRefused(String),
+
+    #[error("Pair Record Error: {0}")]
+    PairRecordError(#[from] PairRecordError),
+}
+
+/// The reason an operation of a `FleetExecutor` run didn't succeed on a device.
+#[derive(Debug, Error)]
+pub enum FleetError<E> {
+    #[error("The operation failed: {0}")]
+    Failed(E),
+
+    #[error("The operation timed out")]
+    TimedOut,
+
+    #[error("The operation panicked")]
+    Panicked,
 }
 
 impl LockdowndErrorTrait for DeviceClientError {
@@ -0,0 +1,299 @@
+//! A bus for the events of the devices, delivered to any number of subscribers.
+//!
+//! Instead of wiring each monitor to each consumer, the monitors publish on a shared `EventBus`,
+//! and each consumer subscribes to get its own channel of every event.
+//!
+//! ## Features
+//! - Devices plugged and unplugged, and the changes of their pairing, polled in the background
+//! - Low battery alerts of a device
+//! - The connection events and failures of the syslog logging
+//!
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rusty_libimobiledevice::{
+    idevice::{self, Device},
+    services::lockdownd::LockdowndClient,
+};
+
+#[cfg(feature = "syslog")]
+use crate::device_syslog::{DeviceSysLog, ReconnectEvent};
+use crate::{
+    device::DeviceClient, device_info::domains::DeviceDomains, devices_collection::SingleDevice,
+    pair_record::PairRecordFiles,
+};
+
+/// Enum representing the events published on an `EventBus`, each one along with the UDID of its device.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceEvent {
+    /// A device was plugged, or came back on the network
+    Connected(String),
+    /// A device was unplugged, or left the network
+    Disconnected(String),
+    /// The device got paired with this host, or its pair record was removed
+    PairingChanged { udid: String, paired: bool },
+    /// The battery went under the alert threshold, with its level in percent
+    BatteryLow { udid: String, level: u64 },
+    /// The battery went back above the alert threshold (e.g., it got charged), with its level in percent
+    BatteryRecovered { udid: String, level: u64 },
+    /// A connection event of the syslog logging (disconnected, reconnected, ...)
+    #[cfg(feature = "syslog")]
+    SysLog { udid: String, event: ReconnectEvent },
+    /// The syslog logging stopped on an error
+    #[cfg(feature = "syslog")]
+    SysLogFailed { udid: String, error: String },
+}
+
+impl DeviceEvent {
+    /// The UDID of the device the event is about
+    pub fn udid(&self) -> &str {
+        match self {
+            DeviceEvent::Connected(udid) | DeviceEvent::Disconnected(udid) => udid,
+            DeviceEvent::PairingChanged { udid, .. }
+            | DeviceEvent::BatteryLow { udid, .. }
+            | DeviceEvent::BatteryRecovered { udid, .. } => udid,
+            #[cfg(feature = "syslog")]
+            DeviceEvent::SysLog { udid, .. } | DeviceEvent::SysLogFailed { udid, .. } => udid,
+        }
+    }
+}
+
+/// Struct for publishing the events of the devices to any number of subscribers.
+///
+/// The bus is cheap to clone, the clones share their subscribers, so it can be handed to every
+/// part of an application. Each subscriber gets every event published after it subscribed,
+/// the subscribers whose receiver was dropped are removed on the next event.
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<DeviceEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus::default()
+    }
+
+    /// Returns a new channel receiving the events published from now on.
+    pub fn subscribe(&self) -> Receiver<DeviceEvent> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    /// The number of subscribers, including the ones that dropped their receiver since the last event.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers
+            .lock()
+            .map(|subscribers| subscribers.len())
+            .unwrap_or_default()
+    }
+
+    /// Sends an event to every subscriber, e.g., the events of an application's own monitors.
+    pub fn publish(&self, event: DeviceEvent) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Publishes the devices being plugged and unplugged, and the changes of their pairing,
+    /// by polling the connected devices and the system pair records.
+    ///
+    /// The devices already plugged are published as `Connected` on the first poll.
+    /// This is a non blocking function, the polling runs until the returned handle is stopped or dropped.
+    ///
+    /// # Parameters
+    /// - `interval`: The time between two polls.
+    pub fn watch_devices(&self, interval: Duration) -> EventMonitor {
+        let bus = self.clone();
+
+        EventMonitor::start(move |stop| {
+            let pair_records = PairRecordFiles::system();
+            // The connected devices, with whether they're paired
+            let mut connected: HashMap<String, bool> = HashMap::new();
+
+            while !stop.load(Ordering::SeqCst) {
+                // The same device can be reached over USB and the network
+                let udids: HashSet<String> = idevice::get_devices()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(Device::get_udid)
+                    .collect();
+
+                connected.retain(|udid, _| {
+                    let still_connected = udids.contains(udid);
+                    if !still_connected {
+                        bus.publish(DeviceEvent::Disconnected(udid.clone()));
+                    }
+                    still_connected
+                });
+
+                for udid in udids {
+                    let paired = pair_records.contains(&udid);
+                    match connected.insert(udid.clone(), paired) {
+                        None => bus.publish(DeviceEvent::Connected(udid)),
+                        Some(was_paired) if was_paired != paired => {
+                            bus.publish(DeviceEvent::PairingChanged { udid, paired })
+                        }
+                        Some(_) => (),
+                    }
+                }
+
+                sleep_unless_stopped(interval, stop);
+            }
+        })
+    }
+
+    /// Publishes an alert once the battery of the device goes under the threshold,
+    /// then once it goes back above it.
+    ///
+    /// The polls that fail (e.g., the device is unplugged for a moment) are skipped.
+    /// This is a non blocking function, the polling runs until the returned handle is stopped or dropped.
+    ///
+    /// # Parameters
+    /// - `device`: The device to watch the battery of.
+    /// - `threshold`: The battery level of the alert, in percent.
+    /// - `interval`: The time between two polls.
+    pub fn watch_battery(
+        &self,
+        device: &DeviceClient<SingleDevice>,
+        threshold: u64,
+        interval: Duration,
+    ) -> EventMonitor {
+        let bus = self.clone();
+        let device = device.get_device().clone();
+
+        EventMonitor::start(move |stop| {
+            let udid = device.get_udid();
+            let mut low = false;
+
+            while !stop.load(Ordering::SeqCst) {
+                if let Some(level) = read_battery_level(&device) {
+                    if level < threshold && !low {
+                        low = true;
+                        bus.publish(DeviceEvent::BatteryLow {
+                            udid: udid.clone(),
+                            level,
+                        });
+                    } else if level >= threshold && low {
+                        low = false;
+                        bus.publish(DeviceEvent::BatteryRecovered {
+                            udid: udid.clone(),
+                            level,
+                        });
+                    }
+                }
+
+                sleep_unless_stopped(interval, stop);
+            }
+        })
+    }
+
+    /// Publishes the connection events and the failures of the logging of a `DeviceSysLog`.
+    ///
+    /// This replaces the callbacks set with `DeviceSysLog::on_reconnect_event` and `DeviceSysLog::on_error`,
+    /// and applies to the logging started after calling it.
+    #[cfg(feature = "syslog")]
+    pub fn attach_syslog<T>(&self, syslog: &mut DeviceSysLog<T>) {
+        let bus = self.clone();
+        syslog.on_reconnect_event(move |udid, event| {
+            bus.publish(DeviceEvent::SysLog {
+                udid: udid.to_string(),
+                event,
+            })
+        });
+
+        let bus = self.clone();
+        syslog.on_error(move |udid, error| {
+            bus.publish(DeviceEvent::SysLogFailed {
+                udid: udid.to_string(),
+                error: error.to_string(),
+            })
+        });
+    }
+}
+
+/// Handle to a thread publishing on an `EventBus`, returned by the `watch_*` functions.
+///
+/// Dropping the handle stops the thread and waits for it to finish.
+#[derive(Debug)]
+pub struct EventMonitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EventMonitor {
+    fn start<F>(watch: F) -> EventMonitor
+    where
+        F: FnOnce(&AtomicBool) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || watch(&thread_stop));
+
+        EventMonitor {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Checks whether the thread is still publishing.
+    pub fn is_running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Stops the thread and waits for it to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for EventMonitor {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+fn read_battery_level(device: &Device) -> Option<u64> {
+    LockdowndClient::new(device, crate::config::label("eventbus"))
+        .and_then(|lockdownd| {
+            lockdownd.get_value(
+                "BatteryCurrentCapacity",
+                DeviceDomains::MobileBattery.as_string(),
+            )
+        })
+        .ok()?
+        .get_uint_val()
+        .ok()
+}
+
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+
+    while !stop.load(Ordering::SeqCst) {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            return;
+        };
+        thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,193 @@
+//! Running an operation on every device of a group, a few devices at a time.
+//!
+//! ## Features
+//! - A bounded pool of workers, so a large fleet doesn't open hundreds of connections at once
+//! - A timeout per device, so a hanging device doesn't hold up the others
+//! - The pacing and the cap of the operations running at once from `RsMobileDeviceConfig`, shared by all the runs
+//! - The results gathered by UDID
+//!
+
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    device::DeviceClient,
+    devices_collection::{DeviceGroup, SingleDevice},
+    errors::FleetError,
+    rate_limit,
+};
+
+/// Struct for running an operation on every device of a group.
+///
+/// By default, one worker runs per available CPU and the devices have no timeout.
+#[derive(Debug, Clone)]
+pub struct FleetExecutor {
+    workers: usize,
+    timeout: Option<Duration>,
+}
+
+impl Default for FleetExecutor {
+    fn default() -> Self {
+        FleetExecutor {
+            workers: thread::available_parallelism().map_or(4, |workers| workers.get()),
+            timeout: None,
+        }
+    }
+}
+
+impl FleetExecutor {
+    pub fn new() -> FleetExecutor {
+        FleetExecutor::default()
+    }
+
+    /// Sets how many devices are handled at the same time, at least one.
+    pub fn with_workers(mut self, workers: usize) -> FleetExecutor {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Sets how long the operation can take on a single device.
+    ///
+    /// An operation can't be interrupted, so a timed out one keeps running in the background
+    /// while its worker moves on to the next device, its result is discarded.
+    pub fn with_timeout(mut self, timeout: Duration) -> FleetExecutor {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Runs the operation on every device of the group, blocking until all of them are done.
+    ///
+    /// # Parameters
+    /// - `group`: The devices to run the operation on.
+    /// - `operation`: The operation, called once per device with a client targeting it.
+    ///
+    /// # Returns
+    /// The result of every device, by UDID.
+    pub fn run<F, R, E>(&self, group: &DeviceClient<DeviceGroup>, operation: F) -> FleetReport<R, E>
+    where
+        F: Fn(&DeviceClient<SingleDevice>) -> Result<R, E> + Send + Sync + 'static,
+        R: Send + 'static,
+        E: Send + 'static,
+    {
+        let devices = group.get_device_clients();
+        let workers = self.workers.min(devices.len());
+
+        let queue = Arc::new(Mutex::new(devices.into_iter()));
+        let operation = Arc::new(operation);
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let handles = (0..workers)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                let operation = Arc::clone(&operation);
+                let result_sender = result_sender.clone();
+                let timeout = self.timeout;
+
+                thread::spawn(move || {
+                    // The lock is released once the next device is taken
+                    while let Some(device) = queue.lock().ok().and_then(|mut queue| queue.next()) {
+                        let udid = device.get_device().get_udid();
+                        let result = run_on_device(device, Arc::clone(&operation), timeout);
+
+                        if result_sender.send((udid, result)).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Only the workers hold a sender now, the results end once they're all done
+        drop(result_sender);
+        let results = result_receiver.into_iter().collect();
+
+        for handle in handles {
+            let _ = handle.join();
+        }
+
+        FleetReport { results }
+    }
+}
+
+/// Runs the operation in its own thread, so it can be abandoned once it times out.
+fn run_on_device<F, R, E>(
+    device: DeviceClient<SingleDevice>,
+    operation: Arc<F>,
+    timeout: Option<Duration>,
+) -> Result<R, FleetError<E>>
+where
+    F: Fn(&DeviceClient<SingleDevice>) -> Result<R, E> + Send + Sync + 'static,
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    let (sender, receiver) = mpsc::sync_channel(1);
+
+    // Waiting for a place isn't counted in the timeout, and the place is given back once the result
+    // is in or the operation timed out, so the hanging devices don't hold the places of the others
+    let _permit = rate_limit::acquire();
+    thread::spawn(move || {
+        let _ = sender.send(operation(&device));
+    });
+
+    // The sender is dropped without sending only if the operation panicked
+    let result = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).map_err(|err| match err {
+            mpsc::RecvTimeoutError::Timeout => FleetError::TimedOut,
+            mpsc::RecvTimeoutError::Disconnected => FleetError::Panicked,
+        }),
+        None => receiver.recv().map_err(|_| FleetError::Panicked),
+    };
+
+    result?.map_err(FleetError::Failed)
+}
+
+/// The results of a `FleetExecutor` run, by UDID.
+#[derive(Debug)]
+pub struct FleetReport<R, E> {
+    results: HashMap<String, Result<R, FleetError<E>>>,
+}
+
+impl<R, E> FleetReport<R, E> {
+    /// The result of a device, `None` if it wasn't part of the group
+    pub fn get(&self, udid: &str) -> Option<&Result<R, FleetError<E>>> {
+        self.results.get(udid)
+    }
+
+    /// The devices the operation succeeded on, with their result
+    pub fn succeeded(&self) -> impl Iterator<Item = (&str, &R)> {
+        self.results
+            .iter()
+            .filter_map(|(udid, result)| Some((udid.as_str(), result.as_ref().ok()?)))
+    }
+
+    /// The devices the operation failed, timed out or panicked on, with the reason
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &FleetError<E>)> {
+        self.results
+            .iter()
+            .filter_map(|(udid, result)| Some((udid.as_str(), result.as_ref().err()?)))
+    }
+
+    /// Checks whether the operation succeeded on every device
+    pub fn is_all_ok(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+
+    /// The number of devices the operation ran on
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Checks whether the group had no devices
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Consumes the report, returning the results by UDID
+    pub fn into_results(self) -> HashMap<String, Result<R, FleetError<E>>> {
+        self.results
+    }
+}
@@ -0,0 +1,131 @@
+//! Keeping the devices connected over the network alive.
+//!
+//! A device reached over Wi-Fi drops the services of a host that stops answering its heartbeat,
+//! about 15 seconds after the last answer. The `DeviceClient` of such a device runs the heartbeat
+//! in the background by itself, see `DeviceClient::with_auto_heartbeat` to manage it manually instead.
+//!
+//! ## Features
+//! - Answering the "Marco" pings of the device on a background thread
+//! - Stopping when the device goes to sleep, or when the last clone of the client is dropped
+//!
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    error::ServiceError, idevice::Device, service::ServiceClient,
+    services::lockdownd::LockdowndClient,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{errors::DeviceClientError, plist_service};
+
+const HEARTBEAT_SERVICE: &str = "com.apple.mobile.heartbeat";
+
+/// Handle to a running heartbeat thread.
+///
+/// Dropping the handle stops the heartbeat and waits for the thread to finish.
+#[derive(Debug)]
+pub struct HeartbeatHandle {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl HeartbeatHandle {
+    /// Starts answering the heartbeat of the device on a background thread.
+    ///
+    /// The connection is made on the thread, its failures are reported through the `log` crate.
+    pub fn start(device: Device) -> HeartbeatHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            if let Err(e) = run_heartbeat(&device, &thread_stop) {
+                log::warn!(udid:% = device.get_udid(), error:% = e; "The heartbeat stopped");
+            }
+        });
+
+        HeartbeatHandle {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Checks whether the heartbeat thread is still answering the device.
+    ///
+    /// It ends by itself when the device goes to sleep or disconnects.
+    pub fn is_running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Stops the heartbeat and waits for the thread to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for HeartbeatHandle {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+fn run_heartbeat(device: &Device, stop: &AtomicBool) -> Result<(), DeviceClientError> {
+    let service = {
+        let mut lockdownd = LockdowndClient::new(device, crate::config::label("heartbeat"))?;
+        let service = lockdownd.start_service(HEARTBEAT_SERVICE, false)?;
+        ServiceClient::new(device, service)?
+    };
+
+    let mut polo = Plist::new_dict();
+    polo.dict_set_item("Command", "Polo".into())?;
+
+    while !stop.load(Ordering::Relaxed) {
+        let Some(command) = receive_command(&service)? else {
+            continue;
+        };
+
+        match command.as_str() {
+            "Marco" => plist_service::send_plist(&service, &polo)?,
+            // The device is going to sleep, it won't ask anymore
+            "SleepyTime" => break,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits a bit for a ping from the device, `None` if nothing came in, so the stop flag can be checked.
+fn receive_command(service: &ServiceClient) -> Result<Option<String>, DeviceClientError> {
+    let mut length = match service.receive_with_timeout(4, 500) {
+        Ok(data) if data.is_empty() => return Ok(None),
+        Ok(data) => data,
+        Err(ServiceError::Timeout) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if length.len() < 4 {
+        length.extend(plist_service::receive_exact(service, 4 - length.len())?);
+    }
+    let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]);
+
+    let message = Plist::from_memory(plist_service::receive_exact(service, length as usize)?)?;
+
+    Ok(message
+        .dict_get_item("Command")
+        .and_then(|command| command.get_string_val())
+        .ok())
+}
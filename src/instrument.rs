@@ -0,0 +1,88 @@
+//! `tracing` spans around the lockdownd handshakes, the service starts and the transfers.
+//!
+//! The spans carry the device UDID, the service name, the transferred bytes and the duration,
+//! they're only created with the `tracing` feature, otherwise `OperationSpan` does nothing.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// A span entered for the duration of an operation, closed once dropped.
+pub(crate) struct OperationSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(feature = "tracing")]
+    started: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl OperationSpan {
+    fn enter(span: tracing::Span) -> OperationSpan {
+        OperationSpan {
+            span: span.entered(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The connection to lockdownd, with its SSL handshake and session
+    pub(crate) fn lockdownd(udid: &str) -> OperationSpan {
+        Self::enter(tracing::debug_span!(
+            "lockdownd_handshake",
+            udid,
+            duration_ms = tracing::field::Empty
+        ))
+    }
+
+    /// The start of a service through lockdownd, and the connection to it
+    pub(crate) fn service(udid: &str, service: &str) -> OperationSpan {
+        Self::enter(tracing::debug_span!(
+            "service_start",
+            udid,
+            service,
+            duration_ms = tracing::field::Empty
+        ))
+    }
+
+    /// A transfer of files between the host and the device (e.g., "afc_upload", "backup")
+    #[cfg(feature = "afc")]
+    pub(crate) fn transfer(udid: &str, operation: &'static str) -> OperationSpan {
+        Self::enter(tracing::info_span!(
+            "transfer",
+            udid,
+            operation,
+            bytes = tracing::field::Empty,
+            duration_ms = tracing::field::Empty
+        ))
+    }
+
+    #[cfg(feature = "afc")]
+    pub(crate) fn record_bytes(&self, bytes: u64) {
+        self.span.record("bytes", bytes);
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for OperationSpan {
+    fn drop(&mut self) {
+        self.span
+            .record("duration_ms", self.started.elapsed().as_millis() as u64);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl OperationSpan {
+    pub(crate) fn lockdownd(_udid: &str) -> OperationSpan {
+        OperationSpan {}
+    }
+
+    pub(crate) fn service(_udid: &str, _service: &str) -> OperationSpan {
+        OperationSpan {}
+    }
+
+    #[cfg(feature = "afc")]
+    pub(crate) fn transfer(_udid: &str, _operation: &'static str) -> OperationSpan {
+        OperationSpan {}
+    }
+
+    #[cfg(feature = "afc")]
+    pub(crate) fn record_bytes(&self, _bytes: u64) {}
+}
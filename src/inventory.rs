@@ -0,0 +1,121 @@
+//! A snapshot of every device of a group, the record asset management systems keep.
+//!
+//! ## Features
+//! - The identity, model, version, battery, storage and management state of each device
+//! - Read from all the devices in parallel, see `DeviceClient::inventory`
+//!
+
+use std::convert::Infallible;
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{idevice::Device, services::lockdownd::LockdowndClient};
+
+use crate::{
+    device::DeviceClient,
+    device_info::{
+        domains::DeviceDomains, keys::DeviceKeys, marketing::marketing_name, version::OsVersion,
+    },
+    devices_collection::DeviceGroup,
+    fleet::FleetExecutor,
+    instrument::OperationSpan,
+    rate_limit,
+};
+
+/// The record of a device, returned by `DeviceClient::inventory`.
+///
+/// The values the device didn't give out (e.g., it's locked, or not paired) are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceInventory {
+    pub udid: String,
+
+    pub serial_number: Option<String>,
+
+    /// The product type (e.g., "iPhone14,2")
+    pub model: Option<String>,
+
+    /// The name the device is sold under (e.g., "iPhone 13 Pro"), for the models known by the library
+    pub marketing_name: Option<&'static str>,
+
+    /// The operating system version, with its build
+    pub os_version: Option<OsVersion>,
+
+    /// The battery level, in percent
+    pub battery_level: Option<u64>,
+
+    /// The free space of the data partition, in bytes
+    pub storage_free: Option<u64>,
+
+    /// The activation state (e.g., "Activated", "Unactivated")
+    pub activation_state: Option<String>,
+
+    /// Whether the device is supervised, as the MDM managed devices usually are
+    pub is_supervised: Option<bool>,
+}
+
+impl DeviceInventory {
+    fn read(device: &Device) -> DeviceInventory {
+        let udid = device.get_udid();
+        let _span = OperationSpan::lockdownd(&udid);
+        rate_limit::pace(&udid);
+
+        let Ok(lockdownd) = LockdowndClient::new(device, crate::config::label("inventory")) else {
+            return DeviceInventory {
+                udid,
+                ..Default::default()
+            };
+        };
+        let get_value = |key: &str, domain: DeviceDomains| -> Option<Plist> {
+            lockdownd.get_value(key, domain.as_string()).ok()
+        };
+        let get_string = |key: DeviceKeys| {
+            get_value(&key.to_string(), DeviceDomains::All)?
+                .get_string_val()
+                .ok()
+        };
+
+        let model = get_string(DeviceKeys::ProductType);
+        let os_version = get_string(DeviceKeys::ProductVersion)
+            .and_then(|version| version.parse::<OsVersion>().ok())
+            .map(|version| match get_string(DeviceKeys::BuildVersion) {
+                Some(build) => version.with_build(build),
+                None => version,
+            });
+
+        DeviceInventory {
+            serial_number: get_string(DeviceKeys::SerialNumber),
+            marketing_name: model.as_deref().and_then(marketing_name),
+            model,
+            os_version,
+            battery_level: get_value("BatteryCurrentCapacity", DeviceDomains::MobileBattery)
+                .and_then(|level| level.get_uint_val().ok()),
+            storage_free: get_value("TotalDataAvailable", DeviceDomains::DiskUsage)
+                .and_then(|free| free.get_uint_val().ok()),
+            activation_state: get_string(DeviceKeys::ActivationState),
+            is_supervised: get_value("DeviceIsChaperoned", DeviceDomains::MobileChaperone)
+                .and_then(|supervised| supervised.get_bool_val().ok()),
+            udid,
+        }
+    }
+}
+
+/// Reads the record of every device of the group in parallel, sorted by UDID.
+pub(crate) fn collect(group: &DeviceClient<DeviceGroup>) -> Vec<DeviceInventory> {
+    let report = FleetExecutor::new().run(group, |device| {
+        Ok::<_, Infallible>(DeviceInventory::read(device.get_device()))
+    });
+
+    // A device whose read panicked is still listed, without its values
+    let mut inventory: Vec<DeviceInventory> = report
+        .into_results()
+        .into_iter()
+        .map(|(udid, result)| {
+            result.unwrap_or_else(|_| DeviceInventory {
+                udid,
+                ..Default::default()
+            })
+        })
+        .collect();
+    inventory.sort_by(|a, b| a.udid.cmp(&b.udid));
+
+    inventory
+}
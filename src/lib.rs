@@ -6,26 +6,96 @@
 //! fetching device information, handling installations, and more.
 //!
 //! ## Modules
+//! - `backup`: Reading the content of device backups.
+//! - `config`: The configuration of the clients, such as their label, the usbmuxd address and the request pacing.
 //! - `device`: Core device abstractions and utilities.
+//! - `device_afc`: Access to the media filesystem of devices through AFC.
+//! - `device_backup`: Managing the backups of devices, such as their encryption.
+//! - `device_debugserver`: Launching apps through the debugserver, with their arguments and environment.
 //! - `device_diagnostic`: Tools for retrieving and analyzing device diagnostics.
+//! - `device_image_mounter`: Listing, mounting and unmounting the disk images of devices, such as the developer one.
 //! - `device_info`: Functionality to fetch detailed information about devices.
 //! - `device_installer`: Provides support for installing applications on devices, supporting both ipcc and ipa.
+//! - `device_instruments`: The instruments of devices, such as the network usage and the time profiling of their processes.
+//! - `device_notification`: Posting and observing the system notifications of devices.
+//! - `device_preboard`: Preparing the stash bag of devices before their first unlock.
+//! - `device_screenshot`: Screenshots and screen recordings of devices.
+//! - `device_springboard`: Access to the home screen of devices, such as the app icons.
 //! - `device_syslog`: Access to the system logs of devices.
+//! - `event_bus`: Publishing the events of the devices (hotplug, pairing, battery, syslog) to any number of subscribers.
+//! - `fleet`: Running an operation on every device of a group, with a bounded pool of workers.
+//! - `heartbeat`: Keeping the devices connected over the network alive.
+//! - `inventory`: A snapshot of the identity, version, battery and storage of every device of a group.
+//! - `pair_record`: Reading and writing the pair records, in the system directory or a custom one, and pairing supervised devices.
+//! - `power_assertion`: Keeping the devices awake during the long operations.
+//! - `progress`: The progress interface common to the transfers, the installs and the backups.
+//! - `service_manager`: Sharing the lockdownd, AFC and service connections to a device.
+//!
+//! Every service module is behind a cargo feature of the same name (`afc`, `backup`, `debugserver`,
+//! `diagnostic`, `image_mounter`, `info`, `installer`, `instruments`, `notification`, `preboard`,
+//! `screenshot`, `springboard` and `syslog`), all enabled by default, so only the needed ones and their dependencies can be built.
+//! The `certificate_fingerprint` feature, also enabled by default, adds the device certificate fingerprint
+//! to `PairingIdentity`, at the cost of the `sha2` dependency.
 //!
 //! ## Features
 //! - Recursive search functionality in `Plist` structures via the `RecursiveFind` trait to look for any key at any part.
 //! - Modular design for ease of integration.
 //! - Comprehensive error handling for robust applications.
+//! - The errors of the background threads, which can't be returned, are reported through the `log` crate,
+//!   with the UDID, the path and the error as key-values.
 
 use plist_plus::{Plist, PlistType};
 
+#[cfg(any(feature = "backup", feature = "installer"))]
+pub(crate) mod apple_date;
+#[cfg(feature = "backup")]
+pub mod backup;
+pub mod config;
 pub mod device;
+#[cfg(feature = "afc")]
+pub mod device_afc;
+#[cfg(feature = "backup")]
+pub mod device_backup;
+#[cfg(feature = "debugserver")]
+pub mod device_debugserver;
+#[cfg(feature = "diagnostic")]
 pub mod device_diagnostic;
+#[cfg(feature = "image_mounter")]
+pub mod device_image_mounter;
 pub mod device_info;
+#[cfg(feature = "installer")]
 pub mod device_installer;
+#[cfg(feature = "instruments")]
+pub mod device_instruments;
+#[cfg(feature = "notification")]
+pub mod device_notification;
+#[cfg(feature = "preboard")]
+pub mod device_preboard;
+#[cfg(feature = "screenshot")]
+pub mod device_screenshot;
+#[cfg(feature = "springboard")]
+pub mod device_springboard;
+#[cfg(feature = "syslog")]
 pub mod device_syslog;
 pub mod devices_collection;
 pub mod errors;
+pub mod event_bus;
+pub mod fleet;
+pub mod heartbeat;
+pub(crate) mod instrument;
+pub mod inventory;
+pub(crate) mod lockdown_connection;
+pub mod pair_record;
+pub(crate) mod plist_service;
+pub mod power_assertion;
+pub mod progress;
+pub(crate) mod rate_limit;
+pub mod service_availability;
+pub mod service_manager;
+pub mod service_options;
+// The syslog rotation tests use the temporary directories too
+#[cfg(any(feature = "backup", all(test, feature = "syslog")))]
+pub(crate) mod temp;
 
 /// Trait providing recursive search functionality for `Plist` structures.
 ///
@@ -0,0 +1,191 @@
+//! A raw connection to lockdownd, outside of a session, through usbmuxd or over the network.
+//!
+//! `LockdowndClient` always does the pairing handshake, which an untrusted device refuses, so the requests
+//! made before the pairing (the prelogin values of `DeviceInfo`) and the pairing itself go through here.
+
+use std::{
+    env,
+    io::{self, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::idevice::Device;
+
+use crate::{
+    config::{self, RsMobileDeviceConfig},
+    errors::DeviceInfoError,
+};
+
+const LOCKDOWND_PORT: u16 = 62078;
+
+/// The largest response accepted, the peer isn't authenticated before the pairing
+/// and its responses are small plists
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// Sends a whole request dictionary outside of a session and returns the response, e.g., for the pairing.
+pub(crate) fn exchange(
+    stream: &mut LockdownStream,
+    request: &Plist,
+) -> Result<Plist, DeviceInfoError> {
+    let xml = request.to_xml();
+    stream.write_all(&(xml.len() as u32).to_be_bytes())?;
+    stream.write_all(xml.as_bytes())?;
+
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(DeviceInfoError::PreloginUnavailable(format!(
+            "lockdownd sent a response of {} bytes",
+            length
+        )));
+    }
+    let mut response = vec![0u8; length];
+    stream.read_exact(&mut response)?;
+
+    Ok(Plist::from_memory(response)?)
+}
+
+/// Opens a connection to lockdownd, directly for a network device, through usbmuxd otherwise.
+pub(crate) fn connect(device: &Device) -> Result<LockdownStream, DeviceInfoError> {
+    let timeout = RsMobileDeviceConfig::global().request_timeout();
+
+    if device.get_network() {
+        let ip = device.get_ip_address().ok_or_else(|| {
+            DeviceInfoError::PreloginUnavailable("the device has no IP address".into())
+        })?;
+        let stream = TcpStream::connect((ip.as_str(), LOCKDOWND_PORT))?;
+        stream.set_read_timeout(Some(timeout))?;
+        return Ok(LockdownStream::Tcp(stream));
+    }
+
+    let device_id = device
+        .get_handle()
+        .map_err(|err| DeviceInfoError::PreloginUnavailable(err.to_string()))?;
+
+    let mut stream = LockdownStream::usbmuxd()?;
+    stream.set_read_timeout(timeout)?;
+
+    let label = config::label("prelogin");
+    let mut connect = Plist::new_dict();
+    connect.dict_set_item("MessageType", "Connect".into())?;
+    connect.dict_set_item("ClientVersionString", label.as_str().into())?;
+    connect.dict_set_item("ProgName", label.as_str().into())?;
+    connect.dict_set_item("DeviceID", Plist::new_uint(device_id as u64))?;
+    // usbmuxd expects the port in network byte order
+    connect.dict_set_item(
+        "PortNumber",
+        Plist::new_uint(LOCKDOWND_PORT.swap_bytes() as u64),
+    )?;
+
+    let xml = connect.to_xml();
+    // The header is the total length, the protocol version (1), the message type (8, plist) and a tag
+    let mut message = Vec::with_capacity(16 + xml.len());
+    for field in [16 + xml.len() as u32, 1, 8, 1] {
+        message.extend_from_slice(&field.to_le_bytes());
+    }
+    message.extend_from_slice(xml.as_bytes());
+    stream.write_all(&message)?;
+
+    let mut header = [0u8; 16];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(DeviceInfoError::PreloginUnavailable(format!(
+            "usbmuxd sent a response of {} bytes",
+            length
+        )));
+    }
+    let mut result = vec![0u8; length.saturating_sub(16)];
+    stream.read_exact(&mut result)?;
+
+    let result = Plist::from_memory(result)?
+        .dict_get_item("Number")
+        .and_then(|number| number.get_uint_val())?;
+    if result != 0 {
+        return Err(DeviceInfoError::PreloginUnavailable(format!(
+            "usbmuxd refused the connection ({})",
+            result
+        )));
+    }
+
+    // From now on, the socket is connected to lockdownd
+    Ok(stream)
+}
+
+/// A raw connection to lockdownd.
+pub(crate) enum LockdownStream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl LockdownStream {
+    /// Connects to usbmuxd, at the address of the configuration or `USBMUXD_SOCKET_ADDRESS` if set
+    /// (e.g., "127.0.0.1:27015").
+    fn usbmuxd() -> io::Result<LockdownStream> {
+        let address = RsMobileDeviceConfig::global()
+            .usbmuxd_address()
+            .map(str::to_string)
+            .or_else(|| env::var("USBMUXD_SOCKET_ADDRESS").ok());
+        if let Some(address) = address {
+            if let Some(path) = address.strip_prefix("UNIX:") {
+                #[cfg(unix)]
+                return Ok(LockdownStream::Unix(
+                    std::os::unix::net::UnixStream::connect(path)?,
+                ));
+                #[cfg(not(unix))]
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("UNIX sockets aren't supported: {}", path),
+                ));
+            }
+            return Ok(LockdownStream::Tcp(TcpStream::connect(address)?));
+        }
+
+        #[cfg(unix)]
+        return Ok(LockdownStream::Unix(
+            std::os::unix::net::UnixStream::connect("/var/run/usbmuxd")?,
+        ));
+        #[cfg(not(unix))]
+        return Ok(LockdownStream::Tcp(TcpStream::connect("127.0.0.1:27015")?));
+    }
+
+    fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        match self {
+            LockdownStream::Tcp(stream) => stream.set_read_timeout(Some(timeout)),
+            #[cfg(unix)]
+            LockdownStream::Unix(stream) => stream.set_read_timeout(Some(timeout)),
+        }
+    }
+}
+
+impl Read for LockdownStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            LockdownStream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            LockdownStream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for LockdownStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            LockdownStream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            LockdownStream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            LockdownStream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            LockdownStream::Unix(stream) => stream.flush(),
+        }
+    }
+}
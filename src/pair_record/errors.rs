@@ -0,0 +1,31 @@
+use plist_plus::error::PlistError;
+use thiserror::Error;
+
+use crate::errors::DeviceInfoError;
+
+#[derive(Debug, Error)]
+pub enum PairRecordError {
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Plist error: {0}")]
+    PlistError(#[from] PlistError),
+
+    #[error("No pair record found for the device `{0}`")]
+    NotFound(String),
+
+    #[error("The pair record has no `{0}`")]
+    MissingKey(String),
+
+    #[error("Couldn't reach lockdownd: {0}")]
+    DeviceInfoError(#[from] DeviceInfoError),
+
+    #[error("The device refused the pairing: {0}")]
+    PairingRefused(String),
+
+    #[error("The device is supervised, pairing with it needs a supervision identity")]
+    SupervisionRequired,
+
+    #[error("Couldn't sign the pairing challenge: {0}")]
+    ChallengeNotSigned(String),
+}
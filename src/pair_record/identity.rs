@@ -0,0 +1,157 @@
+#[cfg(feature = "certificate_fingerprint")]
+use std::fmt::Write;
+
+use plist_plus::Plist;
+#[cfg(feature = "certificate_fingerprint")]
+use sha2::{Digest, Sha256};
+
+use crate::errors::PairRecordError;
+
+/// The identity of a pairing, as read from the pair record by `PairRecordFiles::pairing_identity`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairingIdentity {
+    /// The identifier of the host the device trusts
+    pub host_id: String,
+
+    /// The identifier of the usbmuxd installation that paired the device
+    pub system_buid: String,
+
+    /// The SHA-256 fingerprint of the device certificate, as colon separated hex bytes (e.g., "AB:01:..."),
+    /// always `None` without the `certificate_fingerprint` feature
+    pub device_certificate_fingerprint: Option<String>,
+
+    /// The Wi-Fi MAC address of the device, to find it on the network
+    pub wifi_mac_address: Option<String>,
+}
+
+impl PairingIdentity {
+    pub(crate) fn from_record(record: &Plist) -> Result<PairingIdentity, PairRecordError> {
+        let string = |key: &str| {
+            record
+                .dict_get_item(key)
+                .and_then(|value| value.get_string_val())
+                .ok()
+        };
+        let required =
+            |key: &str| string(key).ok_or_else(|| PairRecordError::MissingKey(key.to_string()));
+
+        #[cfg(feature = "certificate_fingerprint")]
+        let device_certificate_fingerprint = record
+            .dict_get_item("DeviceCertificate")
+            .and_then(|certificate| certificate.get_data_val())
+            .ok()
+            .map(|certificate| certificate.into_iter().map(|byte| byte as u8).collect())
+            .map(|certificate: Vec<u8>| fingerprint(&certificate));
+        #[cfg(not(feature = "certificate_fingerprint"))]
+        let device_certificate_fingerprint = None;
+
+        Ok(PairingIdentity {
+            host_id: required("HostID")?,
+            system_buid: required("SystemBUID")?,
+            device_certificate_fingerprint,
+            wifi_mac_address: string("WiFiMACAddress"),
+        })
+    }
+}
+
+/// The fingerprint of a certificate, over its DER encoding, the way `openssl x509 -fingerprint` shows it.
+#[cfg(feature = "certificate_fingerprint")]
+fn fingerprint(certificate: &[u8]) -> String {
+    // The certificates of the pair records are PEM encoded
+    let der = pem_to_der(certificate).unwrap_or_else(|| certificate.to_vec());
+
+    let mut fingerprint = String::new();
+    for (index, byte) in Sha256::digest(der).iter().enumerate() {
+        if index > 0 {
+            fingerprint.push(':');
+        }
+        let _ = write!(fingerprint, "{:02X}", byte);
+    }
+    fingerprint
+}
+
+/// Decodes the base64 body of a PEM block, `None` if it isn't one.
+#[cfg(feature = "certificate_fingerprint")]
+fn pem_to_der(pem: &[u8]) -> Option<Vec<u8>> {
+    let pem = std::str::from_utf8(pem).ok()?;
+    if !pem.trim_start().starts_with("-----BEGIN") {
+        return None;
+    }
+
+    let body: Vec<u8> = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .flat_map(|line| line.trim().bytes())
+        .filter(|&byte| byte != b'=')
+        .collect();
+
+    let mut der = Vec::with_capacity(body.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for byte in body {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            der.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(der)
+}
+
+#[cfg(all(test, feature = "certificate_fingerprint"))]
+mod tests {
+    use super::*;
+
+    /// A self-signed certificate, with the fingerprint given by `openssl x509 -fingerprint -sha256`
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBkjCCATegAwIBAgIUeLt9LzdCZTeR9X7v4oeVKfZZHrMwCgYIKoZIzj0EAwIw
+HjEcMBoGA1UEAwwTcnNtb2JpbGVkZXZpY2UgdGVzdDAeFw0yNjEwMTYxMjEzMzha
+Fw0zNjEwMTMxMjEzMzhaMB4xHDAaBgNVBAMME3JzbW9iaWxlZGV2aWNlIHRlc3Qw
+WTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAQmxnGmn+wD2CIla5KKFUUcmNt60vfK
+mKdgwGfH3XpQRCNBoOsdfiUjvUQWz7EgjhIRsdtkLX0K28X+GVM1TC7vo1MwUTAd
+BgNVHQ4EFgQUzkGpqt+3zrnMHcqQVs8hd8mIO3MwHwYDVR0jBBgwFoAUzkGpqt+3
+zrnMHcqQVs8hd8mIO3MwDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNJADBG
+AiEA0PrsHe9jZttzKMaymEgaAEQULNkeC8SLm30tRoOcyOICIQCjHTwhgxZ+IucF
+TZZG7RUWVoNEbK7REfqt5fY8A2ubmw==
+-----END CERTIFICATE-----
+";
+    const FINGERPRINT: &str = "D8:63:A7:3D:A1:60:28:0A:14:C7:CA:28:8B:66:15:40:\
+                               0F:D7:85:9F:B9:5C:7B:82:CC:56:CC:D3:63:29:EC:70";
+
+    #[test]
+    fn pem_to_der_decodes_the_body() {
+        let der = pem_to_der(CERTIFICATE.as_bytes()).unwrap();
+
+        assert_eq!(der.len(), 0x196);
+        assert_eq!(&der[..8], &[0x30, 0x82, 0x01, 0x92, 0x30, 0x82, 0x01, 0x37]);
+        assert_eq!(&der[der.len() - 2..], &[0x9b, 0x9b]);
+    }
+
+    #[test]
+    fn pem_to_der_rejects_other_data() {
+        assert_eq!(pem_to_der(b"\x30\x82\x01\x92"), None);
+        assert_eq!(pem_to_der(b"-----BEGIN CERTIFICATE-----\nAB*D\n"), None);
+    }
+
+    #[test]
+    fn fingerprint_matches_openssl() {
+        assert_eq!(fingerprint(CERTIFICATE.as_bytes()), FINGERPRINT);
+    }
+
+    #[test]
+    fn fingerprint_of_der_is_the_same() {
+        let der = pem_to_der(CERTIFICATE.as_bytes()).unwrap();
+
+        assert_eq!(fingerprint(&der), fingerprint(CERTIFICATE.as_bytes()));
+    }
+}
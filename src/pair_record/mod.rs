@@ -0,0 +1,190 @@
+//! Provides access to the pair record files, the keys and certificates a host gets when a device trusts it
+//!
+//! `PairRecordFiles` reads and writes the records of a directory, the system lockdown directory shared by
+//! usbmuxd by default. It's a file manager, not a pairing storage: libimobiledevice reads the records through
+//! usbmuxd, which only knows its own directory, so the connections made by this crate always use the system
+//! records, whatever directory is configured. Another directory is meant to keep, back up, inspect and move
+//! the records, e.g., with `copy_to` the system directory before connecting.
+//!
+//! ## Features
+//! - Reading, writing and removing pair records
+//! - Listing the paired devices
+//! - Reading the identity of a pairing (host ID, system BUID, certificate fingerprint, Wi-Fi MAC address)
+//! - Pairing with a host record, supervised devices included (see `DeviceClient::pair`)
+//!
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use plist_plus::Plist;
+
+pub(crate) mod errors;
+pub mod identity;
+pub mod pairing;
+
+pub use identity::PairingIdentity;
+pub use pairing::{PairingOptions, SupervisionIdentity};
+
+use crate::{config::RsMobileDeviceConfig, errors::PairRecordError};
+
+const SYSTEM_CONFIGURATION: &str = "SystemConfiguration";
+
+/// Struct for reading and writing the pair record files of a directory.
+///
+/// The connections to the devices don't read the records from here, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairRecordFiles {
+    dir: PathBuf,
+}
+
+impl Default for PairRecordFiles {
+    /// The directory of the configuration, or the system lockdown directory used by usbmuxd
+    fn default() -> Self {
+        let dir = RsMobileDeviceConfig::global()
+            .pair_record_dir()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(system_dir);
+        PairRecordFiles { dir }
+    }
+}
+
+impl PairRecordFiles {
+    /// Works in the directory of the configuration (see `RsMobileDeviceConfig::with_pair_record_dir`),
+    /// or in the system lockdown directory
+    pub fn new() -> PairRecordFiles {
+        PairRecordFiles::default()
+    }
+
+    /// Works in the system lockdown directory, the one the connections use, whatever the configuration
+    pub fn system() -> PairRecordFiles {
+        PairRecordFiles { dir: system_dir() }
+    }
+
+    /// Works in the given directory, it's created on the first write
+    pub fn with_dir<S>(dir: &S) -> PairRecordFiles
+    where
+        S: AsRef<Path> + ?Sized,
+    {
+        PairRecordFiles {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The directory of the records
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Reads the pair record of a device.
+    ///
+    /// # Parameters
+    /// - `udid`: The UDID of the device.
+    pub fn load(&self, udid: &str) -> Result<Plist, PairRecordError> {
+        let path = self.record_path(udid);
+        if !path.exists() {
+            return Err(PairRecordError::NotFound(udid.to_string()));
+        }
+
+        // The records are either in the XML or binary format
+        Ok(Plist::from_memory(fs::read(path)?)?)
+    }
+
+    /// Reads the identity of the pairing of a device, e.g., for the network discovery or a security audit.
+    ///
+    /// # Parameters
+    /// - `udid`: The UDID of the device.
+    ///
+    /// # Errors
+    /// Returns `PairRecordError::NotFound` if the device isn't paired,
+    /// and `PairRecordError::MissingKey` if the record has no host ID or system BUID.
+    pub fn pairing_identity(&self, udid: &str) -> Result<PairingIdentity, PairRecordError> {
+        PairingIdentity::from_record(&self.load(udid)?)
+    }
+
+    /// Writes the pair record of a device, replacing the existing one.
+    ///
+    /// # Parameters
+    /// - `udid`: The UDID of the device.
+    /// - `record`: The pair record.
+    pub fn save(&self, udid: &str, record: &Plist) -> Result<(), PairRecordError> {
+        fs::create_dir_all(&self.dir)?;
+
+        // Written aside first, so a crash never leaves a truncated record
+        let path = self.record_path(udid);
+        let temporary_path = path.with_extension("plist.tmp");
+        fs::write(&temporary_path, record.to_xml())?;
+        fs::rename(temporary_path, path)?;
+
+        Ok(())
+    }
+
+    /// Removes the pair record of a device, the device will have to trust the host again.
+    ///
+    /// # Parameters
+    /// - `udid`: The UDID of the device.
+    pub fn remove(&self, udid: &str) -> Result<(), PairRecordError> {
+        match fs::remove_file(self.record_path(udid)) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(PairRecordError::NotFound(udid.to_string()))
+            }
+            result => Ok(result?),
+        }
+    }
+
+    /// Checks whether the directory has a pair record for the device.
+    pub fn contains(&self, udid: &str) -> bool {
+        self.record_path(udid).exists()
+    }
+
+    /// Lists the UDIDs of the devices with a pair record, sorted.
+    pub fn list(&self) -> Result<Vec<String>, PairRecordError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut udids = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("plist") {
+                continue;
+            }
+            if let Some(udid) = path.file_stem().and_then(|stem| stem.to_str()) {
+                if udid != SYSTEM_CONFIGURATION {
+                    udids.push(udid.to_string());
+                }
+            }
+        }
+        udids.sort();
+
+        Ok(udids)
+    }
+
+    /// Copies the pair record of a device to another directory, e.g., from a backup of the records to the system one.
+    ///
+    /// # Parameters
+    /// - `udid`: The UDID of the device.
+    /// - `other`: The directory to copy the record to.
+    pub fn copy_to(&self, udid: &str, other: &PairRecordFiles) -> Result<(), PairRecordError> {
+        other.save(udid, &self.load(udid)?)
+    }
+
+    fn record_path(&self, udid: &str) -> PathBuf {
+        self.dir.join(format!("{}.plist", udid))
+    }
+}
+
+/// The lockdown directory used by usbmuxd on the current platform
+fn system_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/var/db/lockdown")
+    } else if cfg!(target_os = "windows") {
+        let all_users = std::env::var_os("ALLUSERSPROFILE").unwrap_or("C:\\ProgramData".into());
+        PathBuf::from(all_users).join("Apple").join("Lockdown")
+    } else {
+        PathBuf::from("/var/lib/lockdown")
+    }
+}
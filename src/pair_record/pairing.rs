@@ -0,0 +1,188 @@
+//! Pairing a device with a host identity made by the caller, supervised devices included.
+//!
+//! The host record (the certificates and keys of the host, and the device certificate signed for the
+//! `DevicePublicKey` of the device) is generated outside of the crate, e.g., by the tool managing the fleet.
+//! Supervised devices answer the pairing with an `MCChallengeRequired` error and a challenge, which is
+//! signed as CMS (PKCS#7) with the supervision identity of the organization, the way Apple Configurator does.
+//! The signing itself is left to the caller (e.g., `openssl cms -sign -outform DER -nodetach`),
+//! so the crate doesn't need the crypto libraries.
+
+use std::{fmt, sync::Arc};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::idevice::Device;
+
+use crate::{config, errors::PairRecordError, lockdown_connection, pair_record::PairRecordFiles};
+
+/// The keys of the host record sent to the device, the private keys never leave the host
+const PAIR_RECORD_KEYS: [&str; 5] = [
+    "DeviceCertificate",
+    "HostCertificate",
+    "RootCertificate",
+    "HostID",
+    "SystemBUID",
+];
+
+const CHALLENGE_REQUIRED: &str = "MCChallengeRequired";
+
+type ChallengeSigner = Arc<dyn Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync>;
+
+/// The supervision identity of an organization, to pair with the devices it supervises without the trust prompt.
+#[derive(Clone)]
+pub struct SupervisionIdentity {
+    certificate: Vec<u8>,
+    sign_challenge: ChallengeSigner,
+}
+
+impl SupervisionIdentity {
+    /// Creates a supervision identity.
+    ///
+    /// # Parameters
+    /// - `certificate`: The DER encoded supervision certificate, the one the devices were supervised with.
+    /// - `sign_challenge`: Signs the pairing challenge with the key of the certificate, returning the
+    ///   DER encoded CMS `SignedData` with the challenge attached, or the reason it couldn't.
+    pub fn new<F>(certificate: Vec<u8>, sign_challenge: F) -> SupervisionIdentity
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync + 'static,
+    {
+        SupervisionIdentity {
+            certificate,
+            sign_challenge: Arc::new(sign_challenge),
+        }
+    }
+
+    /// The DER encoded supervision certificate
+    pub fn certificate(&self) -> &[u8] {
+        &self.certificate
+    }
+}
+
+impl fmt::Debug for SupervisionIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SupervisionIdentity")
+            .field(
+                "certificate",
+                &format_args!("{} bytes", self.certificate.len()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Options for `DeviceClient::pair`.
+#[derive(Debug, Clone)]
+pub struct PairingOptions {
+    pub(crate) host_record: Plist,
+    pub(crate) supervision: Option<SupervisionIdentity>,
+    pub(crate) records: PairRecordFiles,
+}
+
+impl PairingOptions {
+    /// Pairs with the given host record, saved to the system lockdown directory once the device accepted it,
+    /// so the connections use it.
+    ///
+    /// # Parameters
+    /// - `host_record`: The pair record of the host, with its `DeviceCertificate`, `HostCertificate`,
+    ///   `RootCertificate`, `HostID` and `SystemBUID`, and the private keys to keep with it.
+    pub fn new(host_record: Plist) -> PairingOptions {
+        PairingOptions {
+            host_record,
+            supervision: None,
+            records: PairRecordFiles::system(),
+        }
+    }
+
+    /// Answers the challenge of a supervised device with the identity of the organization,
+    /// instead of failing with `PairRecordError::SupervisionRequired`
+    pub fn with_supervision(mut self, identity: SupervisionIdentity) -> PairingOptions {
+        self.supervision = Some(identity);
+        self
+    }
+
+    /// Saves the record of the pairing to another directory, it must be copied to the system one before connecting
+    pub fn with_records(mut self, records: PairRecordFiles) -> PairingOptions {
+        self.records = records;
+        self
+    }
+}
+
+/// Sends the pairing request, answers the supervision challenge if the device asks for it,
+/// and saves the record with the escrow bag given by the device.
+pub(crate) fn pair(device: &Device, options: &PairingOptions) -> Result<(), PairRecordError> {
+    let mut stream = lockdown_connection::connect(device)?;
+
+    let mut request = pair_request(&options.host_record)?;
+    let mut response = lockdown_connection::exchange(&mut stream, &request)?;
+
+    if error_of(&response).as_deref() == Some(CHALLENGE_REQUIRED) {
+        let supervision = options
+            .supervision
+            .as_ref()
+            .ok_or(PairRecordError::SupervisionRequired)?;
+
+        let challenge = response
+            .dict_get_item("ExtendedResponse")
+            .and_then(|extended| extended.dict_get_item("PairingChallenge"))
+            .and_then(|challenge| challenge.get_data_val())
+            .map_err(|_| PairRecordError::MissingKey("PairingChallenge".to_string()))?
+            .into_iter()
+            .map(|byte| byte as u8)
+            .collect::<Vec<u8>>();
+        let signed_challenge = (supervision.sign_challenge)(&challenge)
+            .map_err(PairRecordError::ChallengeNotSigned)?;
+
+        let mut pairing_options = pairing_options()?;
+        pairing_options.dict_set_item("ChallengeResponse", Plist::new_data(&signed_challenge))?;
+        pairing_options.dict_set_item(
+            "SupervisorCertificate",
+            Plist::new_data(&supervision.certificate),
+        )?;
+        request.dict_set_item("PairingOptions", pairing_options)?;
+
+        response = lockdown_connection::exchange(&mut stream, &request)?;
+    }
+
+    if let Some(error) = error_of(&response) {
+        return Err(PairRecordError::PairingRefused(error));
+    }
+
+    let mut record = options.host_record.clone();
+    if let Ok(escrow_bag) = response.dict_get_item("EscrowBag") {
+        record.dict_set_item("EscrowBag", escrow_bag)?;
+    }
+
+    options.records.save(&device.get_udid(), &record)
+}
+
+/// Builds the `Pair` request, with the public part of the host record.
+fn pair_request(host_record: &Plist) -> Result<Plist, PairRecordError> {
+    let mut pair_record = Plist::new_dict();
+    for key in PAIR_RECORD_KEYS {
+        let value = host_record
+            .dict_get_item(key)
+            .map_err(|_| PairRecordError::MissingKey(key.to_string()))?;
+        pair_record.dict_set_item(key, value)?;
+    }
+
+    let mut request = Plist::new_dict();
+    request.dict_set_item("Label", config::label("pairing").as_str().into())?;
+    request.dict_set_item("Request", "Pair".into())?;
+    request.dict_set_item("PairRecord", pair_record)?;
+    request.dict_set_item("ProtocolVersion", "2".into())?;
+    request.dict_set_item("PairingOptions", pairing_options()?)?;
+
+    Ok(request)
+}
+
+/// The options asking for the detailed errors, without them a supervised device doesn't send its challenge
+fn pairing_options() -> Result<Plist, PairRecordError> {
+    let mut pairing_options = Plist::new_dict();
+    pairing_options.dict_set_item("ExtendedPairingErrors", Plist::new_bool(true))?;
+    Ok(pairing_options)
+}
+
+fn error_of(response: &Plist) -> Option<String> {
+    response
+        .dict_get_item("Error")
+        .and_then(|error| error.get_string_val())
+        .ok()
+}
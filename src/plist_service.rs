@@ -0,0 +1,155 @@
+//! Helpers for the services exchanging plists over a raw `ServiceClient`.
+//!
+//! Most of the lockdown services frame each message as a 4 bytes big-endian length
+//! followed by the plist itself, which is what these helpers read and write.
+//! Some of them (e.g., screenshotr and mobilebackup2) speak DeviceLink on top, see `DeviceLinkClient`.
+
+use plist_plus::{error::PlistError, Plist};
+use rusty_libimobiledevice::{error::ServiceError, service::ServiceClient};
+
+#[cfg(any(feature = "backup", feature = "screenshot"))]
+use crate::errors::UnexpectedResponseErrorTrait;
+
+/// The DeviceLink version answered in the version exchange
+#[cfg(any(feature = "backup", feature = "screenshot"))]
+const DEVICE_LINK_VERSION: u64 = 300;
+
+/// Sends all the given bytes, the service may take them in several writes.
+pub(crate) fn send_all(service: &ServiceClient, data: &[u8]) -> Result<(), ServiceError> {
+    let mut sent = 0;
+    while sent < data.len() {
+        match service.send(data[sent..].to_vec())? {
+            0 => return Err(ServiceError::NotEnoughData),
+            count => sent += count as usize,
+        }
+    }
+    Ok(())
+}
+
+/// Receives exactly `size` bytes, blocking until they all arrive.
+pub(crate) fn receive_exact(service: &ServiceClient, size: usize) -> Result<Vec<u8>, ServiceError> {
+    let mut data = Vec::with_capacity(size);
+    while data.len() < size {
+        let chunk = service.receive((size - data.len()) as u32)?;
+        if chunk.is_empty() {
+            return Err(ServiceError::NotEnoughData);
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+/// Sends a length prefixed XML plist.
+#[cfg(feature = "syslog")]
+pub(crate) fn send_plist_xml(service: &ServiceClient, xml: &str) -> Result<(), ServiceError> {
+    let mut message = (xml.len() as u32).to_be_bytes().to_vec();
+    message.extend_from_slice(xml.as_bytes());
+    send_all(service, &message)
+}
+
+/// Sends a length prefixed binary plist.
+pub(crate) fn send_plist(service: &ServiceClient, plist: &Plist) -> Result<(), ServiceError> {
+    let data = plist.to_bin();
+
+    let mut message = (data.len() as u32).to_be_bytes().to_vec();
+    message.extend_from_slice(&data);
+    send_all(service, &message)
+}
+
+/// Receives a length prefixed plist, either XML or binary.
+pub(crate) fn receive_plist<E>(service: &ServiceClient) -> Result<Plist, E>
+where
+    E: From<ServiceError> + From<PlistError>,
+{
+    let length = receive_exact(service, 4)?;
+    let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]);
+
+    let data = receive_exact(service, length as usize)?;
+    Ok(Plist::from_memory(data)?)
+}
+
+/// A connection to a service speaking the DeviceLink protocol, after its version exchange.
+///
+/// Every message is an array whose first item is the message type, sent as a length prefixed plist,
+/// the requests of the service itself are sent as `DLMessageProcessMessage`.
+#[cfg(any(feature = "backup", feature = "screenshot"))]
+pub(crate) struct DeviceLinkClient<'a> {
+    service: ServiceClient<'a>,
+}
+
+#[cfg(any(feature = "backup", feature = "screenshot"))]
+impl<'a> DeviceLinkClient<'a> {
+    /// Does the version exchange on a freshly started service, until the device is ready.
+    pub(crate) fn connect<E>(service: ServiceClient<'a>) -> Result<DeviceLinkClient<'a>, E>
+    where
+        E: From<ServiceError> + From<PlistError> + UnexpectedResponseErrorTrait,
+    {
+        let client = DeviceLinkClient { service };
+
+        client.expect_message::<E>("DLMessageVersionExchange")?;
+
+        let mut versions_ok = Plist::new_array();
+        versions_ok.array_append_item("DLMessageVersionExchange".into())?;
+        versions_ok.array_append_item("DLVersionsOk".into())?;
+        versions_ok.array_append_item(Plist::new_uint(DEVICE_LINK_VERSION))?;
+        send_plist(&client.service, &versions_ok)?;
+
+        client.expect_message::<E>("DLMessageDeviceReady")?;
+
+        Ok(client)
+    }
+
+    /// The underlying service, for the raw data some services send between the messages
+    #[cfg(feature = "backup")]
+    pub(crate) fn service(&self) -> &ServiceClient<'a> {
+        &self.service
+    }
+
+    /// Sends a DeviceLink message as is.
+    pub(crate) fn send_message(&self, message: &Plist) -> Result<(), ServiceError> {
+        send_plist(&self.service, message)
+    }
+
+    /// Sends a request of the service, wrapped in a `DLMessageProcessMessage`.
+    pub(crate) fn send_process_message<E>(&self, content: Plist) -> Result<(), E>
+    where
+        E: From<ServiceError> + From<PlistError>,
+    {
+        let mut message = Plist::new_array();
+        message.array_append_item("DLMessageProcessMessage".into())?;
+        message.array_append_item(content)?;
+        Ok(self.send_message(&message)?)
+    }
+
+    /// Receives a DeviceLink message, returning its type along with the whole message.
+    pub(crate) fn receive_message<E>(&self) -> Result<(String, Plist), E>
+    where
+        E: From<ServiceError> + From<PlistError> + UnexpectedResponseErrorTrait,
+    {
+        let message = receive_plist::<E>(&self.service)?;
+
+        let message_type = message
+            .array_get_item(0)
+            .and_then(|item| item.get_string_val())
+            .map_err(|_| E::unexpected_response("not a DeviceLink message".into()))?;
+
+        Ok((message_type, message))
+    }
+
+    /// Receives a DeviceLink message, failing if it's not of the expected type.
+    pub(crate) fn expect_message<E>(&self, message_type: &str) -> Result<Plist, E>
+    where
+        E: From<ServiceError> + From<PlistError> + UnexpectedResponseErrorTrait,
+    {
+        let (received_type, message) = self.receive_message::<E>()?;
+
+        if received_type != message_type {
+            return Err(E::unexpected_response(format!(
+                "expected {}, got {}",
+                message_type, received_type
+            )));
+        }
+
+        Ok(message)
+    }
+}
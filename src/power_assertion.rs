@@ -0,0 +1,158 @@
+//! Keeping the devices awake during the long operations.
+//!
+//! A device that idles goes to sleep, which drops the connections of the host, e.g., in the
+//! middle of a backup or a big AFC transfer. The assertion agent of the device takes power
+//! assertions preventing that, each one lasts for a timeout, so they are renewed in the background.
+//!
+//! ## Features
+//! - Preventing the device from idling while a `PowerAssertion` guard is alive
+//! - Renewing the assertion on a background thread, until the guard is dropped
+//!
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    idevice::Device, service::ServiceClient, services::lockdownd::LockdowndClient,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{errors::DeviceClientError, plist_service};
+
+const ASSERTION_AGENT_SERVICE: &str = "com.apple.mobile.assertion_agent";
+const ASSERTION_TYPE: &str = "PreventUserIdleSystemSleep";
+
+/// How long each assertion lasts on the device, in seconds
+const ASSERTION_TIMEOUT: u64 = 60;
+/// The assertion is renewed well before it runs out, in case a renewal is slow
+const RENEW_INTERVAL: Duration = Duration::from_secs(ASSERTION_TIMEOUT / 2);
+/// How often the stop flag is checked while waiting for the next renewal
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Guard keeping a device awake, returned by `DeviceClient::keep_awake`.
+///
+/// Dropping the guard stops renewing the assertion and closes the connection, which releases it,
+/// the device may idle again afterwards.
+#[derive(Debug)]
+pub struct PowerAssertion {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PowerAssertion {
+    /// Takes a power assertion on the device, then keeps renewing it on a background thread.
+    ///
+    /// The first assertion is taken before returning, so its failure is returned,
+    /// the failures of the renewals are reported through the `log` crate.
+    ///
+    /// # Parameters
+    /// - `device`: The device to keep awake.
+    /// - `name`: The name of the assertion, shown in the power logs of the device.
+    pub fn start(device: Device, name: &str) -> Result<PowerAssertion, DeviceClientError> {
+        let name = name.to_string();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let (ready_sender, ready_receiver) = mpsc::sync_channel(1);
+
+        let handle = thread::spawn(move || {
+            let service = match connect(&device).and_then(|service| {
+                create_assertion(&service, &name)?;
+                Ok(service)
+            }) {
+                Ok(service) => {
+                    let _ = ready_sender.send(Ok(()));
+                    service
+                }
+                Err(err) => {
+                    let _ = ready_sender.send(Err(err));
+                    return;
+                }
+            };
+
+            'renew: loop {
+                let next_renewal = Instant::now() + RENEW_INTERVAL;
+                while Instant::now() < next_renewal {
+                    if thread_stop.load(Ordering::SeqCst) {
+                        break 'renew;
+                    }
+                    thread::sleep(STOP_CHECK_INTERVAL);
+                }
+
+                if let Err(e) = create_assertion(&service, &name) {
+                    log::warn!(udid:% = device.get_udid(), error:% = e; "Failed to renew the power assertion");
+                    break;
+                }
+            }
+        });
+
+        match ready_receiver.recv() {
+            Ok(Ok(())) => Ok(PowerAssertion {
+                stop,
+                handle: Some(handle),
+            }),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(DeviceClientError::PowerAssertionRefused(
+                "the assertion thread stopped".into(),
+            )),
+        }
+    }
+
+    /// Checks whether the assertion is still being renewed.
+    ///
+    /// It ends by itself when a renewal fails, e.g., when the device got disconnected.
+    pub fn is_active(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Releases the assertion and waits for the thread to finish.
+    pub fn release(mut self) {
+        self.stop_and_join();
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for PowerAssertion {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+fn connect(device: &Device) -> Result<ServiceClient<'_>, DeviceClientError> {
+    let mut lockdownd = LockdowndClient::new(device, crate::config::label("power-assertion"))?;
+    let service = lockdownd.start_service(ASSERTION_AGENT_SERVICE, false)?;
+    Ok(ServiceClient::new(device, service)?)
+}
+
+/// Takes the assertion, or extends it when one of the same name is already taken.
+fn create_assertion(service: &ServiceClient, name: &str) -> Result<(), DeviceClientError> {
+    let mut command = Plist::new_dict();
+    command.dict_set_item("CommandKey", "CommandCreateAssertion".into())?;
+    command.dict_set_item("AssertionTypeKey", ASSERTION_TYPE.into())?;
+    command.dict_set_item("AssertionNameKey", name.into())?;
+    command.dict_set_item("AssertionTimeoutKey", Plist::new_uint(ASSERTION_TIMEOUT))?;
+    plist_service::send_plist(service, &command)?;
+
+    let response = plist_service::receive_plist::<DeviceClientError>(service)?;
+    if let Ok(error) = response
+        .dict_get_item("Error")
+        .and_then(|error| error.get_string_val())
+    {
+        return Err(DeviceClientError::PowerAssertionRefused(error));
+    }
+
+    Ok(())
+}
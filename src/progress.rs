@@ -0,0 +1,71 @@
+//! A single progress interface for the long running operations.
+//!
+//! Every operation reports its progress with its own type (e.g., `TransferProgress`, `BackupProgress`),
+//! they all convert to a `ProgressUpdate`, so a progress UI only implements `ProgressObserver` once
+//! and plugs it into any of them with `observe`, or with `observe_transfer` and `observe_install`
+//! for the AFC transfers and the installs, whose callbacks have a different shape.
+//!
+//! ## Features
+//! - The `ProgressUpdate` common to the transfers, the installs and the backups
+//! - The `ProgressObserver` trait, implemented by the closures as well
+//!
+
+/// The progress of an operation, in the common form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressUpdate<'p> {
+    /// The stage of the operation (e.g., "Transferring", "Installing")
+    pub phase: &'p str,
+
+    /// How far the operation went, in the unit of `total`
+    pub current: u64,
+
+    /// The value `current` reaches once done, `None` if it's not known yet
+    pub total: Option<u64>,
+
+    /// Details about the current step, such as the file being transferred or an error
+    pub message: Option<&'p str>,
+}
+
+impl ProgressUpdate<'_> {
+    /// The progress from 0 to 1, `None` if the total isn't known
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.current as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+/// Trait for receiving the progress of any operation.
+pub trait ProgressObserver {
+    fn on_progress(&mut self, update: &ProgressUpdate<'_>);
+}
+
+impl<F> ProgressObserver for F
+where
+    F: FnMut(&ProgressUpdate<'_>),
+{
+    fn on_progress(&mut self, update: &ProgressUpdate<'_>) {
+        self(update)
+    }
+}
+
+/// Trait for the progress types of the operations, converting them to the common form.
+pub trait AsProgressUpdate {
+    fn as_progress_update(&self) -> ProgressUpdate<'_>;
+}
+
+/// Adapts an observer to the progress callback of an operation.
+///
+/// # Example
+/// ```ignore
+/// device_backup.backup(dir, BackupOptions::new(), progress::observe(&mut progress_bar))?;
+/// ```
+pub fn observe<P, O>(observer: &mut O) -> impl FnMut(&P) + '_
+where
+    P: AsProgressUpdate,
+    O: ProgressObserver,
+{
+    move |progress: &P| observer.on_progress(&progress.as_progress_update())
+}
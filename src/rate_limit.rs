@@ -0,0 +1,90 @@
+//! The pacing of the requests to the devices, and the cap of the operations running at once.
+//!
+//! Dozens of devices behind a single USB hub start failing their lockdownd requests when they're
+//! all hit at the same time, so the requests wait for their turn instead, following the
+//! `RsMobileDeviceConfig::with_request_pacing` and `RsMobileDeviceConfig::with_max_in_flight` settings.
+
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex},
+    thread,
+    time::Instant,
+};
+
+use crate::config::RsMobileDeviceConfig;
+
+/// The time of the next request allowed to each device, by UDID
+static NEXT_REQUEST: Mutex<Option<HashMap<String, Instant>>> = Mutex::new(None);
+
+static IN_FLIGHT: Mutex<usize> = Mutex::new(0);
+static IN_FLIGHT_RELEASED: Condvar = Condvar::new();
+
+/// Waits for the turn of the next request to the device, following the pacing of the configuration.
+///
+/// The turn is taken before waiting, so the concurrent requests to a device queue up one after the other.
+pub(crate) fn pace(udid: &str) {
+    let Some(pacing) = RsMobileDeviceConfig::global().request_pacing() else {
+        return;
+    };
+
+    let turn = {
+        let Ok(mut next_request) = NEXT_REQUEST.lock() else {
+            return;
+        };
+        let next_request = next_request.get_or_insert_with(HashMap::new);
+
+        let now = Instant::now();
+        let turn = next_request.get(udid).map_or(now, |&next| next.max(now));
+        next_request.insert(udid.to_string(), turn + pacing);
+        turn
+    };
+
+    if let Some(wait) = turn.checked_duration_since(Instant::now()) {
+        thread::sleep(wait);
+    }
+}
+
+/// A place among the operations running at once, given back when dropped.
+pub(crate) struct InFlightPermit {
+    counted: bool,
+}
+
+/// Waits for a place among the operations running at once.
+///
+/// The operation isn't paced here, its requests are when they open their connections.
+/// An operation taking a place shouldn't wait for others taking one too (e.g., a fleet run within
+/// a fleet run), with every place taken they would wait for each other forever.
+pub(crate) fn acquire() -> InFlightPermit {
+    let counted = match RsMobileDeviceConfig::global().max_in_flight() {
+        Some(max) => wait_for_place(max),
+        None => false,
+    };
+
+    InFlightPermit { counted }
+}
+
+fn wait_for_place(max: usize) -> bool {
+    let Ok(mut in_flight) = IN_FLIGHT.lock() else {
+        return false;
+    };
+    while *in_flight >= max {
+        in_flight = match IN_FLIGHT_RELEASED.wait(in_flight) {
+            Ok(in_flight) => in_flight,
+            Err(_) => return false,
+        };
+    }
+
+    *in_flight += 1;
+    true
+}
+
+impl Drop for InFlightPermit {
+    fn drop(&mut self) {
+        if self.counted {
+            if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+                *in_flight -= 1;
+            }
+            IN_FLIGHT_RELEASED.notify_one();
+        }
+    }
+}
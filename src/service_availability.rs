@@ -0,0 +1,70 @@
+//! Whether a lockdown service can be started on a device, and why not.
+
+use rusty_libimobiledevice::error::LockdowndError;
+
+/// The services only available once the developer disk image is mounted
+const DEVELOPER_SERVICES: [&str; 7] = [
+    "com.apple.debugserver",
+    "com.apple.instruments.remoteserver",
+    "com.apple.mobile.screenshotr",
+    "com.apple.mobile.diagnostics_relay.dev",
+    "com.apple.accessibility.axAuditDaemon.remoteserver",
+    "com.apple.dt.",
+    "com.apple.testmanagerd",
+];
+
+/// Enum representing whether a service can be started, as returned by `DeviceClient::is_service_available`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceAvailability {
+    /// The service was started successfully
+    Available,
+    /// The device doesn't know the service
+    NotFound,
+    /// The device is locked with a passcode, it must be unlocked first
+    PasswordProtected,
+    /// The service needs the developer disk image to be mounted
+    RequiresDeveloperImage,
+    /// The service needs the developer mode to be enabled (iOS 16+)
+    RequiresDeveloperMode,
+    /// The service is prohibited, e.g., by a configuration profile
+    Prohibited,
+    /// The service couldn't be started for another reason
+    Unavailable(LockdowndError),
+}
+
+impl ServiceAvailability {
+    pub fn is_available(&self) -> bool {
+        *self == ServiceAvailability::Available
+    }
+
+    /// Maps the error of a failed `StartService`.
+    ///
+    /// # Parameters
+    /// - `developer_mode`: Whether the developer mode is enabled, if known.
+    pub(crate) fn from_error(
+        service: &str,
+        error: LockdowndError,
+        developer_mode: Option<bool>,
+    ) -> ServiceAvailability {
+        match error {
+            LockdowndError::PasswordProtected => ServiceAvailability::PasswordProtected,
+            LockdowndError::ServiceProhibited => ServiceAvailability::Prohibited,
+            // The developer services are unknown to lockdownd until the disk image is mounted
+            LockdowndError::InvalidService if is_developer_service(service) => match developer_mode
+            {
+                Some(false) => ServiceAvailability::RequiresDeveloperMode,
+                _ => ServiceAvailability::RequiresDeveloperImage,
+            },
+            LockdowndError::InvalidService | LockdowndError::MissingService => {
+                ServiceAvailability::NotFound
+            }
+            error => ServiceAvailability::Unavailable(error),
+        }
+    }
+}
+
+pub(crate) fn is_developer_service(service: &str) -> bool {
+    DEVELOPER_SERVICES
+        .iter()
+        .any(|developer_service| service.starts_with(developer_service))
+}
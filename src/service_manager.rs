@@ -0,0 +1,152 @@
+//! Sharing the connections to a device between the parts of an application.
+//!
+//! Each service wrapper opens its own lockdownd connection, and its own service connection,
+//! the `DeviceServiceManager` opens them once and hands out references to them instead.
+//!
+//! ## Features
+//! - A single lockdownd session per device
+//! - A single AFC connection per device
+//! - The started services, such as the syslog relay and the notification proxy, cached by name
+//! - Raw lockdownd requests over the shared session
+//!
+
+use std::{
+    cell::{OnceCell, RefCell, RefMut},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use plist_plus::Plist;
+use rusty_libimobiledevice::{
+    service::ServiceClient,
+    services::{afc::AfcClient, lockdownd::LockdowndClient},
+};
+
+use crate::{
+    device::{self, DeviceClient},
+    devices_collection::SingleDevice,
+    errors::DeviceClientError,
+    service_options::ServiceStartOptions,
+};
+
+const SYSLOG_RELAY_SERVICE: &str = "com.apple.syslog_relay";
+const NOTIFICATION_PROXY_SERVICE: &str = "com.apple.mobile.notification_proxy";
+
+/// Struct caching the connections to a device, created with `DeviceClient::get_service_manager`.
+///
+/// The connections are opened on first use, then kept until `reset` or until the manager is dropped.
+/// It's meant for a single thread, use one manager per thread if needed.
+pub struct DeviceServiceManager<'a> {
+    device: &'a DeviceClient<SingleDevice>,
+    lockdownd: RefCell<Option<LockdowndClient<'a>>>,
+    afc: OnceCell<AfcClient<'a>>,
+    services: RefCell<HashMap<String, Rc<ServiceClient<'a>>>>,
+}
+
+impl<'a> DeviceServiceManager<'a> {
+    pub fn new(device: &'a DeviceClient<SingleDevice>) -> DeviceServiceManager<'a> {
+        DeviceServiceManager {
+            device,
+            lockdownd: RefCell::new(None),
+            afc: OnceCell::new(),
+            services: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The device the connections are made to
+    pub fn device(&self) -> &'a DeviceClient<SingleDevice> {
+        self.device
+    }
+
+    /// Returns the shared lockdownd client, connecting on first use.
+    ///
+    /// The client is borrowed until the returned guard is dropped, keep it for a single call
+    /// (e.g., `manager.lockdownd()?.get_value(...)`), as starting a service borrows it as well.
+    pub fn lockdownd(&self) -> Result<RefMut<'_, LockdowndClient<'a>>, DeviceClientError> {
+        let mut lockdownd = self.lockdownd.borrow_mut();
+
+        if lockdownd.is_none() {
+            *lockdownd = Some(self.device.get_lockdownd_client::<DeviceClientError>()?);
+        }
+
+        Ok(RefMut::map(lockdownd, |lockdownd| {
+            lockdownd
+                .as_mut()
+                .expect("The lockdownd client was just set")
+        }))
+    }
+
+    /// Sends a request lockdownd isn't modeled for by the crate through the shared session,
+    /// see `DeviceClient::send_raw_lockdownd_request`.
+    ///
+    /// # Parameters
+    /// - `request`: The request dictionary, with at least its `Request` key.
+    pub fn send_raw_request(&self, request: Plist) -> Result<Plist, DeviceClientError> {
+        device::send_raw_request(&*self.lockdownd()?, request)
+    }
+
+    /// Returns the shared AFC client, connecting on first use.
+    pub fn afc(&self) -> Result<&AfcClient<'a>, DeviceClientError> {
+        if let Some(afc) = self.afc.get() {
+            return Ok(afc);
+        }
+
+        let afc = self.device.get_afc_client::<DeviceClientError>()?;
+        Ok(self.afc.get_or_init(|| afc))
+    }
+
+    /// Returns the connection to a service, starting it on first use through the shared lockdownd client.
+    ///
+    /// The connection is shared by every caller asking for the same service, so the services
+    /// streaming data (such as the syslog relay) should only be read from one place.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the service (e.g., "com.apple.mobile.heartbeat").
+    /// - `options`: The SSL and escrow bag options, only used when the service is started.
+    pub fn service(
+        &self,
+        name: &str,
+        options: ServiceStartOptions,
+    ) -> Result<Rc<ServiceClient<'a>>, DeviceClientError> {
+        if let Some(service) = self.services.borrow().get(name) {
+            return Ok(Rc::clone(service));
+        }
+
+        let service = {
+            let mut lockdownd = self.lockdownd()?;
+            Rc::new(
+                self.device
+                    .start_service_with(&mut lockdownd, name, &options)?,
+            )
+        };
+        self.services
+            .borrow_mut()
+            .insert(name.to_string(), Rc::clone(&service));
+
+        Ok(service)
+    }
+
+    /// Returns the connection to the syslog relay, see `service`.
+    pub fn syslog_relay(&self) -> Result<Rc<ServiceClient<'a>>, DeviceClientError> {
+        self.service(SYSLOG_RELAY_SERVICE, ServiceStartOptions::new())
+    }
+
+    /// Returns the connection to the notification proxy, see `service`.
+    pub fn notification_proxy(&self) -> Result<Rc<ServiceClient<'a>>, DeviceClientError> {
+        self.service(NOTIFICATION_PROXY_SERVICE, ServiceStartOptions::new())
+    }
+
+    /// Closes the connection to a service, the next `service` call starts it again.
+    ///
+    /// The connection stays open as long as a returned `Rc` is alive.
+    pub fn close_service(&self, name: &str) {
+        self.services.borrow_mut().remove(name);
+    }
+
+    /// Closes every connection, e.g., after the device got reconnected.
+    pub fn reset(&mut self) {
+        self.services.get_mut().clear();
+        self.afc.take();
+        self.lockdownd.get_mut().take();
+    }
+}
@@ -0,0 +1,29 @@
+//! Options for starting the lockdown services.
+
+/// Options for `DeviceClient::start_custom_service_with_options`.
+///
+/// By default, the SSL setting given by lockdownd is used, and the escrow bag isn't sent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServiceStartOptions {
+    pub(crate) use_ssl: Option<bool>,
+    pub(crate) use_escrow_bag: bool,
+}
+
+impl ServiceStartOptions {
+    pub fn new() -> ServiceStartOptions {
+        ServiceStartOptions::default()
+    }
+
+    /// Forces the connection to be wrapped in SSL or not, instead of following lockdownd
+    pub fn with_ssl(mut self, use_ssl: bool) -> ServiceStartOptions {
+        self.use_ssl = Some(use_ssl);
+        self
+    }
+
+    /// Sends the escrow bag of the pair record, so the service can be started while the device is
+    /// passcode locked, as long as it was unlocked once since it booted.
+    pub fn with_escrow_bag(mut self, use_escrow_bag: bool) -> ServiceStartOptions {
+        self.use_escrow_bag = use_escrow_bag;
+        self
+    }
+}
@@ -0,0 +1,144 @@
+//! Temporary files and directories, removed once dropped.
+//!
+//! They're created with `create_new` (and a fresh directory), so an existing file or a symlink planted at
+//! the path by another user is never followed, and they're only readable by the current user,
+//! as they can hold decrypted backup content.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The number of names tried before giving up, a name is only taken again by a file left from a previous run
+const MAX_ATTEMPTS: usize = 16;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A file in the temporary directory, removed once dropped.
+#[cfg(feature = "backup")]
+#[derive(Debug)]
+pub(crate) struct TempFile(PathBuf);
+
+#[cfg(feature = "backup")]
+impl TempFile {
+    /// Creates an empty file, only readable and writable by the current user.
+    pub(crate) fn create(name: &str) -> io::Result<(TempFile, fs::File)> {
+        create_unique(name, |path| {
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+            let file = options.open(path)?;
+            Ok((TempFile(path.to_path_buf()), file))
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(feature = "backup")]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// A directory in the temporary directory, removed with its content once dropped.
+#[derive(Debug)]
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory, only accessible by the current user.
+    pub(crate) fn create(name: &str) -> io::Result<TempDir> {
+        create_unique(name, |path| {
+            let mut builder = fs::DirBuilder::new();
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+            builder.create(path)?;
+            Ok(TempDir(path.to_path_buf()))
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Calls `create` with new paths until one doesn't exist yet.
+fn create_unique<T>(name: &str, create: impl Fn(&Path) -> io::Result<T>) -> io::Result<T> {
+    let mut last_error = None;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+        let path = std::env::temp_dir().join(format!(
+            "rsmobiledevice-{}-{}-{}-{}",
+            process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            name
+        ));
+
+        match create(&path) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => last_error = Some(err),
+            result => return result,
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| io::ErrorKind::AlreadyExists.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_dir_is_removed_with_its_content() {
+        let dir = TempDir::create("dir").unwrap();
+        let path = dir.path().to_path_buf();
+        fs::write(path.join("file"), "content").unwrap();
+
+        assert!(path.is_dir());
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn temp_dirs_get_their_own_path() {
+        let first = TempDir::create("same").unwrap();
+        let second = TempDir::create("same").unwrap();
+
+        assert_ne!(first.path(), second.path());
+    }
+
+    #[cfg(feature = "backup")]
+    #[test]
+    fn temp_file_is_private_and_removed() {
+        let (file, _) = TempFile::create("file").unwrap();
+        let path = file.path().to_path_buf();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        drop(file);
+        assert!(!path.exists());
+    }
+}